@@ -0,0 +1,10 @@
+// Deltas applied on top of a small board, as received from a peer
+#![no_main]
+
+use life_engine::Universe;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut universe = Universe::random_seeded(16, 12, 0.4, 1).unwrap();
+    let _ = universe.decode_delta(data);
+});
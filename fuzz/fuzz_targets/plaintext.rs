@@ -0,0 +1,11 @@
+// Plaintext patterns, as pasted into `from_ascii()`
+#![no_main]
+
+use life_engine::Universe;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(universe) = text.parse::<Universe>() {
+        let _ = universe.to_string();
+    }
+});
@@ -0,0 +1,11 @@
+// Query strings, run against a small random board so out of range regions
+// get exercised too
+#![no_main]
+
+use life_engine::Universe;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let universe = Universe::random_seeded(16, 12, 0.4, 1).unwrap();
+    let _ = universe.query_str(text);
+});
@@ -0,0 +1,10 @@
+// Replay logs. Only replayed as far as a few generations, since a log can
+// ask for any number of ticks on any size of board
+#![no_main]
+
+use life_engine::Universe;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = Universe::replay_until(text, 8);
+});
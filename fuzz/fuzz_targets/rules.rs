@@ -0,0 +1,15 @@
+// Rulestrings in B/S notation (with isotropic non-totalistic letters), 3D
+// rules and Golly rule tables
+#![no_main]
+
+use life_engine::{Rule, Rule3D, RuleTable};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(rule) = text.parse::<Rule>() {
+        // Whatever parses has to print as something that parses back the same
+        assert_eq!(Ok(rule), rule.to_string().parse::<Rule>());
+    }
+    let _ = text.parse::<Rule3D>();
+    let _ = text.parse::<RuleTable>();
+});
@@ -0,0 +1,16 @@
+// Everything a universe can be loaded back from: the binary snapshot, its
+// base64 form, worker transfers, `save()` output and the JSON interop format
+#![no_main]
+
+use life_engine::Universe;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Universe::decode_snapshot(data);
+    let _ = Universe::decode_transfer(data);
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = Universe::decode_base64(text);
+        let _ = Universe::load_str(text);
+        let _ = Universe::from_json_str(text);
+    }
+});
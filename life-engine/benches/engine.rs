@@ -0,0 +1,83 @@
+// Engine benchmarks, run with `cargo bench -p life-engine`
+//
+// Criterion keeps the last run as a baseline under target/criterion and
+// reports each benchmark's change against it, so run once on the base
+// branch and again with a change to compare. `--save-baseline NAME` and
+// `--baseline NAME` keep named ones around instead
+
+use life_engine::Universe;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+// Gosper glider gun, rows top to bottom
+const GLIDER_GUN: &[&str] = &[
+    "........................#...........",
+    "......................#.#...........",
+    "............##......##............##",
+    "...........#...#....##............##",
+    "##........#.....#...##..............",
+    "##........#...#.##....#.#...........",
+    "..........#.....#.......#...........",
+    "...........#...#....................",
+    "............##......................",
+];
+
+fn glider_guns(size: u32) -> Universe {
+    // Tile guns across the universe, each in its own 40x40 block
+    let mut alive = vec![];
+    for block_row in (0..size.saturating_sub(40)).step_by(40) {
+        for block_column in (0..size.saturating_sub(40)).step_by(40) {
+            for (row, line) in GLIDER_GUN.iter().enumerate() {
+                for (column, symbol) in line.chars().enumerate() {
+                    if symbol == '#' {
+                        alive.push((block_row + row as u32) * size + block_column + column as u32);
+                    }
+                }
+            }
+        }
+    }
+    let mut universe = Universe::random_seeded(size, size, 0.0, 0).unwrap();
+    universe.set_cells(&alive);
+    universe
+}
+
+fn tick_random(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick random");
+    for &size in [64, 256, 1024].iter() {
+        let mut universe = Universe::random_seeded(size, size, 0.3, 1).unwrap();
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", size, size)), |b| b.iter(|| universe.tick()));
+    }
+    group.finish();
+}
+
+fn tick_glider_guns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick glider guns");
+    for &size in [128, 512].iter() {
+        let mut universe = glider_guns(size);
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", size, size)), |b| b.iter(|| universe.tick()));
+    }
+    group.finish();
+}
+
+// Every cell's count, the inner loop of a tick without the rest of it
+fn neighbour_counts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbour counts");
+    for &size in [256, 1024].iter() {
+        let universe = Universe::random_seeded(size, size, 0.3, 2).unwrap();
+        group.bench_function(BenchmarkId::from_parameter(format!("{}x{}", size, size)), |b| {
+            b.iter(|| {
+                let mut total = 0u32;
+                for row in 0..size {
+                    for column in 0..size {
+                        total += universe.live_neighbours(row, column) as u32;
+                    }
+                }
+                black_box(total)
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tick_random, tick_glider_guns, neighbour_counts);
+criterion_main!(benches);
@@ -0,0 +1,164 @@
+// How long each live cell has been alive, for renderers that colour old
+// structures differently from fresh births
+//
+// Rather than bumping every live cell each tick, the generation each cell was
+// last born in is kept and ages are worked out when asked for. Cells alive
+// when tracking is switched on count from that point, as do any restored by
+// step_back() or edits
+//
+// With a maximum age set, a cell that has been alive that many generations
+// dies on the next tick whatever its neighbours say. Still lifes and
+// oscillators can't settle in for good, so a board left running keeps
+// churning, which suits a screensaver. Old cells can be anywhere, so each
+// tick looks at every cell while a maximum is set
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Called from tick() once the generation has advanced, and for edits
+    pub(crate) fn record_births(&mut self, changed: &[usize]) {
+        if let Some(born) = self.born.as_mut() {
+            for &idx in changed {
+                if self.cells[idx] == Cell::Alive {
+                    born[idx] = self.generation;
+                }
+            }
+        }
+    }
+
+    // Called from tick() with the cells about to flip, adding the live
+    // cells that have reached the maximum age and weren't dying anyway
+    pub(crate) fn expire_cells(&mut self, mut changed: Vec<usize>) -> Vec<usize> {
+        let max_age = match (self.max_age, &self.born) {
+            (Some(max_age), Some(_)) => max_age,
+            _ => return changed,
+        };
+        for &idx in &changed {
+            self.active_marks[idx] = true;
+        }
+        let expired: Vec<usize> = (0..self.cells.len())
+            .filter(|&idx| !self.active_marks[idx] && self.age(idx) >= max_age)
+            .collect();
+        for &idx in &changed {
+            self.active_marks[idx] = false;
+        }
+        changed.extend(expired);
+        changed
+    }
+
+    fn age(&self, idx: usize) -> u32 {
+        match (&self.born, self.cells[idx]) {
+            (Some(born), Cell::Alive) => (self.generation - born[idx] + 1).min(u32::MAX as u64) as u32,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn enable_age_tracking(&mut self) {
+        if self.born.is_none() {
+            self.born = Some(vec![self.generation; self.cells.len()]);
+        }
+    }
+
+    /// Turning tracking off also drops any maximum age
+    pub fn disable_age_tracking(&mut self) {
+        self.born = None;
+        self.max_age = None;
+    }
+
+    pub fn age_tracking_enabled(&self) -> bool {
+        self.born.is_some()
+    }
+
+    /// Consecutive generations the cell has been alive, counting the current
+    /// one, so 1 for a cell born last tick and 0 for dead cells or while
+    /// tracking is off
+    pub fn age_at(&self, row: u32, column: u32) -> u32 {
+        self.age(self.get_index(row, column))
+    }
+
+    /// Kill cells once they've been alive `max_age` consecutive generations,
+    /// whatever their neighbours. Turns age tracking on; 0 removes the limit
+    pub fn set_max_age(&mut self, max_age: u32) {
+        if max_age == 0 {
+            self.max_age = None;
+        } else {
+            self.enable_age_tracking();
+            self.max_age = Some(max_age);
+        }
+    }
+
+    /// The maximum age, or 0 when cells can live forever
+    pub fn max_age(&self) -> u32 {
+        self.max_age.unwrap_or(0)
+    }
+
+    /// Age of every cell in row major order
+    pub fn ages(&self) -> Vec<u32> {
+        (0..self.cells.len()).map(|idx| self.age(idx)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_of_block_and_blinker() {
+        let mut universe = Universe::new(10, 10, vec![(1, 1), (1, 2), (2, 1), (2, 2), (6, 5), (6, 6), (6, 7)]);
+        assert_eq!(0, universe.age_at(1, 1));
+        universe.enable_age_tracking();
+        assert_eq!(1, universe.age_at(1, 1));
+
+        universe.tick();
+        universe.tick();
+        // The block has survived, the blinker's ends keep being reborn but
+        // its centre never dies
+        assert_eq!(3, universe.age_at(1, 1));
+        assert_eq!(1, universe.age_at(6, 5));
+        assert_eq!(3, universe.age_at(6, 6));
+        assert_eq!(0, universe.age_at(5, 6));
+
+        universe.set_owner(8, 8, 0);
+        assert_eq!(1, universe.age_at(8, 8));
+        assert_eq!(universe.population(), universe.ages().iter().filter(|&&age| age > 0).count() as u32);
+
+        universe.disable_age_tracking();
+        assert!(universe.ages().iter().all(|&age| age == 0));
+    }
+
+    #[test]
+    fn cells_die_of_old_age() {
+        let mut universe = Universe::new(10, 10, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        universe.set_max_age(3);
+        assert!(universe.age_tracking_enabled());
+        universe.tick();
+        universe.tick();
+        assert_eq!(4, universe.population());
+        assert_eq!(3, universe.age_at(1, 1));
+
+        // The whole block goes at once, then nothing is left to be reborn
+        universe.tick();
+        assert_eq!(0, universe.population());
+        assert_eq!(4, universe.deaths_last_tick());
+
+        // A blinker's ends are reborn every tick, only its centre grows old
+        let mut universe = Universe::new(10, 10, vec![(4, 3), (4, 4), (4, 5)]);
+        universe.set_max_age(2);
+        universe.tick();
+        universe.tick();
+        assert!(!universe.is_alive(4, 4));
+        assert_eq!(2, universe.population());
+
+        universe.set_max_age(0);
+        assert_eq!(0, universe.max_age());
+        universe.set_max_age(5);
+        universe.disable_age_tracking();
+        assert_eq!(0, universe.max_age());
+    }
+}
@@ -0,0 +1,237 @@
+// apgcodes, the canonical names Catagolue gives isolated periodic objects
+//
+// An object is run on an unbounded plane until it comes back to its
+// starting shape, giving its period and whether it moved. The code is a
+// prefix saying what it is (xs with the population for still lifes, xp
+// with the period for oscillators, xq for spaceships) and the object in
+// extended Wechsler format: rows are taken in strips of five, each column
+// of a strip becoming one character of 0-9a-v with the top row as the
+// lowest bit. Runs of empty columns shorten to w (2), x (3) or y plus a
+// character (4 to 39), trailing ones are dropped and z separates strips.
+// Of every phase and orientation the shortest, then alphabetically first,
+// encoding is used:
+//
+//     block: xs4_33, blinker: xp2_7, glider: xq4_153
+
+use crate::census::{normalise, orient, step};
+#[cfg(feature = "wasm")]
+use crate::ObjectCounts;
+use crate::{Cell, Rule, Universe};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use tsify::Ts;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAX_PERIOD: u32 = 256;
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const OTHER: &str = "other";
+
+// Extended Wechsler format of normalised cells
+fn wechsler(cells: &[(i64, i64)]) -> String {
+    let height = cells.iter().map(|&(row, _)| row + 1).max().unwrap_or(0);
+    let width = cells.iter().map(|&(_, column)| column + 1).max().unwrap_or(0);
+
+    let mut code = String::new();
+    for strip in 0..(height + 4) / 5 {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut zeroes = 0;
+        for column in 0..width {
+            let value = cells.iter()
+                .filter(|&&(row, cell_column)| cell_column == column && row / 5 == strip)
+                .fold(0, |value, &(row, _)| value | 1 << (row % 5));
+            if value == 0 {
+                zeroes += 1;
+                continue
+            }
+
+            while zeroes > 0 {
+                let run = zeroes.min(39);
+                match run {
+                    1 => code.push('0'),
+                    2 => code.push('w'),
+                    3 => code.push('x'),
+                    _ => {
+                        code.push('y');
+                        code.push(DIGITS[run - 4] as char);
+                    }
+                }
+                zeroes -= run;
+            }
+            code.push(DIGITS[value] as char);
+        }
+    }
+    code
+}
+
+/// apgcode of an isolated pattern evolving under `rule`, None if it isn't
+/// periodic within 256 generations (or dies out, or the rule has B0)
+pub fn apgcode(cells: &[(i64, i64)], rule: Rule) -> Option<String> {
+    if cells.is_empty() || rule.next(Cell::Dead, 0) == Cell::Alive {
+        return None;
+    }
+
+    let mut start = cells.to_vec();
+    normalise(&mut start);
+    let position = |cells: &[(i64, i64)]| cells.iter().min().cloned();
+
+    let mut phases = vec![cells.to_vec()];
+    let mut current = cells.to_vec();
+    for generation in 1..=MAX_PERIOD {
+        current = step(&current, rule);
+        let mut shape = current.clone();
+        normalise(&mut shape);
+        if shape.is_empty() {
+            return None;
+        }
+        if shape != start {
+            phases.push(current.clone());
+            continue
+        }
+
+        let moved = position(&current) != position(cells);
+        let prefix = if moved {
+            format!("xq{}", generation)
+        } else if generation == 1 {
+            format!("xs{}", cells.len())
+        } else {
+            format!("xp{}", generation)
+        };
+
+        let code = phases.iter()
+            .flat_map(|phase| {
+                (0..8).map(move |orientation| {
+                    let mut oriented = phase.iter().map(|&cell| orient(cell, orientation)).collect::<Vec<_>>();
+                    normalise(&mut oriented);
+                    wechsler(&oriented)
+                })
+            })
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            .unwrap_or_default();
+        return Some(format!("{}_{}", prefix, code));
+    }
+    None
+}
+
+// Number of objects by apgcode, those without one counted as "other"
+pub(crate) fn add_apgcodes(counts: &mut BTreeMap<String, u32>, universe: &Universe) {
+    for object in universe.objects() {
+        let code = apgcode(&object, universe.rule).unwrap_or_else(|| OTHER.to_string());
+        *counts.entry(code).or_insert(0) += 1;
+    }
+}
+
+impl Universe {
+    /// Number of objects by apgcode, see `apgcode_census`
+    pub fn apgcode_counts(&self) -> BTreeMap<String, u32> {
+        let mut counts = BTreeMap::new();
+        add_apgcodes(&mut counts, self);
+        counts
+    }
+
+    /// JSON object mapping the apgcode of each object on the board to how
+    /// many there are
+    pub fn apgcode_census(&self) -> String {
+        let mut out = String::from("{");
+        for (i, (code, count)) in self.apgcode_counts().iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "\"{}\":{}", code, count).unwrap();
+        }
+        out.push('}');
+        out
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// apgcode of everything on the board taken as one pattern on an
+    /// unbounded plane under the current rule, or undefined if it doesn't
+    /// repeat within 256 generations
+    pub fn apgcode(&self) -> Option<String> {
+        apgcode(&self.objects().concat(), self.rule)
+    }
+
+    /// How many there are of each object on the board by apgcode, see
+    /// `census()` for how objects are split. Objects which don't repeat
+    /// are counted as "other"
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = apgcode_census)]
+    pub fn apgcode_census_js(&self) -> Ts<ObjectCounts> {
+        crate::utils::to_js(&ObjectCounts(self.apgcode_counts()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(rows: &[&str]) -> Vec<(i64, i64)> {
+        let mut cells = vec![];
+        for (row, line) in rows.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if c == 'O' {
+                    cells.push((row as i64 + 7, column as i64 - 3));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn known_codes() {
+        let code = |rows: &[&str]| apgcode(&cells(rows), Rule::CONWAY);
+        assert_eq!(Some("xs4_33".to_string()), code(&["OO", "OO"]));
+        assert_eq!(Some("xs6_696".to_string()), code(&[".OO.", "O..O", ".OO."]));
+        assert_eq!(Some("xs5_253".to_string()), code(&["OO.", "O.O", ".O."]));
+        assert_eq!(Some("xp2_7".to_string()), code(&["O", "O", "O"]));
+        assert_eq!(Some("xp2_7e".to_string()), code(&[".OOO", "OOO."]));
+        assert_eq!(Some("xq4_153".to_string()), code(&["..O", "O.O", ".OO"]));
+        assert_eq!(Some("xq4_6frc".to_string()), code(&[".O..O", "O....", "O...O", "OOOO."]));
+        assert_eq!(
+            Some("xp3_co9nas0san9oczgoldlo0oldlogz1047210127401".to_string()),
+            code(&[
+                "..OOO...OOO..",
+                ".............",
+                "O....O.O....O",
+                "O....O.O....O",
+                "O....O.O....O",
+                "..OOO...OOO..",
+                ".............",
+                "..OOO...OOO..",
+                "O....O.O....O",
+                "O....O.O....O",
+                "O....O.O....O",
+                ".............",
+                "..OOO...OOO..",
+            ]),
+        );
+
+        // Dies, grows forever or can't be run on a plane
+        assert_eq!(None, code(&["OO"]));
+        assert_eq!(None, code(&[".OO", "OO.", ".O."]));
+        assert_eq!(None, apgcode(&cells(&["OO", "OO"]), "B0/S8".parse().unwrap()));
+    }
+
+    #[test]
+    fn long_gaps() {
+        // Two blinkers far enough apart to need a long run of empty columns
+        let mut far = vec![(0, 0), (1, 0), (2, 0)];
+        far.extend([(0, 45), (1, 45), (2, 45)]);
+        assert_eq!(Some("xp2_7yzy17".to_string()), apgcode(&far, Rule::CONWAY));
+    }
+
+    #[test]
+    fn census_by_apgcode() {
+        let universe = Universe::new(16, 16, vec![(1, 1), (1, 2), (2, 1), (2, 2), (8, 8), (8, 9), (8, 10), (13, 0), (14, 15)]);
+        assert_eq!(Some(&1), universe.apgcode_counts().get("xs4_33"));
+        assert_eq!("{\"other\":1,\"xp2_7\":1,\"xs4_33\":1}", universe.apgcode_census());
+        assert_eq!(None, universe.apgcode());
+        assert_eq!(Some("xs4_33".to_string()), Universe::new(8, 8, vec![(0, 0), (0, 7), (7, 0), (7, 7)]).apgcode());
+    }
+}
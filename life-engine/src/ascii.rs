@@ -0,0 +1,121 @@
+// Parsing the grids Display prints back into a universe
+//
+//     let universe: Universe = "◻◼◻\n◻◼◻\n◻◼◻\n".parse()?;
+//
+// Both the default `◼`/`◻` glyphs and `RenderConfig::ascii()`'s `#`/`.` are
+// understood, as well as `o`/`b` from RLE and plaintext pattern files. Each
+// line is a row; surrounding whitespace and blank lines before and after the
+// grid are ignored so indented text from docs or issues works as is. The
+// result has the default settings (torus, Conway's Life)
+
+use crate::size::check_size;
+use crate::{Cell, Universe};
+
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AsciiError {
+    /// 1 based line and character of the problem
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "grid error at {}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for AsciiError {}
+
+impl FromStr for Universe {
+    type Err = AsciiError;
+
+    fn from_str(text: &str) -> Result<Universe, AsciiError> {
+        let lines = text.lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim()))
+            .collect::<Vec<_>>();
+        let first = lines.iter().position(|(_, line)| !line.is_empty());
+        let last = lines.iter().rposition(|(_, line)| !line.is_empty());
+        let rows = match (first, last) {
+            (Some(first), Some(last)) => &lines[first..=last],
+            _ => return Err(AsciiError { line: 1, column: 1, message: "no grid found".to_string() }),
+        };
+
+        let mut cells = vec![];
+        let mut width = None;
+        for &(number, row) in rows {
+            let mut count = 0;
+            for (column, glyph) in row.chars().enumerate() {
+                cells.push(match glyph {
+                    '◼' | '#' | 'o' | 'O' | '*' => Cell::Alive,
+                    '◻' | '.' | 'b' => Cell::Dead,
+                    _ => {
+                        let message = format!("unexpected '{}'", glyph);
+                        return Err(AsciiError { line: number, column: column + 1, message });
+                    }
+                });
+                count += 1;
+            }
+            match width {
+                None => width = Some(count),
+                Some(width) if width != count => {
+                    let message = format!("row has {} cells but the first has {}", count, width);
+                    return Err(AsciiError { line: number, column: 1, message });
+                }
+                Some(_) => {}
+            }
+        }
+
+        let (width, height) = (width.unwrap_or(0) as u32, rows.len() as u32);
+        check_size(width, height)
+            .map_err(|err| AsciiError { line: rows[0].0, column: 1, message: err.to_string() })?;
+        Ok(Universe::from_cells(width, height, cells))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// A universe from grid text like `render()` produces
+    #[cfg(feature = "wasm")]
+    pub fn from_ascii(text: &str) -> Result<Universe, JsValue> {
+        text.parse::<Universe>().map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderConfig;
+
+    #[test]
+    fn round_trips_display() {
+        let mut universe = Universe::random_seeded(9, 5, 0.4, 2).unwrap();
+        let parsed: Universe = universe.to_string().parse().unwrap();
+        assert_eq!((9, 5), (parsed.width, parsed.height));
+        assert_eq!(universe.cells, parsed.cells);
+
+        universe.set_render_config(&RenderConfig::ascii());
+        let parsed: Universe = universe.render().parse().unwrap();
+        assert_eq!(universe.cells, parsed.cells);
+
+        let indented: Universe = "\n    .o.\n    ..o\n    ooo\n\n".parse().unwrap();
+        assert_eq!(5, indented.population());
+        assert!(indented.is_alive(0, 1) && !indented.is_alive(0, 0));
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        let error = |text: &str| text.parse::<Universe>().err().unwrap();
+        let err = error("◻◼\n◻x\n");
+        assert_eq!((2, 2), (err.line, err.column));
+        assert_eq!(2, error("##\n\n#.#\n").line);
+        assert_eq!(3, error("##\n##\n#.#\n").line);
+        assert!("\n  \n".parse::<Universe>().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+// Picking how a bare board is stored and ticked
+//
+// Every backend runs the same wrapping board behind `LifeEngine`, so the
+// choice is purely about speed:
+//
+// - Dense: a byte a cell (`Engine`). Simplest, fine for small boards
+// - BitPacked: 64 cells a word ticked with bitwise arithmetic, the fastest
+//   general choice once a board is a few thousand cells
+// - Sparse: only the live cells, for huge boards with little alive
+// - HashLife: a shared quadtree remembering what every square becomes, for
+//   large boards built from repeated parts. Square power of two sides only
+//
+// `Backend::Auto` looks at the board's size and how much of it is alive.
+// It never picks HashLife, which only pays off on the right patterns
+
+use crate::{BitPackedEngine, Cell, Engine, HashLifeEngine, LifeEngine, LifeError, SparseEngine};
+
+// Below this many cells the dense board's simplicity wins
+const SMALL_BOARD: u64 = 64 * 64;
+// Below this fraction alive, hashing only the live cells beats visiting
+// every word
+const SPARSE_DENSITY: f64 = 0.005;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Auto,
+    Dense,
+    BitPacked,
+    Sparse,
+    HashLife,
+}
+
+impl Backend {
+    /// What Auto settles on for a `width` x `height` board with `density`
+    /// of its cells alive
+    pub fn choose(width: u32, height: u32, density: f64) -> Backend {
+        if width as u64 * height as u64 <= SMALL_BOARD {
+            Backend::Dense
+        } else if density < SPARSE_DENSITY {
+            Backend::Sparse
+        } else {
+            Backend::BitPacked
+        }
+    }
+
+    /// A board with these `cells`, row by row, or all dead when `cells` is
+    /// empty
+    pub fn create(self, width: u32, height: u32, cells: &[Cell]) -> Result<Box<dyn LifeEngine>, LifeError> {
+        crate::size::check_size(width, height)?;
+        let size = width as usize * height as usize;
+        if !cells.is_empty() && cells.len() != size {
+            return Err(LifeError::InvalidArgument(format!("expected {} cells but got {}", size, cells.len())));
+        }
+
+        let backend = match self {
+            Backend::Auto => {
+                let alive = cells.iter().filter(|&&cell| cell == Cell::Alive).count();
+                Backend::choose(width, height, alive as f64 / size as f64)
+            }
+            backend => backend,
+        };
+        let mut engine: Box<dyn LifeEngine> = match backend {
+            Backend::Auto | Backend::Dense => Box::new(Engine::new(width, height)?),
+            Backend::BitPacked => Box::new(BitPackedEngine::new(width, height)?),
+            Backend::Sparse => Box::new(SparseEngine::new(width, height)?),
+            Backend::HashLife => Box::new(HashLifeEngine::new(width, height)?),
+        };
+        for (idx, _) in cells.iter().enumerate().filter(|(_, &cell)| cell == Cell::Alive) {
+            engine.set(idx as u32 / width, idx as u32 % width, Cell::Alive);
+        }
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::seeded;
+    use crate::{random_cells, Rule};
+
+    #[test]
+    fn backends_agree() {
+        let cells = random_cells(32, 32, 0.35, &mut seeded(6));
+        for rule in ["B3/S23", "B36/S125", "B2-a/S12", "B0/S8", "B1/S012345678"] {
+            let rule: Rule = rule.parse().unwrap();
+            let mut engines = [Backend::Dense, Backend::BitPacked, Backend::Sparse, Backend::HashLife]
+                .map(|backend| backend.create(32, 32, &cells).unwrap());
+            for engine in engines.iter_mut() {
+                engine.set_rule(rule);
+            }
+            for _ in 0..12 {
+                for engine in engines.iter_mut() {
+                    engine.tick();
+                }
+                let expected = engines[0].to_cells();
+                for engine in &engines[1..] {
+                    assert_eq!(expected, engine.to_cells(), "{}", rule);
+                    assert_eq!(engines[0].population(), engine.population());
+                }
+            }
+        }
+
+        // Odd sizes, with rows shorter than a word and longer than one
+        for (width, height) in [(2, 7), (5, 2), (70, 3), (130, 9)] {
+            let cells = random_cells(width, height, 0.4, &mut seeded(2));
+            let mut dense = Backend::Dense.create(width, height, &cells).unwrap();
+            let mut packed = Backend::BitPacked.create(width, height, &cells).unwrap();
+            for _ in 0..6 {
+                dense.tick();
+                packed.tick();
+                assert_eq!(dense.to_cells(), packed.to_cells());
+            }
+        }
+    }
+
+    #[test]
+    fn auto_choice() {
+        assert_eq!(Backend::Dense, Backend::choose(32, 32, 0.5));
+        assert_eq!(Backend::BitPacked, Backend::choose(512, 512, 0.3));
+        assert_eq!(Backend::Sparse, Backend::choose(4096, 4096, 0.0001));
+
+        let mut cells = vec![Cell::Dead; 1000 * 1000];
+        cells[2] = Cell::Alive;
+        let engine = Backend::Auto.create(1000, 1000, &cells).unwrap();
+        assert_eq!((1, Cell::Alive), (engine.population(), engine.get(0, 2)));
+
+        assert!(Backend::HashLife.create(100, 100, &[]).is_err());
+        assert!(Backend::Dense.create(4, 4, &[Cell::Alive; 3]).is_err());
+    }
+}
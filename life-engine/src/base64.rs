@@ -0,0 +1,106 @@
+// Minimal standard base64 (RFC 4648, with padding), enough for snapshots
+// without pulling in another dependency. The URL-safe variant swaps `+` and
+// `/` for `-` and `_` and leaves the padding off, for links
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let group = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn decode_symbol(symbol: u8) -> Option<u32> {
+    ALPHABET.iter().position(|&s| s == symbol).map(|value| value as u32)
+}
+
+/// None if the input isn't valid padded base64
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim().as_bytes();
+    if !encoded.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    let chunk_count = encoded.len() / 4;
+    for (chunk_index, chunk) in encoded.chunks(4).enumerate() {
+        // Padding is only allowed at the very end
+        let padding = chunk.iter().rev().take_while(|&&symbol| symbol == b'=').count();
+        if padding > 2 || (padding > 0 && chunk_index != chunk_count - 1) {
+            return None;
+        }
+
+        let mut group = 0;
+        for &symbol in &chunk[..4 - padding] {
+            group = group << 6 | decode_symbol(symbol)?;
+        }
+        group <<= 6 * padding;
+
+        let bytes = [(group >> 16) as u8, (group >> 8) as u8, group as u8];
+        decoded.extend_from_slice(&bytes[..3 - padding]);
+    }
+    Some(decoded)
+}
+
+pub(crate) fn encode_url(data: &[u8]) -> String {
+    encode(data).trim_end_matches('=').chars()
+        .map(|symbol| match symbol {
+            '+' => '-',
+            '/' => '_',
+            symbol => symbol,
+        })
+        .collect()
+}
+
+/// None if the input isn't valid unpadded URL-safe base64
+pub(crate) fn decode_url(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim();
+    if encoded.contains(['+', '/', '=']) || encoded.len() % 4 == 1 {
+        return None;
+    }
+    let mut standard = encoded.replace('-', "+").replace('_', "/");
+    standard.extend(std::iter::repeat_n('=', (4 - encoded.len() % 4) % 4));
+    decode(&standard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!("", encode(b""));
+        assert_eq!("Zg==", encode(b"f"));
+        assert_eq!("Zm8=", encode(b"fo"));
+        assert_eq!("Zm9v", encode(b"foo"));
+        assert_eq!("Zm9vYmFy", encode(b"foobar"));
+
+        assert_eq!(Some(b"foobar".to_vec()), decode("Zm9vYmFy"));
+        assert_eq!(Some(b"fo".to_vec()), decode("Zm8="));
+        assert_eq!(None, decode("Zm8"));
+        assert_eq!(None, decode("Zg==Zm9v"));
+        assert_eq!(None, decode("Zm9*"));
+    }
+
+    #[test]
+    fn url_safe() {
+        assert_eq!("-_8", encode_url(&[0xfb, 0xff]));
+        assert_eq!("Zg", encode_url(b"f"));
+        assert_eq!(Some(vec![0xfb, 0xff]), decode_url("-_8"));
+        assert_eq!(Some(b"foobar".to_vec()), decode_url("Zm9vYmFy"));
+        assert_eq!(None, decode_url("+/8"));
+        assert_eq!(None, decode_url("Zm8="));
+        assert_eq!(None, decode_url("Zm9vY"));
+    }
+}
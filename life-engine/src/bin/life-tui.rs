@@ -0,0 +1,192 @@
+// Interactive terminal frontend
+//
+//     cargo run -p life-engine --features tui --bin life-tui -- [WIDTH HEIGHT]
+//
+// Keys: space play/pause, n single step, arrows or hjkl move the cursor,
+// x or enter toggle the cell under it, + and - change speed, r new random
+// soup, c clear, q or Ctrl-C quit. Built on crossterm, so it runs in any
+// terminal it supports, Windows included
+
+use life_engine::Universe;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+// Generations per second the speed keys step through
+const SPEEDS: &[u32] = &[1, 2, 5, 10, 20, 30, 60];
+
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Quit,
+}
+
+// Puts the terminal into raw mode and the alternate screen, restoring both
+// when dropped, which includes unwinding from a panic. Raw mode turns off
+// the terminal's signal keys too, so Ctrl-C arrives as a key rather than
+// killing the process before this can run
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        let terminal = Terminal;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide, Clear(ClearType::All))?;
+        Ok(terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn key(event: KeyEvent) -> Option<Key> {
+    if event.kind == KeyEventKind::Release {
+        return None;
+    }
+    Some(match event.code {
+        KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Enter => Key::Char('x'),
+        KeyCode::Char(c) => Key::Char(c),
+        _ => return None,
+    })
+}
+
+// The next key pressed within `timeout`, if any
+fn next_key(timeout: Duration) -> io::Result<Option<Key>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if !event::poll(deadline.saturating_duration_since(Instant::now()))? {
+            return Ok(None);
+        }
+        if let Event::Key(event) = event::read()? {
+            if let Some(key) = key(event) {
+                return Ok(Some(key));
+            }
+        }
+    }
+}
+
+struct App {
+    universe: Universe,
+    cursor: (u32, u32),
+    running: bool,
+    speed: usize,
+    seed: u64,
+}
+
+impl App {
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, cursor::MoveTo(0, 0))?;
+        for row in 0..self.universe.height() {
+            for column in 0..self.universe.width() {
+                let symbol = if self.universe.is_alive(row, column) { '◼' } else { '◻' };
+                if (row, column) == self.cursor {
+                    queue!(out, SetAttribute(Attribute::Reverse), Print(symbol), SetAttribute(Attribute::Reset))?;
+                } else {
+                    queue!(out, Print(symbol))?;
+                }
+            }
+            queue!(out, Print("\r\n"))?;
+        }
+        queue!(out, Clear(ClearType::UntilNewLine))?;
+        write!(
+            out,
+            "{}  generation {}  population {}  {} gen/s  cursor ({}, {})\r\n",
+            if self.running { "running" } else { "paused " },
+            self.universe.generation(),
+            self.universe.population(),
+            SPEEDS[self.speed],
+            self.cursor.0,
+            self.cursor.1,
+        )?;
+        queue!(out, Clear(ClearType::UntilNewLine))?;
+        write!(out, "space play/pause  n step  arrows/hjkl move  x toggle  +/- speed  r random  c clear  q quit")?;
+        out.flush()
+    }
+
+    // Returns false on quit
+    fn handle(&mut self, key: Key) -> bool {
+        let (height, width) = (self.universe.height(), self.universe.width());
+        let (row, column) = self.cursor;
+        match key {
+            Key::Up | Key::Char('k') => self.cursor.0 = (row + height - 1) % height,
+            Key::Down | Key::Char('j') => self.cursor.0 = (row + 1) % height,
+            Key::Left | Key::Char('h') => self.cursor.1 = (column + width - 1) % width,
+            Key::Right | Key::Char('l') => self.cursor.1 = (column + 1) % width,
+            Key::Char(' ') => self.running = !self.running,
+            Key::Char('n') => self.universe.tick(),
+            Key::Char('x') => self.universe.toggle_cell(row, column),
+            Key::Char('+') | Key::Char('=') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Key::Char('-') => self.speed = self.speed.saturating_sub(1),
+            Key::Char('r') => {
+                self.seed += 1;
+                self.universe = Universe::random_seeded(width, height, 0.3, self.seed).unwrap();
+            }
+            Key::Char('c') => self.universe = Universe::random_seeded(width, height, 0.0, 0).unwrap(),
+            Key::Char('q') | Key::Quit => return false,
+            Key::Char(_) => {}
+        }
+        true
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut args = std::env::args().skip(1).map(|arg| arg.parse::<u32>());
+    let (width, height) = match (args.next(), args.next()) {
+        (None, None) => (48, 24),
+        (Some(Ok(width)), Some(Ok(height))) if width > 0 && height > 0 => (width, height),
+        _ => {
+            eprintln!("usage: life-tui [WIDTH HEIGHT]");
+            std::process::exit(2);
+        }
+    };
+
+    let mut app = App {
+        universe: Universe::random_seeded(width, height, 0.3, 0).unwrap(),
+        cursor: (height / 2, width / 2),
+        running: false,
+        speed: 3,
+        seed: 0,
+    };
+
+    let _terminal = Terminal::enter()?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut next_tick = Instant::now();
+    loop {
+        app.draw(&mut out)?;
+
+        let timeout = if app.running {
+            next_tick.saturating_duration_since(Instant::now())
+        } else {
+            Duration::from_secs(3600)
+        };
+        match next_key(timeout)? {
+            Some(key) => {
+                if !app.handle(key) {
+                    return Ok(());
+                }
+            }
+            None => {
+                app.universe.tick();
+                next_tick = Instant::now() + Duration::from_secs(1) / SPEEDS[app.speed];
+            }
+        }
+    }
+}
@@ -0,0 +1,332 @@
+// Run the simulation in a terminal, printing each generation with the
+// universe's Display impl
+//
+//     cargo run --bin life -- --width 40 --height 40 --rule B36/S23 --delay 100
+//
+// A pattern file is either the JSON from `to_json()` or a binary snapshot
+// from `to_snapshot()`, and sets the size itself. Without one the universe is
+// a random soup. With `--soups N` nothing is printed per generation, instead
+// N soups are run headlessly (up to --generations each) and summarised
+//
+// With `--out DIR` it's a batch run for long experiments on a server: each
+// of `--seeds N` runs (the pattern, or a soup from seed + i) ticks for
+// --generations, spread over `--threads` threads, with nothing printed but a
+// line as each finishes. Run i writes DIR/seed-S/stats.csv, a row of
+// generation, population, births and deaths per generation, and every
+// `--every K` generations a `snapshot()` as DIR/seed-S/generation-000000.snap,
+// which --pattern takes to carry on from there. A probabilistic rule
+// (`--birth-probability`, `--survival-probability`) draws from the run's seed
+//
+//     cargo run --release --bin life -- --pattern p.json --out runs --seeds 16
+//         --generations 100000 --every 10000 --birth-probability 0.9
+//
+// Built with the `image` feature, `--png DIR` also saves every generation
+// shown as DIR/generation-000000.png and so on, for time-lapses
+//
+// Nothing here needs wasm-bindgen, so it also builds for WASI and runs
+// under wasmtime or wasmer:
+//
+//     cargo build --release --target wasm32-wasip1 --bin life
+//     wasmtime run target/wasm32-wasip1/release/life.wasm --generations 100
+
+use life_engine::{Rule, SoupSearch, Status, Universe};
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str = "usage: life [--width N] [--height N] [--density F] [--seed N] [--rule B3/S23]
+            [--pattern FILE] [--generations N] [--delay MS] [--soups N] [--png DIR]
+            [--out DIR] [--seeds N] [--every N] [--threads N]
+            [--birth-probability F] [--survival-probability F]";
+
+struct Options {
+    width: u32,
+    height: u32,
+    density: f32,
+    seed: u64,
+    rule: Rule,
+    pattern: Option<String>,
+    // Run forever when None
+    generations: Option<u64>,
+    delay_ms: u64,
+    soups: Option<u32>,
+    png_dir: Option<String>,
+    out_dir: Option<String>,
+    seeds: u64,
+    // Snapshot interval in generations, 0 for only the last
+    every: u64,
+    threads: usize,
+    birth_probability: f64,
+    survival_probability: f64,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        width: 32,
+        height: 32,
+        density: 0.3,
+        seed: 0,
+        rule: Rule::CONWAY,
+        pattern: None,
+        generations: None,
+        delay_ms: 100,
+        soups: None,
+        png_dir: None,
+        out_dir: None,
+        seeds: 1,
+        every: 0,
+        threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        birth_probability: 1.0,
+        survival_probability: 1.0,
+    };
+
+    fn value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+        let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+        value.parse().map_err(|_| format!("invalid value '{}' for {}", value, flag))
+    }
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--width" => options.width = value(&flag, args.next())?,
+            "--height" => options.height = value(&flag, args.next())?,
+            "--density" => options.density = value(&flag, args.next())?,
+            "--seed" => options.seed = value(&flag, args.next())?,
+            "--rule" => {
+                let rule = args.next().ok_or("--rule needs a value")?;
+                options.rule = rule.parse().map_err(|err| format!("{}", err))?;
+            }
+            "--pattern" => options.pattern = Some(value(&flag, args.next())?),
+            "--generations" => options.generations = Some(value(&flag, args.next())?),
+            "--delay" => options.delay_ms = value(&flag, args.next())?,
+            "--soups" => options.soups = Some(value(&flag, args.next())?),
+            #[cfg(feature = "image")]
+            "--png" => options.png_dir = Some(value(&flag, args.next())?),
+            "--out" => options.out_dir = Some(value(&flag, args.next())?),
+            "--seeds" => options.seeds = value(&flag, args.next())?,
+            "--every" => options.every = value(&flag, args.next())?,
+            "--threads" => options.threads = value(&flag, args.next())?,
+            "--birth-probability" => options.birth_probability = value(&flag, args.next())?,
+            "--survival-probability" => options.survival_probability = value(&flag, args.next())?,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument '{}'", flag)),
+        }
+    }
+
+    if options.width == 0 || options.height == 0 {
+        return Err("width and height must be non-zero".to_string());
+    }
+    if options.out_dir.is_some() && options.generations.is_none() {
+        return Err("--out needs --generations".to_string());
+    }
+    if options.threads == 0 {
+        return Err("--threads must be non-zero".to_string());
+    }
+    Ok(options)
+}
+
+fn load_pattern(path: &str) -> Result<Universe, String> {
+    let data = std::fs::read(path).map_err(|err| format!("can't read {}: {}", path, err))?;
+
+    if path.ends_with(".json") {
+        #[cfg(feature = "serde")]
+        {
+            let json = String::from_utf8(data).map_err(|_| format!("{} isn't UTF-8", path))?;
+            return Universe::from_json_str(&json).map_err(|err| format!("{}: {}", path, err));
+        }
+        #[cfg(not(feature = "serde"))]
+        return Err("JSON patterns need the serde feature".to_string());
+    }
+    // A batch run's snapshots, which carry on from their generation
+    if data.starts_with(b"GT") {
+        return Universe::decode_transfer(&data).map_err(|err| format!("{}: {}", path, err));
+    }
+    Universe::decode_snapshot(&data).map_err(|err| format!("{}: {}", path, err))
+}
+
+fn soup_search(options: &Options, count: u32) {
+    let max_generations = options.generations.unwrap_or(10_000);
+    let search = SoupSearch::run(count, options.width, options.height, max_generations, options.seed, options.rule)
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+
+    println!("{} soups of {}x{}, mean final population {:.1}", count, options.width, options.height, search.mean_population());
+    for &(status, name) in [
+        (Status::Extinct, "extinct"),
+        (Status::StillLife, "still life"),
+        (Status::Oscillating, "oscillating"),
+        (Status::Running, "still running"),
+    ].iter() {
+        println!("  {:<14}{}", name, search.count(status));
+    }
+    for (period, count) in search.periods() {
+        println!("  period {:<7}{}", period, count);
+    }
+
+    // Most common objects first
+    let mut census = search.census.iter().collect::<Vec<_>>();
+    census.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    println!("objects");
+    for (code, count) in census.iter().take(10) {
+        println!("  {:<24} {}", code, count);
+    }
+}
+
+// One run of a batch, returning its final population
+fn batch_run(options: &Options, out_dir: &str, seed: u64) -> Result<u32, String> {
+    let mut universe = match &options.pattern {
+        Some(path) => load_pattern(path)?,
+        None => Universe::random_seeded(options.width, options.height, options.density, seed)
+            .map_err(|err| err.to_string())?,
+    };
+    universe.set_life_rule(options.rule);
+    universe.set_rule_probabilities(options.birth_probability, options.survival_probability, seed);
+
+    let dir = Path::new(out_dir).join(format!("seed-{}", seed));
+    fs::create_dir_all(&dir).map_err(|err| format!("can't create {}: {}", dir.display(), err))?;
+    let write_error = |path: &Path, err: std::io::Error| format!("can't write {}: {}", path.display(), err);
+    let save = |universe: &Universe| {
+        let path = dir.join(format!("generation-{:06}.snap", universe.generation()));
+        fs::write(&path, universe.snapshot()).map_err(|err| write_error(&path, err))
+    };
+
+    let csv_path = dir.join("stats.csv");
+    let mut csv = BufWriter::new(File::create(&csv_path).map_err(|err| write_error(&csv_path, err))?);
+    writeln!(csv, "generation,population,births,deaths").map_err(|err| write_error(&csv_path, err))?;
+    let mut row = |universe: &Universe| {
+        writeln!(
+            csv,
+            "{},{},{},{}",
+            universe.generation(),
+            universe.population(),
+            universe.births_last_tick(),
+            universe.deaths_last_tick()
+        )
+        .map_err(|err| write_error(&csv_path, err))
+    };
+    row(&universe)?;
+
+    let start = universe.generation();
+    let end = start.saturating_add(options.generations.unwrap_or(0));
+    if options.every > 0 {
+        save(&universe)?;
+    }
+    while universe.generation() < end {
+        universe.tick();
+        row(&universe)?;
+        if options.every > 0 && (universe.generation() - start) % options.every == 0 {
+            save(&universe)?;
+        }
+    }
+    if options.every == 0 || (end - start) % options.every != 0 {
+        save(&universe)?;
+    }
+    csv.flush().map_err(|err| write_error(&csv_path, err))?;
+    Ok(universe.population())
+}
+
+fn batch(options: &Options, out_dir: &str) {
+    // Runs are handed out one at a time so slow ones don't hold up a thread's
+    // share
+    let next = AtomicU64::new(0);
+    let worker = || {
+        let mut failed = false;
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            if i >= options.seeds {
+                return failed;
+            }
+            let seed = options.seed.wrapping_add(i);
+            match batch_run(options, out_dir, seed) {
+                Ok(population) => println!("seed {} done, final population {}", seed, population),
+                Err(err) => {
+                    eprintln!("seed {}: {}", seed, err);
+                    failed = true;
+                }
+            }
+        }
+    };
+
+    let threads = options.threads.min(options.seeds.max(1) as usize);
+    // No threads at all for one, so it also runs under WASI
+    let failed = if threads == 1 {
+        worker()
+    } else {
+        thread::scope(|scope| {
+            let workers = (0..threads).map(|_| scope.spawn(worker)).collect::<Vec<_>>();
+            let failed = workers.into_iter().map(|worker| worker.join().expect("batch worker panicked")).collect::<Vec<_>>();
+            failed.contains(&true)
+        })
+    };
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(feature = "image")]
+fn save_frame(universe: &Universe, dir: &str) {
+    // Pixels per cell
+    const PNG_CELL_SIZE: u32 = 4;
+
+    let path = std::path::Path::new(dir).join(format!("generation-{:06}.png", universe.generation()));
+    if let Err(err) = universe.save_png(&path, PNG_CELL_SIZE) {
+        eprintln!("can't write {}: {}", path.display(), err);
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "image"))]
+fn save_frame(_universe: &Universe, _dir: &str) {}
+
+fn main() {
+    let options = parse_options(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(2);
+    });
+    if let Some(count) = options.soups {
+        soup_search(&options, count);
+        return;
+    }
+    if let Some(dir) = &options.out_dir {
+        batch(&options, dir);
+        return;
+    }
+
+    let universe = match &options.pattern {
+        Some(path) => load_pattern(path),
+        None => Universe::random_seeded(options.width, options.height, options.density, options.seed)
+            .map_err(|err| err.to_string()),
+    };
+    let mut universe = universe.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+    universe.set_life_rule(options.rule);
+
+    loop {
+        if options.delay_ms > 0 {
+            // Clear the screen and home the cursor so the board animates in place
+            print!("\x1b[2J\x1b[H");
+        }
+        print!("{}", universe);
+        println!("generation {}  population {}", universe.generation(), universe.population());
+        if let Some(dir) = &options.png_dir {
+            save_frame(&universe, dir);
+        }
+
+        if options.generations.is_some_and(|generations| universe.generation() >= generations) {
+            break;
+        }
+        universe.tick();
+        thread::sleep(Duration::from_millis(options.delay_ms));
+    }
+}
@@ -0,0 +1,217 @@
+// A board stored 64 cells to a word, ticked with bitwise arithmetic
+//
+// Each row is a run of u64 words, least significant bit first, with the bits
+// past the width kept clear. A tick works a word at a time: the eight
+// neighbours of 64 cells are the rows above and below, each shifted a column
+// either way, and this row's two shifts, which are added up as binary
+// counters spread across four words. The rule then picks out the bits whose
+// count gives birth or survival. That's around a hundred instructions for 64
+// cells, against eight lookups for each one on a dense board.
+//
+// Rules with Hensel letters need the arrangement of the neighbours and not
+// just how many there are, so they go a cell at a time instead
+
+use crate::engine::wrapped_neighbourhood;
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::{Cell, LifeEngine, Rule};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitPackedEngine {
+    width: u32,
+    height: u32,
+    // Words per row
+    stride: usize,
+    words: Vec<u64>,
+    scratch: Vec<u64>,
+    rule: Rule,
+    generation: u64,
+}
+
+// Adds the one bit `input` to each of the 64 counters held in `counts`,
+// least significant bit first
+fn add(counts: &mut [u64; 4], input: u64) {
+    let mut carry = input;
+    for bits in counts.iter_mut() {
+        let next = *bits & carry;
+        *bits ^= carry;
+        carry = next;
+    }
+}
+
+impl BitPackedEngine {
+    /// An all dead board running Conway's Life
+    pub fn new(width: u32, height: u32) -> Result<BitPackedEngine, LifeError> {
+        check_size(width, height)?;
+        let stride = (width as usize).div_ceil(64);
+        Ok(BitPackedEngine {
+            width,
+            height,
+            stride,
+            words: vec![0; stride * height as usize],
+            scratch: vec![0; stride * height as usize],
+            rule: Rule::CONWAY,
+            generation: 0,
+        })
+    }
+
+    // Bits past the width in the last word of each row
+    fn last_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => u64::MAX,
+            used => (1 << used) - 1,
+        }
+    }
+
+    // Each row's cells moved one column east (so every bit holds its west
+    // neighbour) and one column west, wrapping round
+    fn shifted(&self) -> (Vec<u64>, Vec<u64>) {
+        let (stride, mask) = (self.stride, self.last_mask());
+        let last_bit = (self.width - 1) % 64;
+        let mut west = vec![0; self.words.len()];
+        let mut east = vec![0; self.words.len()];
+        for (row, words) in self.words.chunks(stride).enumerate() {
+            let (west, east) = (&mut west[row * stride..][..stride], &mut east[row * stride..][..stride]);
+            let first = words[0] & 1;
+            let last = words[stride - 1] >> last_bit & 1;
+            for i in 0..stride {
+                west[i] = words[i] << 1 | if i == 0 { last } else { words[i - 1] >> 63 };
+                east[i] = words[i] >> 1 | if i + 1 < stride { words[i + 1] << 63 } else { first << last_bit };
+            }
+            west[stride - 1] &= mask;
+            east[stride - 1] &= mask;
+        }
+        (west, east)
+    }
+
+    fn tick_totalistic(&mut self) {
+        let (west, east) = self.shifted();
+        let (stride, height) = (self.stride, self.height as usize);
+        let mask = self.last_mask();
+        // Which counts give birth and survival
+        let counts: Vec<(u8, bool, bool)> = (0..=8)
+            .map(|count| (count, self.rule.next(Cell::Dead, count) == Cell::Alive, self.rule.next(Cell::Alive, count) == Cell::Alive))
+            .filter(|&(_, birth, survival)| birth || survival)
+            .collect();
+
+        for row in 0..height {
+            let (north, south) = ((row + height - 1) % height, (row + 1) % height);
+            for i in 0..stride {
+                let mut sums = [0; 4];
+                for neighbour_row in [north, south] {
+                    let idx = neighbour_row * stride + i;
+                    add(&mut sums, self.words[idx]);
+                    add(&mut sums, west[idx]);
+                    add(&mut sums, east[idx]);
+                }
+                add(&mut sums, west[row * stride + i]);
+                add(&mut sums, east[row * stride + i]);
+
+                let alive = self.words[row * stride + i];
+                let mut next = 0;
+                for &(count, birth, survival) in &counts {
+                    let matching = sums.iter().enumerate()
+                        .fold(u64::MAX, |matching, (bit, &sum)| matching & if count >> bit & 1 == 1 { sum } else { !sum });
+                    next |= matching & (if birth { !alive } else { 0 } | if survival { alive } else { 0 });
+                }
+                self.scratch[row * stride + i] = if i + 1 == stride { next & mask } else { next };
+            }
+        }
+    }
+
+    fn tick_cellwise(&mut self) {
+        let (width, height) = (self.width, self.height);
+        for row in 0..height {
+            for column in 0..width {
+                let neighbourhood = wrapped_neighbourhood(width, height, row, column, |idx| {
+                    self.get(idx as u32 / width, idx as u32 % width)
+                });
+                let idx = row as usize * self.stride + column as usize / 64;
+                let bit = 1 << (column % 64);
+                if column % 64 == 0 {
+                    self.scratch[idx] = 0;
+                }
+                if self.rule.next_neighbourhood(self.get(row, column), neighbourhood) == Cell::Alive {
+                    self.scratch[idx] |= bit;
+                }
+            }
+        }
+    }
+}
+
+impl LifeEngine for BitPackedEngine {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn get(&self, row: u32, column: u32) -> Cell {
+        assert!(row < self.height && column < self.width);
+        match self.words[row as usize * self.stride + column as usize / 64] >> (column % 64) & 1 {
+            0 => Cell::Dead,
+            _ => Cell::Alive,
+        }
+    }
+
+    fn set(&mut self, row: u32, column: u32, cell: Cell) {
+        assert!(row < self.height && column < self.width);
+        let word = &mut self.words[row as usize * self.stride + column as usize / 64];
+        let bit = 1 << (column % 64);
+        match cell {
+            Cell::Alive => *word |= bit,
+            Cell::Dead => *word &= !bit,
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.rule.is_totalistic() {
+            self.tick_totalistic();
+        } else {
+            self.tick_cellwise();
+        }
+        std::mem::swap(&mut self.words, &mut self.scratch);
+        self.generation += 1;
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn population(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_across_words() {
+        // A blinker straddling the first two words and one wrapping round
+        // the east edge back to column 0
+        let mut engine = BitPackedEngine::new(70, 5).unwrap();
+        for column in 63..66 {
+            engine.set(2, column, Cell::Alive);
+        }
+        for column in [69, 0, 1] {
+            engine.set(0, column, Cell::Alive);
+        }
+        engine.tick();
+        assert_eq!(6, engine.population());
+        assert!((1..4).all(|row| engine.get(row, 64) == Cell::Alive));
+        assert!([4, 0, 1].iter().all(|&row| engine.get(row, 0) == Cell::Alive));
+        assert_eq!(Cell::Dead, engine.get(2, 63));
+    }
+}
@@ -0,0 +1,353 @@
+// Boundary handling, deciding what lies beyond the edges of the universe
+//
+// Torus wraps around (the original behaviour), Dead treats everything outside
+// as dead, Mirror reflects the edge cells back in, and Inject behaves like
+// Dead except one chosen edge is fed a fresh line of cells every tick
+//
+// The rest glue the edges together into other surfaces. KleinBottle wraps
+// like Torus except going off the left or right edge also flips top to
+// bottom, CrossSurface (the projective plane) flips across both pairs of
+// edges, and Sphere joins the top edge to the left edge and the bottom edge
+// to the right edge. Sphere needs a square universe, on any other shape it
+// behaves like Dead
+
+use crate::error::LifeError;
+use crate::rng::{seeded, SeededRng};
+use crate::rule::NEIGHBOURS;
+use crate::{Cell, TimelineKind, Universe};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    Torus,
+    Dead,
+    Mirror,
+    Inject,
+    KleinBottle,
+    CrossSurface,
+    Sphere,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+#[derive(Clone)]
+pub(crate) enum InjectionSource {
+    // Each ghost cell is alive with the given probability
+    Random { probability: f32, rng: Box<SeededRng> },
+    // Successive edge length frames, cycled through one per tick
+    Pattern { frames: Vec<Cell>, next_frame: usize },
+}
+
+#[derive(Clone)]
+pub(crate) struct Injection {
+    pub(crate) edge: Edge,
+    pub(crate) source: InjectionSource,
+    // The line of cells just outside the edge for the current tick
+    pub(crate) ghost: Vec<Cell>,
+}
+
+impl Universe {
+    pub(crate) fn edge_length(&self, edge: Edge) -> usize {
+        match edge {
+            Edge::Top | Edge::Bottom => self.width as usize,
+            Edge::Left | Edge::Right => self.height as usize,
+        }
+    }
+
+    // Map possibly out of range coordinates onto a cell index, None meaning
+    // the coordinates fall outside the universe
+    pub(crate) fn boundary_index(&self, row: i64, column: i64) -> Option<usize> {
+        let height = self.height as i64;
+        let width = self.width as i64;
+
+        let (row, column) = match self.boundary {
+            Boundary::Torus => (row.rem_euclid(height), column.rem_euclid(width)),
+            Boundary::Mirror => (row.max(0).min(height - 1), column.max(0).min(width - 1)),
+            Boundary::Dead | Boundary::Inject => {
+                if row < 0 || row >= height || column < 0 || column >= width {
+                    return None;
+                }
+                (row, column)
+            }
+            Boundary::KleinBottle | Boundary::CrossSurface => {
+                // Each time round one way flips the other axis
+                let mut wrapped = (row.rem_euclid(height), column.rem_euclid(width));
+                if column.div_euclid(width) % 2 != 0 {
+                    wrapped.0 = height - 1 - wrapped.0;
+                }
+                if self.boundary == Boundary::CrossSurface && row.div_euclid(height) % 2 != 0 {
+                    wrapped.1 = width - 1 - wrapped.1;
+                }
+                wrapped
+            }
+            Boundary::Sphere => {
+                let inside = (0..height).contains(&row) && (0..width).contains(&column);
+                if !inside && width != height {
+                    return None;
+                }
+                match (row < 0, row >= height, column < 0, column >= width) {
+                    (false, false, false, false) => (row, column),
+                    (true, false, false, false) => (column, -1 - row),
+                    (false, true, false, false) => (column, 2 * width - 1 - row),
+                    (false, false, true, false) => (-1 - column, row),
+                    (false, false, false, true) => (2 * height - 1 - column, row),
+                    // Off two edges at once, past a corner
+                    _ => return None,
+                }
+            }
+        };
+
+        Some(self.get_index(row as u32, column as u32))
+    }
+
+    // State of possibly out of range coordinates, taking injected cells
+    // into account
+    pub(crate) fn boundary_cell(&self, row: i64, column: i64) -> Cell {
+        if let Some(idx) = self.boundary_index(row, column) {
+            return self.cells[idx];
+        }
+
+        let injection = match &self.injection {
+            Some(injection) if self.boundary == Boundary::Inject => injection,
+            _ => return Cell::Dead,
+        };
+
+        let (height, width) = (self.height as i64, self.width as i64);
+        let position = match injection.edge {
+            Edge::Top if row == -1 => column,
+            Edge::Bottom if row == height => column,
+            Edge::Left if column == -1 => row,
+            Edge::Right if column == width => row,
+            _ => return Cell::Dead,
+        };
+
+        // Corners beyond the ends of the ghost line are dead
+        if position < 0 || position >= injection.ghost.len() as i64 {
+            return Cell::Dead;
+        }
+        injection.ghost[position as usize]
+    }
+
+    pub(crate) fn bounded_neighbour_count(&self, row: u32, column: u32) -> u8 {
+        let mut alive_count = 0;
+        for row_delta in -1..=1 {
+            for column_delta in -1..=1 {
+                if row_delta == 0 && column_delta == 0 {
+                    continue
+                }
+                alive_count += self.boundary_cell(row as i64 + row_delta, column as i64 + column_delta) as u8;
+            }
+        }
+        alive_count
+    }
+
+    // The neighbours of (row, column) in `cells` as bits clockwise from
+    // north, for rules which care where the neighbours are. Anything off the
+    // universe comes from the boundary as usual
+    pub(crate) fn neighbourhood(&self, cells: &[Cell], row: u32, column: u32) -> u8 {
+        NEIGHBOURS.iter().enumerate().fold(0, |neighbourhood, (bit, &(row_delta, column_delta))| {
+            let (row, column) = (row as i64 + row_delta, column as i64 + column_delta);
+            let cell = match self.boundary_index(row, column) {
+                Some(idx) => cells[idx],
+                None => self.boundary_cell(row, column),
+            };
+            neighbourhood | (cell as u8) << bit
+        })
+    }
+
+    // Produce this tick's ghost line, returning the edge cells it can affect
+    // so they get evaluated
+    pub(crate) fn advance_injection(&mut self) -> Vec<usize> {
+        if self.boundary != Boundary::Inject {
+            return vec![];
+        }
+
+        let edge = match &self.injection {
+            Some(injection) => injection.edge,
+            None => return vec![],
+        };
+        let length = self.edge_length(edge);
+
+        let injection = self.injection.as_mut().unwrap();
+        match &mut injection.source {
+            InjectionSource::Random { probability, rng } => {
+                for cell in injection.ghost.iter_mut() {
+                    *cell = if rng.gen::<f32>() < *probability { Cell::Alive } else { Cell::Dead };
+                }
+            }
+            InjectionSource::Pattern { frames, next_frame } => {
+                let start = *next_frame * length;
+                injection.ghost.copy_from_slice(&frames[start..start + length]);
+                *next_frame = (*next_frame + 1) % (frames.len() / length);
+            }
+        }
+
+        (0..length as u32)
+            .map(|position| match edge {
+                Edge::Top => self.get_index(0, position),
+                Edge::Bottom => self.get_index(self.height - 1, position),
+                Edge::Left => self.get_index(position, 0),
+                Edge::Right => self.get_index(position, self.width - 1),
+            })
+            .collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// Switching to Inject without configuring an injection first behaves
+    /// like Dead
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+        self.active_cells = None;
+        self.neighbour_counts.invalidate();
+        self.journal_boundary(boundary);
+        self.timeline_record(TimelineKind::BoundaryChange(boundary));
+        self.replay_record(format_args!("boundary {:?}", boundary));
+    }
+
+    /// Feed `edge` with random cells, each alive with `probability`
+    pub fn set_injection_random(&mut self, edge: Edge, probability: f32, seed: u64) {
+        let source = InjectionSource::Random { probability, rng: Box::new(seeded(seed)) };
+        self.set_injection(edge, source);
+    }
+
+    /// Feed `edge` with a pattern made of one or more frames, each frame
+    /// being one cell (0 dead, anything else alive) per position along the
+    /// edge. Frames are cycled through one per tick. Fails unless the
+    /// pattern is a whole number of frames
+    pub fn set_injection_pattern(&mut self, edge: Edge, pattern: Vec<u8>) -> Result<(), LifeError> {
+        let length = self.edge_length(edge);
+        if pattern.is_empty() || !pattern.len().is_multiple_of(length) {
+            return Err(LifeError::InvalidArgument(format!(
+                "injection pattern length must be a multiple of the edge length {}",
+                length
+            )));
+        }
+
+        let frames = pattern.iter()
+            .map(|&value| if value == 0 { Cell::Dead } else { Cell::Alive })
+            .collect();
+        self.set_injection(edge, InjectionSource::Pattern { frames, next_frame: 0 });
+        Ok(())
+    }
+
+    pub fn clear_injection(&mut self) {
+        self.injection = None;
+        self.active_cells = None;
+    }
+}
+
+impl Universe {
+    fn set_injection(&mut self, edge: Edge, source: InjectionSource) {
+        let ghost = vec![Cell::Dead; self.edge_length(edge)];
+        self.injection = Some(Injection { edge, source, ghost });
+        self.set_boundary(Boundary::Inject);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_boundary() {
+        // Corner cell has no wrapped neighbours
+        let universe = Universe::new(4, 4, vec![(3, 3), (0, 1)]);
+        assert_eq!(2, universe.alive_neighbour_count(0, 0));
+
+        let mut universe = Universe::new(4, 4, vec![(3, 3), (0, 1)]);
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(1, universe.alive_neighbour_count(0, 0));
+    }
+
+    #[test]
+    fn mirror_boundary() {
+        let mut universe = Universe::new(4, 4, vec![(0, 0)]);
+        universe.set_boundary(Boundary::Mirror);
+
+        // The corner reflects into the three virtual cells beyond it
+        assert_eq!(3, universe.alive_neighbour_count(0, 0));
+        assert_eq!(2, universe.alive_neighbour_count(0, 1));
+        assert_eq!(0, universe.alive_neighbour_count(3, 3));
+    }
+
+    #[test]
+    fn glued_boundaries() {
+        // Going off the left or right edge of a Klein bottle comes back on
+        // the other side upside down, top and bottom wrap as usual
+        let mut universe = Universe::new(5, 4, vec![(0, 4)]);
+        universe.set_boundary(Boundary::KleinBottle);
+        assert_eq!(Some(universe.get_index(2, 4)), universe.boundary_index(1, -1));
+        assert_eq!(Some(universe.get_index(2, 0)), universe.boundary_index(1, 5));
+        assert_eq!(Some(universe.get_index(3, 2)), universe.boundary_index(-1, 2));
+        assert_eq!(0, universe.alive_neighbour_count(1, 0));
+        assert_eq!(1, universe.alive_neighbour_count(3, 0));
+
+        // A cross surface flips going off the top and bottom as well
+        universe.set_boundary(Boundary::CrossSurface);
+        assert_eq!(Some(universe.get_index(2, 4)), universe.boundary_index(1, -1));
+        assert_eq!(Some(universe.get_index(3, 3)), universe.boundary_index(-1, 1));
+        assert_eq!(Some(universe.get_index(0, 4)), universe.boundary_index(4, 0));
+
+        // On a sphere the cell above the top row is in the left column, and
+        // past the bottom row is the right column
+        let mut universe = Universe::new(4, 4, vec![(2, 0), (3, 1)]);
+        universe.set_boundary(Boundary::Sphere);
+        assert_eq!(Some(universe.get_index(2, 0)), universe.boundary_index(-1, 2));
+        assert_eq!(Some(universe.get_index(1, 3)), universe.boundary_index(4, 1));
+        assert_eq!(None, universe.boundary_index(-1, -1));
+        assert_eq!(1, universe.alive_neighbour_count(0, 2));
+        assert_eq!(1, universe.alive_neighbour_count(1, 3));
+
+        let mut oblong = Universe::new(4, 3, vec![]);
+        oblong.set_boundary(Boundary::Sphere);
+        assert_eq!(None, oblong.boundary_index(-1, 2));
+    }
+
+    #[test]
+    fn pattern_injection() {
+        // Three alive ghost cells above the top edge give birth beneath them
+        let mut universe = Universe::new(5, 5, vec![]);
+        assert!(universe.set_injection_pattern(Edge::Top, vec![0, 1, 1, 1]).is_err());
+        assert!(universe.set_injection_pattern(Edge::Top, vec![]).is_err());
+        assert_eq!(Boundary::Torus, universe.boundary());
+        universe.set_injection_pattern(Edge::Top, vec![0, 1, 1, 1, 0]).unwrap();
+        universe.tick();
+
+        assert_eq!(Cell::Alive, universe.cells[universe.get_index(0, 2)]);
+        assert_eq!(Cell::Dead, universe.cells[universe.get_index(0, 1)]);
+        assert_eq!(Cell::Dead, universe.cells[universe.get_index(4, 2)]);
+    }
+
+    #[test]
+    fn random_injection() {
+        let mut universe = Universe::new(16, 16, vec![]);
+        universe.set_injection_random(Edge::Left, 1.0, 42);
+        universe.tick();
+
+        // A fully alive ghost column births the whole left column apart
+        // from the two ends which only see two ghost cells
+        for row in 1..15 {
+            assert_eq!(Cell::Alive, universe.cells[universe.get_index(row, 0)]);
+        }
+        assert_eq!(Cell::Dead, universe.cells[universe.get_index(0, 0)]);
+        assert_eq!(Cell::Dead, universe.cells[universe.get_index(8, 1)]);
+    }
+}
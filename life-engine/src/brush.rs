@@ -0,0 +1,149 @@
+// Drawing strokes onto the board
+//
+// Mouse move events arrive far apart when dragging quickly, so painting the
+// cell under each one leaves a dotted trail. `draw_line()` fills in every
+// cell between the last position and the new one instead:
+//
+//     canvas.onmousemove = (e) => {
+//         const [row, column] = cellAt(e);
+//         universe.draw_line(lastRow, lastColumn, row, column, Cell.Alive);
+//         [lastRow, lastColumn] = [row, column];
+//     };
+//
+// On big boards one cell at a time is too fine, so `paint_brush()` sets a
+// whole square or disc around the pointer. Painting with `Cell.Dead` makes
+// either brush an eraser
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BrushShape {
+    /// Every cell within `radius` rows and columns, 2 * radius + 1 across
+    Square,
+    /// Cells within `radius` of the centre, rounded so small discs aren't
+    /// diamonds
+    Circle,
+}
+
+// The cells on a Bresenham line from one end to the other, both included
+fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (mut row, mut column) = (from.0 as i64, from.1 as i64);
+    let (end_row, end_column) = (to.0 as i64, to.1 as i64);
+    let (rows, columns) = ((end_row - row).abs(), -(end_column - column).abs());
+    let (row_step, column_step) = ((end_row - row).signum(), (end_column - column).signum());
+
+    let mut cells = Vec::with_capacity((rows.max(-columns) + 1) as usize);
+    let mut error = rows + columns;
+    loop {
+        cells.push((row as u32, column as u32));
+        if row == end_row && column == end_column {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= columns {
+            error += columns;
+            row += row_step;
+        }
+        if doubled <= rows {
+            error += rows;
+            column += column_step;
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Set every cell on the straight line from (r0, c0) to (r1, c1), ends
+    /// included, to `state`. The line is one edit for undo, and cells past
+    /// the edge of the board are skipped
+    pub fn draw_line(&mut self, r0: u32, c0: u32, r1: u32, c1: u32, state: Cell) {
+        let indices: Vec<u32> = line((r0, c0), (r1, c1)).into_iter()
+            .filter(|&(row, column)| row < self.height && column < self.width)
+            .map(|(row, column)| row * self.width + column)
+            .collect();
+        self.write_cells(&indices, state);
+    }
+
+    /// Set the cells under a `shape` brush of `radius` centred on (row,
+    /// column) to `state` as one edit, skipping any past the edge. A radius
+    /// of 0 is the single cell
+    pub fn paint_brush(&mut self, row: u32, column: u32, shape: BrushShape, radius: u32, state: Cell) {
+        let (row, column, radius) = (row as i64, column as i64, radius as i64);
+        let mut indices = vec![];
+        for r in (row - radius).max(0)..=(row + radius).min(self.height as i64 - 1) {
+            for c in (column - radius).max(0)..=(column + radius).min(self.width as i64 - 1) {
+                let (dr, dc) = (r - row, c - column);
+                if shape == BrushShape::Square || dr * dr + dc * dc <= radius * radius + radius {
+                    indices.push((r * self.width as i64 + c) as u32);
+                }
+            }
+        }
+        self.write_cells(&indices, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_continuous() {
+        assert_eq!(vec![(2, 2)], line((2, 2), (2, 2)));
+        assert_eq!(vec![(0, 3), (0, 2), (0, 1)], line((0, 3), (0, 1)));
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2)], line((0, 0), (3, 2)));
+
+        // Every step moves to a neighbouring cell, whichever way it runs
+        for &(from, to) in &[((0, 0), (5, 17)), ((9, 2), (0, 7)), ((4, 20), (13, 1))] {
+            let cells = line(from, to);
+            assert_eq!((cells[0], cells[cells.len() - 1]), (from, to));
+            assert!(cells.windows(2).all(|pair| {
+                let (a, b) = (pair[0], pair[1]);
+                (a.0 as i64 - b.0 as i64).abs() <= 1 && (a.1 as i64 - b.1 as i64).abs() <= 1
+            }));
+        }
+    }
+
+    #[test]
+    fn draw_line() {
+        let mut universe = Universe::new(6, 4, vec![]);
+        universe.set_undo_capacity(4);
+        universe.draw_line(0, 0, 3, 5, Cell::Alive);
+        assert_eq!(6, universe.population());
+        assert!(universe.is_alive(0, 0) && universe.is_alive(3, 5));
+
+        // Erasing through the middle, running off the bottom of the board
+        universe.draw_line(0, 2, 9, 2, Cell::Dead);
+        assert_eq!(5, universe.population());
+
+        assert!(universe.undo_edit());
+        assert_eq!(6, universe.population());
+        assert!(universe.undo_edit());
+        assert_eq!(0, universe.population());
+    }
+
+    #[test]
+    fn brushes() {
+        let mut universe = Universe::new(12, 12, vec![]);
+        universe.paint_brush(5, 5, BrushShape::Square, 2, Cell::Alive);
+        assert_eq!(25, universe.population());
+        universe.paint_brush(5, 5, BrushShape::Circle, 1, Cell::Dead);
+        assert_eq!(16, universe.population());
+        universe.clear();
+
+        // A radius 2 disc is the 5x5 square less its corners
+        universe.paint_brush(5, 5, BrushShape::Circle, 2, Cell::Alive);
+        assert_eq!(21, universe.population());
+        assert!(!universe.is_alive(3, 3) && universe.is_alive(3, 4));
+        universe.clear();
+
+        // Clipped at the corner of the board
+        universe.paint_brush(0, 11, BrushShape::Square, 3, Cell::Alive);
+        assert_eq!(16, universe.population());
+        universe.paint_brush(0, 0, BrushShape::Circle, 0, Cell::Alive);
+        assert_eq!(17, universe.population());
+    }
+}
@@ -0,0 +1,254 @@
+// Step by step construction of a universe, checked as a whole by build()
+//
+//     let universe = UniverseBuilder::new()
+//         .width(64)
+//         .height(48)
+//         .rule("B36/S23")
+//         .boundary(Boundary::Dead)
+//         .density(0.2)
+//         .seed(7)
+//         .pattern_at(".#./..#/###", 10, 10)
+//         .build()?;
+//
+// Patterns are rows of `#`/`o` (alive) and `.`/`b` (dead) separated by `/`
+// or newlines, the same as in queries, and are drawn over any random fill.
+// Without a width and height the universe is sized to fit the patterns
+
+use crate::error::LifeError;
+use crate::rng::{seeded, unseeded};
+use crate::size::check_size;
+use crate::{random_universe, Boundary, Cell, Rule, RuleError, SizeError, Universe};
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    MissingDimensions,
+    Size(SizeError),
+    InvalidDensity(f32),
+    InvalidRule(RuleError),
+    InvalidPattern(String),
+    PatternOutOfBounds { top: u32, left: u32 },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::MissingDimensions => write!(f, "width and height must be set when there's no pattern"),
+            BuildError::Size(err) => write!(f, "{}", err),
+            BuildError::InvalidDensity(density) => write!(f, "density {} isn't between 0 and 1", density),
+            BuildError::InvalidRule(err) => write!(f, "{}", err),
+            BuildError::InvalidPattern(message) => write!(f, "invalid pattern: {}", message),
+            BuildError::PatternOutOfBounds { top, left } => {
+                write!(f, "pattern at ({}, {}) doesn't fit in the universe", top, left)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+fn parse_pattern(text: &str) -> Result<Vec<Vec<Cell>>, BuildError> {
+    let rows = text.trim().split(['/', '\n'])
+        .map(|row| {
+            row.trim_end_matches('\r').chars()
+                .map(|symbol| match symbol {
+                    '#' | 'o' => Ok(Cell::Alive),
+                    '.' | 'b' => Ok(Cell::Dead),
+                    _ => Err(BuildError::InvalidPattern(format!("unexpected '{}'", symbol))),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if rows.iter().all(|row| row.is_empty()) {
+        return Err(BuildError::InvalidPattern("pattern is empty".to_string()));
+    }
+    Ok(rows)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug)]
+pub struct UniverseBuilder {
+    width: Option<u32>,
+    height: Option<u32>,
+    rule: Option<String>,
+    boundary: Boundary,
+    density: f32,
+    seed: Option<u64>,
+    // (pattern text, top, left)
+    patterns: Vec<(String, u32, u32)>,
+}
+
+impl Default for UniverseBuilder {
+    fn default() -> Self {
+        UniverseBuilder {
+            width: None,
+            height: None,
+            rule: None,
+            boundary: Boundary::Torus,
+            density: 0.0,
+            seed: None,
+            patterns: vec![],
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl UniverseBuilder {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> UniverseBuilder {
+        UniverseBuilder::default()
+    }
+
+    pub fn width(mut self, width: u32) -> UniverseBuilder {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn height(mut self, height: u32) -> UniverseBuilder {
+        self.height = Some(height);
+        self
+    }
+
+    /// In B/S notation, Conway's Life (B3/S23) by default
+    pub fn rule(mut self, rule: &str) -> UniverseBuilder {
+        self.rule = Some(rule.to_string());
+        self
+    }
+
+    pub fn boundary(mut self, boundary: Boundary) -> UniverseBuilder {
+        self.boundary = boundary;
+        self
+    }
+
+    /// Fill with random cells alive with this probability, 0 (empty) by
+    /// default
+    pub fn density(mut self, density: f32) -> UniverseBuilder {
+        self.density = density;
+        self
+    }
+
+    /// Seed for the random fill, without one it's different every time
+    pub fn seed(mut self, seed: u64) -> UniverseBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Draw a pattern with its top left corner at (0, 0)
+    pub fn pattern(self, pattern: &str) -> UniverseBuilder {
+        self.pattern_at(pattern, 0, 0)
+    }
+
+    pub fn pattern_at(mut self, pattern: &str, top: u32, left: u32) -> UniverseBuilder {
+        self.patterns.push((pattern.to_string(), top, left));
+        self
+    }
+
+    #[cfg(feature = "wasm")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = build))]
+    pub fn build_js(self) -> Result<Universe, JsValue> {
+        self.build().map_err(crate::error::js_error)
+    }
+}
+
+impl UniverseBuilder {
+    pub fn build(self) -> Result<Universe, LifeError> {
+        let rule = match &self.rule {
+            Some(rule) => rule.parse::<Rule>().map_err(BuildError::InvalidRule)?,
+            None => Rule::CONWAY,
+        };
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(BuildError::InvalidDensity(self.density).into());
+        }
+
+        let patterns = self.patterns.iter()
+            .map(|(text, top, left)| parse_pattern(text).map(|rows| (rows, *top, *left)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Fit to the patterns where a dimension isn't given
+        let extent = |size: fn(&Vec<Vec<Cell>>) -> u32, offset: fn(u32, u32) -> u32| {
+            patterns.iter().map(|(rows, top, left)| offset(*top, *left).saturating_add(size(rows))).max()
+        };
+        let width = match self.width {
+            Some(width) => width,
+            None => extent(|rows| rows.iter().map(Vec::len).max().unwrap_or(0) as u32, |_, left| left)
+                .ok_or(BuildError::MissingDimensions)?,
+        };
+        let height = match self.height {
+            Some(height) => height,
+            None => extent(|rows| rows.len() as u32, |top, _| top).ok_or(BuildError::MissingDimensions)?,
+        };
+        check_size(width, height).map_err(BuildError::Size)?;
+
+        let mut universe = match self.seed {
+            Some(seed) => random_universe(width, height, self.density, &mut seeded(seed)),
+            None => random_universe(width, height, self.density, &mut unseeded()),
+        };
+        universe.boundary = self.boundary;
+        universe.rule = rule;
+
+        for (rows, top, left) in patterns {
+            let fits = rows.iter().all(|row| left as u64 + row.len() as u64 <= width as u64)
+                && top as u64 + rows.len() as u64 <= height as u64;
+            if !fits {
+                return Err(BuildError::PatternOutOfBounds { top, left }.into());
+            }
+            for (row, cells) in rows.iter().enumerate() {
+                for (column, &cell) in cells.iter().enumerate() {
+                    let idx = universe.get_index(top + row as u32, left + column as u32);
+                    universe.write_cell(idx, cell);
+                }
+            }
+        }
+
+        Ok(universe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_configured_universe() {
+        let universe = UniverseBuilder::new()
+            .width(12)
+            .height(8)
+            .rule("B36/S23")
+            .boundary(Boundary::Dead)
+            .pattern_at(".#./..#/###", 2, 3)
+            .build()
+            .unwrap();
+        assert_eq!((12, 8), (universe.width(), universe.height()));
+        assert_eq!(Boundary::Dead, universe.boundary());
+        assert_eq!("B36/S23", universe.rule());
+        assert_eq!(5, universe.population());
+        assert!(universe.is_alive(2, 4) && universe.is_alive(4, 5) && !universe.is_alive(2, 3));
+
+        // Sized to the pattern
+        let universe = UniverseBuilder::new().pattern("##\n#.\n").build().unwrap();
+        assert_eq!((2, 2), (universe.width(), universe.height()));
+        assert_eq!(3, universe.population());
+
+        let seeded = || UniverseBuilder::new().width(20).height(10).density(0.4).seed(3).build().unwrap();
+        assert_eq!(seeded().cells, seeded().cells);
+        assert_eq!(Universe::random_seeded(20, 10, 0.4, 3).unwrap().cells, seeded().cells);
+    }
+
+    #[test]
+    fn rejects_bad_configuration() {
+        assert_eq!(Some(BuildError::MissingDimensions.into()), UniverseBuilder::new().width(3).build().err());
+        assert!(matches!(UniverseBuilder::new().width(0).height(3).build(), Err(LifeError::InvalidDimensions { .. })));
+        assert!(matches!(UniverseBuilder::new().width(1 << 16).height(1 << 16).build(), Err(LifeError::InvalidDimensions { .. })));
+        assert!(matches!(UniverseBuilder::new().width(4).height(4).density(1.5).build(), Err(LifeError::InvalidArgument(_))));
+        assert!(matches!(UniverseBuilder::new().width(4).height(4).rule("B9").build(), Err(LifeError::UnsupportedRule(_))));
+        assert!(matches!(UniverseBuilder::new().pattern("#x").build(), Err(LifeError::ParseError { .. })));
+        assert_eq!(
+            Some(BuildError::PatternOutOfBounds { top: 3, left: 0 }.into()),
+            UniverseBuilder::new().width(4).height(4).pattern_at("#/#", 3, 0).build().err()
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// Self-benchmarking to pick the fastest tick strategy on this device
+//
+// Which strategy wins depends on the board's size and activity as well as
+// the browser and hardware, so rather than guessing we time a few ticks of
+// each on a copy of the current universe
+
+use crate::utils::now_ms;
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const CALIBRATION_TICKS: u32 = 8;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TickStrategy {
+    // Use the calibrated recommendation, or Sparse if never calibrated
+    Auto,
+    // Only evaluate cells near last tick's changes
+    Sparse,
+    // Evaluate every cell every tick
+    Dense,
+    // Evaluate whole rows near last tick's changes, see dirty_rows.rs
+    Rows,
+    // Evaluate whole tiles near last tick's changes, see tiles.rs
+    Tiles,
+}
+
+const CANDIDATES: [TickStrategy; 4] =
+    [TickStrategy::Sparse, TickStrategy::Dense, TickStrategy::Rows, TickStrategy::Tiles];
+
+impl Universe {
+    // The strategy tick() should actually use
+    pub(crate) fn effective_strategy(&self) -> TickStrategy {
+        match self.strategy {
+            TickStrategy::Auto => self.calibrated_strategy.unwrap_or(TickStrategy::Sparse),
+            strategy => strategy,
+        }
+    }
+
+    // Copy of the board without any of the optional layers, for timing
+    fn calibration_copy(&self) -> Universe {
+        let mut copy = Universe::from_cells(self.width, self.height, self.cells.clone());
+        copy.boundary = self.boundary;
+        copy
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn tick_strategy(&self) -> TickStrategy {
+        self.strategy
+    }
+
+    pub fn set_tick_strategy(&mut self, strategy: TickStrategy) {
+        self.strategy = strategy;
+    }
+
+    /// Time a few ticks of each strategy on a copy of this universe and
+    /// return the fastest, which Auto will use from now on. The universe
+    /// itself isn't advanced
+    pub fn calibrate(&mut self) -> TickStrategy {
+        let mut best = (TickStrategy::Sparse, f64::INFINITY);
+        for &strategy in &CANDIDATES {
+            let mut copy = self.calibration_copy();
+            copy.strategy = strategy;
+
+            // The first tick always evaluates everything so leave it untimed
+            copy.tick();
+            let start = now_ms();
+            for _ in 0..CALIBRATION_TICKS {
+                copy.tick();
+            }
+            let elapsed = now_ms() - start;
+
+            if elapsed < best.1 {
+                best = (strategy, elapsed);
+            }
+        }
+
+        log!("calibrated tick strategy {:?} ({:.3}ms per tick)", best.0, best.1 / CALIBRATION_TICKS as f64);
+        self.calibrated_strategy = Some(best.0);
+        best.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strategies_agree() {
+        let mut sparse = Universe::random_seeded(48, 48, 0.3, 2).unwrap();
+        let mut dense = Universe::random_seeded(48, 48, 0.3, 2).unwrap();
+        sparse.set_tick_strategy(TickStrategy::Sparse);
+        dense.set_tick_strategy(TickStrategy::Dense);
+
+        for _ in 0..20 {
+            sparse.tick();
+            dense.tick();
+            assert_eq!(sparse.cells, dense.cells);
+        }
+    }
+
+    #[test]
+    fn calibrate_leaves_universe_alone() {
+        let mut universe = Universe::random_seeded(48, 48, 0.3, 2).unwrap();
+        let before = universe.cells.clone();
+
+        let recommended = universe.calibrate();
+        assert_ne!(TickStrategy::Auto, recommended);
+        assert_eq!(recommended, universe.effective_strategy());
+        assert_eq!(before, universe.cells);
+        assert_eq!(0, universe.generation);
+    }
+}
@@ -0,0 +1,123 @@
+// Drawing straight onto a 2D canvas context, behind the `web` feature
+//
+// `draw_to_canvas()` and friends take a CanvasRenderingContext2D, and
+// `draw_to_offscreen_canvas()` the OffscreenCanvas one for drawing from a
+// worker. Both go through the same code by way of `Context2d`
+
+use crate::{Cell, Rect, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use web_sys::{CanvasRenderingContext2d, OffscreenCanvasRenderingContext2d};
+
+const DEAD_COLOUR: &str = "#FFFFFF";
+const ALIVE_COLOUR: &str = "#000000";
+
+// The drawing methods the two contexts have in common
+trait Context2d {
+    fn set_fill_style(&self, colour: &str);
+    fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64);
+    fn begin_path(&self);
+    fn rect(&self, x: f64, y: f64, width: f64, height: f64);
+    fn fill(&self);
+}
+
+macro_rules! context_2d {
+    ($context:ty) => {
+        impl Context2d for $context {
+            fn set_fill_style(&self, colour: &str) {
+                self.set_fill_style_str(colour);
+            }
+
+            fn fill_rect(&self, x: f64, y: f64, width: f64, height: f64) {
+                <$context>::fill_rect(self, x, y, width, height);
+            }
+
+            fn begin_path(&self) {
+                <$context>::begin_path(self);
+            }
+
+            fn rect(&self, x: f64, y: f64, width: f64, height: f64) {
+                <$context>::rect(self, x, y, width, height);
+            }
+
+            fn fill(&self) {
+                <$context>::fill(self);
+            }
+        }
+    };
+}
+
+context_2d!(CanvasRenderingContext2d);
+context_2d!(OffscreenCanvasRenderingContext2d);
+
+impl Universe {
+    // Paint `areas` dead, then the live cells within `occupied`, which are
+    // the parts of them that can hold any
+    fn draw_areas(&self, ctx: &impl Context2d, cell_size: u32, areas: &[Rect], occupied: &[Rect]) {
+        let size = cell_size as f64;
+
+        ctx.set_fill_style(DEAD_COLOUR);
+        for area in areas {
+            let [top, left, rows, columns] = [area.top, area.left, area.rows, area.columns].map(|n| n as f64 * size);
+            ctx.fill_rect(left, top, columns, rows);
+        }
+
+        // One path for all the live cells is far cheaper than a fill each
+        ctx.set_fill_style(ALIVE_COLOUR);
+        ctx.begin_path();
+        for (row, column) in occupied.iter().flat_map(Rect::cells) {
+            if self.cells[self.get_index(row, column)] == Cell::Alive {
+                ctx.rect(column as f64 * size, row as f64 * size, size, size);
+            }
+        }
+        ctx.fill();
+    }
+
+    fn draw_rows(&self, ctx: &impl Context2d, cell_size: u32, rows: &[u32]) {
+        let rows = rows.iter().map(|&row| Rect::new(row, 0, 1, self.width)).collect::<Vec<_>>();
+        self.draw_areas(ctx, cell_size, &rows, &rows);
+    }
+
+    fn draw_all(&self, ctx: &impl Context2d, cell_size: u32) {
+        if self.tiles.is_none() {
+            return self.draw_rows(ctx, cell_size, &(0..self.height).collect::<Vec<_>>());
+        }
+        let occupied = self.occupied_tiles(0..self.tile_columns() * self.tile_rows());
+        self.draw_areas(ctx, cell_size, &[Rect::new(0, 0, self.height, self.width)], &occupied);
+    }
+
+    // Tiles with live cells in, as rects
+    fn occupied_tiles(&self, tiles: impl Iterator<Item = u32>) -> Vec<Rect> {
+        let populations = self.tile_populations();
+        tiles.filter(|&tile| populations[tile as usize] > 0).filter_map(|tile| self.tile_rect(tile)).collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Draw every cell as a `cell_size` pixel square with the top left cell
+    /// at the canvas origin. With tiles on, empty tiles are only filled in
+    pub fn draw_to_canvas(&self, ctx: &CanvasRenderingContext2d, cell_size: u32) {
+        self.draw_all(ctx, cell_size);
+    }
+
+    /// `draw_to_canvas()` for an OffscreenCanvas, from a worker
+    pub fn draw_to_offscreen_canvas(&self, ctx: &OffscreenCanvasRenderingContext2d, cell_size: u32) {
+        self.draw_all(ctx, cell_size);
+    }
+
+    /// Redraw only the rows in `dirty_rows()`, for a canvas already showing
+    /// the previous generation
+    pub fn draw_dirty_rows_to_canvas(&self, ctx: &CanvasRenderingContext2d, cell_size: u32) {
+        self.draw_rows(ctx, cell_size, &self.dirty_rows());
+    }
+
+    /// Redraw only the tiles in `changed_tiles()`, like
+    /// `draw_dirty_rows_to_canvas()`. Draws nothing while tiles are off
+    pub fn draw_changed_tiles_to_canvas(&self, ctx: &CanvasRenderingContext2d, cell_size: u32) {
+        let changed = self.changed_tiles();
+        let areas = changed.iter().filter_map(|&tile| self.tile_rect(tile)).collect::<Vec<_>>();
+        self.draw_areas(ctx, cell_size, &areas, &self.occupied_tiles(changed.into_iter()));
+    }
+}
@@ -0,0 +1,124 @@
+// A byte of application data for every cell
+//
+// Cell data is a buffer alongside the cells that the engine only moves
+// around, never reads: a tag, a territory id or a note index, whatever the
+// application wants. A live cell keeps its value for as long as it
+// survives. Any change of state, whether from a tick or an edit, resets
+// the cell's value to 0, so a newborn cell starts blank and a dead cell
+// keeps nothing from its life. Dead cells can still be given values, which
+// stay until they're born. It's off until `enable_cell_data()`, and JS can
+// read it in place through `cell_data_ptr()`
+
+use crate::{Region, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Called from tick() with the cells that just flipped, and for edits
+    pub(crate) fn reset_cell_data(&mut self, changed: &[usize]) {
+        if let Some(data) = self.cell_data.as_mut() {
+            for &idx in changed {
+                data[idx] = 0;
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start keeping a byte of data per cell, all 0
+    pub fn enable_cell_data(&mut self) {
+        if self.cell_data.is_none() {
+            self.cell_data = Some(vec![0; self.cells.len()]);
+        }
+    }
+
+    pub fn disable_cell_data(&mut self) {
+        self.cell_data = None;
+    }
+
+    pub fn cell_data_enabled(&self) -> bool {
+        self.cell_data.is_some()
+    }
+
+    /// The cell's value, 0 while cell data is off
+    pub fn cell_data_at(&self, row: u32, column: u32) -> u8 {
+        self.cell_data.as_ref().map_or(0, |data| data[self.get_index(row, column)])
+    }
+
+    /// Give the cell a value, turning cell data on if it's off
+    pub fn set_cell_data(&mut self, row: u32, column: u32, value: u8) {
+        self.enable_cell_data();
+        let idx = self.get_index(row, column);
+        self.cell_data.as_mut().unwrap()[idx] = value;
+    }
+
+    /// Give every cell of the region on the board a value, turning cell
+    /// data on if it's off
+    pub fn fill_cell_data(&mut self, region: &Region, value: u8) {
+        self.enable_cell_data();
+        let cells = self.clip_region(region).cells().collect::<Vec<_>>();
+        for (row, column) in cells {
+            let idx = self.get_index(row, column);
+            self.cell_data.as_mut().unwrap()[idx] = value;
+        }
+    }
+
+    /// Set the region's values back to 0
+    pub fn clear_cell_data(&mut self, region: &Region) {
+        if self.cell_data.is_some() {
+            self.fill_cell_data(region, 0);
+        }
+    }
+
+    /// Every cell's value, in the same layout as the cell buffer
+    pub fn cell_data(&self) -> Vec<u8> {
+        match &self.cell_data {
+            Some(data) => data.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Pointer to the values in WASM memory, one byte per cell, or null
+    /// while cell data is off. Only valid until the next call that changes
+    /// the universe
+    pub fn cell_data_ptr(&self) -> *const u8 {
+        match &self.cell_data {
+            Some(data) => data.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rect;
+
+    #[test]
+    fn kept_while_cells_survive() {
+        // A block and a blinker
+        let mut universe = Universe::new(10, 10, vec![(1, 1), (1, 2), (2, 1), (2, 2), (6, 5), (6, 6), (6, 7)]);
+        assert!(universe.cell_data_ptr().is_null());
+        universe.fill_cell_data(&Region::from_rect(Rect::new(1, 1, 2, 2)), 7);
+        universe.fill_cell_data(&Region::from_rect(Rect::new(6, 5, 1, 3)), 9);
+        universe.set_cell_data(5, 6, 3);
+        universe.tick();
+
+        assert_eq!(7, universe.cell_data_at(2, 2));
+        // The blinker's centre survives, its ends die and the cell above
+        // is born
+        assert_eq!(9, universe.cell_data_at(6, 6));
+        assert_eq!((0, 0), (universe.cell_data_at(6, 5), universe.cell_data_at(5, 6)));
+
+        universe.toggle_cell(1, 1);
+        assert_eq!(0, universe.cell_data_at(1, 1));
+        universe.clear_cell_data(&Region::from_rect(Rect::new(0, 0, 20, 3)));
+        let tagged = universe.cell_data().iter().filter(|&&value| value != 0).count();
+        assert_eq!((1, 9), (tagged, universe.cell_data_at(6, 6)));
+
+        universe.disable_cell_data();
+        assert_eq!(0, universe.cell_data_at(6, 6));
+    }
+}
@@ -0,0 +1,299 @@
+// Splitting the board into separate objects and naming the common ones
+//
+// Live cells at most two apart (one dead cell between them) belong to the
+// same object, close enough that they'd interact. Plain adjacency would
+// split spaceships like the LWSS, which are never fully connected. Each
+// object is reduced to a canonical form, the smallest of its eight
+// rotations and reflections, and looked up among every phase of a catalog
+// of well known Conway's Life objects. From JS the census comes back as
+// an object, anything not in the catalog counted under "other":
+//
+//     {block: 3, blinker: 2, glider: 1, other: 4}
+
+use crate::rule::NEIGHBOURS;
+use crate::{Boundary, Cell, Rule, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const OTHER: &str = "other";
+
+/// How many of each kind of object there are, by name or apgcode
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(hashmap_as_object))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjectCounts(pub BTreeMap<String, u32>);
+
+// Name, period and one phase of each known object
+const CATALOG: &[(&str, usize, &[&str])] = &[
+    ("block", 1, &["OO", "OO"]),
+    ("beehive", 1, &[".OO.", "O..O", ".OO."]),
+    ("loaf", 1, &[".OO.", "O..O", ".O.O", "..O."]),
+    ("boat", 1, &["OO.", "O.O", ".O."]),
+    ("ship", 1, &["OO.", "O.O", ".OO"]),
+    ("tub", 1, &[".O.", "O.O", ".O."]),
+    ("pond", 1, &[".OO.", "O..O", "O..O", ".OO."]),
+    ("blinker", 2, &["OOO"]),
+    ("toad", 2, &[".OOO", "OOO."]),
+    ("beacon", 2, &["OO..", "OO..", "..OO", "..OO"]),
+    ("pulsar", 3, &[
+        "..OOO...OOO..",
+        ".............",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        "..OOO...OOO..",
+        ".............",
+        "..OOO...OOO..",
+        "O....O.O....O",
+        "O....O.O....O",
+        "O....O.O....O",
+        ".............",
+        "..OOO...OOO..",
+    ]),
+    ("pentadecathlon", 15, &["..O....O..", "OO.OOOO.OO", "..O....O.."]),
+    ("glider", 4, &[".O.", "..O", "OOO"]),
+    ("lwss", 4, &[".O..O", "O....", "O...O", "OOOO."]),
+    ("mwss", 4, &["...O..", ".O...O", "O.....", "O....O", "OOOOO."]),
+    ("hwss", 4, &["...OO..", ".O....O", "O......", "O.....O", "OOOOOO."]),
+];
+
+// One of the eight rotations and reflections of a cell, 0 leaving it as is
+pub(crate) fn orient((row, column): (i64, i64), orientation: u8) -> (i64, i64) {
+    match orientation {
+        0 => (row, column),
+        1 => (column, -row),
+        2 => (-row, -column),
+        3 => (-column, row),
+        4 => (row, -column),
+        5 => (-row, column),
+        6 => (column, row),
+        _ => (-column, -row),
+    }
+}
+
+// Shift cells so their bounding box starts at the origin, and sort them
+pub(crate) fn normalise(cells: &mut [(i64, i64)]) {
+    let top = cells.iter().map(|&(row, _)| row).min().unwrap_or(0);
+    let left = cells.iter().map(|&(_, column)| column).min().unwrap_or(0);
+    for cell in cells.iter_mut() {
+        *cell = (cell.0 - top, cell.1 - left);
+    }
+    cells.sort_unstable();
+}
+
+// Smallest of the object's eight orientations once normalised
+fn canonical(cells: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    (0..8)
+        .map(|orientation| {
+            let mut oriented = cells.iter().map(|&cell| orient(cell, orientation)).collect::<Vec<_>>();
+            normalise(&mut oriented);
+            oriented
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+// Next generation of a pattern on an unbounded plane. Rules with B0 would
+// fill the plane, callers rule those out
+pub(crate) fn step(cells: &[(i64, i64)], rule: Rule) -> Vec<(i64, i64)> {
+    let alive = cells.iter().cloned().collect::<HashSet<_>>();
+    // Neighbourhoods of every cell next to a live one, each live cell being
+    // the neighbour in the opposite direction to the offset reaching it
+    let mut neighbourhoods = HashMap::new();
+    for &(row, column) in cells {
+        for (bit, &(row_delta, column_delta)) in NEIGHBOURS.iter().enumerate() {
+            *neighbourhoods.entry((row + row_delta, column + column_delta)).or_insert(0u8) |= 1 << ((bit + 4) % 8);
+        }
+    }
+
+    neighbourhoods.into_iter()
+        .filter(|&(cell, neighbourhood)| {
+            let current = if alive.contains(&cell) { Cell::Alive } else { Cell::Dead };
+            rule.next_neighbourhood(current, neighbourhood) == Cell::Alive
+        })
+        .map(|(cell, _)| cell)
+        .collect()
+}
+
+// Canonical form of every phase of every catalog object
+fn catalog() -> HashMap<Vec<(i64, i64)>, &'static str> {
+    let mut forms = HashMap::new();
+    for &(name, period, rows) in CATALOG {
+        let mut phase = rows.iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == 'O')
+                    .map(move |(column, _)| (row as i64, column as i64))
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..period {
+            forms.insert(canonical(&phase), name);
+            phase = step(&phase, Rule::CONWAY);
+        }
+    }
+    forms
+}
+
+impl Universe {
+    /// Live cells grouped into separate objects. Coordinates are relative to
+    /// the universe, except that objects wrapping around a torus carry on
+    /// past the edge so they stay in one piece
+    pub fn objects(&self) -> Vec<Vec<(i64, i64)>> {
+        let torus = self.boundary == Boundary::Torus;
+        let (height, width) = (self.height as i64, self.width as i64);
+
+        let mut seen = vec![false; self.cells.len()];
+        let mut objects = vec![];
+        for start in 0..self.cells.len() {
+            if seen[start] || self.cells[start] == Cell::Dead {
+                continue
+            }
+
+            seen[start] = true;
+            let mut object = vec![];
+            let mut pending = vec![(start as i64 / width, start as i64 % width)];
+            while let Some((row, column)) = pending.pop() {
+                object.push((row, column));
+                for row_delta in -2..=2 {
+                    for column_delta in -2..=2 {
+                        let (neighbour_row, neighbour_column) = (row + row_delta, column + column_delta);
+                        let (wrapped_row, wrapped_column) = if torus {
+                            (neighbour_row.rem_euclid(height), neighbour_column.rem_euclid(width))
+                        } else if (0..height).contains(&neighbour_row) && (0..width).contains(&neighbour_column) {
+                            (neighbour_row, neighbour_column)
+                        } else {
+                            continue
+                        };
+
+                        let idx = self.get_index(wrapped_row as u32, wrapped_column as u32);
+                        if !seen[idx] && self.cells[idx] == Cell::Alive {
+                            seen[idx] = true;
+                            pending.push((neighbour_row, neighbour_column));
+                        }
+                    }
+                }
+            }
+            objects.push(object);
+        }
+        objects
+    }
+
+    /// Number of objects of each kind, see `census`
+    pub fn census_counts(&self) -> BTreeMap<&'static str, u32> {
+        let catalog = catalog();
+        let mut counts = BTreeMap::new();
+        for object in self.objects() {
+            let name = catalog.get(&canonical(&object)).cloned().unwrap_or(OTHER);
+            *counts.entry(name).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// JSON object mapping the name of each kind of object on the board,
+    /// e.g. "block" or "glider", to how many there are
+    pub fn census(&self) -> String {
+        let mut out = String::from("{");
+        for (i, (name, count)) in self.census_counts().iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "\"{}\":{}", name, count).unwrap();
+        }
+        out.push('}');
+        out
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Universe {
+    /// How many there are of each kind of object on the board, e.g.
+    /// `{block: 3, glider: 1}`. Only Conway's Life objects are recognised,
+    /// the rest are counted as "other"
+    #[wasm_bindgen(js_name = census)]
+    pub fn census_js(&self) -> Ts<ObjectCounts> {
+        let counts = self.census_counts().into_iter().map(|(name, count)| (name.to_string(), count)).collect();
+        crate::utils::to_js(&ObjectCounts(counts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(alive: &mut Vec<(u32, u32)>, top: u32, left: u32, rows: &[&str]) {
+        for (row, line) in rows.iter().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                if c == 'O' {
+                    alive.push((top + row as u32, left + column as u32));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn classifies_objects() {
+        let mut alive = vec![];
+        place(&mut alive, 1, 1, &["OO", "OO"]);
+        place(&mut alive, 1, 10, &["OO", "OO"]);
+        place(&mut alive, 6, 1, &["O", "O", "O"]);
+        place(&mut alive, 6, 10, &[".O.", "O..", "OOO"]);
+        place(&mut alive, 12, 1, &["O..", "OO.", ".OO"]);
+        place(&mut alive, 12, 10, &["OOOO.", "O...O", "O....", ".O..O"]);
+        place(&mut alive, 20, 1, &["OO.", "O.O", ".OO"]);
+        place(&mut alive, 20, 10, &["OOOOO"]);
+        let universe = Universe::new(24, 24, alive);
+
+        let counts = universe.census_counts();
+        assert_eq!(Some(&2), counts.get("block"));
+        assert_eq!(Some(&1), counts.get("blinker"));
+        assert_eq!(Some(&1), counts.get("glider"));
+        assert_eq!(Some(&1), counts.get("lwss"));
+        assert_eq!(Some(&1), counts.get("ship"));
+        // The bent triomino and the row of five
+        assert_eq!(Some(&2), counts.get("other"));
+        assert_eq!(8, universe.objects().len());
+        assert_eq!(
+            "{\"blinker\":1,\"block\":2,\"glider\":1,\"lwss\":1,\"other\":2,\"ship\":1}",
+            universe.census(),
+        );
+    }
+
+    #[test]
+    fn every_phase_is_recognised() {
+        let mut alive = vec![];
+        place(&mut alive, 8, 8, &["...O..", ".O...O", "O.....", "O....O", "OOOOO."]);
+        place(&mut alive, 20, 20, &[".OOO", "OOO."]);
+        let mut universe = Universe::new(32, 32, alive);
+
+        for _ in 0..8 {
+            let counts = universe.census_counts();
+            assert_eq!(Some(&1), counts.get("mwss"));
+            assert_eq!(Some(&1), counts.get("toad"));
+            universe.tick();
+        }
+        assert_eq!("{}", Universe::new(4, 4, vec![]).census());
+    }
+
+    #[test]
+    fn objects_wrap_around_a_torus() {
+        // Block split across all four corners
+        let mut universe = Universe::new(8, 8, vec![(0, 0), (0, 7), (7, 0), (7, 7)]);
+        assert_eq!(1, universe.objects().len());
+        assert_eq!(Some(&1), universe.census_counts().get("block"));
+
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(4, universe.objects().len());
+        assert_eq!(Some(&4), universe.census_counts().get("other"));
+    }
+}
@@ -0,0 +1,161 @@
+// Labelling the connected clusters of live cells
+//
+// Live cells touching, including diagonally, are in the same cluster, and
+// on a torus cells touching across an edge are too. This is plain
+// connectivity, unlike the census's objects which also join cells one dead
+// cell apart. Clusters are numbered from 1 in reading order of their first
+// cell, and `cluster_labels()` gives every cell its cluster's id (0 for dead
+// cells) for colouring each one differently:
+//
+//     [{id: 1, size: 5, top: 1, left: 1, rows: 3, columns: 3}, ...]
+
+use crate::rule::NEIGHBOURS;
+use crate::{Boundary, Cell, Rect, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cluster {
+    pub id: u32,
+    /// Live cells in the cluster
+    pub size: u32,
+    /// Bounding box on the board. A cluster joined across the edge of a
+    /// torus spans the board between its pieces
+    pub top: u32,
+    pub left: u32,
+    pub rows: u32,
+    pub columns: u32,
+}
+
+impl Cluster {
+    pub fn bounds(&self) -> Rect {
+        Rect::new(self.top, self.left, self.rows, self.columns)
+    }
+}
+
+/// Every cluster, in id order
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clusters(pub Vec<Cluster>);
+
+impl Universe {
+    // The cluster id of each cell, and how many clusters there are
+    fn label_clusters(&self) -> (Vec<u32>, u32) {
+        let torus = self.boundary == Boundary::Torus;
+        let (height, width) = (self.height as i64, self.width as i64);
+        let mut labels = vec![0; self.cells.len()];
+        let mut count = 0;
+
+        for start in 0..self.cells.len() {
+            if labels[start] != 0 || self.cells[start] == Cell::Dead {
+                continue
+            }
+            count += 1;
+            labels[start] = count;
+            let mut pending = vec![start];
+            while let Some(idx) = pending.pop() {
+                let (row, column) = (idx as i64 / width, idx as i64 % width);
+                for (row_delta, column_delta) in NEIGHBOURS {
+                    let (mut neighbour_row, mut neighbour_column) = (row + row_delta, column + column_delta);
+                    if torus {
+                        neighbour_row = neighbour_row.rem_euclid(height);
+                        neighbour_column = neighbour_column.rem_euclid(width);
+                    } else if !(0..height).contains(&neighbour_row) || !(0..width).contains(&neighbour_column) {
+                        continue
+                    }
+
+                    let neighbour = (neighbour_row * width + neighbour_column) as usize;
+                    if labels[neighbour] == 0 && self.cells[neighbour] == Cell::Alive {
+                        labels[neighbour] = count;
+                        pending.push(neighbour);
+                    }
+                }
+            }
+        }
+        (labels, count)
+    }
+
+    pub fn clusters(&self) -> Vec<Cluster> {
+        let (labels, count) = self.label_clusters();
+        // Size and inclusive bounds of each, indexed by id - 1
+        let mut found = vec![(0, u32::MAX, u32::MAX, 0, 0); count as usize];
+        for (idx, &label) in labels.iter().enumerate().filter(|(_, &label)| label != 0) {
+            let (row, column) = (idx as u32 / self.width, idx as u32 % self.width);
+            let (size, top, left, bottom, right) = &mut found[label as usize - 1];
+            *size += 1;
+            *top = (*top).min(row);
+            *left = (*left).min(column);
+            *bottom = (*bottom).max(row);
+            *right = (*right).max(column);
+        }
+
+        found.into_iter().enumerate()
+            .map(|(i, (size, top, left, bottom, right))| Cluster {
+                id: i as u32 + 1,
+                size,
+                top,
+                left,
+                rows: bottom - top + 1,
+                columns: right - left + 1,
+            })
+            .collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Id of the cluster each cell belongs to, row by row, 0 for dead cells
+    pub fn cluster_labels(&self) -> Vec<u32> {
+        self.label_clusters().0
+    }
+
+    pub fn cluster_count(&self) -> u32 {
+        self.label_clusters().1
+    }
+
+    /// Every cluster of connected live cells, with its id, size and
+    /// bounding box
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = clusters)]
+    pub fn clusters_js(&self) -> Ts<Clusters> {
+        crate::utils::to_js(&Clusters(self.clusters()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_clusters() {
+        // A glider, a lone cell two away from it and a block
+        let universe = Universe::new(10, 8, vec![
+            (1, 2), (2, 3), (3, 1), (3, 2), (3, 3),
+            (3, 5),
+            (6, 7), (6, 8), (7, 7), (7, 8),
+        ]);
+        let clusters = universe.clusters();
+        assert_eq!(3, clusters.len());
+        assert_eq!(Cluster { id: 1, size: 5, top: 1, left: 1, rows: 3, columns: 3 }, clusters[0]);
+        assert_eq!((2, 1), (clusters[1].id, clusters[1].size));
+        assert_eq!(Rect::new(6, 7, 2, 2), clusters[2].bounds());
+
+        let labels = universe.cluster_labels();
+        assert_eq!((1, 2, 3, 0), (labels[12], labels[35], labels[77], labels[0]));
+
+        // Joined round the torus, but not with a dead edge
+        let mut universe = Universe::new(6, 4, vec![(1, 0), (1, 5), (2, 5)]);
+        assert_eq!(1, universe.cluster_count());
+        assert_eq!(Rect::new(1, 0, 2, 6), universe.clusters()[0].bounds());
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(2, universe.cluster_count());
+    }
+}
@@ -0,0 +1,142 @@
+// Cutting parts out of a universe and pasting universes into each other
+//
+// `crop` copies a rectangle into a new universe with the same settings, for
+// keeping an interesting corner or studying a structure on its own, and
+// `overlay` composites a saved pattern or board back onto a running one.
+// Overlays are ordinary edits, so they're journaled and can be undone like
+// any other
+
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::{Cell, Rect, SizeError, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How an overlaid universe's cells combine with the ones underneath
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayMode {
+    /// Alive where either is alive, the usual way to stamp a pattern down
+    Or,
+    /// Alive where exactly one is alive, so overlaying twice undoes it
+    Xor,
+    /// Alive only where both are alive
+    And,
+    /// The overlaid cells win, dead ones included
+    Replace,
+}
+
+impl OverlayMode {
+    fn combine(self, under: Cell, over: Cell) -> Cell {
+        let alive = match self {
+            OverlayMode::Or => under == Cell::Alive || over == Cell::Alive,
+            OverlayMode::Xor => (under == Cell::Alive) != (over == Cell::Alive),
+            OverlayMode::And => under == Cell::Alive && over == Cell::Alive,
+            OverlayMode::Replace => over == Cell::Alive,
+        };
+        if alive { Cell::Alive } else { Cell::Dead }
+    }
+}
+
+impl Universe {
+    /// Rust side of `crop`
+    pub fn crop_to(&self, area: &Rect) -> Result<Universe, LifeError> {
+        // Whatever part of the area is inside the universe
+        let clipped = area.intersection(&self.bounds())
+            .ok_or(SizeError::Empty { width: area.columns, height: area.rows })?;
+        check_size(clipped.columns, clipped.rows)?;
+
+        let cells = clipped.cells().map(|(row, column)| self.cells[self.get_index(row, column)]).collect();
+        let mut cropped = Universe::from_cells(clipped.columns, clipped.rows, cells);
+        cropped.boundary = self.boundary;
+        cropped.rule = self.rule;
+        cropped.render_config = self.render_config.clone();
+        Ok(cropped)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// A new universe holding a copy of the cells in `area`, clipped to this
+    /// universe, with the same boundary, rule and render settings. Throws if
+    /// none of the area is inside the universe
+    #[cfg(feature = "wasm")]
+    pub fn crop(&self, area: &Rect) -> Result<Universe, JsValue> {
+        self.crop_to(area).map_err(crate::error::js_error)
+    }
+
+    /// Combine `other` into this universe with its top left corner at (row,
+    /// column). Anything hanging off the edges is left out
+    pub fn overlay(&mut self, other: &Universe, row: u32, column: u32, mode: OverlayMode) {
+        let placed = Rect::new(row, column, other.height, other.width);
+        let area = match placed.intersection(&self.bounds()) {
+            Some(area) => area,
+            None => return,
+        };
+        self.edit_group(|universe| {
+            for (target_row, target_column) in area.cells() {
+                let idx = universe.get_index(target_row, target_column);
+                let over = other.cells[other.get_index(target_row - row, target_column - column)];
+                universe.write_cell(idx, mode.combine(universe.cells[idx], over));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Boundary, RenderConfig};
+
+    #[test]
+    fn crop_copies_region() {
+        let mut universe: Universe = "\
+            ........\n\
+            ..#.....\n\
+            ...#....\n\
+            .###....\n\
+            ......##\n".parse().unwrap();
+        universe.set_boundary(Boundary::Dead);
+
+        let glider = universe.crop_to(&Rect::new(1, 1, 3, 3)).unwrap();
+        assert_eq!((3, 3), (glider.width, glider.height));
+        assert_eq!(".#.\n..#\n###\n", glider.render_with(&RenderConfig::ascii()));
+        assert_eq!(Boundary::Dead, glider.boundary);
+
+        // Clipped to the universe
+        let corner = universe.crop_to(&Rect::new(3, 6, 10, 10)).unwrap();
+        assert_eq!((2, 2), (corner.width, corner.height));
+        assert_eq!(2, corner.population());
+
+        assert!(universe.crop_to(&Rect::new(9, 0, 2, 2)).is_err());
+        assert!(universe.crop_to(&Rect::new(0, 0, 0, 2)).is_err());
+    }
+
+    #[test]
+    fn overlay_modes() {
+        let board = || -> Universe { "##..\n##..\n....\n".parse().unwrap() };
+        let stamp: Universe = "#.\n.#\n".parse().unwrap();
+        let overlaid = |mode, row, column| {
+            let mut universe = board();
+            universe.overlay(&stamp, row, column, mode);
+            universe.render_with(&RenderConfig::ascii())
+        };
+
+        assert_eq!("##..\n##..\n....\n", overlaid(OverlayMode::Or, 0, 0));
+        assert_eq!(".#..\n#...\n....\n", overlaid(OverlayMode::Xor, 0, 0));
+        assert_eq!("#...\n.#..\n....\n", overlaid(OverlayMode::And, 0, 0));
+        assert_eq!("##..\n#.#.\n....\n", overlaid(OverlayMode::Replace, 0, 1));
+        // Clipped at the corner
+        assert_eq!("##..\n##..\n...#\n", overlaid(OverlayMode::Or, 2, 3));
+        assert_eq!("##..\n##..\n....\n", overlaid(OverlayMode::Or, 7, 7));
+
+        // Overlaid cells tick like hand drawn ones
+        let mut universe: Universe = "....\n....\n....\n....\n".parse().unwrap();
+        universe.tick();
+        universe.overlay(&"###\n".parse().unwrap(), 1, 0, OverlayMode::Or);
+        assert_eq!(3, universe.population());
+        universe.tick();
+        assert!(universe.is_alive(0, 1) && universe.is_alive(2, 1) && !universe.is_alive(1, 0));
+    }
+}
@@ -0,0 +1,109 @@
+// Critters, a reversible and particle conserving block automaton, as an
+// alternative rule for the standard grid
+//
+// The grid is split into 2x2 blocks, offset by one cell diagonally on every
+// other tick. The raw Critters rule leaves blocks of two alive cells alone,
+// inverts and rotates blocks of three by 180 degrees and inverts everything
+// else. Raw Critters only conserves particles if alive and dead swap
+// meaning every tick, so cells are stored in that alternating "particle
+// view" instead. That way the population really is constant and everything
+// else (editing, rendering, stats) works on particles without knowing about
+// the phase. The blocks themselves are run by margolus.rs
+
+use crate::{BlockRule, Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+fn invert(block: [Cell; 4]) -> [Cell; 4] {
+    let mut inverted = block;
+    for cell in inverted.iter_mut() {
+        *cell = if *cell == Cell::Alive { Cell::Dead } else { Cell::Alive };
+    }
+    inverted
+}
+
+// Raw Critters rule on a block laid out [top left, top right, bottom left,
+// bottom right]
+fn critters_rule(block: [Cell; 4]) -> [Cell; 4] {
+    let alive = block.iter().filter(|&&cell| cell == Cell::Alive).count();
+    match alive {
+        2 => block,
+        3 => {
+            let inverted = invert(block);
+            [inverted[3], inverted[2], inverted[1], inverted[0]]
+        }
+        _ => invert(block),
+    }
+}
+
+// The rule as seen in the alternating particle view
+pub(crate) fn particle_rule(block: [Cell; 4], phase: u32) -> [Cell; 4] {
+    if phase == 0 {
+        invert(critters_rule(block))
+    } else {
+        critters_rule(invert(block))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Switch tick() between Conway's rules and Critters, the block rule
+    /// `BlockRule::critters()`. Critters needs even dimensions so the
+    /// blocks tile, returns whether it's enabled
+    pub fn set_critters_mode(&mut self, enabled: bool) -> bool {
+        if !enabled {
+            // Leaving any other block rule running
+            if self.critters_mode() {
+                self.clear_block_rule();
+            }
+            return false;
+        }
+        self.critters_mode() || self.set_block_rule(&BlockRule::critters())
+    }
+
+    pub fn critters_mode(&self) -> bool {
+        self.block_rule() == Some(BlockRule::critters())
+    }
+
+    /// Which block partition the next tick uses, 0 or 1
+    pub fn critters_phase(&self) -> u32 {
+        self.block_phase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conserves_particles() {
+        let mut universe = Universe::random_seeded(32, 32, 0.2, 11).unwrap();
+        assert!(universe.set_critters_mode(true));
+
+        let population = universe.population();
+        for _ in 0..50 {
+            universe.tick();
+            assert_eq!(population, universe.population());
+        }
+    }
+
+    #[test]
+    fn reversible() {
+        // Reversible means no two blocks map to the same result
+        let mut seen = std::collections::HashSet::new();
+        for bits in 0..16u8 {
+            let block = [0, 1, 2, 3].map(|i| if bits >> i & 1 == 1 { Cell::Alive } else { Cell::Dead });
+            for &phase in &[0, 1] {
+                assert!(seen.insert((phase, particle_rule(block, phase))));
+            }
+        }
+    }
+
+    #[test]
+    fn odd_dimensions() {
+        let mut universe = Universe::new(5, 4, vec![]);
+        assert!(!universe.set_critters_mode(true));
+        assert!(!universe.critters_mode());
+    }
+}
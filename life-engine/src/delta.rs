@@ -0,0 +1,451 @@
+// Differential serialization for autosaves and live mirrors
+//
+// With change tracking enabled every cell remembers the last generation it
+// changed in, so `serialize_delta(since)` only needs to write the cells
+// touched since an earlier full save. Patches carry absolute cell states so
+// including a cell which changed and then changed back is harmless.
+//
+// For mirroring a universe as it runs, say from a server to browsers over
+// a WebSocket, `delta_since_last()` gives the cells changed since it was
+// last called, costing a list of those cells rather than 8 bytes for every
+// one. The first call starts the stream with a keyframe: every cell, with
+// a base generation of u64::MAX meaning it applies whatever generation the
+// receiver is at. `delta_keyframe()` gives one any time for viewers joining
+// late. A mirror is a universe of the same size fed each frame in turn
+// through `apply_delta()`. Rotating a non-square board breaks the stream,
+// as its size changes, and the next frame is a keyframe of the new size.
+//
+// Like snapshots, deltas hold the true states, not the complemented buffer
+// of a B0 rule (see strobing.rs). Every cell's true state changes when the
+// buffer changes phase, so that counts as a change to every cell: a B0 rule
+// without S8 changes phase every tick, and every frame then lists every
+// cell.
+//
+// Layout, integers little endian:
+//
+//     bytes 0..2    magic b"GD"
+//     byte  2       format version (1)
+//     bytes 3..7    width  (u32)
+//     bytes 7..11   height (u32)
+//     bytes 11..19  base generation the patch applies on top of (u64)
+//     bytes 19..27  generation after applying (u64)
+//     bytes 27..31  number of entries (u32)
+//     bytes 31..    entries as LEB128 varints of (index gap << 1 | state)
+
+use crate::error::LifeError;
+use crate::{Cell, Universe};
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8; 2] = b"GD";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 31;
+// Base generation of a delta listing every cell
+const KEYFRAME: u64 = u64::MAX;
+
+#[derive(Clone)]
+pub(crate) struct ChangeLog {
+    // Generation each cell last changed in
+    stamps: Vec<u64>,
+    // Generation tracking was enabled at, deltas can't go back further
+    started: u64,
+}
+
+impl ChangeLog {
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.stamps.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct DeltaStream {
+    // Cells changed since the last frame, each once
+    changed: Vec<usize>,
+    marked: Vec<bool>,
+    // Generation of the last frame
+    base: u64,
+}
+
+impl DeltaStream {
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.changed.capacity() * std::mem::size_of::<usize>() + self.marked.capacity()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeltaError {
+    NotTracking,
+    TooOld { since: u64, tracked_from: u64 },
+    BadHeader,
+    UnsupportedVersion(u8),
+    DimensionMismatch,
+    GenerationMismatch { expected: u64, found: u64 },
+    Truncated,
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeltaError::NotTracking => write!(f, "change tracking isn't enabled"),
+            DeltaError::TooOld { since, tracked_from } => write!(
+                f, "can't produce a delta since generation {}, changes are only tracked from {}",
+                since, tracked_from
+            ),
+            DeltaError::BadHeader => write!(f, "data isn't a universe delta"),
+            DeltaError::UnsupportedVersion(version) => write!(f, "unsupported delta version {}", version),
+            DeltaError::DimensionMismatch => write!(f, "delta is for a universe of different dimensions"),
+            DeltaError::GenerationMismatch { expected, found } => write!(
+                f, "delta applies on top of generation {} but the universe is at {}", expected, found
+            ),
+            DeltaError::Truncated => write!(f, "delta data is truncated or corrupt"),
+        }
+    }
+}
+
+impl std::error::Error for DeltaError {}
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+pub(crate) fn read_varint(data: &[u8], position: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn read_u64(data: &[u8], start: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[start..start + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+impl Universe {
+    // Stamp cells as changed in the current generation
+    pub(crate) fn record_changes(&mut self, changed: &[usize]) {
+        if let Some(change_log) = self.change_log.as_mut() {
+            for &idx in changed {
+                change_log.stamps[idx] = self.generation;
+            }
+        }
+        if let Some(stream) = self.delta_stream.as_mut() {
+            for &idx in changed {
+                if !stream.marked[idx] {
+                    stream.marked[idx] = true;
+                    stream.changed.push(idx);
+                }
+            }
+        }
+    }
+
+    // A delta from `base` setting the cells at `indices`, in ascending order
+    fn write_delta(&self, base: u64, indices: impl Iterator<Item = usize>) -> Vec<u8> {
+        let mut entries = vec![];
+        let mut count = 0u32;
+        let mut previous = 0;
+        for idx in indices {
+            let gap = (idx - previous) as u64;
+            write_varint(&mut entries, gap << 1 | self.true_state(self.cells[idx]) as u64);
+            previous = idx;
+            count += 1;
+        }
+
+        let mut data = Vec::with_capacity(HEADER_LEN + entries.len());
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.extend_from_slice(&self.width.to_le_bytes());
+        data.extend_from_slice(&self.height.to_le_bytes());
+        data.extend_from_slice(&base.to_le_bytes());
+        data.extend_from_slice(&self.generation.to_le_bytes());
+        data.extend_from_slice(&count.to_le_bytes());
+        data.extend(entries);
+        data
+    }
+
+    // Called when the buffer changes phase, and with it the true state of
+    // every cell it didn't flip
+    pub(crate) fn record_phase_change(&mut self) {
+        if self.change_log.is_some() || self.delta_stream.is_some() {
+            let all = (0..self.cells.len()).collect::<Vec<_>>();
+            self.record_changes(&all);
+        }
+    }
+
+    /// Rust side of `serialize_delta`
+    pub fn encode_delta(&self, since_generation: u64) -> Result<Vec<u8>, LifeError> {
+        let change_log = self.change_log.as_ref().ok_or(DeltaError::NotTracking)?;
+        if since_generation < change_log.started {
+            return Err(DeltaError::TooOld { since: since_generation, tracked_from: change_log.started }.into());
+        }
+
+        // Cells stamped with the base generation itself may have changed
+        // after it was saved (e.g. an edit), so include them to be safe
+        let changed = change_log.stamps.iter()
+            .enumerate()
+            .filter(|&(_, &stamp)| stamp >= since_generation)
+            .map(|(idx, _)| idx);
+        Ok(self.write_delta(since_generation, changed))
+    }
+
+    /// Rust side of `apply_delta`. Nothing is modified unless the whole
+    /// delta is valid
+    pub fn decode_delta(&mut self, data: &[u8]) -> Result<(), LifeError> {
+        if data.len() < HEADER_LEN || &data[0..2] != MAGIC {
+            return Err(DeltaError::BadHeader.into());
+        }
+        if data[2] != VERSION {
+            return Err(DeltaError::UnsupportedVersion(data[2]).into());
+        }
+        if data[3..7] != self.width.to_le_bytes() || data[7..11] != self.height.to_le_bytes() {
+            return Err(DeltaError::DimensionMismatch.into());
+        }
+
+        let base = read_u64(data, 11);
+        if base != KEYFRAME && base != self.generation {
+            return Err(DeltaError::GenerationMismatch { expected: base, found: self.generation }.into());
+        }
+        let generation = read_u64(data, 19);
+        let count = u32::from_le_bytes([data[27], data[28], data[29], data[30]]);
+        // Each entry takes at least a byte and names a different cell, so a
+        // larger count is corrupt, and mustn't be allocated for
+        if count as usize > (data.len() - HEADER_LEN).min(self.cells.len()) {
+            return Err(DeltaError::Truncated.into());
+        }
+
+        let mut updates = Vec::with_capacity(count as usize);
+        let mut position = HEADER_LEN;
+        let mut idx = 0u64;
+        for _ in 0..count {
+            let entry = read_varint(data, &mut position).ok_or(DeltaError::Truncated)?;
+            idx = idx.checked_add(entry >> 1).ok_or(DeltaError::Truncated)?;
+            if idx >= self.cells.len() as u64 {
+                return Err(DeltaError::Truncated.into());
+            }
+            let cell = if entry & 1 == 1 { Cell::Alive } else { Cell::Dead };
+            updates.push((idx as usize, cell));
+        }
+        if position != data.len() {
+            return Err(DeltaError::Truncated.into());
+        }
+
+        for (idx, cell) in updates {
+            self.write_cell(idx, self.true_state(cell));
+        }
+        self.generation = generation;
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start remembering which cells change so deltas can be produced,
+    /// costing 8 bytes per cell. Deltas can only go back as far as the
+    /// generation tracking was enabled at
+    pub fn enable_change_tracking(&mut self) {
+        if self.change_log.is_none() {
+            self.change_log = Some(ChangeLog {
+                stamps: vec![0; self.cells.len()],
+                started: self.generation,
+            });
+        }
+    }
+
+    pub fn disable_change_tracking(&mut self) {
+        self.change_log = None;
+    }
+
+    /// Compact patch of every cell changed since `since_generation`, which
+    /// `apply_delta` can apply to a copy saved at that generation
+    #[cfg(feature = "wasm")]
+    pub fn serialize_delta(&self, since_generation: u64) -> Result<Vec<u8>, JsValue> {
+        self.encode_delta(since_generation).map_err(crate::error::js_error)
+    }
+
+    /// Cells changed since the last call, for `apply_delta()` on a mirror
+    /// that has had every frame before it. The first call gives a keyframe
+    pub fn delta_since_last(&mut self) -> Vec<u8> {
+        let mut stream = match self.delta_stream.take() {
+            Some(stream) => stream,
+            None => {
+                let size = self.cells.len();
+                self.delta_stream = Some(DeltaStream { changed: vec![], marked: vec![false; size], base: self.generation });
+                return self.delta_keyframe();
+            }
+        };
+        stream.changed.sort_unstable();
+        let data = self.write_delta(stream.base, stream.changed.iter().cloned());
+        for &idx in &stream.changed {
+            stream.marked[idx] = false;
+        }
+        stream.changed.clear();
+        stream.base = self.generation;
+        self.delta_stream = Some(stream);
+        data
+    }
+
+    /// Every cell as a delta that `apply_delta()` takes whatever generation
+    /// the universe it's applied to is at, to bring a mirror up to date
+    pub fn delta_keyframe(&self) -> Vec<u8> {
+        self.write_delta(KEYFRAME, 0..self.cells.len())
+    }
+
+    /// Stop collecting changes for `delta_since_last()`, whose next call
+    /// starts over with a keyframe
+    pub fn stop_delta_stream(&mut self) {
+        self.delta_stream = None;
+    }
+
+    /// Apply a delta from `serialize_delta()`, `delta_since_last()` or
+    /// `delta_keyframe()`
+    #[cfg(feature = "wasm")]
+    pub fn apply_delta(&mut self, data: &[u8]) -> Result<(), JsValue> {
+        self.decode_delta(data).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_round_trip() {
+        // Soup in one corner of an otherwise empty board
+        let soup = Universe::random_seeded(16, 16, 0.3, 3).unwrap();
+        let alive = (0..16u32)
+            .flat_map(|row| (0..16u32).map(move |column| (row, column)))
+            .filter(|&(row, column)| soup.cells[soup.get_index(row, column)] == Cell::Alive)
+            .map(|(row, column)| (row + 20, column + 20))
+            .collect();
+        let mut universe = Universe::new(128, 128, alive);
+        universe.enable_change_tracking();
+        for _ in 0..5 {
+            universe.tick();
+        }
+
+        // Baseline autosave at generation 5
+        let mut restored = Universe::decode_snapshot(&universe.to_snapshot()).unwrap();
+        restored.generation = 5;
+
+        for _ in 0..20 {
+            universe.tick();
+        }
+        universe.set_owner(0, 0, 1);
+
+        let delta = universe.encode_delta(5).unwrap();
+        assert!(delta.len() < universe.to_snapshot().len());
+
+        restored.decode_delta(&delta).unwrap();
+        assert_eq!(universe.cells, restored.cells);
+        assert_eq!(25, restored.generation);
+    }
+
+    #[test]
+    fn mirrors_a_stream() {
+        // A glider and a blinker
+        let cells = vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2), (30, 30), (30, 31), (30, 32)];
+        let mut server = Universe::new(64, 64, cells);
+        server.tick();
+        let mut mirror = Universe::new(64, 64, vec![]);
+        mirror.decode_delta(&server.delta_since_last()).unwrap();
+        assert_eq!((&server.cells, 1), (&mirror.cells, mirror.generation));
+
+        for generation in 0..10 {
+            server.tick();
+            if generation == 4 {
+                server.set_cells(&[0, 1, 2]);
+            }
+            let frame = server.delta_since_last();
+            assert!(frame.len() < HEADER_LEN + 20);
+            mirror.decode_delta(&frame).unwrap();
+            assert_eq!(server.cells, mirror.cells);
+        }
+        assert_eq!(11, mirror.generation);
+
+        // Nothing since the last frame, and a frame missed
+        assert_eq!(HEADER_LEN, server.delta_since_last().len());
+        server.tick();
+        server.delta_since_last();
+        server.tick();
+        assert!(mirror.decode_delta(&server.delta_since_last()).is_err());
+
+        // A late joiner catches up from a keyframe
+        let mut late = Universe::new(64, 64, vec![(3, 3)]);
+        late.decode_delta(&server.delta_keyframe()).unwrap();
+        assert_eq!((&server.cells, server.generation), (&late.cells, late.generation));
+    }
+
+    #[test]
+    fn mirrors_b0_rules() {
+        for rule in ["B0/S8", "B013/S23"] {
+            let mut server = Universe::random_seeded(24, 24, 0.3, 5).unwrap();
+            server.set_life_rule(rule.parse().unwrap());
+            server.enable_change_tracking();
+            let mut mirror = Universe::new(24, 24, vec![]);
+            mirror.decode_delta(&server.delta_since_last()).unwrap();
+
+            let mut autosave = None;
+            for generation in 0..6 {
+                server.tick();
+                mirror.decode_delta(&server.delta_since_last()).unwrap();
+                assert_eq!(server.render(), mirror.render(), "{} generation {}", rule, generation);
+                if generation == 2 {
+                    autosave = Some(server.to_snapshot());
+                }
+            }
+
+            // A snapshot autosave and a delta since, taken in either phase
+            let mut restored = Universe::decode_snapshot(&autosave.unwrap()).unwrap();
+            restored.generation = 3;
+            restored.decode_delta(&server.encode_delta(3).unwrap()).unwrap();
+            assert_eq!(server.render(), restored.render(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn delta_errors() {
+        let mut universe = Universe::new(8, 8, vec![(1, 1), (1, 2), (1, 3)]);
+        assert_eq!(Err(DeltaError::NotTracking.into()), universe.encode_delta(0));
+
+        universe.tick();
+        universe.enable_change_tracking();
+        assert_eq!(Err(DeltaError::TooOld { since: 0, tracked_from: 1 }.into()), universe.encode_delta(0));
+
+        universe.tick();
+        let delta = universe.encode_delta(1).unwrap();
+
+        let mut other = Universe::new(8, 8, vec![]);
+        assert_eq!(Err(DeltaError::GenerationMismatch { expected: 1, found: 0 }.into()), other.decode_delta(&delta));
+        let mut other = Universe::new(4, 8, vec![]);
+        assert_eq!(Err(DeltaError::DimensionMismatch.into()), other.decode_delta(&delta));
+        let mut other = Universe::new(8, 8, vec![]);
+        other.generation = 1;
+        assert_eq!(Err(DeltaError::Truncated.into()), other.decode_delta(&delta[..delta.len() - 1]));
+        assert_eq!(Err(DeltaError::BadHeader.into()), other.decode_delta(b"GD"));
+
+        // A header claiming more entries than there's data or cells for
+        let mut huge = delta[..HEADER_LEN].to_vec();
+        huge[27..31].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Err(DeltaError::Truncated.into()), other.decode_delta(&huge));
+        let mut crowded = delta.clone();
+        crowded[27..31].copy_from_slice(&65u32.to_le_bytes());
+        crowded.extend(std::iter::repeat_n(2, 65));
+        assert_eq!(Err(DeltaError::Truncated.into()), other.decode_delta(&crowded));
+    }
+}
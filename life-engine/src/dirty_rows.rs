@@ -0,0 +1,94 @@
+// Rows touched by the last tick, for partial redraws and the Rows strategy
+//
+// A row is dirty if any of its cells changed in the last tick or was edited
+// since. A frontend only needs to repaint those strips of the canvas. The
+// Rows tick strategy evaluates every cell of each row within reach of a
+// dirty row, which on busy boards scans memory in order where Sparse jumps
+// around, and skips the quiet rows Dense would go through
+//
+// Neighbours are taken from the boundary mode, so a band can wrap round to
+// the far edge or reach into columns on the glued surfaces
+
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Every cell in a row holding or neighbouring one of `changed`, in
+    // index order
+    pub(crate) fn row_band_candidates(&self, changed: &[usize]) -> Vec<usize> {
+        let width = self.width as usize;
+        let mut band = vec![false; self.height as usize];
+        for &idx in changed {
+            let (neighbours, count) = self.neighbour_indices(idx);
+            band[idx / width] = true;
+            for &neighbour in &neighbours[..count] {
+                band[neighbour / width] = true;
+            }
+        }
+
+        band.iter()
+            .enumerate()
+            .filter(|&(_, &dirty)| dirty)
+            .flat_map(|(row, _)| row * width..(row + 1) * width)
+            .collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Rows with a cell that changed in the last tick or has been edited
+    /// since, in order. Every row when that isn't known, such as before the
+    /// first tick
+    pub fn dirty_rows(&self) -> Vec<u32> {
+        let changed = match &self.active_cells {
+            Some(changed) => changed,
+            None => return (0..self.height).collect(),
+        };
+
+        let mut dirty = vec![false; self.height as usize];
+        for &idx in changed {
+            dirty[idx / self.width as usize] = true;
+        }
+        (0..self.height).filter(|&row| dirty[row as usize]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Boundary, TickStrategy, Universe};
+
+    #[test]
+    fn dirty_rows_follow_activity() {
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        assert_eq!(8, universe.dirty_rows().len());
+
+        // The vertical phase adds a cell above and below and loses two beside
+        universe.tick();
+        assert_eq!(vec![2, 3, 4], universe.dirty_rows());
+        universe.toggle_cell(7, 0);
+        assert_eq!(vec![2, 3, 4, 7], universe.dirty_rows());
+
+        // Still lifes leave nothing to repaint
+        let mut block = Universe::new(8, 8, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        block.tick();
+        assert!(block.dirty_rows().is_empty());
+    }
+
+    #[test]
+    fn rows_strategy_agrees() {
+        for &boundary in &[Boundary::Torus, Boundary::Dead, Boundary::KleinBottle] {
+            let mut sparse = Universe::random_seeded(30, 30, 0.3, 5).unwrap();
+            let mut rows = Universe::random_seeded(30, 30, 0.3, 5).unwrap();
+            sparse.set_boundary(boundary);
+            rows.set_boundary(boundary);
+            rows.set_tick_strategy(TickStrategy::Rows);
+            for _ in 0..20 {
+                sparse.tick();
+                rows.tick();
+                assert_eq!(sparse.cells, rows.cells, "{:?}", boundary);
+            }
+        }
+    }
+}
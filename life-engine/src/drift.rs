@@ -0,0 +1,148 @@
+// Following the population's centre of mass, to measure how fast patterns
+// travel
+//
+// With tracking on, every tick moves the centre by the mean offset of the
+// live cells from where it was, so on a torus a spaceship crossing the edge
+// keeps its heading rather than jumping back across the board. The distance
+// travelled over the generations tracked gives the drift velocity, which
+// for a lone glider settles on a quarter of a cell a generation both down
+// and across: c/4 diagonally.
+//
+// Edits between ticks move the centre too and count towards the next
+// tick's travel, and ticks with nothing alive aren't counted
+
+use crate::{Boundary, Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone)]
+pub(crate) struct Drift {
+    // Row and column of the centre after the last tick, if anything was
+    // alive
+    centre: Option<(f64, f64)>,
+    travelled: (f64, f64),
+    generations: u64,
+}
+
+// `value` wrapped into [-size / 2, size / 2)
+fn wrap(value: f64, size: u32) -> f64 {
+    let size = size as f64;
+    (value + size / 2.0).rem_euclid(size) - size / 2.0
+}
+
+impl Universe {
+    // Mean position of the live cells, or their mean offset from `from`
+    // taking the shortest way round a torus
+    fn mean_offset(&self, from: Option<(f64, f64)>) -> Option<(f64, f64)> {
+        let torus = from.is_some() && self.boundary == Boundary::Torus;
+        let from = from.unwrap_or((0.0, 0.0));
+        let (mut rows, mut columns, mut count) = (0.0, 0.0, 0u64);
+        for (idx, _) in self.cells.iter().enumerate().filter(|(_, &cell)| cell == Cell::Alive) {
+            let mut row = (idx / self.width as usize) as f64 - from.0;
+            let mut column = (idx % self.width as usize) as f64 - from.1;
+            if torus {
+                row = wrap(row, self.height);
+                column = wrap(column, self.width);
+            }
+            rows += row;
+            columns += column;
+            count += 1;
+        }
+        (count > 0).then(|| (rows / count as f64, columns / count as f64))
+    }
+
+    // Called at the end of tick()
+    pub(crate) fn update_drift(&mut self) {
+        let centre = match self.drift.as_ref() {
+            Some(drift) => drift.centre,
+            None => return,
+        };
+        let (offset, centre) = match centre {
+            Some(centre) => match self.mean_offset(Some(centre)) {
+                Some(offset) => (Some(offset), Some((centre.0 + offset.0, centre.1 + offset.1))),
+                None => (None, None),
+            },
+            None => (None, self.mean_offset(None)),
+        };
+
+        let drift = self.drift.as_mut().expect("checked above");
+        drift.centre = centre;
+        if let Some((rows, columns)) = offset {
+            drift.travelled.0 += rows;
+            drift.travelled.1 += columns;
+            drift.generations += 1;
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start following the centre of mass from where it is now, forgetting
+    /// any distance already travelled
+    pub fn enable_drift_tracking(&mut self) {
+        let centre = self.mean_offset(None);
+        self.drift = Some(Drift { centre, travelled: (0.0, 0.0), generations: 0 });
+    }
+
+    pub fn disable_drift_tracking(&mut self) {
+        self.drift = None;
+    }
+
+    /// `[row, column]` of the population's centre of mass, or nothing when
+    /// the board is empty. While tracking on a torus this is the followed
+    /// centre, which can lie off the board once things have crossed an edge
+    pub fn centroid(&self) -> Option<Vec<f64>> {
+        let centre = match self.drift.as_ref() {
+            Some(drift) => drift.centre,
+            None => self.mean_offset(None),
+        };
+        centre.map(|(row, column)| vec![row, column])
+    }
+
+    /// `[rows, columns]` the centre of mass has moved per generation on
+    /// average since tracking started, positive being down and right.
+    /// Nothing until a tick has been tracked
+    pub fn drift_velocity(&self) -> Option<Vec<f64>> {
+        let drift = self.drift.as_ref().filter(|drift| drift.generations > 0)?;
+        let generations = drift.generations as f64;
+        Some(vec![drift.travelled.0 / generations, drift.travelled.1 / generations])
+    }
+
+    /// Speed as a fraction of c, the larger of the two drift velocity
+    /// components, so 0.25 for a glider and 0.5 for a lightweight spaceship
+    pub fn drift_speed(&self) -> Option<f64> {
+        self.drift_velocity().map(|velocity| velocity[0].abs().max(velocity[1].abs()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounded(values: Option<Vec<f64>>) -> Vec<f64> {
+        values.unwrap().iter().map(|value| (value * 1000.0).round() / 1000.0).collect()
+    }
+
+    #[test]
+    fn glider_speed() {
+        let mut universe = Universe::new(10, 10, vec![(5, 6), (6, 7), (7, 5), (7, 6), (7, 7)]);
+        assert_eq!(Some(vec![6.4, 6.2]), universe.centroid());
+        universe.enable_drift_tracking();
+        assert_eq!(None, universe.drift_velocity());
+
+        // Far enough to wrap round the torus a couple of times
+        for _ in 0..80 {
+            universe.tick();
+        }
+        assert_eq!(vec![0.25, 0.25], rounded(universe.drift_velocity()));
+        assert!((universe.drift_speed().unwrap() - 0.25).abs() < 1e-9);
+        assert_eq!(vec![26.4, 26.2], rounded(universe.centroid()));
+
+        // Still lifes stay put
+        let mut universe = Universe::new(6, 6, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        universe.enable_drift_tracking();
+        universe.tick();
+        assert_eq!(Some(0.0), universe.drift_speed());
+    }
+}
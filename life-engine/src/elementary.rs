@@ -0,0 +1,205 @@
+// Wolfram's elementary cellular automata, a single row of cells where each
+// cell's next state depends on itself and its two neighbours
+//
+// The rule number's bits give the new state for each of the eight
+// neighbourhoods, bit n being the state for the one whose left, centre and
+// right cells read as the binary number n. The row wraps round at the ends.
+// Every row is kept in a history, oldest first, so stacking them gives the
+// familiar triangles of rule 30 or 110 as a 2D image
+
+use crate::error::LifeError;
+use crate::rng::seeded;
+use crate::size::check_size;
+use crate::{Cell, RenderConfig};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_HISTORY_CAPACITY: u32 = 256;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct ElementaryCA {
+    width: u32,
+    rule: u8,
+    // Rows oldest first, the last one being the current row
+    history: Vec<Cell>,
+    history_capacity: u32,
+    generation: u64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ElementaryCA {
+    /// An all dead row of `width` cells under rule 0 to 255. Throws for a
+    /// width of 0
+    #[cfg(feature = "wasm")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, rule: u8) -> Result<ElementaryCA, JsValue> {
+        ElementaryCA::empty(width, rule).map_err(crate::error::js_error)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn rule(&self) -> u8 {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: u8) {
+        self.rule = rule;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn is_alive(&self, position: u32) -> bool {
+        self.row()[position as usize] == Cell::Alive
+    }
+
+    pub fn set_alive(&mut self, position: u32, alive: bool) {
+        let start = self.history.len() - self.width as usize;
+        self.history[start + position as usize] = if alive { Cell::Alive } else { Cell::Dead };
+    }
+
+    /// Refill the current row, each cell alive with probability `density`
+    pub fn randomize(&mut self, density: f32, seed: u64) {
+        let density = density.clamp(0.0, 1.0);
+        let mut rng = seeded(seed);
+        for position in 0..self.width {
+            self.set_alive(position, rng.gen::<f32>() < density);
+        }
+    }
+
+    pub fn step(&mut self) {
+        let row = self.row();
+        let width = row.len();
+        let next = (0..width)
+            .map(|position| {
+                let left = row[(position + width - 1) % width] as u8;
+                let centre = row[position] as u8;
+                let right = row[(position + 1) % width] as u8;
+                if self.rule >> (left << 2 | centre << 1 | right) & 1 == 1 { Cell::Alive } else { Cell::Dead }
+            })
+            .collect::<Vec<_>>();
+
+        self.history.extend(next);
+        self.trim_history();
+        self.generation += 1;
+    }
+
+    /// Number of rows in the history, including the current row
+    pub fn history_rows(&self) -> u32 {
+        (self.history.len() / self.width as usize) as u32
+    }
+
+    /// Every row in the history, oldest first, one byte per cell (0 dead,
+    /// 1 alive), ready to draw as a width x history_rows() image
+    pub fn history(&self) -> Vec<u8> {
+        self.history.iter().map(|&cell| cell as u8).collect()
+    }
+
+    /// Most rows to keep, the oldest being dropped first. Always at least
+    /// the current row
+    pub fn set_history_capacity(&mut self, rows: u32) {
+        self.history_capacity = rows.max(1);
+        self.trim_history();
+    }
+
+    /// Forget every row but the current one
+    pub fn clear_history(&mut self) {
+        let start = self.history.len() - self.width as usize;
+        self.history.drain(..start);
+    }
+
+    /// The history as text, one row per line like `Universe::render()`
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        RenderConfig::default().write(&mut out, self.width, &self.history).unwrap();
+        out
+    }
+}
+
+impl ElementaryCA {
+    /// Rust side of `new`
+    pub fn empty(width: u32, rule: u8) -> Result<ElementaryCA, LifeError> {
+        check_size(width, 1)?;
+        Ok(ElementaryCA {
+            width,
+            rule,
+            history: vec![Cell::Dead; width as usize],
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            generation: 0,
+        })
+    }
+
+    /// The current row
+    pub fn row(&self) -> &[Cell] {
+        &self.history[self.history.len() - self.width as usize..]
+    }
+
+    // Drop the oldest rows beyond the capacity
+    fn trim_history(&mut self) {
+        let rows = self.history_rows();
+        if rows > self.history_capacity {
+            let excess = (rows - self.history_capacity) as usize * self.width as usize;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_text(ca: &ElementaryCA) -> String {
+        ca.row().iter().map(|&cell| if cell == Cell::Alive { '#' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn rule_30_and_90() {
+        let mut ca = ElementaryCA::empty(11, 30).unwrap();
+        ca.set_alive(5, true);
+        ca.step();
+        assert_eq!("....###....", row_text(&ca));
+        ca.step();
+        assert_eq!("...##..#...", row_text(&ca));
+        ca.step();
+        assert_eq!("..##.####..", row_text(&ca));
+
+        // Rule 90 is the XOR of the neighbours, wrapping round the ends so the
+        // two halves of the triangle cancel out where they meet
+        let mut ca = ElementaryCA::empty(8, 90).unwrap();
+        ca.set_alive(0, true);
+        ca.step();
+        assert_eq!(".#.....#", row_text(&ca));
+        ca.step();
+        assert_eq!("..#...#.", row_text(&ca));
+        assert_eq!(2, ca.generation());
+    }
+
+    #[test]
+    fn history_accumulates_rows() {
+        let mut ca = ElementaryCA::empty(5, 110).unwrap();
+        ca.set_alive(4, true);
+        for _ in 0..3 {
+            ca.step();
+        }
+        assert_eq!(4, ca.history_rows());
+        let history = ca.history();
+        assert_eq!(20, history.len());
+        assert_eq!(vec![0, 0, 0, 0, 1], history[..5].to_vec());
+        assert_eq!(4, ca.render().lines().count());
+
+        ca.set_history_capacity(2);
+        assert_eq!(2, ca.history_rows());
+        assert_eq!(ca.row(), &ca.history[5..]);
+        ca.step();
+        assert_eq!(2, ca.history_rows());
+
+        ca.clear_history();
+        assert_eq!(1, ca.history_rows());
+        assert!(ElementaryCA::empty(0, 30).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+// The simulation core, which only needs `core` and `alloc`
+//
+// Cells, the rule and neighbour counting live here so everything can share
+// them, along with `Engine`, a bare board that wraps at the edges and ticks
+// every cell. It has none of Universe's layers (history, tracking, the JS
+// bindings) which makes it small enough for microcontrollers driving LED
+// matrix displays, built with `default-features = false`:
+//
+//     let mut engine = Engine::new(16, 16)?;
+//     engine.set(1, 2, Cell::Alive);
+//     engine.tick();
+//     for (idx, &cell) in engine.cells().iter().enumerate() { ... }
+//
+// `LifeEngine` is what every bare board offers, so code written against it
+// runs on whichever backend suits the pattern: `Engine` here, or with std
+// the bit-packed, sparse and HashLife boards chosen through `Backend`
+
+use crate::error::LifeError;
+use crate::rule::NEIGHBOURS;
+use crate::size::index;
+use crate::Rule;
+
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// u8 for efficient use of memory
+// values are important for efficient summing of alive neighbours
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Cell {
+    Dead = 0,
+    Alive = 1,
+}
+
+// The coordinates either side of `position` on a ring of `size`, worked out
+// without adding anything that could overflow at the largest sizes
+#[inline]
+fn ring_neighbours(position: u32, size: u32) -> [u32; 3] {
+    let before = if position == 0 { size - 1 } else { position - 1 };
+    let after = if position == size - 1 { 0 } else { position + 1 };
+    [before, position, after]
+}
+
+// Counted straight from the board's size, for the bare Engine. Universe
+// looks the wrapped positions up in tables instead, see offsets.rs
+pub(crate) fn torus_neighbour_count(cells: &[Cell], width: u32, height: u32, row: u32, column: u32) -> u8 {
+    let mut alive_count = 0;
+
+    let columns = ring_neighbours(column, width);
+    for (row_offset, &neighbour_row) in ring_neighbours(row, height).iter().enumerate() {
+        for (column_offset, &neighbour_column) in columns.iter().enumerate() {
+            if row_offset == 1 && column_offset == 1 {
+                continue // Don't count our own cell, we only want the neighbours
+            }
+            alive_count += cells[index(width, neighbour_row, neighbour_column)] as u8;
+        }
+    }
+
+    alive_count
+}
+
+// The neighbours as bits clockwise from north, for non-totalistic rules
+fn torus_neighbourhood(cells: &[Cell], width: u32, height: u32, row: u32, column: u32) -> u8 {
+    wrapped_neighbourhood(width, height, row, column, |idx| cells[idx])
+}
+
+// Same for any backend, given the cell at each flat index
+pub(crate) fn wrapped_neighbourhood(width: u32, height: u32, row: u32, column: u32, cell: impl Fn(usize) -> Cell) -> u8 {
+    NEIGHBOURS.iter().enumerate().fold(0, |neighbourhood, (bit, &(row_delta, column_delta))| {
+        let neighbour_row = (row as i64 + row_delta).rem_euclid(height as i64) as u32;
+        let neighbour_column = (column as i64 + column_delta).rem_euclid(width as i64) as u32;
+        neighbourhood | (cell(index(width, neighbour_row, neighbour_column)) as u8) << bit
+    })
+}
+
+/// A board of cells wrapping round at the edges, ticked by a rule. How the
+/// cells are stored and stepped is up to the implementation
+pub trait LifeEngine {
+    fn width(&self) -> u32;
+
+    fn height(&self) -> u32;
+
+    /// Panics outside the board
+    fn get(&self, row: u32, column: u32) -> Cell;
+
+    /// Panics outside the board
+    fn set(&mut self, row: u32, column: u32, cell: Cell);
+
+    /// Advance one generation
+    fn tick(&mut self);
+
+    fn generation(&self) -> u64;
+
+    fn population(&self) -> u32;
+
+    fn rule(&self) -> Rule;
+
+    fn set_rule(&mut self, rule: Rule);
+
+    /// Every cell, row by row
+    fn to_cells(&self) -> Vec<Cell> {
+        (0..self.height())
+            .flat_map(|row| (0..self.width()).map(move |column| (row, column)))
+            .map(|(row, column)| self.get(row, column))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Engine {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    // The next generation is written here then swapped in, so ticking
+    // doesn't allocate
+    scratch: Vec<Cell>,
+    rule: Rule,
+    generation: u64,
+}
+
+impl Engine {
+    /// An all dead board running Conway's Life
+    pub fn new(width: u32, height: u32) -> Result<Engine, LifeError> {
+        let size = crate::size::check_size(width, height)?;
+        Ok(Engine {
+            width,
+            height,
+            cells: vec![Cell::Dead; size],
+            scratch: vec![Cell::Dead; size],
+            rule: Rule::CONWAY,
+            generation: 0,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Every cell, row by row
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn get(&self, row: u32, column: u32) -> Cell {
+        self.cells[index(self.width, row, column)]
+    }
+
+    pub fn set(&mut self, row: u32, column: u32, cell: Cell) {
+        self.cells[index(self.width, row, column)] = cell;
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u32 {
+        self.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32
+    }
+
+    /// Kill every cell
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = Cell::Dead);
+    }
+
+    pub fn tick(&mut self) {
+        for row in 0..self.height {
+            for column in 0..self.width {
+                let idx = index(self.width, row, column);
+                self.scratch[idx] = if self.rule.is_totalistic() {
+                    let count = torus_neighbour_count(&self.cells, self.width, self.height, row, column);
+                    self.rule.next(self.cells[idx], count)
+                } else {
+                    let neighbourhood = torus_neighbourhood(&self.cells, self.width, self.height, row, column);
+                    self.rule.next_neighbourhood(self.cells[idx], neighbourhood)
+                };
+            }
+        }
+        core::mem::swap(&mut self.cells, &mut self.scratch);
+        self.generation += 1;
+    }
+}
+
+impl LifeEngine for Engine {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn get(&self, row: u32, column: u32) -> Cell {
+        Engine::get(self, row, column)
+    }
+
+    fn set(&mut self, row: u32, column: u32, cell: Cell) {
+        Engine::set(self, row, column, cell)
+    }
+
+    fn tick(&mut self) {
+        Engine::tick(self)
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn population(&self) -> u32 {
+        Engine::population(self)
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    fn to_cells(&self) -> Vec<Cell> {
+        self.cells.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SizeError;
+
+    #[test]
+    fn engine_ticks() {
+        let mut engine = Engine::new(5, 5).unwrap();
+        for column in 1..4 {
+            engine.set(2, column, Cell::Alive);
+        }
+        engine.tick();
+        assert_eq!((1, 3), (engine.generation(), engine.population()));
+        assert!((1..4).all(|row| engine.get(row, 2) == Cell::Alive));
+        assert_eq!(Cell::Dead, engine.get(2, 1));
+
+        // Wraps round at the edges
+        engine.clear();
+        for row in [4, 0, 1] {
+            engine.set(row, 0, Cell::Alive);
+        }
+        engine.tick();
+        assert!([4, 0, 1].iter().all(|&column| engine.get(0, column) == Cell::Alive));
+
+        assert_eq!(Err(SizeError::Empty { width: 0, height: 5 }.into()), Engine::new(0, 5));
+    }
+
+    #[test]
+    fn neighbours_at_the_largest_sizes() {
+        assert_eq!([u32::MAX - 1, 0, 1], ring_neighbours(0, u32::MAX));
+        assert_eq!([u32::MAX - 2, u32::MAX - 1, 0], ring_neighbours(u32::MAX - 1, u32::MAX));
+        assert_eq!([0, 0, 0], ring_neighbours(0, 1));
+    }
+}
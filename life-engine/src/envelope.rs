@@ -0,0 +1,125 @@
+// Every cell that has ever been alive, like the history states of Golly's
+// LifeHistory rule
+//
+// With the envelope on, each cell a live cell has stood on since it was
+// switched on (or last cleared) stays marked, whether by a tick or an edit.
+// Drawn under the live cells it shows the smoke trail a spaceship leaves,
+// the full reach of an explosion, or the cells an oscillator's rotor
+// sweeps. Cells are only ever added, so stepping back or killing cells
+// leaves the envelope as it was; `clear_envelope()` starts it again from
+// the current board. JS can read it in place through `envelope_ptr()`
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Called from tick() with the cells that just flipped, and for edits
+    pub(crate) fn extend_envelope(&mut self, changed: &[usize]) {
+        if let Some(envelope) = self.envelope.as_mut() {
+            for &idx in changed {
+                if self.cells[idx] == Cell::Alive {
+                    envelope[idx] = 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start marking the cells that have been alive, beginning with the
+    /// ones alive now
+    pub fn enable_envelope(&mut self) {
+        if self.envelope.is_none() {
+            self.envelope = Some(self.cells.iter().map(|&cell| cell as u8).collect());
+        }
+    }
+
+    pub fn disable_envelope(&mut self) {
+        self.envelope = None;
+    }
+
+    pub fn envelope_enabled(&self) -> bool {
+        self.envelope.is_some()
+    }
+
+    /// Forget the cells marked so far, keeping just the ones alive now
+    pub fn clear_envelope(&mut self) {
+        if self.envelope.is_some() {
+            self.envelope = Some(self.cells.iter().map(|&cell| cell as u8).collect());
+        }
+    }
+
+    /// Whether the cell has been alive since the envelope was switched on
+    /// or cleared, always false while it's off
+    pub fn ever_alive(&self, row: u32, column: u32) -> bool {
+        self.envelope.as_ref().is_some_and(|envelope| envelope[self.get_index(row, column)] == 1)
+    }
+
+    /// 1 for each cell that has been alive and 0 for the rest, in the same
+    /// layout as the cell buffer
+    pub fn envelope(&self) -> Vec<u8> {
+        match &self.envelope {
+            Some(envelope) => envelope.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Pointer to the envelope in WASM memory, one byte per cell, or null
+    /// while it's off. Only valid until the next call that changes the
+    /// universe
+    pub fn envelope_ptr(&self) -> *const u8 {
+        match &self.envelope {
+            Some(envelope) => envelope.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// Number of cells that have been alive
+    pub fn envelope_area(&self) -> u32 {
+        self.envelope.as_ref().map_or(0, |envelope| envelope.iter().filter(|&&marked| marked == 1).count() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trails_a_glider() {
+        let mut universe: Universe = "\
+            .#......\n\
+            ..#.....\n\
+            ###.....\n\
+            ........\n\
+            ........\n\
+            ........\n".parse().unwrap();
+        assert!(universe.envelope_ptr().is_null());
+        universe.enable_envelope();
+        assert_eq!(5, universe.envelope_area());
+
+        for _ in 0..4 {
+            universe.tick();
+        }
+        // The glider has moved on a cell, leaving its old cells marked
+        assert!(universe.ever_alive(0, 1) && !universe.is_alive(0, 1));
+        assert!(universe.is_alive(3, 3) && universe.ever_alive(3, 3));
+        assert!(!universe.ever_alive(5, 7));
+        let area = universe.envelope_area();
+        assert!(area > 9);
+
+        // Stepping back or killing cells takes nothing away, edits add
+        universe.step_back();
+        universe.clear();
+        universe.toggle_cell(5, 7);
+        assert_eq!(area + 1, universe.envelope_area());
+        assert_eq!(universe.envelope_area(), universe.envelope().iter().map(|&marked| marked as u32).sum::<u32>());
+
+        universe.clear_envelope();
+        assert_eq!(1, universe.envelope_area());
+        universe.disable_envelope();
+        assert!(!universe.ever_alive(5, 7));
+    }
+}
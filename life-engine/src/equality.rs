@@ -0,0 +1,125 @@
+// Comparing universes
+//
+// Two universes are equal when they'd look and run the same from here on:
+// the same size, cells, rule and boundary. Generation counts, tracking
+// layers and callbacks are left out, so a board that has come back round
+// to an earlier state equals a copy taken back then
+//
+// Ignoring translation, the live cells only have to match after shifting
+// the whole board. On a torus the shift wraps round, elsewhere the cells
+// are compared relative to their bounding boxes
+
+use crate::census::normalise;
+use crate::{Boundary, Universe};
+
+use std::collections::HashSet;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl PartialEq for Universe {
+    fn eq(&self, other: &Universe) -> bool {
+        self.same_settings(other) && self.cells == other.cells
+    }
+}
+
+impl Eq for Universe {}
+
+impl Universe {
+    fn same_settings(&self, other: &Universe) -> bool {
+        (self.width, self.height) == (other.width, other.height)
+            && self.rule == other.rule
+            && self.boundary == other.boundary
+    }
+
+    fn live_positions(&self) -> Vec<(i64, i64)> {
+        self.live_cells().map(|(row, column)| (row as i64, column as i64)).collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Same size, cells, rule and boundary, whatever the generation
+    pub fn equals(&self, other: &Universe) -> bool {
+        self == other
+    }
+
+    /// Like `equals()` but the live cells may have been shifted, as a
+    /// spaceship on an otherwise empty board is from one period to the next
+    pub fn equals_ignoring_translation(&self, other: &Universe) -> bool {
+        if !self.same_settings(other) || self.population() != other.population() {
+            return false;
+        }
+        let (mut ours, mut theirs) = (self.live_positions(), other.live_positions());
+        if ours.is_empty() {
+            return true;
+        }
+
+        if self.boundary != Boundary::Torus {
+            normalise(&mut ours);
+            normalise(&mut theirs);
+            return ours == theirs;
+        }
+
+        // Try each shift taking our first live cell onto one of theirs
+        let (height, width) = (self.height as i64, self.width as i64);
+        let (first_row, first_column) = ours[0];
+        let theirs_set = theirs.iter().cloned().collect::<HashSet<_>>();
+        theirs.iter().any(|&(row, column)| {
+            let (dy, dx) = (row - first_row, column - first_column);
+            ours.iter().all(|&(row, column)| {
+                theirs_set.contains(&((row + dy).rem_euclid(height), (column + dx).rem_euclid(width)))
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structural_equality() {
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        let start = universe.duplicate();
+        universe.tick();
+        assert!(universe != start);
+        universe.tick();
+        assert!(universe == start);
+        assert!(universe.equals(&start));
+
+        let mut highlife = start.duplicate();
+        highlife.set_life_rule("B36/S23".parse().unwrap());
+        assert!(!highlife.equals(&start));
+        assert!(!start.equals(&Universe::new(8, 9, vec![(3, 2), (3, 3), (3, 4)])));
+    }
+
+    #[test]
+    fn equality_ignoring_translation() {
+        // A glider is back in shape four generations on, one cell across
+        // and down, and on a torus wrapping round doesn't matter
+        let glider = vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)];
+        let mut universe = Universe::new(6, 6, glider.clone());
+        let start = universe.duplicate();
+        for _ in 0..4 {
+            universe.tick();
+        }
+        assert!(universe != start);
+        assert!(universe.equals_ignoring_translation(&start));
+        for _ in 0..12 {
+            universe.tick();
+        }
+        assert!(universe.is_alive(0, 4) && universe.is_alive(5, 0));
+        assert!(universe.equals_ignoring_translation(&start));
+
+        // A reflection isn't a translation
+        let mirrored = Universe::new(6, 6, vec![(0, 1), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        assert!(!mirrored.equals_ignoring_translation(&start));
+
+        let mut dead = Universe::new(6, 6, glider);
+        dead.set_boundary(Boundary::Dead);
+        let mut shifted = dead.duplicate();
+        shifted.shift(2, 1);
+        assert!(shifted.equals_ignoring_translation(&dead));
+        assert!(!shifted.equals_ignoring_translation(&start));
+    }
+}
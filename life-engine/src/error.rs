@@ -0,0 +1,288 @@
+// One error type for everything that can fail
+//
+// Every public API that can fail returns a `LifeError`, so code juggling
+// several formats can use `?` into a single type. Modules still build their
+// own errors inside (`SnapshotError::Truncated` says how many bytes were
+// missing, say) and convert them on the way out, keeping the details in the
+// message. JS callers get the same `LifeError`, thrown as an `Error` whose
+// `kind` is the variant's name, with the position fields alongside the
+// message where there are any:
+//
+//     try { Universe.from_ascii(text) } catch (err) {
+//         if (err.kind === "ParseError") highlight(err.line, err.column);
+//     }
+//
+// Like `SizeError` it only needs `core` and `alloc`, so the bare `Engine`
+// returns it without the `std` feature too
+
+use crate::{RuleError, SizeError};
+#[cfg(feature = "std")]
+use crate::{AsciiError, BuildError, DeltaError, GifError, QueryError, ReplayError, RuleTableError, SnapshotError};
+#[cfg(feature = "serde")]
+use crate::JsonError;
+
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LifeError {
+    /// A width or height of 0, or more cells than a universe can hold
+    InvalidDimensions { width: u32, height: u32 },
+    /// A cell outside the universe
+    OutOfBounds { row: u32, column: u32 },
+    /// Text that doesn't parse. `line` and `column` are 1 based, 0 when
+    /// the problem isn't at one place
+    ParseError { line: usize, column: usize, message: String },
+    /// A rule that's malformed or that this kind of universe can't run
+    UnsupportedRule(String),
+    /// Data from a newer (or unknown) version of a binary format
+    UnsupportedVersion(u8),
+    /// Binary data that's corrupt, cut short or not of the expected kind
+    InvalidData(String),
+    /// Something the universe isn't set up for, like a delta when changes
+    /// aren't being tracked
+    InvalidState(String),
+    /// An argument out of its range, like a density over 1
+    InvalidArgument(String),
+    /// An id `UniverseManager` doesn't hold
+    UnknownUniverse(u32),
+}
+
+impl LifeError {
+    /// The variant's name, given to JS as the error's `kind`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LifeError::InvalidDimensions { .. } => "InvalidDimensions",
+            LifeError::OutOfBounds { .. } => "OutOfBounds",
+            LifeError::ParseError { .. } => "ParseError",
+            LifeError::UnsupportedRule(_) => "UnsupportedRule",
+            LifeError::UnsupportedVersion(_) => "UnsupportedVersion",
+            LifeError::InvalidData(_) => "InvalidData",
+            LifeError::InvalidState(_) => "InvalidState",
+            LifeError::InvalidArgument(_) => "InvalidArgument",
+            LifeError::UnknownUniverse(_) => "UnknownUniverse",
+        }
+    }
+}
+
+impl fmt::Display for LifeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &LifeError::InvalidDimensions { width, height } => {
+                if width == 0 || height == 0 {
+                    write!(f, "{}", SizeError::Empty { width, height })
+                } else {
+                    write!(f, "{}", SizeError::TooLarge { width, height })
+                }
+            }
+            LifeError::OutOfBounds { row, column } => write!(f, "({}, {}) is outside the universe", row, column),
+            LifeError::ParseError { line: 0, message, .. } => write!(f, "parse error: {}", message),
+            LifeError::ParseError { line, column: 0, message } => {
+                write!(f, "parse error at line {}: {}", line, message)
+            }
+            LifeError::ParseError { line, column, message } => {
+                write!(f, "parse error at line {}, column {}: {}", line, column, message)
+            }
+            LifeError::UnsupportedRule(message) => write!(f, "unsupported rule: {}", message),
+            LifeError::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+            LifeError::InvalidData(message)
+            | LifeError::InvalidState(message)
+            | LifeError::InvalidArgument(message) => write!(f, "{}", message),
+            LifeError::UnknownUniverse(id) => write!(f, "no universe with id {}", id),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LifeError {}
+
+#[cfg(feature = "wasm")]
+impl From<LifeError> for JsValue {
+    fn from(err: LifeError) -> JsValue {
+        let error = js_sys::Error::new(&err.to_string());
+        let mut fields = vec![("kind", JsValue::from_str(err.kind()))];
+        match err {
+            LifeError::InvalidDimensions { width, height } => {
+                fields.push(("width", width.into()));
+                fields.push(("height", height.into()));
+            }
+            LifeError::OutOfBounds { row, column } => {
+                fields.push(("row", row.into()));
+                fields.push(("column", column.into()));
+            }
+            LifeError::ParseError { line, column, .. } => {
+                fields.push(("line", (line as f64).into()));
+                fields.push(("column", (column as f64).into()));
+            }
+            LifeError::UnsupportedVersion(version) => fields.push(("version", version.into())),
+            LifeError::UnknownUniverse(id) => fields.push(("id", id.into())),
+            _ => {}
+        }
+        for (name, value) in fields {
+            // Only fails on frozen objects, which a new Error isn't
+            let _ = js_sys::Reflect::set(&error, &JsValue::from_str(name), &value);
+        }
+        error.into()
+    }
+}
+
+// For `map_err()` in the wasm_bindgen wrappers
+#[cfg(feature = "wasm")]
+pub(crate) fn js_error(err: impl Into<LifeError>) -> JsValue {
+    err.into().into()
+}
+
+impl From<SizeError> for LifeError {
+    fn from(err: SizeError) -> LifeError {
+        match err {
+            SizeError::Empty { width, height } | SizeError::TooLarge { width, height } => {
+                LifeError::InvalidDimensions { width, height }
+            }
+        }
+    }
+}
+
+impl From<RuleError> for LifeError {
+    fn from(err: RuleError) -> LifeError {
+        LifeError::UnsupportedRule(err.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<RuleTableError> for LifeError {
+    fn from(err: RuleTableError) -> LifeError {
+        LifeError::ParseError { line: err.line, column: 0, message: err.message }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<AsciiError> for LifeError {
+    fn from(err: AsciiError) -> LifeError {
+        LifeError::ParseError { line: err.line, column: err.column, message: err.message }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<QueryError> for LifeError {
+    fn from(err: QueryError) -> LifeError {
+        // Queries are a single line
+        LifeError::ParseError { line: 1, column: err.position + 1, message: err.message }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ReplayError> for LifeError {
+    fn from(err: ReplayError) -> LifeError {
+        LifeError::ParseError { line: err.line, column: 0, message: err.message }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<BuildError> for LifeError {
+    fn from(err: BuildError) -> LifeError {
+        match err {
+            BuildError::Size(err) => err.into(),
+            BuildError::InvalidRule(err) => err.into(),
+            BuildError::InvalidPattern(message) => LifeError::ParseError { line: 0, column: 0, message },
+            BuildError::MissingDimensions | BuildError::InvalidDensity(_) | BuildError::PatternOutOfBounds { .. } => {
+                LifeError::InvalidArgument(err.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SnapshotError> for LifeError {
+    fn from(err: SnapshotError) -> LifeError {
+        match err {
+            SnapshotError::UnsupportedVersion(version) => LifeError::UnsupportedVersion(version),
+            SnapshotError::InvalidDimensions { width, height } => LifeError::InvalidDimensions { width, height },
+            SnapshotError::InvalidBase64 | SnapshotError::BadHeader | SnapshotError::Truncated { .. } => {
+                LifeError::InvalidData(err.to_string())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DeltaError> for LifeError {
+    fn from(err: DeltaError) -> LifeError {
+        match err {
+            DeltaError::UnsupportedVersion(version) => LifeError::UnsupportedVersion(version),
+            DeltaError::BadHeader | DeltaError::Truncated => LifeError::InvalidData(err.to_string()),
+            DeltaError::NotTracking
+            | DeltaError::TooOld { .. }
+            | DeltaError::DimensionMismatch
+            | DeltaError::GenerationMismatch { .. } => LifeError::InvalidState(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<GifError> for LifeError {
+    fn from(err: GifError) -> LifeError {
+        LifeError::InvalidArgument(err.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Error> for LifeError {
+    fn from(err: serde_json::Error) -> LifeError {
+        LifeError::ParseError { line: err.line(), column: err.column(), message: err.to_string() }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<JsonError> for LifeError {
+    fn from(err: JsonError) -> LifeError {
+        match err {
+            JsonError::Syntax(err) => err.into(),
+            JsonError::InvalidDimensions { width, height } => LifeError::InvalidDimensions { width, height },
+            JsonError::OutOfBounds { row, column } => LifeError::OutOfBounds { row, column },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::Universe;
+
+    #[test]
+    fn conversions() {
+        let err = Universe::random(0, 3, 0.5).err().unwrap();
+        assert_eq!(LifeError::InvalidDimensions { width: 0, height: 3 }, err);
+        assert_eq!("a 0x3 universe has no cells, width and height must be non-zero", err.to_string());
+
+        let err: LifeError = "..o\n.x.\n".parse::<Universe>().err().unwrap().into();
+        assert_eq!("ParseError", err.kind());
+        assert!(matches!(err, LifeError::ParseError { line: 2, column: 2, .. }));
+
+        let err = Universe::new(4, 4, vec![]).query_str("count alive in (1,").err().unwrap();
+        assert!(matches!(err, LifeError::ParseError { line: 1, column: 19, .. }));
+
+        let err: LifeError = "B3/S2x".parse::<crate::Rule>().err().unwrap().into();
+        assert_eq!("UnsupportedRule", err.kind());
+
+        let mut data = Universe::new(4, 4, vec![]).to_snapshot();
+        data.truncate(data.len() - 1);
+        let err = Universe::decode_snapshot(&data).err().unwrap();
+        assert_eq!("InvalidData", err.kind());
+        assert_eq!(LifeError::UnsupportedVersion(9), DeltaError::UnsupportedVersion(9).into());
+    }
+
+    // `?` gathers the module errors into one
+    #[test]
+    fn question_mark() {
+        fn load(text: &str, rule: &str) -> Result<Universe, LifeError> {
+            let mut universe: Universe = text.parse()?;
+            universe.set_life_rule(rule.parse()?);
+            Ok(universe)
+        }
+        assert!(load(".o\no.\n", "B36/S23").is_ok());
+        assert!(matches!(load(".o\no.\n", "B9/S"), Err(LifeError::UnsupportedRule(_))));
+        assert!(matches!(load(".o\nx.\n", "B3/S23"), Err(LifeError::ParseError { line: 2, column: 1, .. })));
+    }
+}
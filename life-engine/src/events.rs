@@ -0,0 +1,234 @@
+// Callbacks fired from tick() when something interesting happens, so the
+// frontend doesn't have to poll status() after every generation
+//
+// Region watches narrow that down to part of the board: each is a rect and
+// a callback given the births and deaths inside it, fired after any tick
+// that changed something there and skipped otherwise. A detail panel or a
+// sound trigger can watch its own corner without diffing the whole board.
+// Edits don't fire them, only ticks. Births and deaths are of the cell
+// buffer, so the other way round while a B0 rule has it complemented
+//
+// The universe is still mid-tick when they run, so a JS callback calling back
+// into the same universe will fail on the borrow. Queue that work instead
+// (e.g. with `setTimeout` or a promise)
+
+use crate::{Cell, Rect, Status, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+type Hook = Box<dyn FnMut()>;
+type GenerationHook = Box<dyn FnMut(u64)>;
+type StabilizedHook = Box<dyn FnMut(u32)>;
+type RegionHook = Box<dyn FnMut(u32, u32)>;
+
+struct RegionWatch {
+    id: u32,
+    area: Rect,
+    hook: RegionHook,
+}
+
+#[derive(Default)]
+pub(crate) struct Events {
+    extinction: Option<Hook>,
+    stabilized: Option<StabilizedHook>,
+    generation: Option<GenerationHook>,
+    watches: Vec<RegionWatch>,
+    next_watch: u32,
+}
+
+impl Universe {
+    /// Called once when the last cell dies
+    pub fn set_on_extinction(&mut self, hook: impl FnMut() + 'static) {
+        self.events.extinction = Some(Box::new(hook));
+    }
+
+    /// Called once with the period when the universe becomes a still life
+    /// (period 1) or starts oscillating
+    pub fn set_on_stabilized(&mut self, hook: impl FnMut(u32) + 'static) {
+        self.events.stabilized = Some(Box::new(hook));
+    }
+
+    /// Called with the new generation number after every tick
+    pub fn set_on_generation(&mut self, hook: impl FnMut(u64) + 'static) {
+        self.events.generation = Some(Box::new(hook));
+    }
+
+    /// Called with the births and deaths inside `area` after every tick
+    /// that changes a cell there. Returns an id for `unwatch_region()`
+    pub fn add_region_watch(&mut self, area: Rect, hook: impl FnMut(u32, u32) + 'static) -> u32 {
+        let id = self.events.next_watch;
+        self.events.next_watch += 1;
+        self.events.watches.push(RegionWatch { id, area, hook: Box::new(hook) });
+        id
+    }
+
+    // Called at the end of tick() with the status from before it
+    pub(crate) fn fire_events(&mut self, previous: Status) {
+        let status = self.status();
+        if status != previous {
+            match status {
+                Status::Extinct => {
+                    if let Some(hook) = self.events.extinction.as_mut() {
+                        hook();
+                    }
+                }
+                Status::StillLife | Status::Oscillating => {
+                    let period = self.status_period();
+                    if let Some(hook) = self.events.stabilized.as_mut() {
+                        hook(period);
+                    }
+                }
+                Status::Running => {}
+            }
+        }
+
+        if let Some(hook) = self.events.generation.as_mut() {
+            hook(self.generation);
+        }
+        self.fire_region_watches();
+    }
+
+    fn fire_region_watches(&mut self) {
+        // Straight after a tick the active set is just the cells it changed
+        let changed = match &self.active_cells {
+            Some(changed) if !self.events.watches.is_empty() => changed,
+            _ => return,
+        };
+        for watch in self.events.watches.iter_mut() {
+            let (mut births, mut deaths) = (0, 0);
+            for &idx in changed {
+                if watch.area.contains(idx as u32 / self.width, idx as u32 % self.width) {
+                    match self.cells[idx] {
+                        Cell::Alive => births += 1,
+                        Cell::Dead => deaths += 1,
+                    }
+                }
+            }
+            if births + deaths > 0 {
+                (watch.hook)(births, deaths);
+            }
+        }
+    }
+}
+
+// JS exceptions thrown by a callback are swallowed, they shouldn't stop the
+// simulation
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    #[cfg(feature = "wasm")]
+    pub fn on_extinction(&mut self, callback: js_sys::Function) {
+        self.set_on_extinction(move || {
+            let _ = callback.call0(&JsValue::NULL);
+        });
+    }
+
+    /// `callback` receives the period of the cycle
+    #[cfg(feature = "wasm")]
+    pub fn on_stabilized(&mut self, callback: js_sys::Function) {
+        self.set_on_stabilized(move |period| {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from(period));
+        });
+    }
+
+    /// `callback` receives the generation number
+    #[cfg(feature = "wasm")]
+    pub fn on_generation(&mut self, callback: js_sys::Function) {
+        self.set_on_generation(move |generation| {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from(generation as f64));
+        });
+    }
+
+    /// `callback` receives the births and deaths inside `area`, and the
+    /// returned id stops it again through `unwatch_region()`
+    #[cfg(feature = "wasm")]
+    pub fn watch_region(&mut self, area: &Rect, callback: js_sys::Function) -> u32 {
+        self.add_region_watch(*area, move |births, deaths| {
+            let _ = callback.call2(&JsValue::NULL, &JsValue::from(births), &JsValue::from(deaths));
+        })
+    }
+
+    /// Stop a region watch, false if there was none with this id
+    pub fn unwatch_region(&mut self, id: u32) -> bool {
+        let before = self.events.watches.len();
+        self.events.watches.retain(|watch| watch.id != id);
+        self.events.watches.len() < before
+    }
+
+    /// Drop every callback, region watches included
+    pub fn clear_event_callbacks(&mut self) {
+        self.events = Events::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn callbacks_fire_once() {
+        let log = Rc::new(RefCell::new(vec![]));
+
+        let mut blinker = Universe::new(8, 8, vec![(1, 1), (1, 2), (1, 3)]);
+        let sink = log.clone();
+        blinker.set_on_stabilized(move |period| sink.borrow_mut().push(format!("stabilized {}", period)));
+        let sink = log.clone();
+        blinker.set_on_generation(move |generation| sink.borrow_mut().push(format!("generation {}", generation)));
+        for _ in 0..4 {
+            blinker.tick();
+        }
+        assert_eq!(
+            vec!["generation 1", "generation 2", "stabilized 2", "generation 3", "generation 4"],
+            *log.borrow()
+        );
+
+        log.borrow_mut().clear();
+        let mut single = Universe::new(8, 8, vec![(4, 4)]);
+        let sink = log.clone();
+        single.set_on_extinction(move || sink.borrow_mut().push("extinct".to_string()));
+        let sink = log.clone();
+        single.set_on_stabilized(move |_| sink.borrow_mut().push("stabilized".to_string()));
+        single.tick();
+        single.tick();
+        assert_eq!(vec!["extinct"], *log.borrow());
+
+        single.clear_event_callbacks();
+        single.set_owner(1, 1, 0);
+        single.tick();
+        assert_eq!(1, log.borrow().len());
+    }
+
+    #[test]
+    fn region_watches_fire_on_changes_inside() {
+        let log = Rc::new(RefCell::new(vec![]));
+        // A blinker on the left and a block on the right
+        let mut universe = Universe::new(12, 8, vec![(2, 1), (2, 2), (2, 3), (2, 8), (2, 9), (3, 8), (3, 9)]);
+        let sink = log.clone();
+        let left = universe.add_region_watch(Rect::new(0, 0, 8, 6), move |births, deaths| {
+            sink.borrow_mut().push(("left", births, deaths))
+        });
+        let sink = log.clone();
+        universe.add_region_watch(Rect::new(0, 6, 8, 6), move |births, deaths| {
+            sink.borrow_mut().push(("right", births, deaths))
+        });
+        // Just the top cell of the blinker's upright phase
+        let sink = log.clone();
+        universe.add_region_watch(Rect::new(0, 0, 2, 12), move |births, deaths| {
+            sink.borrow_mut().push(("top", births, deaths))
+        });
+
+        universe.tick();
+        universe.tick();
+        assert_eq!(vec![("left", 2, 2), ("top", 1, 0), ("left", 2, 2), ("top", 0, 1)], *log.borrow());
+
+        // Edits don't count, and an unwatched region stays quiet
+        assert!(universe.unwatch_region(left));
+        assert!(!universe.unwatch_region(left));
+        log.borrow_mut().clear();
+        universe.toggle_cell(7, 0);
+        universe.tick();
+        assert_eq!(vec![("top", 1, 0)], *log.borrow());
+    }
+}
@@ -0,0 +1,374 @@
+// Evolving small seed patterns toward an objective with a genetic algorithm
+//
+// A genome is a square of cells, `seed_size` across, placed in the middle of
+// an otherwise empty board with dead edges. Every genome in the population
+// is run for up to `max_generations` (stopping once it dies or settles) and
+// scored by the objective:
+//
+//     Lifespan          generations before it dies or settles, so
+//                       methuselahs score well
+//     FinalPopulation   live cells at the end
+//     Growth            area of the live cells' bounding box at the end over
+//                       the area at the start
+//
+// Each step of the search keeps the best `elite` genomes as they are and
+// breeds the rest from parents picked by tournament, mixing two parents cell
+// by cell with probability `crossover_rate` (otherwise copying the first)
+// then flipping each cell with probability `mutation_rate`. The whole search
+// comes from one seed, so the same settings always find the same patterns.
+// Changing anything but the two rates starts it again from fresh random
+// genomes on the next step
+//
+//     const evolution = new Evolution(Objective.Lifespan, 1n);
+//     evolution.on_progress((step, best, mean) => chart.push(step, best, mean));
+//     evolution.run(50);
+//     const universe = evolution.best_universe();
+
+use crate::rng::{seeded, SeededRng};
+use crate::{Boundary, Cell, Rule, Status, Universe};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const TOURNAMENT_SIZE: usize = 3;
+
+type ProgressHook = Box<dyn FnMut(u32, f64, f64)>;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Objective {
+    Lifespan,
+    FinalPopulation,
+    Growth,
+}
+
+#[derive(Clone, Debug)]
+struct Genome {
+    cells: Vec<Cell>,
+    fitness: f64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Evolution {
+    objective: Objective,
+    rule: Rule,
+    population_size: u32,
+    elite: u32,
+    mutation_rate: f64,
+    crossover_rate: f64,
+    seed_size: u32,
+    board_width: u32,
+    board_height: u32,
+    max_generations: u32,
+    rng: SeededRng,
+    // Scored and sorted best first, empty until the first step
+    population: Vec<Genome>,
+    steps: u32,
+    progress: Option<ProgressHook>,
+}
+
+impl Evolution {
+    /// Progress after every step, with the step number, best fitness and
+    /// mean fitness
+    pub fn set_on_progress(&mut self, hook: impl FnMut(u32, f64, f64) + 'static) {
+        self.progress = Some(Box::new(hook));
+    }
+
+    pub fn set_life_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.restart();
+    }
+
+    fn restart(&mut self) {
+        self.population.clear();
+        self.steps = 0;
+    }
+
+    /// The best seed found so far, `seed_size` rows of `seed_size` cells
+    pub fn best_seed(&self) -> Option<&[Cell]> {
+        self.population.first().map(|genome| &genome.cells[..])
+    }
+
+    // The board a genome runs on, the seed in the middle
+    fn board(&self, seed: &[Cell]) -> Universe {
+        let mut cells = vec![Cell::Dead; (self.board_width * self.board_height) as usize];
+        let top = (self.board_height - self.seed_size) / 2;
+        let left = (self.board_width - self.seed_size) / 2;
+        for (idx, &cell) in seed.iter().enumerate() {
+            let (row, column) = (top + idx as u32 / self.seed_size, left + idx as u32 % self.seed_size);
+            cells[(row * self.board_width + column) as usize] = cell;
+        }
+
+        let mut universe = Universe::from_cells(self.board_width, self.board_height, cells);
+        universe.boundary = Boundary::Dead;
+        universe.rule = self.rule;
+        universe
+    }
+
+    fn fitness(&self, seed: &[Cell]) -> f64 {
+        let mut universe = self.board(seed);
+        let start_area = universe.live_bounds().map_or(0, |bounds| bounds.area());
+        universe.run_until_stable(self.max_generations as u64);
+
+        match self.objective {
+            Objective::Lifespan if universe.status() == Status::Running => universe.generation() as f64,
+            Objective::Lifespan => universe.status_generation() as f64,
+            Objective::FinalPopulation => universe.population() as f64,
+            Objective::Growth if start_area == 0 => 0.0,
+            Objective::Growth => universe.live_bounds().map_or(0, |bounds| bounds.area()) as f64 / start_area as f64,
+        }
+    }
+
+    fn random_seed(&mut self) -> Vec<Cell> {
+        let rng = &mut self.rng;
+        (0..self.seed_size * self.seed_size)
+            .map(|_| if rng.gen_bool(0.5) { Cell::Alive } else { Cell::Dead })
+            .collect()
+    }
+
+    // Fittest of a few genomes picked at random
+    fn tournament(&mut self) -> usize {
+        let size = self.population.len();
+        (0..TOURNAMENT_SIZE).map(|_| self.rng.gen_range(0..size)).min().unwrap()
+    }
+
+    fn offspring(&mut self) -> Vec<Cell> {
+        let first = self.tournament();
+        let mut cells = self.population[first].cells.clone();
+        if self.rng.gen_bool(self.crossover_rate) {
+            let second = self.tournament();
+            for (cell, &other) in cells.iter_mut().zip(&self.population[second].cells) {
+                if self.rng.gen_bool(0.5) {
+                    *cell = other;
+                }
+            }
+        }
+        for cell in cells.iter_mut() {
+            if self.rng.gen_bool(self.mutation_rate) {
+                *cell = match *cell {
+                    Cell::Dead => Cell::Alive,
+                    Cell::Alive => Cell::Dead,
+                };
+            }
+        }
+        cells
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Evolution {
+    /// A search toward `objective` with 32 genomes of 8 by 8 cells on a 64
+    /// by 64 board, run for up to 500 generations each
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(objective: Objective, seed: u64) -> Evolution {
+        Evolution {
+            objective,
+            rule: Rule::CONWAY,
+            population_size: 32,
+            elite: 2,
+            mutation_rate: 0.02,
+            crossover_rate: 0.7,
+            seed_size: 8,
+            board_width: 64,
+            board_height: 64,
+            max_generations: 500,
+            rng: seeded(seed),
+            population: vec![],
+            steps: 0,
+            progress: None,
+        }
+    }
+
+    pub fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    pub fn set_objective(&mut self, objective: Objective) {
+        self.objective = objective;
+        self.restart();
+    }
+
+    /// At least 2
+    pub fn set_population_size(&mut self, size: u32) {
+        self.population_size = size.max(2);
+        self.restart();
+    }
+
+    /// Genomes carried over unchanged each step, fewer than the population
+    pub fn set_elite(&mut self, elite: u32) {
+        self.elite = elite;
+        self.restart();
+    }
+
+    /// Chance of each cell flipping in a new genome, clamped to 0 to 1
+    pub fn set_mutation_rate(&mut self, rate: f64) {
+        self.mutation_rate = if rate.is_nan() { 0.0 } else { rate.clamp(0.0, 1.0) };
+    }
+
+    /// Chance of a new genome mixing two parents rather than copying one,
+    /// clamped to 0 to 1
+    pub fn set_crossover_rate(&mut self, rate: f64) {
+        self.crossover_rate = if rate.is_nan() { 0.0 } else { rate.clamp(0.0, 1.0) };
+    }
+
+    /// Cells across the square seed pattern, at least 1 and at most the
+    /// board's smaller side
+    pub fn set_seed_size(&mut self, size: u32) {
+        self.seed_size = size.max(1).min(self.board_width.min(self.board_height));
+        self.restart();
+    }
+
+    /// Board every genome runs on, no smaller than the seed
+    pub fn set_board_size(&mut self, width: u32, height: u32) {
+        self.board_width = width.max(self.seed_size);
+        self.board_height = height.max(self.seed_size);
+        self.restart();
+    }
+
+    pub fn set_max_generations(&mut self, generations: u32) {
+        self.max_generations = generations;
+        self.restart();
+    }
+
+    /// Switch rule, e.g. "B36/S23"
+    #[cfg(feature = "wasm")]
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        let rule = rule.parse::<Rule>().map_err(crate::error::js_error)?;
+        self.set_life_rule(rule);
+        Ok(())
+    }
+
+    /// `callback` receives the step number, best fitness and mean fitness
+    /// after every step. Exceptions it throws are swallowed
+    #[cfg(feature = "wasm")]
+    pub fn on_progress(&mut self, callback: js_sys::Function) {
+        self.set_on_progress(move |step, best, mean| {
+            let _ = callback.call3(&JsValue::NULL, &JsValue::from(step), &JsValue::from(best), &JsValue::from(mean));
+        });
+    }
+
+    /// Breed the next population, or make and score the first one, returning
+    /// the best fitness
+    pub fn step(&mut self) -> f64 {
+        let seeds = if self.population.is_empty() {
+            (0..self.population_size).map(|_| self.random_seed()).collect::<Vec<_>>()
+        } else {
+            let elite = self.elite.min(self.population_size - 1) as usize;
+            let mut seeds = self.population[..elite].iter().map(|genome| genome.cells.clone()).collect::<Vec<_>>();
+            while seeds.len() < self.population_size as usize {
+                seeds.push(self.offspring());
+            }
+            seeds
+        };
+
+        let mut population = seeds.into_iter()
+            .map(|cells| Genome { fitness: self.fitness(&cells), cells })
+            .collect::<Vec<_>>();
+        // Stable, so earlier genomes win ties and elites stay on top
+        population.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+        self.population = population;
+        self.steps += 1;
+
+        let (best, mean) = (self.best_fitness(), self.mean_fitness());
+        if let Some(hook) = self.progress.as_mut() {
+            hook(self.steps, best, mean);
+        }
+        best
+    }
+
+    /// Run `steps` steps, returning the best fitness at the end
+    pub fn run(&mut self, steps: u32) -> f64 {
+        for _ in 0..steps {
+            self.step();
+        }
+        self.best_fitness()
+    }
+
+    /// Steps taken since the search last started
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Best fitness in the current population, 0 before the first step
+    pub fn best_fitness(&self) -> f64 {
+        self.population.first().map_or(0.0, |genome| genome.fitness)
+    }
+
+    pub fn mean_fitness(&self) -> f64 {
+        if self.population.is_empty() {
+            return 0.0;
+        }
+        self.population.iter().map(|genome| genome.fitness).sum::<f64>() / self.population.len() as f64
+    }
+
+    /// The best seed so far as `seed_size` rows of 0s and 1s, empty before
+    /// the first step
+    pub fn best_cells(&self) -> Vec<u8> {
+        self.best_seed().map_or(vec![], |cells| cells.iter().map(|&cell| cell as u8).collect())
+    }
+
+    /// The best seed on its board, ready to watch from generation 0. A blank
+    /// board before the first step
+    pub fn best_universe(&self) -> Universe {
+        let blank = vec![Cell::Dead; (self.seed_size * self.seed_size) as usize];
+        self.board(self.best_seed().unwrap_or(&blank))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn small(objective: Objective, seed: u64) -> Evolution {
+        let mut evolution = Evolution::new(objective, seed);
+        evolution.set_board_size(24, 24);
+        evolution.set_seed_size(5);
+        evolution.set_population_size(10);
+        evolution.set_max_generations(60);
+        evolution
+    }
+
+    #[test]
+    fn fitness_never_drops() {
+        let progress = Rc::new(RefCell::new(vec![]));
+        let mut evolution = small(Objective::Lifespan, 3);
+        let sink = progress.clone();
+        evolution.set_on_progress(move |step, best, mean| sink.borrow_mut().push((step, best, mean)));
+
+        assert_eq!(0.0, evolution.best_fitness());
+        evolution.run(6);
+        let progress = progress.borrow();
+        assert_eq!(6, progress.len());
+        assert_eq!((1..=6).collect::<Vec<_>>(), progress.iter().map(|&(step, _, _)| step).collect::<Vec<_>>());
+        // Elites carry over, so the best can only improve
+        assert!(progress.windows(2).all(|pair| pair[1].1 >= pair[0].1));
+        assert!(progress.iter().all(|&(_, best, mean)| best >= mean));
+
+        // The best universe scores what the search says it does
+        let mut universe = evolution.best_universe();
+        assert_eq!(25, evolution.best_cells().len());
+        universe.run_until_stable(60);
+        let lifespan = if universe.status() == Status::Running { universe.generation() } else { universe.status_generation() };
+        assert_eq!(evolution.best_fitness(), lifespan as f64);
+    }
+
+    #[test]
+    fn searches_are_reproducible() {
+        let mut first = small(Objective::Growth, 8);
+        let mut second = small(Objective::Growth, 8);
+        assert_eq!(first.run(3), second.run(3));
+        assert_eq!(first.best_cells(), second.best_cells());
+
+        let mut population = small(Objective::FinalPopulation, 8);
+        population.run(2);
+        assert_eq!(population.best_fitness(), {
+            let mut universe = population.best_universe();
+            universe.run_until_stable(60);
+            universe.population() as f64
+        });
+        assert!(Evolution::new(Objective::Lifespan, 1).best_cells().is_empty());
+    }
+}
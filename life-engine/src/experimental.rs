@@ -0,0 +1,96 @@
+// Runtime switches for experimental engines and renderers
+//
+// Flags are global to the module instance so a frontend can flip them per
+// session without needing a separate build, and include `list_features()`
+// in bug reports. Only the names in FLAGS can be enabled, and only when
+// their code is in this build, so the list never claims something ran that
+// couldn't have
+
+use std::sync::Mutex;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Every known flag, what it switches on and whether this build has it
+const FLAGS: &[(&str, &str, bool)] = &[
+    ("simd", "vectorised neighbour counting", cfg!(feature = "simd")),
+    (
+        "parallel",
+        "multithreaded tick on large universes",
+        cfg!(feature = "wasm-threads") || !cfg!(target_arch = "wasm32"),
+    ),
+];
+
+static ENABLED: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+fn compiled_in() -> impl Iterator<Item = (&'static str, &'static str)> {
+    FLAGS.iter().filter(|&&(_, _, compiled)| compiled).map(|&(flag, description, _)| (flag, description))
+}
+
+fn known(name: &str) -> Option<&'static str> {
+    compiled_in().map(|(flag, _)| flag).find(|&flag| flag == name)
+}
+
+/// Turn on an experimental flag, returning false if there's no such flag
+/// or this build leaves out its code (the `simd` cargo feature, say)
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn enable_experimental(name: &str) -> bool {
+    let flag = match known(name) {
+        Some(flag) => flag,
+        None => return false,
+    };
+    let mut enabled = ENABLED.lock().unwrap();
+    if !enabled.contains(&flag) {
+        enabled.push(flag);
+    }
+    true
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn disable_experimental(name: &str) {
+    ENABLED.lock().unwrap().retain(|&flag| flag != name);
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn experimental_enabled(name: &str) -> bool {
+    ENABLED.lock().unwrap().contains(&name)
+}
+
+/// Names of the flags currently enabled, in the order they were turned on
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn list_features() -> Vec<String> {
+    ENABLED.lock().unwrap().iter().map(|flag| flag.to_string()).collect()
+}
+
+/// Every flag this build can enable as "name: description" lines
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn available_features() -> Vec<String> {
+    compiled_in().map(|(flag, description)| format!("{}: {}", flag, description)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_flags() {
+        assert!(!enable_experimental("warp-drive"));
+        assert!(!experimental_enabled("warp-drive"));
+        assert_eq!(compiled_in().count(), available_features().len());
+
+        // Flags whose code isn't compiled in can't be turned on
+        let simd_listed = available_features().iter().any(|flag| flag.starts_with("simd:"));
+        assert_eq!(cfg!(feature = "simd"), simd_listed);
+        if !cfg!(feature = "simd") {
+            assert!(!enable_experimental("simd"));
+            assert!(!list_features().contains(&"simd".to_string()));
+        }
+
+        // Other tests may toggle flags too, so only look at this one
+        assert!(enable_experimental("parallel"));
+        assert!(enable_experimental("parallel"));
+        assert!(experimental_enabled("parallel"));
+        assert_eq!(1, list_features().iter().filter(|flag| *flag == "parallel").count());
+        disable_experimental("parallel");
+        assert!(!experimental_enabled("parallel"));
+    }
+}
@@ -0,0 +1,139 @@
+// Exploring the rules one step away from the current one
+//
+// Each variant adds or takes away a single birth or survival count, so
+// B3/S23 has the 18 neighbours B03/S23, B3/S23 without S2 and so on. Every
+// variant, and the current rule for comparison, runs from the same seeded
+// random fill at the universe's size and boundary for a few generations,
+// stopping early once it settles. The summaries say enough to pick out the
+// interesting ones (still growing, oscillating, neither dead nor exploding)
+// for a closer look with `set_rule()`. The universe itself is left alone
+//
+//     {"rule": "B3/S23", "current": {...}, "variants": [{"rule": "B03/S23",
+//      "change": "+B0", "initial_population": 131, "final_population": 398,
+//      "status": 3, "period": 2, "activity": 201.5}, ...]}
+
+use crate::{Rule, Status, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How one rule did from the fill
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleVariant {
+    /// The rule in B/S notation
+    pub rule: String,
+    /// What differs from the current rule, e.g. "+B6" or "-S2", empty for
+    /// the current rule itself
+    pub change: String,
+    pub initial_population: u32,
+    pub final_population: u32,
+    /// Status at the end, Running if it hadn't settled
+    pub status: Status,
+    /// Period of the final cycle, 1 for still lifes and 0 if it didn't settle
+    pub period: u32,
+    /// Mean births plus deaths per generation
+    pub activity: f64,
+    /// Generations run, fewer than asked for if it settled
+    pub generations: u32,
+}
+
+/// The current rule's run alongside every variant's
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleExploration {
+    pub rule: String,
+    pub current: RuleVariant,
+    pub variants: Vec<RuleVariant>,
+}
+
+impl Universe {
+    /// Run the current rule and each variant one count away for up to
+    /// `generations` from the fill `randomize(density, seed)` would give
+    pub fn explore_rules(&self, generations: u32, density: f32, seed: u64) -> RuleExploration {
+        let current = self.try_rule(self.rule, String::new(), generations, density, seed);
+        let mut variants = Vec::with_capacity(18);
+        for (birth, letter) in [(true, 'B'), (false, 'S')] {
+            for count in 0..=8u8 {
+                let sign = if self.rule.contains_count(birth, count) { '-' } else { '+' };
+                let change = format!("{}{}{}", sign, letter, count);
+                variants.push(self.try_rule(self.rule.toggled(birth, count), change, generations, density, seed));
+            }
+        }
+        RuleExploration { rule: self.rule.to_string(), current, variants }
+    }
+
+    fn try_rule(&self, rule: Rule, change: String, generations: u32, density: f32, seed: u64) -> RuleVariant {
+        let mut universe = Universe::random_seeded(self.width, self.height, density, seed)
+            .expect("the universe's own size is valid");
+        universe.boundary = self.boundary;
+        universe.rule = rule;
+
+        let initial_population = universe.population();
+        let mut changes = 0u64;
+        let mut run = 0;
+        while run < generations && universe.status() == Status::Running {
+            universe.tick();
+            changes += (universe.births_last_tick() + universe.deaths_last_tick()) as u64;
+            run += 1;
+        }
+
+        let status = universe.status();
+        RuleVariant {
+            rule: rule.to_string(),
+            change,
+            initial_population,
+            final_population: universe.population(),
+            status,
+            period: if status == Status::Running || status == Status::Extinct { 0 } else { universe.status_period() },
+            activity: if run == 0 { 0.0 } else { changes as f64 / run as f64 },
+            generations: run,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Run the current rule and every rule one birth or survival count away
+    /// for up to `generations` each from the same seeded fill, returning how
+    /// each did
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = explore_rules)]
+    pub fn explore_rules_js(&self, generations: u32, density: f32, seed: u64) -> Ts<RuleExploration> {
+        crate::utils::to_js(&self.explore_rules(generations, density, seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explore_neighbouring_rules() {
+        let universe = Universe::random_seeded(24, 24, 0.3, 5).unwrap();
+        let exploration = universe.explore_rules(60, 0.4, 9);
+        assert_eq!("B3/S23", exploration.rule);
+        assert_eq!(18, exploration.variants.len());
+        assert_eq!(("+B0", "B03/S23"), (&exploration.variants[0].change[..], &exploration.variants[0].rule[..]));
+        assert_eq!(("-B3", "B/S23"), (&exploration.variants[3].change[..], &exploration.variants[3].rule[..]));
+        assert_eq!(("-S2", "B3/S3"), (&exploration.variants[11].change[..], &exploration.variants[11].rule[..]));
+
+        // Every run starts from the same fill, which the universe doesn't
+        // see
+        let filled = Universe::random_seeded(24, 24, 0.4, 9).unwrap();
+        assert!(exploration.variants.iter().all(|variant| variant.initial_population == filled.population()));
+        assert_eq!("", exploration.current.change);
+        assert_eq!(0, universe.generation());
+
+        // Without births everything dies out or freezes
+        let barren = &exploration.variants[3];
+        assert!(barren.status != Status::Running && barren.generations < 60);
+        assert_eq!(exploration, universe.explore_rules(60, 0.4, 9));
+    }
+}
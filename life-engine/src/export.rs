@@ -0,0 +1,88 @@
+// Exporting the cell buffer in row bands, so huge universes can be
+// serialized or uploaded piece by piece without one gigantic allocation
+
+use crate::error::LifeError;
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Iterator over successive row bands of a universe, each one byte per cell
+/// (0 dead, 1 alive). The last band may have fewer rows
+pub struct ExportChunks<'a> {
+    universe: &'a Universe,
+    chunk_rows: u32,
+    next_row: u32,
+}
+
+impl<'a> Iterator for ExportChunks<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_row >= self.universe.height {
+            return None;
+        }
+
+        let rows = self.chunk_rows.min(self.universe.height - self.next_row);
+        let start = self.universe.get_index(self.next_row, 0);
+        let end = start + (rows * self.universe.width) as usize;
+        self.next_row += rows;
+
+        Some(self.universe.cells[start..end].iter().map(|&cell| cell as u8).collect())
+    }
+}
+
+fn check_chunk_rows(chunk_rows: u32) -> Result<(), LifeError> {
+    match chunk_rows {
+        0 => Err(LifeError::InvalidArgument("chunk_rows must be at least 1".into())),
+        _ => Ok(()),
+    }
+}
+
+impl Universe {
+    pub fn export_chunks(&self, chunk_rows: u32) -> Result<ExportChunks<'_>, LifeError> {
+        check_chunk_rows(chunk_rows)?;
+        Ok(ExportChunks { universe: self, chunk_rows, next_row: 0 })
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Number of bands `export_chunk` splits the universe into
+    pub fn export_chunk_count(&self, chunk_rows: u32) -> Result<u32, LifeError> {
+        check_chunk_rows(chunk_rows)?;
+        Ok(self.height.div_ceil(chunk_rows))
+    }
+
+    /// The `index`th band of `chunk_rows` rows, for JS which can't hold on to
+    /// a Rust iterator
+    pub fn export_chunk(&self, chunk_rows: u32, index: u32) -> Result<Vec<u8>, LifeError> {
+        let mut chunks = self.export_chunks(chunk_rows)?;
+        chunks.next_row = index.saturating_mul(chunk_rows);
+        Ok(chunks.next().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_cover_universe() {
+        let universe = Universe::new(3, 5, vec![(0, 0), (2, 1), (4, 2)]);
+
+        let chunks: Vec<Vec<u8>> = universe.export_chunks(2).unwrap().collect();
+        assert_eq!(3, chunks.len());
+        assert_eq!(vec![1, 0, 0, 0, 0, 0], chunks[0]);
+        assert_eq!(vec![0, 1, 0, 0, 0, 0], chunks[1]);
+        assert_eq!(vec![0, 0, 1], chunks[2]);
+
+        assert_eq!(Ok(3), universe.export_chunk_count(2));
+        assert_eq!(Ok(chunks[2].clone()), universe.export_chunk(2, 2));
+        assert_eq!(Ok(vec![]), universe.export_chunk(2, 3));
+
+        assert!(universe.export_chunks(0).is_err());
+        assert!(universe.export_chunk_count(0).is_err());
+        assert!(universe.export_chunk(0, 0).is_err());
+    }
+}
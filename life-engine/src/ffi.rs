@@ -0,0 +1,261 @@
+// C API, for embedding the engine in C and C++ programs
+//
+// A universe is an opaque handle made by one of the constructors and freed
+// with `life_universe_destroy`. Constructors return NULL for dimensions the
+// engine rejects, and every other function treats a NULL handle as empty
+// (reads give 0, writes do nothing), so a failed constructor can't crash
+// the caller further down. The header is generated into
+// life-ffi/include/life.h by that crate's build script
+//
+//     LifeUniverse *universe = life_universe_new(64, 64, 0.3, 1);
+//     life_universe_tick(universe, 100);
+//     const uint8_t *cells = life_universe_cells(universe);
+//     ...
+//     life_universe_destroy(universe);
+
+use crate::{Rule, Universe};
+
+use std::ffi::{c_char, CStr};
+use std::ptr;
+
+fn into_handle(universe: Option<Universe>) -> *mut Universe {
+    universe.map_or(ptr::null_mut(), |universe| Box::into_raw(Box::new(universe)))
+}
+
+/// Random universe, each cell alive with probability `density`, the same
+/// `seed` always giving the same cells. NULL for a width or height of 0 or
+/// too many cells
+#[no_mangle]
+pub extern "C" fn life_universe_new(width: u32, height: u32, density: f32, seed: u64) -> *mut Universe {
+    into_handle(Universe::random_seeded(width, height, density, seed).ok())
+}
+
+/// Universe from grid text like `life_universe_render` writes, or a
+/// plaintext pattern. NULL if the text can't be read
+///
+/// # Safety
+///
+/// `text` must be NULL or a NUL terminated string
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_from_text(text: *const c_char) -> *mut Universe {
+    if text.is_null() {
+        return ptr::null_mut();
+    }
+    let text = CStr::from_ptr(text).to_str().ok();
+    into_handle(text.and_then(|text| text.parse().ok()))
+}
+
+/// Free a universe. Does nothing for NULL
+///
+/// # Safety
+///
+/// `universe` must be NULL or a handle which hasn't been destroyed yet
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_destroy(universe: *mut Universe) {
+    if !universe.is_null() {
+        drop(Box::from_raw(universe));
+    }
+}
+
+/// Run `generations` ticks
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_tick(universe: *mut Universe, generations: u32) {
+    if let Some(universe) = universe.as_mut() {
+        for _ in 0..generations {
+            universe.tick();
+        }
+    }
+}
+
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_width(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::width)
+}
+
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_height(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::height)
+}
+
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_generation(universe: *const Universe) -> u64 {
+    universe.as_ref().map_or(0, Universe::generation)
+}
+
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_population(universe: *const Universe) -> u32 {
+    universe.as_ref().map_or(0, Universe::population)
+}
+
+/// The cells, width * height bytes row by row, 1 alive and 0 dead. NULL for
+/// a NULL handle
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle. The pointer is only valid until
+/// the universe is next changed or destroyed
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_cells(universe: *const Universe) -> *const u8 {
+    universe.as_ref().map_or(ptr::null(), |universe| universe.cells.as_ptr() as *const u8)
+}
+
+/// Copy up to `len` cells into `out`, laid out like `life_universe_cells`,
+/// returning how many were copied
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle, and `out` must have room for
+/// `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_copy_cells(universe: *const Universe, out: *mut u8, len: usize) -> usize {
+    match universe.as_ref() {
+        Some(universe) if !out.is_null() => {
+            let count = len.min(universe.cells.len());
+            ptr::copy_nonoverlapping(universe.cells.as_ptr() as *const u8, out, count);
+            count
+        }
+        _ => 0,
+    }
+}
+
+/// Whether the cell is alive, false outside the universe
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_is_alive(universe: *const Universe, row: u32, column: u32) -> bool {
+    match universe.as_ref() {
+        Some(universe) if row < universe.height() && column < universe.width() => universe.is_alive(row, column),
+        _ => false,
+    }
+}
+
+/// Flip a cell, doing nothing outside the universe
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_toggle_cell(universe: *mut Universe, row: u32, column: u32) {
+    if let Some(universe) = universe.as_mut() {
+        if row < universe.height() && column < universe.width() {
+            universe.toggle_cell(row, column);
+        }
+    }
+}
+
+/// Switch to a B/S rule like "B36/S23", returning false and keeping the old
+/// rule if it can't be parsed
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle, and `rule` NULL or a NUL
+/// terminated string
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_set_rule(universe: *mut Universe, rule: *const c_char) -> bool {
+    if universe.is_null() || rule.is_null() {
+        return false;
+    }
+    match CStr::from_ptr(rule).to_str().ok().and_then(|rule| rule.parse::<Rule>().ok()) {
+        Some(rule) => {
+            (*universe).set_life_rule(rule);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Write the grid `render()` prints into `out` as a NUL terminated string,
+/// returning the length it needs without the terminator. Nothing is
+/// written unless `len` is more than that, so call with a NULL `out` first
+/// to size the buffer
+///
+/// # Safety
+///
+/// `universe` must be NULL or a live handle, and `out` NULL or room for
+/// `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn life_universe_render(universe: *const Universe, out: *mut c_char, len: usize) -> usize {
+    let text = match universe.as_ref() {
+        Some(universe) => universe.render(),
+        None => return 0,
+    };
+    if !out.is_null() && len > text.len() {
+        ptr::copy_nonoverlapping(text.as_ptr() as *const c_char, out, text.len());
+        *out.add(text.len()) = 0;
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    fn text(text: &str) -> CString {
+        CString::new(text).unwrap()
+    }
+
+    #[test]
+    fn c_api() {
+        unsafe {
+            let universe = life_universe_from_text(text("....\n.##.\n.##.\n....\n").as_ptr());
+            assert_eq!((4, 4), (life_universe_width(universe), life_universe_height(universe)));
+            assert_eq!(4, life_universe_population(universe));
+            life_universe_tick(universe, 3);
+            assert_eq!(3, life_universe_generation(universe));
+
+            let cells = std::slice::from_raw_parts(life_universe_cells(universe), 16);
+            assert_eq!(&[0, 0, 0, 0, 0, 1, 1, 0], &cells[..8]);
+            let mut copied = [9u8; 20];
+            assert_eq!(16, life_universe_copy_cells(universe, copied.as_mut_ptr(), copied.len()));
+            assert_eq!(cells, &copied[..16]);
+
+            life_universe_toggle_cell(universe, 0, 0);
+            life_universe_toggle_cell(universe, 9, 9);
+            assert!(life_universe_is_alive(universe, 0, 0) && !life_universe_is_alive(universe, 9, 9));
+
+            assert!(life_universe_set_rule(universe, text("B36/S23").as_ptr()));
+            assert!(!life_universe_set_rule(universe, text("nonsense").as_ptr()));
+            assert_eq!("B36/S23", (*universe).rule());
+
+            let needed = life_universe_render(universe, ptr::null_mut(), 0);
+            let mut rendered = vec![1 as c_char; needed + 1];
+            assert_eq!(needed, life_universe_render(universe, rendered.as_mut_ptr(), rendered.len()));
+            assert_eq!((*universe).render(), CStr::from_ptr(rendered.as_ptr()).to_str().unwrap());
+
+            life_universe_destroy(universe);
+        }
+    }
+
+    #[test]
+    fn null_handles() {
+        unsafe {
+            let universe = life_universe_new(0, 8, 0.5, 1);
+            assert!(universe.is_null());
+            life_universe_tick(universe, 1);
+            assert_eq!(0, life_universe_population(universe));
+            assert!(life_universe_cells(universe).is_null());
+            assert!(!life_universe_set_rule(universe, text("B3/S23").as_ptr()));
+            assert!(life_universe_from_text(text("xyz").as_ptr()).is_null());
+            life_universe_destroy(universe);
+        }
+    }
+}
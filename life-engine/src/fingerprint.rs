@@ -0,0 +1,153 @@
+// Hashes for spotting repeated or visually similar states
+//
+// `state_hash` is an exact hash of the board, cheap enough to call every
+// tick for loop detection or deduplication on the JS side.
+//
+// `thumbnail_hash` is a perceptual style fingerprint instead. The universe
+// is shrunk to an 8x8 grid of live cell densities and each
+// block becomes one bit, set if it's denser than the average block. States
+// which look alike give hashes a small Hamming distance apart, so galleries
+// of soup search results can be deduplicated without comparing boards
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const GRID: u32 = 8;
+
+// Rows or columns of a side `size` long falling in block `block` of the
+// grid, worked out in u64 as the product can be past u32::MAX
+fn grid_span(block: u32, size: u32) -> core::ops::Range<u32> {
+    let edge = |block: u32| (block as u64 * size as u64 / GRID as u64) as u32;
+    edge(block)..edge(block + 1)
+}
+
+// FxHash style multiply-rotate over the cell bytes, eight at a time. The
+// width is mixed in so boards with the same cells in a different shape
+// don't collide
+fn hash_cells(width: u32, cells: &[Cell]) -> u64 {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    let mut hash = (width as u64) << 32 | cells.len() as u64;
+    let mut chunks = cells.chunks_exact(8);
+    for chunk in &mut chunks {
+        let mut word = [0; 8];
+        for (byte, &cell) in word.iter_mut().zip(chunk) {
+            *byte = cell as u8;
+        }
+        hash = (hash.rotate_left(5) ^ u64::from_le_bytes(word)).wrapping_mul(SEED);
+    }
+    for &cell in chunks.remainder() {
+        hash = (hash.rotate_left(5) ^ cell as u64).wrapping_mul(SEED);
+    }
+    hash
+}
+
+impl Universe {
+    // Live cell density of each block of the 8x8 grid, row by row
+    fn block_densities(&self) -> Vec<f64> {
+        let mut densities = Vec::with_capacity((GRID * GRID) as usize);
+        for block_row in 0..GRID {
+            let rows = grid_span(block_row, self.height);
+            for block_column in 0..GRID {
+                let columns = grid_span(block_column, self.width);
+
+                let area = rows.len() * columns.len();
+                if area == 0 {
+                    // Universes smaller than the grid leave some blocks empty
+                    densities.push(0.0);
+                    continue
+                }
+
+                let alive = rows.clone()
+                    .flat_map(|row| columns.clone().map(move |column| (row, column)))
+                    .filter(|&(row, column)| self.cells[self.get_index(row, column)] == Cell::Alive)
+                    .count();
+                densities.push(alive as f64 / area as f64);
+            }
+        }
+        densities
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Fast non-cryptographic hash of the dimensions and cells, equal
+    /// states always hash the same
+    pub fn state_hash(&self) -> u64 {
+        hash_cells(self.width, &self.cells)
+    }
+
+    /// 64 bit fingerprint of the board's overall shape, see
+    /// `thumbnail_distance` for comparing them
+    pub fn thumbnail_hash(&self) -> u64 {
+        let densities = self.block_densities();
+        let mean = densities.iter().sum::<f64>() / densities.len() as f64;
+
+        densities.iter()
+            .enumerate()
+            .filter(|&(_, &density)| density > mean)
+            .fold(0, |hash, (bit, _)| hash | 1 << bit)
+    }
+}
+
+/// Number of differing bits between two thumbnail hashes, 0 meaning they
+/// look the same at thumbnail resolution
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn thumbnail_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_states_hash_close() {
+        // Block of life in the top left quarter
+        let mut alive = vec![];
+        for row in 0..16 {
+            for column in 0..16 {
+                alive.push((row, column));
+            }
+        }
+        let universe = Universe::new(32, 32, alive.clone());
+        let hash = universe.thumbnail_hash();
+        assert_eq!(16, hash.count_ones());
+        assert_eq!(hash, Universe::new(32, 32, alive.clone()).thumbnail_hash());
+
+        // A couple of stray cells barely move the fingerprint
+        alive.push((30, 30));
+        alive.push((20, 4));
+        let similar = Universe::new(32, 32, alive).thumbnail_hash();
+        assert!(thumbnail_distance(hash, similar) <= 2);
+
+        let empty = Universe::new(32, 32, vec![]).thumbnail_hash();
+        assert_eq!(0, empty);
+        assert_eq!(16, thumbnail_distance(hash, empty));
+    }
+
+    #[test]
+    fn state_hash() {
+        let blinker = Universe::new(5, 5, vec![(2, 1), (2, 2), (2, 3)]);
+        let mut ticked = Universe::new(5, 5, vec![(2, 1), (2, 2), (2, 3)]);
+        assert_eq!(blinker.state_hash(), ticked.state_hash());
+
+        ticked.tick();
+        assert_ne!(blinker.state_hash(), ticked.state_hash());
+        ticked.tick();
+        assert_eq!(blinker.state_hash(), ticked.state_hash());
+
+        // Same cell buffer in a different shape
+        assert_ne!(Universe::new(2, 8, vec![]).state_hash(), Universe::new(4, 4, vec![]).state_hash());
+    }
+
+    #[test]
+    fn tiny_universe() {
+        // Only some blocks cover a cell, the top left cell lands in block
+        // row 3 column 2
+        let universe = Universe::new(3, 2, vec![(0, 0)]);
+        assert_eq!(1 << 26, universe.thumbnail_hash());
+    }
+}
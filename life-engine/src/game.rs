@@ -0,0 +1,238 @@
+// Two player matches, built on the ownership layer
+//
+// Each player places cells for their team (owner 1 or 2) with set_owner(),
+// then start_match() and tick away. Births already go to whichever team
+// holds most of the parents. On top of that a match keeps territory: every
+// cell a team's live cell has stood on is theirs until the other team's
+// cells take it over, even after it dies. A match ends when:
+//
+// - one team has no live cells left (both gone at once is a draw)
+// - a team's territory reaches the goal, a fraction of the board
+// - the generation limit runs out, the larger territory winning
+//
+// The result is kept once decided, while the board can carry on ticking
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const TEAMS: [u8; 2] = [1, 2];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchResult {
+    /// No match started, or still being played
+    Playing,
+    TeamOneWins,
+    TeamTwoWins,
+    Draw,
+}
+
+#[derive(Clone)]
+pub(crate) struct Match {
+    // Team that last held each cell, 0 for neither
+    claimed: Vec<u8>,
+    territory: [u32; 2],
+    // Territory that wins outright, 0 for no goal
+    goal: u32,
+    // Generation the match ends on, None to play until a win
+    end: Option<u64>,
+    result: MatchResult,
+}
+
+impl Match {
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.claimed.capacity()
+    }
+
+    pub(crate) fn rearrange(&mut self, source: &[Option<usize>]) {
+        self.claimed = source.iter().map(|from| from.map_or(0, |from| self.claimed[from])).collect();
+        self.territory = TEAMS.map(|team| self.claimed.iter().filter(|&&claim| claim == team).count() as u32);
+    }
+
+    fn claim(&mut self, idx: usize, owner: u8) {
+        let previous = self.claimed[idx];
+        if !TEAMS.contains(&owner) || previous == owner {
+            return;
+        }
+        if previous != 0 {
+            self.territory[previous as usize - 1] -= 1;
+        }
+        self.territory[owner as usize - 1] += 1;
+        self.claimed[idx] = owner;
+    }
+}
+
+fn winner(first: u32, second: u32) -> MatchResult {
+    match first.cmp(&second) {
+        std::cmp::Ordering::Greater => MatchResult::TeamOneWins,
+        std::cmp::Ordering::Less => MatchResult::TeamTwoWins,
+        std::cmp::Ordering::Equal => MatchResult::Draw,
+    }
+}
+
+impl Universe {
+    // Called from tick() once the new generation and its owners are in
+    // place
+    pub(crate) fn update_match(&mut self, changed: &[usize]) {
+        let (cells, generation) = (&self.cells, self.generation);
+        let (game, ownership) = match (self.game.as_mut(), &self.ownership) {
+            (Some(game), Some(ownership)) if game.result == MatchResult::Playing => (game, ownership),
+            _ => return,
+        };
+        let owners = ownership.owners();
+        for &idx in changed {
+            if cells[idx] == Cell::Alive {
+                game.claim(idx, owners[idx]);
+            }
+        }
+
+        let [first, second] = TEAMS.map(|team| {
+            owners.iter().zip(cells).filter(|&(&owner, &cell)| owner == team && cell == Cell::Alive).count()
+        });
+        let [first_territory, second_territory] = game.territory;
+        let reached_goal = game.goal > 0 && first_territory.max(second_territory) >= game.goal;
+        let out_of_time = game.end.is_some_and(|end| generation >= end);
+        game.result = if first == 0 || second == 0 {
+            winner(first as u32, second as u32)
+        } else if reached_goal || out_of_time {
+            winner(first_territory, second_territory)
+        } else {
+            MatchResult::Playing
+        };
+    }
+
+    // For set_owner(), so cells placed mid-match are claimed straight away
+    pub(crate) fn match_claim(&mut self, idx: usize, owner: u8) {
+        if let Some(game) = self.game.as_mut() {
+            if game.result == MatchResult::Playing {
+                game.claim(idx, owner);
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start a match between teams 1 and 2 from the cells placed so far.
+    /// It's won outright by claiming `goal` of the board (0 for no goal) and
+    /// otherwise decided on territory after `generations` ticks (0 to play
+    /// on until one team is wiped out or reaches the goal)
+    pub fn start_match(&mut self, generations: u32, goal: f64) {
+        self.enable_ownership();
+        let mut game = Match {
+            claimed: vec![0; self.cells.len()],
+            territory: [0; 2],
+            goal: (goal.max(0.0) * self.cells.len() as f64).ceil().min(u32::MAX as f64) as u32,
+            end: (generations > 0).then_some(self.generation + generations as u64),
+            result: MatchResult::Playing,
+        };
+        let owners = self.ownership.as_ref().unwrap().owners();
+        for (idx, &owner) in owners.iter().enumerate() {
+            if self.cells[idx] == Cell::Alive {
+                game.claim(idx, owner);
+            }
+        }
+        self.game = Some(game);
+    }
+
+    /// Stop keeping score, leaving the teams' cells and owners as they are
+    pub fn end_match(&mut self) {
+        self.game = None;
+    }
+
+    pub fn match_result(&self) -> MatchResult {
+        self.game.as_ref().map_or(MatchResult::Playing, |game| game.result)
+    }
+
+    /// Cells claimed by `team` (1 or 2) in the current match
+    pub fn territory(&self, team: u8) -> u32 {
+        match (&self.game, team) {
+            (Some(game), 1 | 2) => game.territory[team as usize - 1],
+            _ => 0,
+        }
+    }
+
+    /// Team holding each cell, in the same layout as the cell buffer, for
+    /// shading the board under the live cells
+    pub fn territory_map(&self) -> Vec<u8> {
+        match &self.game {
+            Some(game) => game.claimed.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Generations left before the match is decided on territory, 0 once
+    /// it's over or when it has no limit
+    pub fn match_generations_left(&self) -> u64 {
+        match &self.game {
+            Some(Match { end: Some(end), result: MatchResult::Playing, .. }) => end.saturating_sub(self.generation),
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A glider for each team, well apart
+    fn board() -> Universe {
+        let mut universe = Universe::new(32, 32, vec![]);
+        for (row, column) in [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            universe.set_owner(row + 2, column + 2, 1);
+            universe.set_owner(row + 20, column + 20, 2);
+        }
+        universe
+    }
+
+    #[test]
+    fn decided_on_territory() {
+        // Team 2 gets a block as well, for a territory lead
+        let mut universe = board();
+        for (row, column) in [(10, 25), (10, 26), (11, 25), (11, 26)] {
+            universe.set_owner(row, column, 2);
+        }
+        universe.start_match(8, 0.0);
+        assert_eq!((5, 9), (universe.territory(1), universe.territory(2)));
+        for _ in 0..7 {
+            universe.tick();
+        }
+        assert_eq!(MatchResult::Playing, universe.match_result());
+        assert_eq!(1, universe.match_generations_left());
+        // Gliders only ever cover new ground
+        assert!(universe.territory(1) > 5);
+        assert_eq!(universe.territory(1) + 4, universe.territory(2));
+
+        universe.tick();
+        assert_eq!(MatchResult::TeamTwoWins, universe.match_result());
+        assert_eq!(0, universe.match_generations_left());
+        let territory = universe.territory_map();
+        assert_eq!(2, territory[10 * 32 + 25]);
+        assert_eq!(universe.territory(1), territory.iter().filter(|&&team| team == 1).count() as u32);
+    }
+
+    #[test]
+    fn won_by_elimination_or_goal() {
+        // Team 1 is a lone cell that dies on the first tick
+        let mut universe = board();
+        universe.clear_cells(&[2 * 32 + 3, 3 * 32 + 4, 4 * 32 + 2, 4 * 32 + 4]);
+        universe.start_match(0, 0.0);
+        universe.tick();
+        assert_eq!(MatchResult::TeamTwoWins, universe.match_result());
+
+        // Everything after the result is left alone
+        universe.set_owner(0, 0, 1);
+        assert_eq!(1, universe.territory(1));
+
+        let mut universe = board();
+        universe.start_match(0, 6.0 / 1024.0);
+        assert_eq!(MatchResult::Playing, universe.match_result());
+        universe.tick();
+        assert_eq!(MatchResult::Draw, universe.match_result());
+
+        universe.end_match();
+        assert_eq!(0, universe.territory(2));
+    }
+}
@@ -0,0 +1,228 @@
+// Animated GIF export, for downloading a run as a looping animation
+//
+// The encoder is written out here rather than pulled in as a dependency as
+// it only ever needs two colours: a global palette of white (dead) and black
+// (alive), then per frame a delay, the image descriptor and the pixels
+// LZW compressed. The NETSCAPE2.0 extension makes the animation loop forever
+//
+// Frames are taken from a duplicate, so the universe itself isn't advanced
+
+use crate::error::LifeError;
+use crate::{Cell, Universe};
+
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// GIF dimensions are u16
+const MAX_SIDE: u32 = u16::MAX as u32;
+// Two colours need the smallest code size GIF allows
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODE: u16 = 4095;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GifError {
+    TooLarge { width: u64, height: u64 },
+}
+
+impl fmt::Display for GifError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GifError::TooLarge { width, height } => {
+                write!(f, "a {}x{} pixel GIF is too large, sides can be at most {}", width, height, MAX_SIDE)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GifError {}
+
+// Packs variable width codes least significant bit first, as GIF wants
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+// LZW compress colour indices the way GIF decoders expect
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear: u16 = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut out = BitWriter { bytes: vec![], buffer: 0, bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut width = MIN_CODE_SIZE + 1;
+
+    out.write(clear, width);
+    let mut prefix = match indices.first() {
+        Some(&first) => first as u16,
+        None => {
+            out.write(end, width);
+            return out.finish();
+        }
+    };
+    for &index in &indices[1..] {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        out.write(prefix, width);
+        if next_code <= MAX_CODE {
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+            // Decoders add their entry a code later, so widen a code later
+            if next_code > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            out.write(clear, width);
+            table.clear();
+            next_code = end + 1;
+            width = MIN_CODE_SIZE + 1;
+        }
+        prefix = index as u16;
+    }
+    out.write(prefix, width);
+    out.write(end, width);
+    out.finish()
+}
+
+fn push_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+impl Universe {
+    /// Rust side of `export_gif`
+    pub fn encode_gif(&self, generations: u32, cell_size: u32, frame_delay_ms: u32) -> Result<Vec<u8>, LifeError> {
+        let cell_size = cell_size.max(1);
+        let (width, height) = (self.width as u64 * cell_size as u64, self.height as u64 * cell_size as u64);
+        if width > MAX_SIDE as u64 || height > MAX_SIDE as u64 {
+            return Err(GifError::TooLarge { width, height }.into());
+        }
+
+        let mut gif = b"GIF89a".to_vec();
+        push_u16(&mut gif, width as u16);
+        push_u16(&mut gif, height as u16);
+        // Global colour table of two entries, white then black
+        gif.extend_from_slice(&[0x80, 0, 0, 0xFF, 0xFF, 0xFF, 0, 0, 0]);
+        // Loop forever
+        gif.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        // GIF delays are in hundredths of a second
+        let delay = (frame_delay_ms / 10).min(u16::MAX as u32) as u16;
+        let mut universe = self.duplicate();
+        for frame in 0..generations.max(1) {
+            if frame > 0 {
+                universe.tick();
+            }
+
+            gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            push_u16(&mut gif, delay);
+            gif.extend_from_slice(&[0x00, 0x00]);
+
+            gif.push(0x2C);
+            push_u16(&mut gif, 0);
+            push_u16(&mut gif, 0);
+            push_u16(&mut gif, width as u16);
+            push_u16(&mut gif, height as u16);
+            gif.push(0x00);
+
+            let mut indices = Vec::with_capacity(width as usize * height as usize);
+            for row in 0..universe.height {
+                let start = indices.len();
+                for column in 0..universe.width {
+                    let index = (universe.cells[universe.get_index(row, column)] == Cell::Alive) as u8;
+                    indices.extend(std::iter::repeat_n(index, cell_size as usize));
+                }
+                for _ in 1..cell_size {
+                    indices.extend_from_within(start..start + width as usize);
+                }
+            }
+
+            gif.push(MIN_CODE_SIZE);
+            for block in lzw(&indices).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend_from_slice(block);
+            }
+            gif.push(0x00);
+        }
+
+        gif.push(0x3B);
+        Ok(gif)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// An animated GIF of this generation and the `generations - 1` after
+    /// it, each cell `cell_size` pixels square, ready to wrap in a Blob for
+    /// download. Throws if the image would be wider or taller than 65535
+    /// pixels
+    #[cfg(feature = "wasm")]
+    pub fn export_gif(&self, generations: u32, cell_size: u32, frame_delay_ms: u32) -> Result<Vec<u8>, JsValue> {
+        self.encode_gif(generations, cell_size, frame_delay_ms).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(data: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        let mut options = ::gif::DecodeOptions::new();
+        options.set_color_output(::gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(data).unwrap();
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+        frames
+    }
+
+    #[test]
+    fn gif_frames_follow_generations() {
+        let universe = Universe::new(5, 5, vec![(2, 1), (2, 2), (2, 3)]);
+        let data = universe.encode_gif(3, 2, 100).unwrap();
+        assert_eq!(0, universe.generation());
+
+        let frames = decode(&data);
+        assert_eq!(3, frames.len());
+        assert!(frames.iter().all(|(delay, pixels)| *delay == 10 && pixels.len() == 100));
+        // Horizontal then vertical then horizontal again
+        assert_eq!(frames[0].1, frames[2].1);
+        assert_eq!(vec![0, 0, 1, 1, 1, 1, 1, 1, 0, 0], frames[0].1[40..50].to_vec());
+        assert_eq!(vec![0, 0, 0, 0, 1, 1, 0, 0, 0, 0], frames[1].1[20..30].to_vec());
+    }
+
+    #[test]
+    fn gif_compression_round_trips() {
+        // Big and noisy enough to fill the code table and start it over
+        let universe = Universe::random_seeded(200, 150, 0.4, 12).unwrap();
+        let frames = decode(&universe.encode_gif(2, 1, 40).unwrap());
+        let expected = universe.cells.iter().map(|&cell| cell as u8).collect::<Vec<_>>();
+        assert_eq!(expected, frames[0].1);
+
+        let too_wide = Universe::new(40_000, 1, vec![]);
+        assert_eq!(Err(GifError::TooLarge { width: 80_000, height: 2 }.into()), too_wide.encode_gif(1, 2, 0));
+    }
+}
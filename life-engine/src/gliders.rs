@@ -0,0 +1,228 @@
+// Gliders dropped in at the edges, to keep a long running board lively
+//
+// A screensaver left running settles into still lifes and blinkers before
+// long. `spawn_gliders(rate, seed)` adds `rate` gliders a tick on average
+// (so 0.05 is one every twenty ticks and 2 is two every tick) straight after
+// each tick, at random along the edges and heading in towards the middle:
+// one heading south east starts along the top or left edge, and so on.
+// `set_glider_heading` keeps them all to one diagonal, and
+// `set_glider_margin` sets how many cells in from the edge they start, so
+// they aren't wrapped round or cut off by the boundary. Each glider is
+// written over a clear 3x3 box, like an edit but not one that can be undone
+// or that shows on the timeline, and the spawns are seeded so runs can be
+// reproduced
+
+use crate::rng::{seeded, SeededRng};
+use crate::{Cell, Universe};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Heading south east, the others are flips of it
+const GLIDER: [[bool; 3]; 3] = [[false, true, false], [false, false, true], [true, true, true]];
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GliderHeading {
+    // A random diagonal for each glider
+    Any,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+const HEADINGS: [GliderHeading; 4] =
+    [GliderHeading::NorthEast, GliderHeading::NorthWest, GliderHeading::SouthEast, GliderHeading::SouthWest];
+
+#[derive(Clone)]
+pub(crate) struct Gliders {
+    pub(crate) rate: f64,
+    pub(crate) heading: GliderHeading,
+    pub(crate) margin: u32,
+    pub(crate) rng: Box<SeededRng>,
+}
+
+impl Universe {
+    // Called at the end of tick(), after everything else has seen the
+    // generation
+    pub(crate) fn release_gliders(&mut self) {
+        let mut gliders = match self.gliders.take() {
+            Some(gliders) => gliders,
+            None => return,
+        };
+        let mut count = gliders.rate.trunc() as u32;
+        if gliders.rng.gen_bool(gliders.rate.fract()) {
+            count += 1;
+        }
+        let room = 3 + gliders.margin;
+        if self.width >= room && self.height >= room {
+            for _ in 0..count {
+                self.place_glider(&mut gliders);
+            }
+        }
+        self.gliders = Some(gliders);
+    }
+
+    fn place_glider(&mut self, gliders: &mut Gliders) {
+        let rng = &mut gliders.rng;
+        let heading = match gliders.heading {
+            GliderHeading::Any => HEADINGS[rng.gen_range(0..HEADINGS.len())],
+            heading => heading,
+        };
+        let north = matches!(heading, GliderHeading::NorthEast | GliderHeading::NorthWest);
+        let west = matches!(heading, GliderHeading::NorthWest | GliderHeading::SouthWest);
+
+        // Top left of its box, against the edge it's coming in from
+        let (last_row, last_column) = (self.height - 3 - gliders.margin, self.width - 3 - gliders.margin);
+        let (top, left) = if rng.gen_bool(0.5) {
+            let top = if north { last_row } else { gliders.margin };
+            (top, rng.gen_range(0..=self.width - 3))
+        } else {
+            let left = if west { last_column } else { gliders.margin };
+            (rng.gen_range(0..=self.height - 3), left)
+        };
+
+        for (row, cells) in GLIDER.iter().enumerate() {
+            for (column, &alive) in cells.iter().enumerate() {
+                let row = if north { 2 - row } else { row } as u32;
+                let column = if west { 2 - column } else { column } as u32;
+                let idx = self.get_index(top + row, left + column);
+                self.place_cell(idx, if alive { Cell::Alive } else { Cell::Dead });
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Add `rate` gliders per tick on average at the edges, heading inwards,
+    /// with `seed` driving where. A rate of 0 stops them. The heading and
+    /// margin are kept when changing the rate
+    pub fn spawn_gliders(&mut self, rate: f64, seed: u64) {
+        if rate <= 0.0 || rate.is_nan() {
+            self.gliders = None;
+            return;
+        }
+        let (heading, margin) = self.gliders.as_ref()
+            .map_or((GliderHeading::Any, 1), |gliders| (gliders.heading, gliders.margin));
+        self.gliders = Some(Gliders { rate, heading, margin, rng: Box::new(seeded(seed)) });
+    }
+
+    /// Gliders added per tick on average, 0 when not spawning
+    pub fn glider_rate(&self) -> f64 {
+        self.gliders.as_ref().map_or(0.0, |gliders| gliders.rate)
+    }
+
+    pub fn glider_heading(&self) -> GliderHeading {
+        self.gliders.as_ref().map_or(GliderHeading::Any, |gliders| gliders.heading)
+    }
+
+    /// Only has an effect while spawning gliders
+    pub fn set_glider_heading(&mut self, heading: GliderHeading) {
+        if let Some(gliders) = self.gliders.as_mut() {
+            gliders.heading = heading;
+        }
+    }
+
+    /// Cells between the edge and the box a glider starts in
+    pub fn glider_margin(&self) -> u32 {
+        self.gliders.as_ref().map_or(1, |gliders| gliders.margin)
+    }
+
+    /// Only has an effect while spawning gliders
+    pub fn set_glider_margin(&mut self, margin: u32) {
+        if let Some(gliders) = self.gliders.as_mut() {
+            gliders.margin = margin;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boundary;
+
+    // Top left of the live cells' bounding box
+    fn corner(universe: &Universe) -> (u32, u32) {
+        let cells = universe.live_cells().collect::<Vec<_>>();
+        (cells.iter().map(|cell| cell.0).min().unwrap(), cells.iter().map(|cell| cell.1).min().unwrap())
+    }
+
+    #[test]
+    fn glider_heads_inwards() {
+        for &(heading, rows, columns) in &[
+            (GliderHeading::SouthEast, 2, 2),
+            (GliderHeading::SouthWest, 2, -2),
+            (GliderHeading::NorthEast, -2, 2),
+            (GliderHeading::NorthWest, -2, -2),
+        ] {
+            let mut universe = Universe::new(30, 20, vec![]);
+            universe.set_boundary(Boundary::Dead);
+            universe.spawn_gliders(1.0, 4);
+            universe.set_glider_heading(heading);
+            universe.set_glider_margin(3);
+            universe.tick();
+            assert_eq!(5, universe.population());
+            let (top, left) = corner(&universe);
+            assert!(
+                [3, 20 - 6].contains(&top) || [3, 30 - 6].contains(&left),
+                "{:?} starts at ({}, {})",
+                heading,
+                top,
+                left
+            );
+
+            universe.spawn_gliders(0.0, 0);
+            for _ in 0..8 {
+                universe.tick();
+            }
+            let moved = corner(&universe);
+            assert_eq!(((top as i32 + rows) as u32, (left as i32 + columns) as u32), moved, "{:?}", heading);
+        }
+    }
+
+    #[test]
+    fn spawns_reproducibly_at_the_rate() {
+        let spawned = |seed| {
+            let mut universe = Universe::new(64, 64, vec![]);
+            universe.set_life_rule("B/S012345678".parse().unwrap());
+            universe.spawn_gliders(2.5, seed);
+            for _ in 0..40 {
+                universe.tick();
+            }
+            universe
+        };
+        let (a, b) = (spawned(7), spawned(7));
+        assert_eq!(a.cells, b.cells);
+        assert_ne!(a.cells, spawned(8).cells);
+        // Nothing dies or is born, so every glider is still there, some on
+        // top of others
+        assert!((150..=500).contains(&a.population()), "{}", a.population());
+
+        let mut universe = Universe::new(4, 4, vec![]);
+        universe.spawn_gliders(5.0, 1);
+        assert_eq!((5.0, GliderHeading::Any, 1), (universe.glider_rate(), universe.glider_heading(), universe.glider_margin()));
+        universe.set_glider_margin(2);
+        universe.tick();
+        assert_eq!(0, universe.population());
+    }
+
+    #[test]
+    fn not_undoable_edits() {
+        let mut universe = Universe::new(20, 20, vec![]);
+        universe.set_undo_capacity(8);
+        universe.enable_timeline();
+        universe.spawn_gliders(1.0, 2);
+        universe.tick();
+        assert_eq!(5, universe.population());
+        assert!(!universe.undo_edit());
+        assert!(!universe.timeline().iter().any(|event| matches!(event.kind, crate::TimelineKind::Edit { .. })));
+
+        // The user's edits still undo as before
+        universe.toggle_cell(10, 10);
+        assert!(universe.undo_edit());
+        assert_eq!(5, universe.population());
+    }
+}
@@ -0,0 +1,280 @@
+// Gosper's HashLife, on a square board with a power of two side
+//
+// The board is a quadtree whose nodes are shared: every distinct square of
+// cells is stored once, looked up by its four quarters. Each node also
+// remembers what its centre becomes a generation later, so a square seen
+// before (empty space, a still life, a spaceship's every phase) is never
+// worked out again. Boards made of repeated parts tick far faster than
+// their size suggests, though a soup with nothing in common is slower than
+// a plain scan.
+//
+// Working out a node's future only gives its centre, the half away from the
+// edges. The board wraps round, so its neighbourhood is the board tiled 2x2:
+// the centre of that is the whole board, moved half a side diagonally, and
+// swapping its quarters back over moves it into place.
+//
+// Nodes pile up as the pattern changes, so once there are too many the
+// board is copied into a fresh table and the old one dropped
+
+use crate::error::LifeError;
+use crate::rule::NEIGHBOURS;
+use crate::size::check_size;
+use crate::{Cell, LifeEngine, Rule};
+
+use std::collections::HashMap;
+
+// Nodes kept before starting a fresh table
+const MAX_NODES: usize = 1 << 21;
+
+const DEAD: u32 = 0;
+const ALIVE: u32 = 1;
+
+#[derive(Clone, Debug)]
+struct Node {
+    // North west, north east, south west, south east
+    quarters: [u32; 4],
+    // 0 for a single cell, with 2^level cells a side
+    level: u32,
+    population: u64,
+    // The centre one generation on, once worked out
+    next: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HashLifeEngine {
+    side: u32,
+    nodes: Vec<Node>,
+    index: HashMap<[u32; 4], u32>,
+    root: u32,
+    rule: Rule,
+    generation: u64,
+}
+
+impl HashLifeEngine {
+    /// An all dead board running Conway's Life. The board has to be square
+    /// with a side that's a power of two, 4 or more
+    pub fn new(width: u32, height: u32) -> Result<HashLifeEngine, LifeError> {
+        check_size(width, height)?;
+        if width != height || width < 4 || !width.is_power_of_two() {
+            return Err(LifeError::InvalidArgument(format!(
+                "HashLife needs a square board with a power of two side of at least 4, not {}x{}",
+                width, height
+            )));
+        }
+        let mut engine = HashLifeEngine {
+            side: width,
+            nodes: vec![],
+            index: HashMap::new(),
+            root: DEAD,
+            rule: Rule::CONWAY,
+            generation: 0,
+        };
+        engine.clear_table();
+        engine.root = engine.empty(width.trailing_zeros());
+        Ok(engine)
+    }
+
+    // Just the two leaves
+    fn clear_table(&mut self) {
+        let leaf = |population| Node { quarters: [DEAD; 4], level: 0, population, next: None };
+        self.nodes = vec![leaf(0), leaf(1)];
+        self.index.clear();
+    }
+
+    fn join(&mut self, quarters: [u32; 4]) -> u32 {
+        if let Some(&id) = self.index.get(&quarters) {
+            return id;
+        }
+        let level = self.nodes[quarters[0] as usize].level + 1;
+        let population = quarters.iter().map(|&quarter| self.nodes[quarter as usize].population).sum();
+        let id = self.nodes.len() as u32;
+        self.nodes.push(Node { quarters, level, population, next: None });
+        self.index.insert(quarters, id);
+        id
+    }
+
+    fn empty(&mut self, level: u32) -> u32 {
+        (0..level).fold(DEAD, |node, _| self.join([node; 4]))
+    }
+
+    fn quarters(&self, node: u32) -> [u32; 4] {
+        self.nodes[node as usize].quarters
+    }
+
+    // The centre of a level 2 node one generation on, cell by cell
+    fn next_4x4(&mut self, node: u32) -> u32 {
+        let mut cells = [[Cell::Dead; 4]; 4];
+        for (quarter_idx, &quarter) in self.quarters(node).iter().enumerate() {
+            for (leaf_idx, &leaf) in self.quarters(quarter).iter().enumerate() {
+                let row = quarter_idx / 2 * 2 + leaf_idx / 2;
+                let column = quarter_idx % 2 * 2 + leaf_idx % 2;
+                cells[row][column] = if leaf == ALIVE { Cell::Alive } else { Cell::Dead };
+            }
+        }
+
+        let mut next = [DEAD; 4];
+        for (idx, leaf) in next.iter_mut().enumerate() {
+            let (row, column) = (1 + idx / 2, 1 + idx % 2);
+            let neighbourhood = NEIGHBOURS.iter().enumerate().fold(0, |neighbourhood, (bit, &(row_delta, column_delta))| {
+                let cell = cells[(row as i64 + row_delta) as usize][(column as i64 + column_delta) as usize];
+                neighbourhood | (cell as u8) << bit
+            });
+            if self.rule.next_neighbourhood(cells[row][column], neighbourhood) == Cell::Alive {
+                *leaf = ALIVE;
+            }
+        }
+        self.join(next)
+    }
+
+    // The centre of `node`, half its side, one generation on
+    fn next(&mut self, node: u32) -> u32 {
+        if let Some(next) = self.nodes[node as usize].next {
+            return next;
+        }
+        let next = if self.nodes[node as usize].level == 2 {
+            self.next_4x4(node)
+        } else {
+            let [nw, ne, sw, se] = self.quarters(node);
+            // Corner quarters of the corners aren't needed
+            let ([_, nw_ne, nw_sw, nw_se], [ne_nw, _, ne_sw, ne_se]) = (self.quarters(nw), self.quarters(ne));
+            let ([sw_nw, sw_ne, _, sw_se], [se_nw, se_ne, se_sw, _]) = (self.quarters(sw), self.quarters(se));
+
+            // Nine overlapping squares half the size, each stepped to give
+            // its centre
+            let north = self.join([nw_ne, ne_nw, nw_se, ne_sw]);
+            let west = self.join([nw_sw, nw_se, sw_nw, sw_ne]);
+            let centre = self.join([nw_se, ne_sw, sw_ne, se_nw]);
+            let east = self.join([ne_sw, ne_se, se_nw, se_ne]);
+            let south = self.join([sw_ne, se_nw, sw_se, se_sw]);
+            let steps = [nw, north, ne, west, centre, east, sw, south, se].map(|square| self.next(square));
+
+            // The result's quarters are pieced together from the steps'
+            // quarters around them
+            let mut quarters = [DEAD; 4];
+            for (idx, quarter) in quarters.iter_mut().enumerate() {
+                let base = idx / 2 * 3 + idx % 2;
+                let pieces = [
+                    self.quarters(steps[base])[3],
+                    self.quarters(steps[base + 1])[2],
+                    self.quarters(steps[base + 3])[1],
+                    self.quarters(steps[base + 4])[0],
+                ];
+                *quarter = self.join(pieces);
+            }
+            self.join(quarters)
+        };
+        self.nodes[node as usize].next = Some(next);
+        next
+    }
+
+    fn build(&mut self, cells: &[Cell], level: u32, top: u32, left: u32) -> u32 {
+        if level == 0 {
+            return cells[(top * self.side + left) as usize] as u32;
+        }
+        let half = 1 << (level - 1);
+        let quarters = [(0, 0), (0, half), (half, 0), (half, half)]
+            .map(|(row, column)| self.build(cells, level - 1, top + row, left + column));
+        self.join(quarters)
+    }
+
+    fn with_cell(&mut self, node: u32, row: u32, column: u32, cell: Cell) -> u32 {
+        let level = self.nodes[node as usize].level;
+        if level == 0 {
+            return cell as u32;
+        }
+        let half = 1 << (level - 1);
+        let quarter = (row / half * 2 + column / half) as usize;
+        let mut quarters = self.quarters(node);
+        quarters[quarter] = self.with_cell(quarters[quarter], row % half, column % half, cell);
+        self.join(quarters)
+    }
+}
+
+impl LifeEngine for HashLifeEngine {
+    fn width(&self) -> u32 {
+        self.side
+    }
+
+    fn height(&self) -> u32 {
+        self.side
+    }
+
+    fn get(&self, mut row: u32, mut column: u32) -> Cell {
+        assert!(row < self.side && column < self.side);
+        let mut node = self.root;
+        while self.nodes[node as usize].level > 0 {
+            let half = 1 << (self.nodes[node as usize].level - 1);
+            node = self.quarters(node)[(row / half * 2 + column / half) as usize];
+            row %= half;
+            column %= half;
+        }
+        if node == ALIVE { Cell::Alive } else { Cell::Dead }
+    }
+
+    fn set(&mut self, row: u32, column: u32, cell: Cell) {
+        assert!(row < self.side && column < self.side);
+        self.root = self.with_cell(self.root, row, column, cell);
+    }
+
+    fn tick(&mut self) {
+        let tiled = self.join([self.root; 4]);
+        let moved = self.next(tiled);
+        let [nw, ne, sw, se] = self.quarters(moved);
+        self.root = self.join([se, sw, ne, nw]);
+        self.generation += 1;
+
+        if self.nodes.len() > MAX_NODES {
+            let cells = self.to_cells();
+            self.clear_table();
+            self.root = self.build(&cells, self.side.trailing_zeros(), 0, 0);
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn population(&self) -> u32 {
+        self.nodes[self.root as usize].population as u32
+    }
+
+    fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        // What every node becomes was for the old rule
+        for node in &mut self.nodes {
+            node.next = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_nodes() {
+        assert!(HashLifeEngine::new(12, 12).is_err());
+        assert!(HashLifeEngine::new(8, 16).is_err());
+
+        // Blocks everywhere are one node over and over
+        let mut engine = HashLifeEngine::new(256, 256).unwrap();
+        for row in (0..256).step_by(4) {
+            for column in (0..256).step_by(4) {
+                for (row_delta, column_delta) in [(1, 1), (1, 2), (2, 1), (2, 2)] {
+                    engine.set(row + row_delta, column + column_delta, Cell::Alive);
+                }
+            }
+        }
+        let nodes = engine.nodes.len();
+        for _ in 0..10 {
+            engine.tick();
+        }
+        assert_eq!(4 * 64 * 64, engine.population());
+        assert!(engine.nodes.len() < nodes + 100);
+        assert_eq!(Cell::Alive, engine.get(253, 254));
+    }
+}
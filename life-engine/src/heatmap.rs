@@ -0,0 +1,146 @@
+// How busy each cell has been lately, for drawing activity heatmaps
+//
+// With the heatmap on, every cell counts how many times it has changed
+// state over the last `window` ticks. Oscillator rotors glow at a steady
+// rate, glider lanes show up as streaks and settled or empty ground stays
+// at 0. The changes of each tick in the window are kept so the oldest can
+// be taken off as a new one comes in, which costs memory in proportion to
+// the activity rather than the board.
+//
+// Only ticks count, not edits. Stepping back leaves the counts alone, and
+// resetting the generation or moving the board around starts them again
+// from nothing. JS can read the counts in place through `heatmap_ptr()`
+
+use crate::Universe;
+
+use std::collections::VecDeque;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone)]
+pub(crate) struct Heatmap {
+    window: usize,
+    counts: Vec<u32>,
+    // Cells changed by each tick in the window, oldest first
+    ticks: VecDeque<Vec<u32>>,
+}
+
+impl Heatmap {
+    fn new(window: usize, size: usize) -> Heatmap {
+        Heatmap { window, counts: vec![0; size], ticks: VecDeque::with_capacity(window) }
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let ticks = self.ticks.iter().map(Vec::capacity).sum::<usize>();
+        (self.counts.capacity() + ticks) * std::mem::size_of::<u32>()
+            + self.ticks.capacity() * std::mem::size_of::<Vec<u32>>()
+    }
+}
+
+impl Universe {
+    // Called from tick() with the cells that just flipped
+    pub(crate) fn record_heatmap(&mut self, changed: &[usize]) {
+        let heatmap = match self.heatmap.as_mut() {
+            Some(heatmap) => heatmap,
+            None => return,
+        };
+        if heatmap.ticks.len() == heatmap.window {
+            for idx in heatmap.ticks.pop_front().unwrap() {
+                heatmap.counts[idx as usize] -= 1;
+            }
+        }
+        for &idx in changed {
+            heatmap.counts[idx] += 1;
+        }
+        heatmap.ticks.push_back(changed.iter().map(|&idx| idx as u32).collect());
+    }
+
+    // For when the counts no longer line up with the board
+    pub(crate) fn restart_heatmap(&mut self) {
+        if let Some(heatmap) = self.heatmap.as_mut() {
+            *heatmap = Heatmap::new(heatmap.window, self.cells.len());
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Count each cell's changes over the last `window` ticks, starting
+    /// from none. A window of 0 turns the heatmap off
+    pub fn enable_heatmap(&mut self, window: u32) {
+        self.heatmap = (window > 0).then(|| Heatmap::new(window as usize, self.cells.len()));
+    }
+
+    pub fn disable_heatmap(&mut self) {
+        self.heatmap = None;
+    }
+
+    /// Ticks counted over, 0 while the heatmap is off
+    pub fn heatmap_window(&self) -> u32 {
+        self.heatmap.as_ref().map_or(0, |heatmap| heatmap.window as u32)
+    }
+
+    /// Ticks counted so far, up to the window
+    pub fn heatmap_ticks(&self) -> u32 {
+        self.heatmap.as_ref().map_or(0, |heatmap| heatmap.ticks.len() as u32)
+    }
+
+    /// Changes of every cell over the window, in the same layout as the
+    /// cell buffer
+    pub fn heatmap(&self) -> Vec<u32> {
+        match &self.heatmap {
+            Some(heatmap) => heatmap.counts.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Pointer to the counts in WASM memory, one u32 per cell, or null while
+    /// the heatmap is off. Only valid until the next call that changes the
+    /// universe
+    pub fn heatmap_ptr(&self) -> *const u32 {
+        match &self.heatmap {
+            Some(heatmap) => heatmap.counts.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// The highest count, for scaling colours
+    pub fn heatmap_peak(&self) -> u32 {
+        self.heatmap.as_ref().and_then(|heatmap| heatmap.counts.iter().max().copied()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_over_the_window() {
+        // A blinker and a block
+        let mut universe = Universe::new(12, 8, vec![(2, 1), (2, 2), (2, 3), (5, 8), (5, 9), (6, 8), (6, 9)]);
+        assert!(universe.heatmap_ptr().is_null());
+        universe.enable_heatmap(4);
+        for _ in 0..3 {
+            universe.tick();
+        }
+        assert_eq!(3, universe.heatmap_ticks());
+        let at = |universe: &Universe, row: u32, column: u32| universe.heatmap()[(row * 12 + column) as usize];
+        // The blinker's ends change every tick, its centre and the block
+        // never do
+        assert_eq!((3, 3, 0), (at(&universe, 2, 1), at(&universe, 1, 2), at(&universe, 2, 2)));
+        assert_eq!(0, at(&universe, 5, 8));
+
+        // Only the last four ticks count
+        for _ in 0..6 {
+            universe.tick();
+        }
+        assert_eq!(4, universe.heatmap_ticks());
+        assert_eq!(4, universe.heatmap_peak());
+        assert_eq!(16, universe.heatmap().iter().sum::<u32>());
+
+        universe.reset_generation();
+        assert_eq!(0, universe.heatmap_peak());
+        universe.enable_heatmap(0);
+        assert_eq!(0, universe.heatmap_window());
+    }
+}
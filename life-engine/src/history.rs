@@ -0,0 +1,194 @@
+// Ring buffer of previous generations so the UI can rewind
+//
+// Generations are stored bit-packed, so each entry costs an eighth of the
+// cell buffer, plus a byte a cell for the owners while ownership is on.
+// History is off (capacity 0) until a capacity is set.
+//
+// Stepping back isn't an edit: the cells are put back in one go, without
+// going through undo, journals, replay logs or cell ages, and a replay log
+// gets a single `generation N` line for it
+
+use crate::snapshot::{pack_cells, unpack_cells};
+use crate::{Cell, Universe};
+
+use std::collections::VecDeque;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// The generation number, whether its cells were stored complemented (see
+// strobing.rs), its packed cells and its owners
+type Entry = (u64, bool, Vec<u8>, Option<Vec<u8>>);
+
+#[derive(Clone, Default)]
+pub(crate) struct History {
+    capacity: usize,
+    // Oldest first
+    entries: VecDeque<Entry>,
+}
+
+impl History {
+    // Bytes held by the ring buffer and the packed generations in it
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.entries.capacity() * std::mem::size_of::<Entry>()
+            + self.entries.iter()
+                .map(|(_, _, packed, owners)| packed.capacity() + owners.as_ref().map_or(0, Vec::capacity))
+                .sum::<usize>()
+    }
+}
+
+impl Universe {
+    // Called at the start of tick() before the cells change
+    pub(crate) fn push_history(&mut self) {
+        if self.history.capacity == 0 {
+            return;
+        }
+
+        if self.history.entries.len() == self.history.capacity {
+            self.history.entries.pop_front();
+        }
+        let packed = pack_cells(&self.cells);
+        let owners = self.ownership_enabled().then(|| self.owners());
+        self.history.entries.push_back((self.generation, self.inverted, packed, owners));
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Keep up to `capacity` previous generations for `step_back`, dropping
+    /// the oldest ones if there are already more than that
+    pub fn set_history_capacity(&mut self, capacity: u32) {
+        self.history.capacity = capacity as usize;
+        while self.history.entries.len() > self.history.capacity {
+            self.history.entries.pop_front();
+        }
+        self.replay_record(format_args!("history {}", capacity));
+    }
+
+    pub fn history_capacity(&self) -> u32 {
+        self.history.capacity as u32
+    }
+
+    /// Number of generations `step_back` can currently go back
+    pub fn history_len(&self) -> u32 {
+        self.history.entries.len() as u32
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.entries.clear();
+    }
+
+    /// Restore the previous generation, returning false if there's no
+    /// history left to go back to
+    pub fn step_back(&mut self) -> bool {
+        let (generation, inverted, packed, owners) = match self.history.entries.pop_back() {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let previous = unpack_cells(&packed, self.cells.len());
+        self.restore_cells(&previous);
+        if let (Some(ownership), Some(owners)) = (self.ownership.as_mut(), owners) {
+            ownership.set_owners(owners);
+        }
+        self.generation = generation;
+        if self.inverted != inverted {
+            self.inverted = inverted;
+            self.record_phase_change();
+        }
+        self.shared_publish();
+        self.active_cells = None;
+        self.forget_edits();
+        self.replay_record(format_args!("generation {}", generation));
+        true
+    }
+}
+
+impl Universe {
+    // Write back a generation from the history, keeping up everything
+    // derived from the cells that an edit would
+    fn restore_cells(&mut self, previous: &[Cell]) {
+        let changed = (0..self.cells.len())
+            .filter(|&idx| self.cells[idx] != previous[idx] && !self.is_wall_index(idx))
+            .collect::<Vec<_>>();
+
+        self.shared_begin();
+        for &idx in &changed {
+            self.cells[idx] = previous[idx];
+            self.stats.record_edit(previous[idx]);
+        }
+        self.shift_neighbour_counts(&changed);
+        self.shared_publish();
+        for &idx in &changed {
+            self.lineage_edit(idx, previous[idx]);
+            self.ownership_edit(idx);
+        }
+        self.record_changes(&changed);
+        self.reset_cell_data(&changed);
+        self.mark_trails(&changed);
+        self.mark_tiles(&changed);
+        self.stability.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_back() {
+        let mut universe = Universe::random_seeded(32, 32, 0.3, 9).unwrap();
+        universe.set_history_capacity(3);
+
+        let mut states = vec![universe.cells.clone()];
+        for _ in 0..5 {
+            universe.tick();
+            states.push(universe.cells.clone());
+        }
+        assert_eq!(3, universe.history_len());
+
+        // Only the last three generations are kept
+        for generation in (2..5).rev() {
+            assert!(universe.step_back());
+            assert_eq!(states[generation], universe.cells);
+            assert_eq!(generation as u64, universe.generation);
+        }
+        assert!(!universe.step_back());
+
+        // Ticking forward again gives the same result
+        universe.tick();
+        assert_eq!(states[3], universe.cells);
+    }
+
+    #[test]
+    fn step_back_is_one_replay_line() {
+        let mut universe = Universe::random_recorded(32, 32, 0.3, 9).unwrap();
+        universe.set_history_capacity(2);
+        universe.tick();
+        universe.tick();
+        universe.tick();
+        let before = universe.replay_log().lines().count();
+        assert!(universe.step_back());
+        let log = universe.replay_log();
+        assert_eq!(before + 1, log.lines().count());
+        assert_eq!(Some("generation 2"), log.lines().last());
+
+        let replayed = Universe::replay_str(&log).unwrap();
+        assert_eq!(universe.cells, replayed.cells);
+        assert_eq!(universe.population(), universe.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut universe = Universe::new(4, 4, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        universe.tick();
+        assert_eq!(0, universe.history_len());
+        assert!(!universe.step_back());
+
+        universe.set_history_capacity(2);
+        universe.tick();
+        universe.tick();
+        universe.tick();
+        universe.set_history_capacity(1);
+        assert_eq!(1, universe.history_len());
+    }
+}
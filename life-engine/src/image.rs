@@ -0,0 +1,75 @@
+// PNG snapshots for native and WASI builds, behind the `image` feature
+//
+// Each cell is drawn as a `cell_size` pixel square, black for alive and
+// white for dead, in an 8 bit greyscale image. Writing a file per
+// generation gives frames ready for stitching into a time-lapse
+
+use crate::{Cell, Universe};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+const ALIVE: u8 = 0;
+const DEAD: u8 = 255;
+
+impl Universe {
+    /// Encode the current generation as a PNG into `out`
+    pub fn write_png(&self, out: impl Write, cell_size: u32) -> io::Result<()> {
+        let cell_size = cell_size.max(1);
+        let scale = |side: u32| side.checked_mul(cell_size)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "image too large for the cell size"));
+        let (width, height) = (scale(self.width)?, scale(self.height)?);
+
+        let mut encoder = ::png::Encoder::new(out, width, height);
+        encoder.set_color(::png::ColorType::Grayscale);
+        encoder.set_depth(::png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        let mut pixels = Vec::with_capacity(width as usize * height as usize);
+        for row in 0..self.height {
+            let start = pixels.len();
+            for column in 0..self.width {
+                let shade = if self.cells[self.get_index(row, column)] == Cell::Alive { ALIVE } else { DEAD };
+                pixels.extend(std::iter::repeat_n(shade, cell_size as usize));
+            }
+            // The rest of the cell's pixel rows are the same
+            for _ in 1..cell_size {
+                pixels.extend_from_within(start..start + width as usize);
+            }
+        }
+        writer.write_image_data(&pixels)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Write the current generation to a PNG file at `path`
+    pub fn save_png(&self, path: impl AsRef<Path>, cell_size: u32) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?), cell_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_snapshot() {
+        let universe: Universe = "#.\n.#\n#.\n".parse().unwrap();
+        let mut data = vec![];
+        universe.write_png(&mut data, 3).unwrap();
+
+        let mut reader = ::png::Decoder::new(&data[..]).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((6, 9), (info.width, info.height));
+        // Row 4 is the middle of the second row of cells
+        assert_eq!(vec![DEAD, DEAD, DEAD, ALIVE, ALIVE, ALIVE], pixels[4 * 6..5 * 6].to_vec());
+        assert_eq!(ALIVE, pixels[8 * 6]);
+
+        let path = std::env::temp_dir().join("life-png-snapshot-test.png");
+        universe.save_png(&path, 3).unwrap();
+        assert_eq!(data, std::fs::read(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
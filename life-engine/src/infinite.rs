@@ -0,0 +1,226 @@
+// A universe with no edges, growing as patterns travel
+//
+// Only the live cells are stored, as a set of (row, column) coordinates which
+// may be negative, so gliders and spaceships fly off forever instead of
+// wrapping round or hitting a wall. Memory and tick time scale with the
+// population rather than the area. Rules with B0 aren't supported since
+// they'd fill the infinite plane in one tick
+
+use crate::error::LifeError;
+use crate::rule::NEIGHBOURS;
+use crate::size::check_size;
+use crate::{Cell, Rule, RuleError, Universe};
+
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default)]
+pub struct InfiniteUniverse {
+    live: HashSet<(i32, i32)>,
+    generation: u64,
+    rule: Rule,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl InfiniteUniverse {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> InfiniteUniverse {
+        InfiniteUniverse::default()
+    }
+
+    /// Copy the live cells of a finite universe, keeping its coordinates
+    pub fn from_universe(universe: &Universe) -> InfiniteUniverse {
+        let mut infinite = InfiniteUniverse { rule: universe.life_rule(), ..InfiniteUniverse::default() };
+        for row in 0..universe.height {
+            for column in 0..universe.width {
+                if universe.cells[universe.get_index(row, column)] == Cell::Alive {
+                    infinite.live.insert((row as i32, column as i32));
+                }
+            }
+        }
+        infinite
+    }
+
+    pub fn is_alive(&self, row: i32, column: i32) -> bool {
+        self.live.contains(&(row, column))
+    }
+
+    pub fn set_alive(&mut self, row: i32, column: i32, alive: bool) {
+        if alive {
+            self.live.insert((row, column));
+        } else {
+            self.live.remove(&(row, column));
+        }
+    }
+
+    pub fn population(&self) -> u32 {
+        self.live.len() as u32
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        let rule = rule.parse::<Rule>().map_err(crate::error::js_error)?;
+        self.set_life_rule(rule).map_err(crate::error::js_error)
+    }
+
+    pub fn tick(&mut self) {
+        // Neighbourhoods of every cell next to a live one, each live cell
+        // being the neighbour in the opposite direction to the offset
+        let mut neighbourhoods: HashMap<(i32, i32), u8> = HashMap::with_capacity(self.live.len() * 8);
+        for &(row, column) in &self.live {
+            for (bit, &(row_delta, column_delta)) in NEIGHBOURS.iter().enumerate() {
+                let neighbour = (row.wrapping_add(row_delta as i32), column.wrapping_add(column_delta as i32));
+                *neighbourhoods.entry(neighbour).or_insert(0) |= 1 << ((bit + 4) % 8);
+            }
+        }
+
+        let rule = self.rule;
+        let live = &self.live;
+        // Live cells with no neighbours never made it into the map
+        let isolated = live.iter()
+            .filter(|position| rule.next(Cell::Alive, 0) == Cell::Alive && !neighbourhoods.contains_key(position))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut next: HashSet<(i32, i32)> = neighbourhoods.into_iter()
+            .filter(|(position, neighbourhood)| {
+                let current = if live.contains(position) { Cell::Alive } else { Cell::Dead };
+                rule.next_neighbourhood(current, *neighbourhood) == Cell::Alive
+            })
+            .map(|(position, _)| position)
+            .collect();
+        next.extend(isolated);
+
+        self.live = next;
+        self.generation += 1;
+    }
+
+    /// `[top, left, rows, columns]` of the smallest rectangle holding every
+    /// live cell, for the renderer to follow the action, or nothing when
+    /// empty
+    pub fn bounding_box(&self) -> Option<Vec<i32>> {
+        self.bounds().map(|(top, left, rows, columns)| vec![top, left, rows as i32, columns as i32])
+    }
+
+    /// One byte per cell (0 dead, 1 alive) of the window with its top left
+    /// at (top, left), row by row
+    pub fn window_cells(&self, top: i32, left: i32, rows: u32, columns: u32) -> Vec<u8> {
+        let mut cells = Vec::with_capacity(rows as usize * columns as usize);
+        for row in 0..rows as i64 {
+            for column in 0..columns as i64 {
+                let position = ((top as i64 + row) as i32, (left as i64 + column) as i32);
+                cells.push(self.live.contains(&position) as u8);
+            }
+        }
+        cells
+    }
+
+    /// The bounding box as a finite universe, e.g. for the existing renderers
+    /// or to save it. Empty universes give a 1x1 dead one. Throws when the
+    /// live cells are spread too far apart to fit
+    #[cfg(feature = "wasm")]
+    pub fn to_universe(&self) -> Result<Universe, JsValue> {
+        self.to_finite().map_err(crate::error::js_error)
+    }
+}
+
+impl InfiniteUniverse {
+    /// Top, left, rows and columns of the live cells
+    pub fn bounds(&self) -> Option<(i32, i32, u32, u32)> {
+        let mut cells = self.live.iter();
+        let &(first_row, first_column) = cells.next()?;
+        let (mut top, mut left, mut bottom, mut right) = (first_row, first_column, first_row, first_column);
+        for &(row, column) in cells {
+            top = top.min(row);
+            bottom = bottom.max(row);
+            left = left.min(column);
+            right = right.max(column);
+        }
+        let span = |low: i32, high: i32| (high as i64 - low as i64 + 1).min(u32::MAX as i64) as u32;
+        Some((top, left, span(top, bottom), span(left, right)))
+    }
+
+    /// Rust side of `to_universe`
+    pub fn to_finite(&self) -> Result<Universe, LifeError> {
+        let (top, left, rows, columns) = self.bounds().unwrap_or((0, 0, 1, 1));
+        check_size(columns, rows)?;
+        let alive = self.live.iter()
+            .map(|&(row, column)| ((row as i64 - top as i64) as u32, (column as i64 - left as i64) as u32))
+            .collect();
+        let mut universe = Universe::new(columns, rows, alive);
+        universe.set_life_rule(self.rule);
+        Ok(universe)
+    }
+
+    pub fn life_rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_life_rule(&mut self, rule: Rule) -> Result<(), LifeError> {
+        if rule.next(Cell::Dead, 0) == Cell::Alive {
+            return Err(RuleError { message: "B0 rules aren't supported by an infinite universe".to_string() }.into());
+        }
+        self.rule = rule;
+        Ok(())
+    }
+
+    /// Live cells in no particular order
+    pub fn live_cells(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.live.iter().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glider_travels_forever() {
+        let glider = Universe::new(5, 5, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        let mut infinite = InfiniteUniverse::from_universe(&glider);
+        assert_eq!(Some((0, 0, 3, 3)), infinite.bounds());
+
+        // A glider moves one cell diagonally every four generations
+        for _ in 0..400 {
+            infinite.tick();
+        }
+        assert_eq!(5, infinite.population());
+        assert_eq!(Some((100, 100, 3, 3)), infinite.bounds());
+        let start = InfiniteUniverse::from_universe(&glider);
+        let moved = infinite.live_cells().map(|(row, column)| (row - 100, column - 100)).collect();
+        assert_eq!(start.live, moved);
+    }
+
+    #[test]
+    fn negative_coordinates_and_windows() {
+        let mut infinite = InfiniteUniverse::new();
+        for column in -1..=1 {
+            infinite.set_alive(-5, column, true);
+        }
+        infinite.tick();
+        assert!(infinite.is_alive(-6, 0) && infinite.is_alive(-4, 0) && !infinite.is_alive(-5, 1));
+        assert_eq!(vec![0, 1, 0, 0, 1, 0, 0, 1, 0], infinite.window_cells(-6, -1, 3, 3));
+        assert_eq!(Some(vec![-6, 0, 3, 1]), infinite.bounding_box());
+
+        let universe = infinite.to_finite().unwrap();
+        assert_eq!((1, 3), (universe.width, universe.height));
+
+        let mut spread = InfiniteUniverse::new();
+        spread.set_alive(i32::MIN, 0, true);
+        spread.set_alive(i32::MAX, 0, true);
+        spread.set_alive(0, i32::MAX, true);
+        assert!(matches!(spread.to_finite(), Err(LifeError::InvalidDimensions { .. })));
+
+        assert!(infinite.set_life_rule("B0/S8".parse().unwrap()).is_err());
+        assert!(infinite.set_life_rule("B36/S23".parse().unwrap()).is_ok());
+    }
+}
@@ -0,0 +1,122 @@
+// Journaling of every state-changing operation to an external sink
+//
+// Each operation is sent to the sink as one frame:
+//
+//     byte  0      frame kind
+//     bytes 1..5   payload length (u32 little endian)
+//     bytes 5..    payload
+//
+// Frame kinds and their payloads:
+//
+//     1 tick      generation after the tick (u64 LE), then every flipped cell
+//                 as a LEB128 varint of the gap from the previous index
+//     2 edit      cell index (u32 LE), new state (u8, 0 dead 1 alive)
+//     3 boundary  new boundary mode (u8, as the Boundary enum)
+
+use crate::delta::write_varint;
+use crate::{Boundary, Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) const FRAME_TICK: u8 = 1;
+pub(crate) const FRAME_EDIT: u8 = 2;
+pub(crate) const FRAME_BOUNDARY: u8 = 3;
+
+type Sink = Box<dyn FnMut(&[u8])>;
+
+pub(crate) struct Journal {
+    sink: Sink,
+}
+
+impl Journal {
+    fn send(&mut self, kind: u8, payload: &[u8]) {
+        let mut frame = Vec::with_capacity(5 + payload.len());
+        frame.push(kind);
+        frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        frame.extend_from_slice(payload);
+        (self.sink)(&frame);
+    }
+}
+
+impl Universe {
+    /// Send a frame for every state-changing operation to `sink`
+    pub fn set_journal(&mut self, sink: impl FnMut(&[u8]) + 'static) {
+        self.journal = Some(Journal { sink: Box::new(sink) });
+    }
+
+    pub(crate) fn journal_tick(&mut self, changed: &[usize]) {
+        if let Some(journal) = self.journal.as_mut() {
+            let mut payload = self.generation.to_le_bytes().to_vec();
+
+            // Changed cells aren't necessarily in order so sort a copy
+            let mut sorted = changed.to_vec();
+            sorted.sort_unstable();
+            let mut previous = 0;
+            for idx in sorted {
+                write_varint(&mut payload, (idx - previous) as u64);
+                previous = idx;
+            }
+
+            journal.send(FRAME_TICK, &payload);
+        }
+    }
+
+    pub(crate) fn journal_edit(&mut self, idx: usize, cell: Cell) {
+        if let Some(journal) = self.journal.as_mut() {
+            let mut payload = (idx as u32).to_le_bytes().to_vec();
+            payload.push(cell as u8);
+            journal.send(FRAME_EDIT, &payload);
+        }
+    }
+
+    pub(crate) fn journal_boundary(&mut self, boundary: Boundary) {
+        if let Some(journal) = self.journal.as_mut() {
+            journal.send(FRAME_BOUNDARY, &[boundary as u8]);
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Call `sink` with a Uint8Array frame for every state-changing operation
+    #[cfg(feature = "wasm")]
+    pub fn set_journal_sink(&mut self, sink: js_sys::Function) {
+        self.set_journal(move |frame| {
+            // A throwing sink shouldn't take the simulation down with it
+            let _ = sink.call1(&JsValue::NULL, &js_sys::Uint8Array::from(frame));
+        });
+    }
+
+    pub fn clear_journal_sink(&mut self) {
+        self.journal = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn frames() {
+        let frames = Rc::new(RefCell::new(vec![]));
+        let recorded = frames.clone();
+
+        let mut universe = Universe::new(5, 5, vec![(2, 1), (2, 2), (2, 3)]);
+        universe.set_journal(move |frame| recorded.borrow_mut().push(frame.to_vec()));
+
+        universe.tick();
+        universe.set_owner(0, 0, 1);
+        universe.set_boundary(Boundary::Dead);
+
+        let frames = frames.borrow();
+        assert_eq!(3, frames.len());
+
+        // Blinker flips cells 7, 11, 13 and 17
+        assert_eq!(vec![FRAME_TICK, 12, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7, 4, 2, 4], frames[0]);
+        assert_eq!(vec![FRAME_EDIT, 5, 0, 0, 0, 0, 0, 0, 0, 1], frames[1]);
+        assert_eq!(vec![FRAME_BOUNDARY, 1, 0, 0, 0, Boundary::Dead as u8], frames[2]);
+    }
+}
@@ -0,0 +1,112 @@
+// JSON import/export for web frontends
+//
+// The shape is stable and deliberately simple so it can be stored in
+// localStorage, sent to a server or written by hand:
+//
+//     {
+//         "width": 8,
+//         "height": 6,
+//         "alive": [[0, 1], [2, 3]]
+//     }
+//
+// where `alive` lists the [row, column] of every live cell. Unlike `save()`
+// this only covers the board itself, not the rest of the simulation state
+
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::Universe;
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+struct JsonUniverse {
+    width: u32,
+    height: u32,
+    alive: Vec<(u32, u32)>,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    Syntax(serde_json::Error),
+    InvalidDimensions { width: u32, height: u32 },
+    OutOfBounds { row: u32, column: u32 },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::Syntax(err) => write!(f, "invalid universe JSON: {}", err),
+            JsonError::InvalidDimensions { width, height } => {
+                write!(f, "invalid universe dimensions {}x{}", width, height)
+            }
+            JsonError::OutOfBounds { row, column } => {
+                write!(f, "alive cell ({}, {}) is outside the universe", row, column)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl Universe {
+    /// Rust side of `from_json`, reporting what was wrong with the input
+    pub fn from_json_str(json: &str) -> Result<Universe, LifeError> {
+        let parsed: JsonUniverse = serde_json::from_str(json).map_err(JsonError::Syntax)?;
+
+        let (width, height) = (parsed.width, parsed.height);
+        check_size(width, height).map_err(|_| JsonError::InvalidDimensions { width, height })?;
+        if let Some(&(row, column)) = parsed.alive.iter().find(|&&(row, column)| row >= height || column >= width) {
+            return Err(JsonError::OutOfBounds { row, column }.into());
+        }
+
+        Ok(Universe::new(width, height, parsed.alive))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn to_json(&self) -> String {
+        let alive = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |column| (row, column)))
+            .filter(|&(row, column)| self.cells[self.get_index(row, column)] == crate::Cell::Alive)
+            .collect();
+
+        let json = JsonUniverse { width: self.width, height: self.height, alive };
+        serde_json::to_string(&json).expect("universe JSON is always serializable")
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn from_json(json: &str) -> Result<Universe, JsValue> {
+        Universe::from_json_str(json).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_shape() {
+        let universe = Universe::new(3, 2, vec![(0, 1), (1, 2)]);
+        assert_eq!(r#"{"width":3,"height":2,"alive":[[0,1],[1,2]]}"#, universe.to_json());
+
+        let parsed = Universe::from_json_str(&universe.to_json()).unwrap();
+        assert_eq!(universe.cells, parsed.cells);
+    }
+
+    #[test]
+    fn json_errors() {
+        assert!(matches!(Universe::from_json_str("[]"), Err(LifeError::ParseError { .. })));
+        assert!(matches!(
+            Universe::from_json_str(r#"{"width":0,"height":2,"alive":[]}"#),
+            Err(LifeError::InvalidDimensions { width: 0, height: 2 })
+        ));
+        assert!(matches!(
+            Universe::from_json_str(r#"{"width":3,"height":2,"alive":[[2,0]]}"#),
+            Err(LifeError::OutOfBounds { row: 2, column: 0 })
+        ));
+    }
+}
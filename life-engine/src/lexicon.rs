@@ -0,0 +1,236 @@
+// Named patterns from a Life Lexicon
+//
+// The Life Lexicon is a plain text glossary of Life terms, most of them with
+// a pattern. An entry starts with its name between colons at the start of a
+// line, then its description, wrapped over as many lines as it needs, and
+// the pattern as rows indented by a tab with `.` for dead and `O` for live
+// cells:
+//
+//     :glider: (c/4 diagonally, p4) The smallest, most common and first
+//     discovered spaceship.
+//
+//     	.O.
+//     	..O
+//     	OOO
+//
+// Anything before the first entry, or after a line of dashes and before
+// the next entry, is front matter or a section break and skipped. Names in braces in a
+// description link to other entries and lose their braces here.
+//
+// Built with the `lexicon` feature, `Lexicon::embedded()` is a copy of
+// data/lexicon.txt compiled in: a few dozen of the best known patterns, in
+// the same format so the full Lexicon can replace it. From JS
+// `lexicon_lookup(name)` gives an entry's pattern as a universe of its own
+// size, ready to `overlay()` onto the board, and `lexicon_search(text)` the
+// names to offer
+
+use crate::Universe;
+
+#[cfg(all(feature = "wasm", feature = "lexicon"))]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LexiconEntry {
+    pub name: String,
+    pub description: String,
+    // Rows of the first pattern, if it has one
+    pattern: Option<String>,
+}
+
+impl LexiconEntry {
+    /// The entry's pattern at its own size, None for entries without one
+    pub fn pattern(&self) -> Option<Universe> {
+        self.pattern.as_ref().and_then(|rows| rows.parse().ok())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lexicon {
+    pub entries: Vec<LexiconEntry>,
+}
+
+fn is_pattern_row(row: &str) -> bool {
+    !row.is_empty() && row.chars().all(|glyph| matches!(glyph, '.' | 'O' | '*'))
+}
+
+impl Lexicon {
+    pub fn parse(text: &str) -> Lexicon {
+        let mut entries: Vec<LexiconEntry> = vec![];
+        // Pattern rows of the last entry so far, and whether they're done
+        let mut rows: Vec<&str> = vec![];
+        let mut rows_done = false;
+        let mut in_entry = false;
+
+        let finish = |entries: &mut Vec<LexiconEntry>, rows: &mut Vec<&str>| {
+            if let Some(entry) = entries.last_mut() {
+                if !rows.is_empty() && entry.pattern.is_none() {
+                    entry.pattern = Some(rows.join("\n"));
+                }
+            }
+            rows.clear();
+        };
+
+        for line in text.lines() {
+            if let Some(name) = line.strip_prefix(':').and_then(|rest| rest.split_once(':')) {
+                finish(&mut entries, &mut rows);
+                entries.push(LexiconEntry {
+                    name: name.0.trim().to_string(),
+                    description: name.1.trim().to_string(),
+                    pattern: None,
+                });
+                rows_done = false;
+                in_entry = true;
+            } else if !in_entry {
+                continue;
+            } else if line.starts_with("----") {
+                finish(&mut entries, &mut rows);
+                in_entry = false;
+            } else if let Some(row) = line.strip_prefix('\t') {
+                // Indented text that isn't cells is a table or quote, which
+                // belongs to the description
+                if is_pattern_row(row.trim_end()) && !rows_done {
+                    rows.push(row.trim_end());
+                } else if !row.trim().is_empty() {
+                    rows_done |= !rows.is_empty();
+                }
+            } else if line.trim().is_empty() {
+                rows_done |= !rows.is_empty();
+            } else if rows.is_empty() {
+                let description = &mut entries.last_mut().expect("in an entry").description;
+                description.push(' ');
+                description.push_str(line.trim());
+            }
+        }
+        finish(&mut entries, &mut rows);
+
+        for entry in &mut entries {
+            entry.description.retain(|glyph| glyph != '{' && glyph != '}');
+        }
+        Lexicon { entries }
+    }
+
+    /// The entry called `name`, ignoring case
+    pub fn get(&self, name: &str) -> Option<&LexiconEntry> {
+        self.entries.iter().find(|entry| entry.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// Entries with a pattern whose name contains `text`, ignoring case
+    pub fn search<'a>(&'a self, text: &str) -> impl Iterator<Item = &'a LexiconEntry> + 'a {
+        let text = text.trim().to_lowercase();
+        self.entries.iter().filter(move |entry| entry.pattern.is_some() && entry.name.to_lowercase().contains(&text))
+    }
+
+    /// The copy of data/lexicon.txt built in, parsed on first use
+    #[cfg(feature = "lexicon")]
+    pub fn embedded() -> &'static Lexicon {
+        static LEXICON: std::sync::OnceLock<Lexicon> = std::sync::OnceLock::new();
+        LEXICON.get_or_init(|| Lexicon::parse(include_str!("../data/lexicon.txt")))
+    }
+}
+
+#[cfg(all(feature = "wasm", feature = "lexicon"))]
+/// The pattern of the built in Lexicon entry called `name`, ignoring case,
+/// as a universe just big enough for it. None for unknown names
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn lexicon_lookup(name: &str) -> Option<Universe> {
+    Lexicon::embedded().get(name)?.pattern()
+}
+
+#[cfg(all(feature = "wasm", feature = "lexicon"))]
+/// Names of the built in Lexicon's patterns containing `text`, all of them
+/// for an empty string
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn lexicon_search(text: &str) -> Vec<String> {
+    Lexicon::embedded().search(text).map(|entry| entry.name.clone()).collect()
+}
+
+#[cfg(all(feature = "wasm", feature = "lexicon"))]
+/// Description of the built in Lexicon entry called `name`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn lexicon_description(name: &str) -> Option<String> {
+    Lexicon::embedded().get(name).map(|entry| entry.description.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Front matter, skipped
+:first: Its pattern
+\tOOO
+
+:blinker: (p2) The smallest
+{oscillator}.
+
+\tOOO
+
+More about it, then a table which isn't cells:
+
+\tp2  blinker
+
+:blonk: See {blinker}.
+:block: (p1) Two rows
+\tOO.
+\tOO.
+\t..O
+
+\tOOO
+-----
+Back matter, skipped
+\t.O.
+";
+
+    #[test]
+    fn parses_entries() {
+        let lexicon = Lexicon::parse(TEXT);
+        let names = lexicon.entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["first", "blinker", "blonk", "block"], names);
+
+        let blinker = lexicon.get("BLINKER").unwrap();
+        assert_eq!("(p2) The smallest oscillator.", blinker.description);
+        assert_eq!(vec![(0, 0), (0, 1), (0, 2)], blinker.pattern().unwrap().live_cells().collect::<Vec<_>>());
+        assert_eq!("See blinker.", lexicon.get("blonk").unwrap().description);
+        assert!(lexicon.get("blonk").unwrap().pattern().is_none());
+        // Only the first pattern counts
+        assert_eq!(5, lexicon.get("block").unwrap().pattern().unwrap().population());
+
+        let found = lexicon.search("BL").map(|entry| entry.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["blinker", "block"], found);
+    }
+
+    #[cfg(feature = "lexicon")]
+    #[test]
+    fn embedded_patterns_behave() {
+        use crate::{Boundary, OverlayMode};
+
+        let lexicon = Lexicon::embedded();
+        assert!(lexicon.entries.len() > 20);
+        assert_eq!(5, lexicon.get("Glider").unwrap().pattern().unwrap().population());
+
+        // Still lifes, oscillators and spaceships come back to the same
+        // shape after their period, with room to do it in
+        let shape = |universe: &Universe| {
+            let cells = universe.live_cells().collect::<Vec<_>>();
+            let top = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+            let left = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+            cells.into_iter().map(|(row, column)| (row - top, column - left)).collect::<Vec<_>>()
+        };
+        for entry in &lexicon.entries {
+            let pattern = entry.pattern().unwrap_or_else(|| panic!("{} has no pattern", entry.name));
+            let heading = entry.description.strip_prefix('(').and_then(|rest| rest.split(')').next());
+            let period = heading.and_then(|heading| heading.rsplit(", ").next()?.strip_prefix('p'));
+            let period = match period.and_then(|period| period.parse::<u32>().ok()) {
+                Some(period) if !entry.name.contains("gun") => period,
+                _ => continue,
+            };
+            let mut universe = Universe::new(pattern.width() + 16, pattern.height() + 16, vec![]);
+            universe.set_boundary(Boundary::Dead);
+            universe.overlay(&pattern, 8, 8, OverlayMode::Or);
+            let start = shape(&universe);
+            for _ in 0..period {
+                universe.tick();
+            }
+            assert_eq!(start, shape(&universe), "{}", entry.name);
+        }
+    }
+}
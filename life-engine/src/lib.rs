@@ -0,0 +1,1143 @@
+//!
+//! Rust code for Conway's Game of Life. The WASM bindings for running in
+//! the browser are compiled in with the `wasm` feature, which the life-wasm
+//! crate turns on
+//!
+//! TODO: Add some more tests
+//!
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+// Everything but the simulation core needs std, see engine.rs
+macro_rules! with_std {
+    ($($item:item)*) => {
+        $(#[cfg(feature = "std")] $item)*
+    };
+}
+
+mod engine;
+mod error;
+mod rule;
+mod size;
+
+pub use engine::{Cell, Engine, LifeEngine};
+pub use error::LifeError;
+pub use rule::{Rule, RuleError};
+pub use size::SizeError;
+
+// Outside with_std! so cbindgen can find the functions, see life-ffi. The
+// feature implies std
+#[cfg(feature = "ffi")]
+mod ffi;
+
+with_std! {
+    #[macro_use]
+    mod utils;
+    mod age;
+    mod analysis;
+    mod apgcode;
+    mod ascii;
+    mod backend;
+    mod base64;
+    mod bitpacked;
+    mod boundary;
+    mod brush;
+    mod builder;
+    mod calibrate;
+    #[cfg(feature = "web")]
+    mod canvas;
+    mod cell_data;
+    mod census;
+    mod clusters;
+    mod compose;
+    mod critters;
+    mod delta;
+    mod dirty_rows;
+    mod drift;
+    mod duplicate;
+    mod elementary;
+    mod envelope;
+    mod equality;
+    mod events;
+    mod evolve;
+    mod experimental;
+    mod explore;
+    mod export;
+    mod fingerprint;
+    mod game;
+    mod gif;
+    mod gliders;
+    mod hashlife;
+    mod heatmap;
+    mod history;
+    #[cfg(all(feature = "image", any(not(target_arch = "wasm32"), target_os = "wasi")))]
+    mod image;
+    mod infinite;
+    mod journal;
+    mod lineage;
+    mod manager;
+    mod margolus;
+    mod memory;
+    #[cfg(feature = "serde")]
+    mod json;
+    mod lexicon;
+    mod neighbour_counts;
+    mod offsets;
+    mod ownership;
+    mod packed;
+    mod perf;
+    #[cfg(test)]
+    mod properties;
+    #[cfg(feature = "python")]
+    mod python;
+    mod parallel;
+    mod populations;
+    mod predecessor;
+    mod profile;
+    mod query;
+    mod recovery;
+    mod region;
+    mod registry;
+    mod render;
+    mod replay;
+    mod rgba;
+    mod rle;
+    mod rng;
+    mod rule_table;
+    mod runner;
+    mod sand;
+    mod schedule;
+    mod selection;
+    #[cfg(feature = "serde")]
+    mod serialization;
+    mod series;
+    mod share;
+    mod shared;
+    #[cfg(feature = "simd")]
+    mod simd;
+    #[cfg(feature = "web")]
+    mod simulator;
+    mod snapshot;
+    mod soup;
+    mod sparse;
+    mod stability;
+    mod stats;
+    mod stochastic;
+    mod strobing;
+    mod svg;
+    mod symmetry;
+    mod tiles;
+    mod timeline;
+    mod transaction;
+    mod transfer;
+    mod transform;
+    mod trails;
+    mod transition;
+    mod undo;
+    mod universe3d;
+    mod verify;
+    mod viewport;
+    mod walls;
+    #[cfg(feature = "web")]
+    mod webgl;
+
+    pub use analysis::Analysis;
+    pub use apgcode::apgcode;
+    pub use ascii::AsciiError;
+    pub use backend::Backend;
+    pub use bitpacked::BitPackedEngine;
+    pub use boundary::{Boundary, Edge};
+    pub use brush::BrushShape;
+    pub use builder::{BuildError, UniverseBuilder};
+    pub use calibrate::TickStrategy;
+    pub use census::ObjectCounts;
+    pub use clusters::{Cluster, Clusters};
+    pub use compose::OverlayMode;
+    pub use delta::DeltaError;
+    pub use elementary::ElementaryCA;
+    pub use evolve::{Evolution, Objective};
+    pub use experimental::{
+        available_features, disable_experimental, enable_experimental, experimental_enabled, list_features,
+    };
+    pub use explore::{RuleExploration, RuleVariant};
+    pub use export::ExportChunks;
+    pub use fingerprint::thumbnail_distance;
+    pub use game::MatchResult;
+    pub use gif::GifError;
+    pub use gliders::GliderHeading;
+    pub use hashlife::HashLifeEngine;
+    pub use infinite::InfiniteUniverse;
+    pub use manager::UniverseManager;
+    pub use margolus::BlockRule;
+    pub use memory::MemoryStats;
+    #[cfg(feature = "serde")]
+    pub use json::JsonError;
+    pub use lexicon::{Lexicon, LexiconEntry};
+    #[cfg(feature = "wasm-threads")]
+    pub use parallel::init_thread_pool;
+    pub use perf::PerfStats;
+    pub use predecessor::PredecessorSearch;
+    pub use profile::TickProfile;
+    #[cfg(feature = "python")]
+    pub use python::{register_python, PyUniverse};
+    pub use query::{Query, QueryError, QueryResult};
+    pub use region::{Rect, Region};
+    pub use registry::PatternRegistry;
+    pub use render::RenderConfig;
+    #[cfg(feature = "wasm")]
+    pub use replay::generate_universe_recorded;
+    pub use replay::ReplayError;
+    pub use rule_table::{RuleTable, RuleTableError};
+    pub use runner::Runner;
+    pub use sand::{FallingSand, Material};
+    pub use schedule::UpdateMode;
+    #[cfg(feature = "web")]
+    pub use simulator::Simulator;
+    pub use snapshot::SnapshotError;
+    #[cfg(feature = "wasm")]
+    pub use soup::{soup_search, SoupReport};
+    pub use soup::{SoupResult, SoupSearch};
+    pub use sparse::SparseEngine;
+    pub use stability::{StabilizationResult, Status};
+    pub use stats::Statistics;
+    pub use symmetry::Symmetry;
+    pub use timeline::{TimelineEvent, TimelineKind};
+    pub use transaction::Edit;
+    pub use universe3d::{Rule3D, Universe3D};
+    pub use utils::{set_log_level, LogLevel};
+    pub use verify::check_backends;
+    #[cfg(feature = "wasm")]
+    pub use verify::verify_backends;
+    pub use viewport::{Viewport, ViewportEdges};
+    #[cfg(feature = "web")]
+    pub use webgl::GlRenderer;
+}
+
+#[cfg(feature = "std")]
+use rng::{seeded, unseeded};
+#[cfg(feature = "std")]
+use rand::Rng;
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::ops::{Index, IndexMut};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Universe {
+    width: u32,
+    height: u32,
+    cells: Vec<Cell>,
+    // Cells which changed state last tick. Only these and their neighbours
+    // can change state next tick. None means every cell needs evaluating,
+    // e.g. straight after construction. Anything modifying cells outside of
+    // tick() must go through write_cell() to keep this up to date
+    active_cells: Option<Vec<usize>>,
+    // Scratch marks used to de-duplicate the active set, kept around so we
+    // don't allocate a full size buffer every tick
+    active_marks: Vec<bool>,
+    // Wrapped neighbour positions for the board's size, see offsets.rs
+    offsets: offsets::NeighbourOffsets,
+    // Live neighbours of each cell, see neighbour_counts.rs
+    neighbour_counts: neighbour_counts::NeighbourCounts,
+    // Live counts and changed flags per block of cells, see tiles.rs
+    tiles: Option<tiles::Tiles>,
+    // Mirrors and turns kept through every tick, see symmetry.rs
+    symmetry: symmetry::Symmetry,
+    boundary: Boundary,
+    injection: Option<boundary::Injection>,
+    // Gliders added at the edges after each tick, see gliders.rs
+    gliders: Option<gliders::Gliders>,
+    ownership: Option<ownership::Ownership>,
+    // Score of a two player match, see game.rs
+    game: Option<game::Match>,
+    lineage: Option<lineage::Lineage>,
+    // Ticks since construction
+    generation: u64,
+    change_log: Option<delta::ChangeLog>,
+    // Changes for the next frame of delta_since_last(), see delta.rs
+    delta_stream: Option<delta::DeltaStream>,
+    journal: Option<journal::Journal>,
+    history: history::History,
+    // Undo stack of edits, see undo.rs
+    edits: undo::EditHistory,
+    stability: stability::StabilityTracker,
+    strategy: TickStrategy,
+    calibrated_strategy: Option<TickStrategy>,
+    stats: stats::TickStats,
+    // Rolling statistics for charts, see series.rs
+    series: Option<series::Series>,
+    // Centre of mass being followed, see drift.rs
+    drift: Option<drift::Drift>,
+    // Block rule run instead of the rule, see margolus.rs
+    margolus: Option<margolus::Margolus>,
+    // Which registration holds the recovery slot, see recovery.rs
+    recovery: Option<u64>,
+    events: events::Events,
+    viewports: Vec<Option<Viewport>>,
+    timeline: Option<Vec<TimelineEvent>>,
+    schedule: Option<schedule::Schedule>,
+    stochastic: Option<stochastic::Stochastic>,
+    // Header for a rendering worker, see shared.rs
+    shared: Option<shared::SharedState>,
+    // Cells fixed dead, see walls.rs
+    walls: Option<Vec<bool>>,
+    // Area the editor's selection tools act on, see selection.rs
+    selection: Option<Rect>,
+    // Generation each cell was last born in, when age tracking is on
+    born: Option<Vec<u64>>,
+    // Cells that have ever been alive, see envelope.rs
+    envelope: Option<Vec<u8>>,
+    // Application data per cell, see cell_data.rs
+    cell_data: Option<Vec<u8>>,
+    // Recent changes per cell, see heatmap.rs
+    heatmap: Option<heatmap::Heatmap>,
+    // Fading intensity per cell, see trails.rs
+    trails: Option<trails::Trails>,
+    // Phase timings of the last tick, see profile.rs
+    tick_profile: Option<TickProfile>,
+    // Rolling tick rate and times, see perf.rs
+    perf: perf::PerfCounter,
+    // Generations a cell can stay alive before dying of old age, see age.rs
+    max_age: Option<u32>,
+    rule: Rule,
+    // Whether the cells are stored complemented, and the rule the last tick
+    // ran in place of `rule` if any, see strobing.rs
+    inverted: bool,
+    step_rule: Option<Rule>,
+    // Transition table used instead of the rule, when one is loaded
+    rule_table: Option<RuleTable>,
+    // Runtime replacement for the rule, see transition.rs
+    transition: Option<transition::Transition>,
+    // Log of everything done since recording started, see replay.rs
+    replay: Option<replay::ReplayLog>,
+    render_config: RenderConfig,
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    fn new(width: u32, height: u32, alive_cells: Vec<(u32, u32)>) -> Self {
+        // Initialize all dead cells then update alive ones
+        let mut cells = vec![Cell::Dead; width as usize * height as usize];
+        for (alive_cell_row, alive_cell_column) in alive_cells {
+            cells[size::index(width, alive_cell_row, alive_cell_column)] = Cell::Alive;
+        }
+
+        Self::from_cells(width, height, cells)
+    }
+
+    // Any constructor with a ready made cell buffer should come through here
+    // so derived state like the population starts out right
+    fn from_cells(width: u32, height: u32, cells: Vec<Cell>) -> Self {
+        debug_assert_eq!(width as usize * height as usize, cells.len());
+        // Before anything can panic, so panics in the browser get reported
+        utils::set_panic_hook();
+        log!(debug, "new {}x{} universe", width, height);
+        let stats = stats::TickStats::new(&cells);
+        let active_marks = vec![false; cells.len()];
+        Universe {
+            width,
+            height,
+            cells,
+            active_cells: None,
+            active_marks,
+            offsets: offsets::NeighbourOffsets::new(width, height),
+            neighbour_counts: neighbour_counts::NeighbourCounts::stale(width as usize * height as usize),
+            tiles: None,
+            symmetry: symmetry::Symmetry::None,
+            boundary: Boundary::Torus,
+            injection: None,
+            gliders: None,
+            ownership: None,
+            game: None,
+            lineage: None,
+            generation: 0,
+            change_log: None,
+            delta_stream: None,
+            journal: None,
+            history: history::History::default(),
+            edits: undo::EditHistory::default(),
+            stability: stability::StabilityTracker::default(),
+            strategy: TickStrategy::Auto,
+            calibrated_strategy: None,
+            stats,
+            series: None,
+            drift: None,
+            margolus: None,
+            recovery: None,
+            events: events::Events::default(),
+            viewports: vec![],
+            timeline: None,
+            schedule: None,
+            stochastic: None,
+            shared: None,
+            walls: None,
+            selection: None,
+            born: None,
+            envelope: None,
+            cell_data: None,
+            heatmap: None,
+            trails: None,
+            tick_profile: None,
+            perf: perf::PerfCounter::default(),
+            max_age: None,
+            rule: Rule::CONWAY,
+            inverted: false,
+            step_rule: None,
+            rule_table: None,
+            transition: None,
+            replay: None,
+            render_config: RenderConfig::default(),
+        }
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        size::index(self.width, row, column)
+    }
+
+    // Every edit outside of tick() should go through here so the active set
+    // and change tracking stay consistent
+    fn write_cell(&mut self, idx: usize, cell: Cell) {
+        let owner = self.owner_of(idx);
+        if self.place_cell(idx, cell) {
+            self.undo_record(idx, owner);
+            self.timeline_record(TimelineKind::Edit { cells: 1 });
+        }
+    }
+
+    // write_cell() without making it an edit of the user's, so it can't be
+    // undone and isn't on the timeline. Returns whether the cell changed
+    fn place_cell(&mut self, idx: usize, cell: Cell) -> bool {
+        if self.cells[idx] == cell || self.is_wall_index(idx) {
+            return false;
+        }
+
+        self.shared_begin();
+        self.cells[idx] = cell;
+        self.shift_neighbour_counts(&[idx]);
+        self.stats.record_edit(cell);
+        self.shared_publish();
+        if let Some(active_cells) = self.active_cells.as_mut() {
+            active_cells.push(idx);
+        }
+        self.record_changes(&[idx]);
+        self.journal_edit(idx, cell);
+        self.lineage_edit(idx, cell);
+        self.ownership_edit(idx);
+        self.replay_edit(idx, cell);
+        self.record_births(&[idx]);
+        self.extend_envelope(&[idx]);
+        self.reset_cell_data(&[idx]);
+        self.mark_trails(&[idx]);
+        self.mark_tiles(&[idx]);
+        self.stability.reset();
+        true
+    }
+
+    fn write_cells(&mut self, indices: &[u32], cell: Cell) {
+        let size = self.cells.len();
+        self.edit_group(|universe| {
+            for &idx in indices.iter().filter(|&&idx| (idx as usize) < size) {
+                universe.write_cell(idx as usize, cell);
+            }
+        });
+    }
+
+    fn alive_neighbour_count(&self, row: u32, column: u32) -> u8 {
+        if let Some(counts) = self.neighbour_counts.current() {
+            return counts[self.get_index(row, column)];
+        }
+        self.counted_neighbours(row, column)
+    }
+
+    // Neighbours counted one by one rather than looked up
+    fn counted_neighbours(&self, row: u32, column: u32) -> u8 {
+        // Wrapping is by far the most common so keep it on the fast path
+        if self.boundary != Boundary::Torus {
+            return self.bounded_neighbour_count(row, column);
+        }
+
+        self.offsets.count(&self.cells, row, column)
+    }
+
+    // Indices of the cells neighbouring idx, which may be fewer than eight
+    // (or contain repeats) depending on the boundary mode
+    fn neighbour_indices(&self, idx: usize) -> ([usize; 8], usize) {
+        let row = (idx as u32 / self.width) as i64;
+        let column = (idx as u32 % self.width) as i64;
+
+        let mut neighbours = [0; 8];
+        let mut count = 0;
+        for row_delta in -1..=1 {
+            for column_delta in -1..=1 {
+                if row_delta == 0 && column_delta == 0 {
+                    continue
+                }
+
+                if let Some(neighbour) = self.boundary_index(row + row_delta, column + column_delta) {
+                    neighbours[count] = neighbour;
+                    count += 1;
+                }
+            }
+        }
+
+        (neighbours, count)
+    }
+
+    // Collect the cells which could possibly change this tick: the cells that
+    // changed last tick plus their neighbours, or everything if unknown
+    fn candidate_cells(&mut self) -> Vec<usize> {
+        let injected = self.advance_injection();
+        if self.effective_strategy() == TickStrategy::Dense || self.stochastic.is_some() || self.transition.is_some() {
+            self.active_cells = None;
+        }
+        if self.effective_strategy() == TickStrategy::Tiles {
+            let changed = self.active_cells.take();
+            return self.tile_candidates(changed, injected);
+        }
+        let mut changed = match self.active_cells.take() {
+            Some(changed) => changed,
+            None => return (0..self.cells.len()).collect(),
+        };
+
+        // Injected edges can change every tick regardless of activity
+        changed.extend(injected);
+        if self.effective_strategy() == TickStrategy::Rows {
+            return self.row_band_candidates(&changed);
+        }
+
+        let mut candidates = Vec::with_capacity(changed.len() * 9);
+        for idx in changed {
+            let (neighbours, count) = self.neighbour_indices(idx);
+            for candidate in std::iter::once(idx).chain(neighbours[..count].iter().cloned()) {
+                if !self.active_marks[candidate] {
+                    self.active_marks[candidate] = true;
+                    candidates.push(candidate);
+                }
+            }
+        }
+
+        // Reset the marks ready for the next tick
+        for &candidate in &candidates {
+            self.active_marks[candidate] = false;
+        }
+
+        candidates
+    }
+
+    fn cell_transform(&self, current_cell: Cell, alive_neighbour_count: u8) -> Cell {
+        if let Some(transition) = &self.transition {
+            return transition(current_cell, alive_neighbour_count);
+        }
+        self.rule.next(current_cell, alive_neighbour_count)
+    }
+
+    // States in the next generation which differ from the current one
+    fn life_changes(&mut self) -> Vec<usize> {
+        // Only loop over cells which could have changed, working out the new
+        // states before writing any so every cell sees the previous generation
+        let candidates = self.candidate_cells();
+        self.refresh_neighbour_counts();
+        if self.keeps_symmetry() {
+            return self.symmetric_changes(&candidates);
+        }
+        if self.uses_neighbourhoods() {
+            return self.neighbourhood_changes(&candidates);
+        }
+        if self.use_parallel(candidates.len()) && self.transition.is_none() {
+            return self.parallel_life_changes(&candidates);
+        }
+        #[cfg(feature = "simd")]
+        if self.use_simd(candidates.len()) {
+            return self.simd_life_changes();
+        }
+
+        let mut changed = vec![];
+        for idx in candidates {
+            // Grab current cell state and neighbour alive count
+            let current_cell = self.cells[idx];
+            let row = idx as u32 / self.width;
+            let column = idx as u32 % self.width;
+            let alive_neighbour_count = self.alive_neighbour_count(row, column);
+
+            let new_cell = self.cell_transform(current_cell, alive_neighbour_count);
+            if new_cell != current_cell {
+                changed.push(idx);
+            }
+        }
+        changed
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_alive(&self, row: u32, column: u32) -> bool {
+        self.cells[self.get_index(row, column)] == Cell::Alive
+    }
+
+    /// Row and column of every live cell as `[row, column, row, column, ...]`,
+    /// in reading order. Cheaper than copying the whole board when little is
+    /// alive
+    pub fn live_cells_flat(&self) -> Vec<u32> {
+        self.live_cells().flat_map(|(row, column)| [row, column]).collect()
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, column: u32) {
+        let idx = self.get_index(row, column);
+        let toggled = match self.cells[idx] {
+            Cell::Dead => Cell::Alive,
+            Cell::Alive => Cell::Dead,
+        };
+        self.write_cell(idx, toggled);
+    }
+
+    /// Bring the cells at each flat index (row * width + column) to life in
+    /// one call, for committing a painted stroke. Indices past the end of
+    /// the board are ignored
+    pub fn set_cells(&mut self, indices: &[u32]) {
+        self.write_cells(indices, Cell::Alive);
+    }
+
+    /// Kill the cells at each flat index, like `set_cells()`
+    pub fn clear_cells(&mut self, indices: &[u32]) {
+        self.write_cells(indices, Cell::Dead);
+    }
+
+    /// Kill every cell. This is an edit like any other, so the generation
+    /// count, rule and other settings are kept
+    pub fn clear(&mut self) {
+        self.replay_as(format_args!("clear"), |universe| {
+            universe.edit_group(|universe| {
+                for idx in 0..universe.cells.len() {
+                    universe.write_cell(idx, Cell::Dead);
+                }
+            });
+        });
+    }
+
+    /// Refill in place, each cell alive with probability `density`. The same
+    /// seed gives the same cells as `generate_universe_seeded`
+    pub fn randomize(&mut self, density: f32, seed: u64) {
+        let cells = random_cells(self.width, self.height, density, &mut seeded(seed));
+        self.replay_as(format_args!("randomize {} {}", density, seed), |universe| {
+            universe.edit_group(|universe| {
+                for (idx, cell) in cells.into_iter().enumerate() {
+                    universe.write_cell(idx, cell);
+                }
+            });
+        });
+    }
+
+    /// Refill just the `rows` x `cols` cells from (top, left), clipped to the
+    /// board, leaving the rest alone. The same seed gives the same cells in
+    /// any area of the same size
+    pub fn randomize_region(&mut self, top: u32, left: u32, rows: u32, cols: u32, density: f32, seed: u64) {
+        let area = match Rect::new(top, left, rows, cols).intersection(&self.bounds()) {
+            Some(area) => area,
+            None => return,
+        };
+        let cells = random_cells(area.columns, area.rows, density, &mut seeded(seed));
+        let entry = format_args!("randomize_region {} {} {} {} {} {}", top, left, rows, cols, density, seed);
+        self.replay_as(entry, |universe| {
+            universe.edit_group(|universe| {
+                for ((row, column), cell) in area.cells().zip(cells) {
+                    let idx = universe.get_index(row, column);
+                    universe.write_cell(idx, cell);
+                }
+            });
+        });
+    }
+
+    /// Alive cells around (row, column) under the current boundary mode
+    pub fn live_neighbours(&self, row: u32, column: u32) -> u8 {
+        self.alive_neighbour_count(row, column)
+    }
+
+    pub fn tick(&mut self) {
+        let started = utils::now_ms();
+        let mut profiler = profile::Profiler::start();
+        let status = self.status();
+        self.push_history();
+        self.forget_edits();
+        let phase = self.inverted;
+        let rule = self.begin_phase_step();
+
+        let changed = if self.margolus.is_some() {
+            self.margolus_changes()
+        } else if self.rule_table.is_some() {
+            self.table_changes()
+        } else if self.asynchronous() {
+            self.asynchronous_changes()
+        } else {
+            self.life_changes()
+        };
+        self.end_phase_step(rule);
+        profiler.end_phase();
+        let changed = self.apply_probabilities(changed);
+        let changed = self.remove_walls(changed);
+        let changed = self.expire_cells(changed);
+
+        self.update_owners(&changed);
+        self.update_lineage(&changed);
+        self.stats.record_tick(&self.cells, &changed);
+        profiler.end_phase();
+
+        // Every changed cell flips between dead and alive
+        self.shared_begin();
+        for &idx in &changed {
+            self.cells[idx] = match self.cells[idx] {
+                Cell::Dead => Cell::Alive,
+                Cell::Alive => Cell::Dead,
+            };
+        }
+        self.shift_neighbour_counts(&changed);
+        profiler.end_phase();
+
+        self.generation += 1;
+        self.shared_publish();
+        self.replay_tick();
+        self.record_series();
+        self.update_drift();
+        self.record_changes(&changed);
+        if self.inverted != phase {
+            self.record_phase_change();
+        }
+        self.record_heatmap(&changed);
+        self.record_births(&changed);
+        self.extend_envelope(&changed);
+        self.reset_cell_data(&changed);
+        self.fade_trails(&changed);
+        self.update_tiles(&changed);
+        self.update_match(&changed);
+        self.journal_tick(&changed);
+        self.update_stability(&changed);
+        let changed_count = changed.len();
+        self.active_cells = Some(changed);
+        self.record_owner_areas();
+        self.update_recovery();
+        self.timeline_status(status);
+        self.fire_events(status);
+        self.release_gliders();
+        profiler.end_phase();
+        self.tick_profile = profiler.finish(changed_count);
+        self.perf.record(started, utils::now_ms(), changed_count);
+    }
+
+    /// Number of ticks since construction or the last reset_generation()
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Start counting generations from zero again. Anything recorded
+    /// against the old numbering (history, change tracking, stabilization,
+    /// cell ages, the heatmap) is discarded
+    pub fn reset_generation(&mut self) {
+        self.generation = 0;
+        self.clear_history();
+        if self.change_log.is_some() {
+            self.disable_change_tracking();
+            self.enable_change_tracking();
+        }
+        if let Some(born) = self.born.as_mut() {
+            born.fill(0);
+        }
+        self.restart_heatmap();
+        self.stability.reset();
+        self.shared_publish();
+    }
+
+    pub fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Universe {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.render_config.write(f, self.width, &self.true_cells())
+    }
+}
+
+/// The cell at `(row, column)`, panicking outside the board
+#[cfg(feature = "std")]
+impl Index<(u32, u32)> for Universe {
+    type Output = Cell;
+
+    fn index(&self, (row, column): (u32, u32)) -> &Cell {
+        assert!(row < self.height && column < self.width, "({}, {}) is outside the {}x{} board", row, column, self.width, self.height);
+        &self.cells[self.get_index(row, column)]
+    }
+}
+
+/// Direct access to the cell at `(row, column)`, as in
+/// `universe[(row, column)] = Cell::Alive`. Unlike `set_cells()` this is a raw
+/// write: it isn't undoable, doesn't respect walls and isn't seen by
+/// journals, replays, lineage or change tracking. The next tick still picks
+/// it up and the population is counted again
+#[cfg(feature = "std")]
+impl IndexMut<(u32, u32)> for Universe {
+    fn index_mut(&mut self, (row, column): (u32, u32)) -> &mut Cell {
+        assert!(row < self.height && column < self.width, "({}, {}) is outside the {}x{} board", row, column, self.width, self.height);
+        let idx = self.get_index(row, column);
+        self.stats.invalidate();
+        if let Some(active_cells) = self.active_cells.as_mut() {
+            active_cells.push(idx);
+        }
+        if let Some(tiles) = self.tiles.as_mut() {
+            tiles.invalidate();
+        }
+        self.neighbour_counts.invalidate();
+        self.stability.reset();
+        &mut self.cells[idx]
+    }
+}
+
+#[cfg(feature = "std")]
+impl Universe {
+    /// Rust side of `generate_universe`
+    pub fn random(width: u32, height: u32, density: f32) -> Result<Universe, LifeError> {
+        size::check_size(width, height)?;
+        Ok(random_universe(width, height, density, &mut unseeded()))
+    }
+
+    /// Rust side of `generate_universe_seeded`
+    pub fn random_seeded(width: u32, height: u32, density: f32, seed: u64) -> Result<Universe, LifeError> {
+        size::check_size(width, height)?;
+        Ok(random_universe(width, height, density, &mut seeded(seed)))
+    }
+
+    /// `(row, column)` of every live cell, in reading order
+    pub fn live_cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.cells.iter()
+            .enumerate()
+            .filter(|&(_, &cell)| cell == Cell::Alive)
+            .map(move |(idx, _)| (idx as u32 / self.width, idx as u32 % self.width))
+    }
+}
+
+#[cfg(feature = "wasm")]
+#[cfg(feature = "std")]
+/// Random universe where each cell starts alive with probability `density`
+/// (0.0 to 1.0, values outside are clamped). Throws for a width or height of
+/// 0 or too many cells
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_universe(width: u32, height: u32, density: f32) -> Result<Universe, JsValue> {
+    Universe::random(width, height, density).map_err(crate::error::js_error)
+}
+
+#[cfg(feature = "wasm")]
+#[cfg(feature = "std")]
+/// Same as `generate_universe` but the same seed always produces the same
+/// universe, handy for demos, tests and bug reports
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_universe_seeded(width: u32, height: u32, density: f32, seed: u64) -> Result<Universe, JsValue> {
+    Universe::random_seeded(width, height, density, seed).map_err(crate::error::js_error)
+}
+
+#[cfg(feature = "std")]
+// Callers check the dimensions first
+pub(crate) fn random_universe<R: Rng>(width: u32, height: u32, density: f32, rng: &mut R) -> Universe {
+    Universe::from_cells(width, height, random_cells(width, height, density, rng))
+}
+
+#[cfg(feature = "std")]
+fn random_cells<R: Rng>(width: u32, height: u32, density: f32, rng: &mut R) -> Vec<Cell> {
+    // Not sure the best way to define the initial state of the board
+    let density = density.clamp(0.0, 1.0);
+
+    let mut cells = Vec::with_capacity(width as usize * height as usize);
+    for _row in 0..height {
+        for _column in 0..width {
+            cells.push(if rng.gen::<f32>() < density { Cell::Alive } else { Cell::Dead });
+        }
+    }
+    cells
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_index() {
+        let universe = Universe::new(4, 4, vec![]);
+        assert_eq!(2, universe.get_index(0, 2));
+        assert_eq!(10, universe.get_index(2, 2));
+
+        let universe = Universe::new(10, 10, vec![]);
+        assert_eq!(2, universe.get_index(0, 2));
+        assert_eq!(22, universe.get_index(2, 2));
+    }
+
+    #[test]
+    fn large_boards() {
+        // A glider crossing the corner of a board thousands of cells a side
+        let size = 4096;
+        let glider = [(size - 2, size - 1), (size - 1, 0), (0, size - 2), (0, size - 1), (0, 0)];
+        let mut universe = Universe::new(size, size, glider.to_vec());
+        assert_eq!(size as usize * size as usize - 1, universe.get_index(size - 1, size - 1));
+        for _ in 0..8 {
+            universe.tick();
+        }
+        let moved = glider.iter().map(|&(row, column)| ((row + 2) % size, (column + 2) % size));
+        let mut moved = moved.collect::<Vec<_>>();
+        moved.sort_unstable();
+        assert_eq!(moved, universe.live_cells().collect::<Vec<_>>());
+
+        // Long strips one cell across behave the same across the seam as
+        // away from it
+        for (width, height) in [(1, 1 << 20), (1 << 20, 1)] {
+            let end = width.max(height) - 1;
+            let strip = |positions: [u32; 3]| {
+                let cells = positions.iter().map(|&at| if width == 1 { (at, 0) } else { (0, at) });
+                let mut strip = Universe::new(width, height, cells.collect());
+                strip.tick();
+                strip.live_cells().map(|(row, column)| row.max(column)).collect::<Vec<_>>()
+            };
+            let mut across = strip([end, 0, 1]).iter().map(|&at| (at + 2) % (end + 1)).collect::<Vec<_>>();
+            across.sort_unstable();
+            assert_eq!(strip([1, 2, 3]), across);
+        }
+    }
+
+    #[test]
+    fn alive_count() {
+        // None alive
+        let universe = Universe::new(4, 4, vec![]);
+        assert_eq!(0, universe.alive_neighbour_count(2, 2));
+
+        // Row alive
+        let universe = Universe::new(4, 4, vec![(0, 2), (2, 2)]);
+        assert_eq!(0, universe.alive_neighbour_count(2, 2));
+        assert_eq!(2, universe.alive_neighbour_count(1, 2));
+        assert_eq!(1, universe.alive_neighbour_count(0, 3));
+
+        // Column alive
+        let universe = Universe::new(4, 4, vec![(2, 0), (2, 2)]);
+        assert_eq!(0, universe.alive_neighbour_count(2, 2));
+        assert_eq!(2, universe.alive_neighbour_count(2, 1));
+        assert_eq!(1, universe.alive_neighbour_count(3, 0));
+
+        // TODO: Check wraps
+    }
+
+    #[test]
+    fn live_cells() {
+        let universe = Universe::new(5, 4, vec![(3, 1), (0, 4), (2, 2)]);
+        assert_eq!(vec![(0, 4), (2, 2), (3, 1)], universe.live_cells().collect::<Vec<_>>());
+        assert_eq!(vec![0, 4, 2, 2, 3, 1], universe.live_cells_flat());
+        assert_eq!(0, Universe::new(3, 3, vec![]).live_cells().count());
+    }
+
+    #[test]
+    fn index_by_position() {
+        let mut universe = Universe::new(4, 3, vec![(1, 1), (1, 2)]);
+        assert_eq!(Cell::Alive, universe[(1, 2)]);
+        assert_eq!(Cell::Dead, universe[(2, 3)]);
+
+        universe.tick();
+        universe[(1, 3)] = Cell::Alive;
+        assert_eq!(1, universe.population());
+        universe[(0, 0)] = Cell::Alive;
+        universe[(2, 0)] = Cell::Alive;
+        assert_eq!(3, universe.population());
+        // The active set saw the writes, so the tick matches a full one
+        let mut full = universe.duplicate();
+        full.active_cells = None;
+        universe.tick();
+        full.tick();
+        assert_eq!(full.cells, universe.cells);
+        assert_eq!(full.population(), universe.population());
+    }
+
+    #[test]
+    fn cell_transform() {
+        let universe = Universe::new(1, 1, vec![]);
+        // Alive transformations
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 0));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 1));
+        assert_eq!(Cell::Alive, universe.cell_transform(Cell::Alive, 2));
+        assert_eq!(Cell::Alive, universe.cell_transform(Cell::Alive, 3));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 4));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 5));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 6));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Alive, 7));
+
+        // Dead transformations
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 0));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 1));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 2));
+        assert_eq!(Cell::Alive, universe.cell_transform(Cell::Dead, 3));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 4));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 5));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 6));
+        assert_eq!(Cell::Dead, universe.cell_transform(Cell::Dead, 7));
+    }
+
+    // Reference implementation evaluating every cell, used to check the
+    // active set optimisation doesn't miss anything
+    fn full_tick(universe: &Universe) -> Vec<Cell> {
+        let mut new_cells = universe.cells.clone();
+        for row in 0..universe.height {
+            for column in 0..universe.width {
+                let idx = universe.get_index(row, column);
+                let count = universe.alive_neighbour_count(row, column);
+                new_cells[idx] = universe.cell_transform(universe.cells[idx], count);
+            }
+        }
+        new_cells
+    }
+
+    #[test]
+    fn active_cells_tick() {
+        let mut universe = Universe::random(32, 32, 0.125).unwrap();
+        for _ in 0..50 {
+            let expected = full_tick(&universe);
+            universe.tick();
+            assert_eq!(expected, universe.cells);
+        }
+
+        // Blinker only ever has its own cells and neighbours active
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        universe.tick();
+        assert_eq!(4, universe.active_cells.as_ref().unwrap().len());
+        universe.tick();
+        assert_eq!(4, universe.active_cells.as_ref().unwrap().len());
+        assert_eq!(Cell::Alive, universe.cells[universe.get_index(3, 2)]);
+    }
+
+    #[test]
+    fn seeded_universe() {
+        let mut first = Universe::random_seeded(32, 32, 0.125, 1234).unwrap();
+        let mut second = Universe::random_seeded(32, 32, 0.125, 1234).unwrap();
+        assert_eq!(first.cells, second.cells);
+
+        first.tick();
+        second.tick();
+        assert_eq!(first.cells, second.cells);
+
+        let first = Universe::random_seeded(32, 32, 0.125, 1234).unwrap();
+        let other = Universe::random_seeded(32, 32, 0.125, 4321).unwrap();
+        assert_ne!(first.cells, other.cells);
+    }
+
+    #[test]
+    fn universe_density() {
+        let population = |universe: &Universe| universe.cells.iter().filter(|&&c| c == Cell::Alive).count();
+
+        assert_eq!(0, population(&Universe::random_seeded(32, 32, 0.0, 1).unwrap()));
+        assert_eq!(32 * 32, population(&Universe::random_seeded(32, 32, 1.0, 1).unwrap()));
+        assert_eq!(32 * 32, population(&Universe::random_seeded(32, 32, 7.0, 1).unwrap()));
+
+        let half = population(&Universe::random_seeded(64, 64, 0.5, 1).unwrap());
+        assert!(half > 1800 && half < 2300);
+    }
+
+    #[test]
+    fn generation_counter() {
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        assert_eq!(0, universe.generation());
+
+        universe.set_history_capacity(4);
+        for _ in 0..3 {
+            universe.tick();
+        }
+        assert_eq!(3, universe.generation());
+
+        universe.reset_generation();
+        assert_eq!(0, universe.generation());
+        assert!(!universe.step_back());
+
+        universe.tick();
+        assert_eq!(1, universe.generation());
+    }
+
+    #[test]
+    fn toggle_cell() {
+        let mut universe = Universe::new(5, 3, vec![(1, 1)]);
+        universe.tick();
+        assert!(!universe.is_alive(1, 1));
+
+        // A blinker drawn by hand on an idle universe still gets ticked
+        for column in 1..4 {
+            universe.toggle_cell(1, column);
+        }
+        assert_eq!(3, universe.population());
+        universe.tick();
+        assert!(universe.is_alive(0, 2) && universe.is_alive(2, 2) && !universe.is_alive(1, 1));
+
+        universe.toggle_cell(0, 2);
+        assert!(!universe.is_alive(0, 2));
+    }
+
+    #[test]
+    fn bulk_cell_edits() {
+        let mut universe = Universe::new(5, 3, vec![]);
+        universe.tick();
+        universe.set_cells(&[6, 7, 8, 15, 99]);
+        assert_eq!(3, universe.population());
+        universe.tick();
+        assert!(universe.is_alive(0, 2) && universe.is_alive(2, 2) && !universe.is_alive(1, 1));
+
+        universe.clear_cells(&[2, 7, 12]);
+        assert_eq!(0, universe.population());
+    }
+
+    #[test]
+    fn clear_and_randomize_in_place() {
+        let mut universe = Universe::random_seeded(24, 16, 0.3, 5).unwrap();
+        universe.tick();
+        universe.clear();
+        assert_eq!(0, universe.population());
+        assert_eq!(1, universe.generation());
+
+        universe.randomize(0.4, 9);
+        assert_eq!(Universe::random_seeded(24, 16, 0.4, 9).unwrap().cells, universe.cells);
+        assert_eq!(universe.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32, universe.population());
+
+        // The refilled universe ticks like a freshly generated one
+        let mut fresh = Universe::random_seeded(24, 16, 0.4, 9).unwrap();
+        universe.tick();
+        fresh.tick();
+        assert_eq!(fresh.cells, universe.cells);
+    }
+
+    #[test]
+    fn randomize_a_region() {
+        let mut universe = Universe::new(12, 10, vec![(0, 0), (9, 11)]);
+        universe.set_undo_capacity(4);
+        universe.randomize_region(6, 8, 10, 10, 1.0, 3);
+        // Only the clipped 4x4 corner is filled, the corner cell included
+        assert_eq!(17, universe.population());
+        assert!(universe.is_alive(0, 0) && universe.is_alive(6, 8) && !universe.is_alive(5, 8));
+
+        universe.undo_edit();
+        universe.randomize_region(2, 3, 4, 5, 0.5, 7);
+        let block = Universe::random_seeded(5, 4, 0.5, 7).unwrap();
+        for (row, column) in Rect::new(2, 3, 4, 5).cells() {
+            assert_eq!(block.is_alive(row - 2, column - 3), universe.is_alive(row, column));
+        }
+        assert!(universe.is_alive(0, 0) && universe.is_alive(9, 11));
+        universe.randomize_region(20, 0, 4, 4, 1.0, 1);
+        assert_eq!(2 + block.population(), universe.population());
+    }
+
+    #[test]
+    fn test_generate_universe() {
+        let mut universe = Universe::random(64, 64, 0.125).unwrap();
+        println!("{}", universe.render());
+        universe.tick();
+        println!("{}", universe.render());
+    }
+}
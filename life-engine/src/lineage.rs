@@ -0,0 +1,199 @@
+// Ancestry ids for colouring competing colonies
+//
+// Switching lineage on gives every live cell its own id, and a cell brought
+// to life by an edit afterwards gets a fresh one. Newborn cells inherit the
+// id held by most of their live neighbours, like the colours of Immigration
+// or QuadLife, and dead cells go back to 0. Unlike ownership's 255 owners
+// the ids are u32 so every starting cell can found a lineage of its own,
+// and JS can read them in place through `lineage_ptr()`
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone)]
+pub(crate) struct Lineage {
+    ids: Vec<u32>,
+    // Given to the next cell brought to life by an edit
+    next_id: u32,
+}
+
+impl Lineage {
+    // Ids follow their cells when the board is moved around, see
+    // Universe::rearrange. Worked out before the cells are rewritten and
+    // put back afterwards, as the rewrites count as edits
+    pub(crate) fn rearranged(&self, source: &[Option<usize>]) -> Vec<u32> {
+        source.iter().map(|from| from.map_or(0, |from| self.ids[from])).collect()
+    }
+
+    pub(crate) fn set_ids(&mut self, ids: Vec<u32>) {
+        self.ids = ids;
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.ids.capacity() * std::mem::size_of::<u32>()
+    }
+}
+
+/// Most common non-zero id, ties going to the lowest so runs are
+/// deterministic, or 0 if there are none
+pub(crate) fn dominant(ids: impl Iterator<Item = u32>) -> u32 {
+    // At most eight neighbours, so a list beats a map
+    let mut counts: Vec<(u32, u32)> = Vec::with_capacity(8);
+    for id in ids.filter(|&id| id != 0) {
+        match counts.iter_mut().find(|(counted, _)| *counted == id) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((id, 1)),
+        }
+    }
+    counts.iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .map_or(0, |&(id, _)| id)
+}
+
+impl Universe {
+    // Called from tick() with the cells about to flip, before they are
+    // written, so births see the previous generation's ids
+    pub(crate) fn update_lineage(&mut self, changed: &[usize]) {
+        let lineage = match &self.lineage {
+            Some(lineage) => lineage,
+            None => return,
+        };
+
+        let new_ids = changed.iter()
+            .map(|&idx| match self.cells[idx] {
+                Cell::Alive => 0,
+                Cell::Dead => {
+                    let (neighbours, count) = self.neighbour_indices(idx);
+                    dominant(neighbours[..count].iter()
+                        .filter(|&&neighbour| self.cells[neighbour] == Cell::Alive)
+                        .map(|&neighbour| lineage.ids[neighbour]))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let lineage = self.lineage.as_mut().unwrap();
+        for (&idx, id) in changed.iter().zip(new_ids) {
+            lineage.ids[idx] = id;
+        }
+    }
+
+    // Called from write_cell() for edits
+    pub(crate) fn lineage_edit(&mut self, idx: usize, cell: Cell) {
+        if let Some(lineage) = self.lineage.as_mut() {
+            lineage.ids[idx] = match cell {
+                Cell::Alive => {
+                    lineage.next_id += 1;
+                    lineage.next_id - 1
+                }
+                Cell::Dead => 0,
+            };
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start tracking lineage, every live cell founding its own
+    pub fn enable_lineage(&mut self) {
+        if self.lineage.is_none() {
+            let ids = self.cells.iter()
+                .enumerate()
+                .map(|(idx, &cell)| if cell == Cell::Alive { idx as u32 + 1 } else { 0 })
+                .collect();
+            self.lineage = Some(Lineage { ids, next_id: self.cells.len() as u32 + 1 });
+        }
+    }
+
+    pub fn disable_lineage(&mut self) {
+        self.lineage = None;
+    }
+
+    pub fn lineage_enabled(&self) -> bool {
+        self.lineage.is_some()
+    }
+
+    /// Ancestry id of a cell, 0 for dead cells or while tracking is off
+    pub fn lineage_at(&self, row: u32, column: u32) -> u32 {
+        match &self.lineage {
+            Some(lineage) => lineage.ids[self.get_index(row, column)],
+            None => 0,
+        }
+    }
+
+    /// Ancestry id of every cell, in the same layout as the cell buffer
+    pub fn lineage(&self) -> Vec<u32> {
+        match &self.lineage {
+            Some(lineage) => lineage.ids.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Pointer to the ids in WASM memory, one u32 per cell, or null while
+    /// tracking is off. Only valid until the next call that changes the
+    /// universe
+    pub fn lineage_ptr(&self) -> *const u32 {
+        match &self.lineage {
+            Some(lineage) => lineage.ids.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+
+    /// Number of distinct lineages with a living cell
+    pub fn lineage_count(&self) -> u32 {
+        match &self.lineage {
+            Some(lineage) => {
+                let mut living = lineage.ids.iter().cloned().filter(|&id| id != 0).collect::<Vec<_>>();
+                living.sort_unstable();
+                living.dedup();
+                living.len() as u32
+            }
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newborns_inherit_majority_lineage() {
+        assert_eq!(3, dominant([0, 5, 3, 5, 3, 0].iter().cloned()));
+        assert_eq!(5, dominant([5, 3, 5].iter().cloned()));
+        assert_eq!(0, dominant([0, 0].iter().cloned()));
+
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        universe.enable_lineage();
+        assert_eq!(27, universe.lineage_at(3, 2));
+        assert_eq!(3, universe.lineage_count());
+
+        // Both births see one cell of each lineage, so take the lowest
+        universe.tick();
+        assert_eq!(27, universe.lineage_at(2, 3));
+        assert_eq!(27, universe.lineage_at(4, 3));
+        assert_eq!(28, universe.lineage_at(3, 3));
+        assert_eq!(0, universe.lineage_at(3, 2));
+        assert_eq!(2, universe.lineage_count());
+
+        // Drawn cells start lineages of their own
+        universe.toggle_cell(0, 0);
+        assert_eq!(65, universe.lineage_at(0, 0));
+        universe.toggle_cell(0, 0);
+        assert_eq!(0, universe.lineage_at(0, 0));
+    }
+
+    #[test]
+    fn lineage_moves_with_the_board() {
+        let mut universe = Universe::new(4, 4, vec![(0, 0)]);
+        assert!(universe.lineage_ptr().is_null());
+        universe.enable_lineage();
+        universe.flip_horizontal();
+        assert_eq!(1, universe.lineage_at(0, 3));
+        assert_eq!(vec![0, 0, 0, 1], universe.lineage()[..4].to_vec());
+
+        universe.disable_lineage();
+        assert_eq!(vec![0; 16], universe.lineage());
+    }
+}
@@ -0,0 +1,188 @@
+// Several universes owned together, for side by side comparisons
+//
+// Each universe is kept under an id handed out when it's added, so a page
+// comparing rules on the same seed holds one handle rather than one per
+// universe. Ids are never reused, so a stale id can't reach a universe
+// added after its own was destroyed
+
+use crate::Universe;
+
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Default)]
+pub struct UniverseManager {
+    // Ordered so tick_all() and ids() go oldest first
+    universes: BTreeMap<u32, Universe>,
+    next_id: u32,
+}
+
+#[cfg(feature = "wasm")]
+fn missing(id: u32) -> JsValue {
+    crate::LifeError::UnknownUniverse(id).into()
+}
+
+impl UniverseManager {
+    pub fn get(&self, id: u32) -> Option<&Universe> {
+        self.universes.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Universe> {
+        self.universes.get_mut(&id)
+    }
+
+    /// Rust side of `take`
+    pub fn remove(&mut self, id: u32) -> Option<Universe> {
+        self.universes.remove(&id)
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl UniverseManager {
+    fn universe(&self, id: u32) -> Result<&Universe, JsValue> {
+        self.get(id).ok_or_else(|| missing(id))
+    }
+
+    fn universe_mut(&mut self, id: u32) -> Result<&mut Universe, JsValue> {
+        self.get_mut(id).ok_or_else(|| missing(id))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl UniverseManager {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> UniverseManager {
+        UniverseManager::default()
+    }
+
+    /// Take ownership of a universe made elsewhere, returning its id. The
+    /// JS handle passed in can't be used afterwards
+    pub fn insert(&mut self, universe: Universe) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.universes.insert(id, universe);
+        id
+    }
+
+    /// Drop a universe, returning false if there was none with that id
+    pub fn destroy(&mut self, id: u32) -> bool {
+        self.universes.remove(&id).is_some()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.universes.contains_key(&id)
+    }
+
+    /// Ids of every universe, oldest first
+    pub fn ids(&self) -> Vec<u32> {
+        self.universes.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.universes.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.universes.is_empty()
+    }
+
+    pub fn tick_all(&mut self) {
+        for universe in self.universes.values_mut() {
+            universe.tick();
+        }
+    }
+}
+
+// The per-id accessors, which from Rust are `get()` and `get_mut()`
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl UniverseManager {
+    /// Add a seeded random universe, returning its id. Throws for sizes
+    /// `generate_universe` would reject
+    pub fn create(&mut self, width: u32, height: u32, density: f32, seed: u64) -> Result<u32, JsValue> {
+        let universe = Universe::random_seeded(width, height, density, seed)
+            .map_err(crate::error::js_error)?;
+        Ok(self.insert(universe))
+    }
+
+    /// Hand a universe back to JS, removing it from the manager
+    pub fn take(&mut self, id: u32) -> Result<Universe, JsValue> {
+        self.universes.remove(&id).ok_or_else(|| missing(id))
+    }
+
+    pub fn tick(&mut self, id: u32) -> Result<(), JsValue> {
+        self.universe_mut(id)?.tick();
+        Ok(())
+    }
+
+    pub fn width(&self, id: u32) -> Result<u32, JsValue> {
+        Ok(self.universe(id)?.width())
+    }
+
+    pub fn height(&self, id: u32) -> Result<u32, JsValue> {
+        Ok(self.universe(id)?.height())
+    }
+
+    pub fn generation(&self, id: u32) -> Result<u64, JsValue> {
+        Ok(self.universe(id)?.generation())
+    }
+
+    pub fn population(&self, id: u32) -> Result<u32, JsValue> {
+        Ok(self.universe(id)?.population())
+    }
+
+    pub fn is_alive(&self, id: u32, row: u32, column: u32) -> Result<bool, JsValue> {
+        Ok(self.universe(id)?.is_alive(row, column))
+    }
+
+    pub fn toggle_cell(&mut self, id: u32, row: u32, column: u32) -> Result<(), JsValue> {
+        self.universe_mut(id)?.toggle_cell(row, column);
+        Ok(())
+    }
+
+    /// Set the B/S rule of one universe, throwing for an unknown id or an
+    /// invalid rule
+    pub fn set_rule(&mut self, id: u32, rule: &str) -> Result<(), JsValue> {
+        self.universe_mut(id)?.set_rule(rule)
+    }
+
+    pub fn render(&self, id: u32) -> Result<String, JsValue> {
+        Ok(self.universe(id)?.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manages_universes_by_id() {
+        let mut manager = UniverseManager::new();
+        let conway = manager.insert(Universe::random_seeded(16, 16, 0.4, 9).unwrap());
+        let highlife = manager.insert(Universe::random_seeded(16, 16, 0.4, 9).unwrap());
+        manager.get_mut(highlife).unwrap().set_life_rule("B36/S23".parse().unwrap());
+        assert_eq!(vec![0, 1], manager.ids());
+
+        let mut expected = Universe::random_seeded(16, 16, 0.4, 9).unwrap();
+        for _ in 0..5 {
+            manager.tick_all();
+            expected.tick();
+        }
+        assert_eq!(expected.cells, manager.get(conway).unwrap().cells);
+        assert_eq!(5, manager.get(highlife).unwrap().generation());
+
+        // Ids aren't reused after a universe goes
+        assert!(manager.destroy(conway));
+        assert!(!manager.destroy(conway));
+        let added = manager.insert(Universe::new(4, 4, vec![]));
+        assert_eq!(2, added);
+        assert_eq!(vec![1, 2], manager.ids());
+        assert_eq!(2, manager.len());
+
+        let taken = manager.remove(highlife).unwrap();
+        assert_eq!(5, taken.generation());
+        assert!(!manager.contains(highlife));
+    }
+}
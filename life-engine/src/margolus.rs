@@ -0,0 +1,253 @@
+// Block cellular automata on the Margolus neighbourhood
+//
+// Instead of each cell looking at its neighbours, the grid is split into
+// 2x2 blocks and each block is replaced as a whole. The partition is offset
+// by one cell diagonally on every other tick so information crosses block
+// edges. A `BlockRule` is a lookup table from each of the 16 possible blocks
+// to its replacement, one table per partition, so rules like Critters which
+// treat the two differently fit as well. A rule whose tables only shuffle
+// blocks around (every block appears once as a result) is reversible.
+//
+// Blocks are numbered by their cells as bits: 1 top left, 2 top right, 4
+// bottom left, 8 bottom right. The board needs even dimensions for the
+// blocks to tile
+
+use crate::critters::particle_rule;
+use crate::{Cell, TimelineKind, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockRule {
+    // Replacement for each block, for the aligned and offset partitions
+    tables: [[u8; 16]; 2],
+}
+
+fn to_block(bits: u8) -> [Cell; 4] {
+    [0, 1, 2, 3].map(|i| if bits >> i & 1 == 1 { Cell::Alive } else { Cell::Dead })
+}
+
+fn to_bits(block: [Cell; 4]) -> u8 {
+    block.iter().enumerate().map(|(i, &cell)| (cell as u8) << i).sum()
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl BlockRule {
+    /// Critters, see critters.rs. Particles are conserved
+    pub fn critters() -> BlockRule {
+        let table = |phase| {
+            let mut table = [0; 16];
+            for (bits, entry) in table.iter_mut().enumerate() {
+                *entry = to_bits(particle_rule(to_block(bits as u8), phase));
+            }
+            table
+        };
+        BlockRule { tables: [table(0), table(1)] }
+    }
+
+    /// Fredkin's billiard ball model. A lone particle moves to the opposite
+    /// corner of its block, so balls travel diagonally, and two particles
+    /// meeting head on bounce off to the other diagonal. Everything else is
+    /// left as it is, making walls from blocks of two in a line
+    pub fn billiard_ball() -> BlockRule {
+        let mut table = [0; 16];
+        for (bits, entry) in table.iter_mut().enumerate() {
+            *entry = match bits {
+                0b0001 => 0b1000,
+                0b1000 => 0b0001,
+                0b0010 => 0b0100,
+                0b0100 => 0b0010,
+                0b1001 => 0b0110,
+                0b0110 => 0b1001,
+                other => other as u8,
+            };
+        }
+        BlockRule { tables: [table; 2] }
+    }
+
+    /// Tron: blocks all alive or all dead invert and the rest stay put
+    pub fn tron() -> BlockRule {
+        let mut table = [0; 16];
+        for (bits, entry) in table.iter_mut().enumerate() {
+            *entry = match bits {
+                0 => 15,
+                15 => 0,
+                other => other as u8,
+            };
+        }
+        BlockRule { tables: [table; 2] }
+    }
+
+    /// A rule from the replacement for each block number: 16 entries used
+    /// on both partitions, or 32 with the offset partition's second.
+    /// Nothing if the length is wrong or an entry isn't a block (over 15)
+    pub fn from_table(table: &[u8]) -> Option<BlockRule> {
+        if (table.len() != 16 && table.len() != 32) || table.iter().any(|&entry| entry > 15) {
+            return None;
+        }
+        let mut tables = [[0; 16]; 2];
+        tables[0].copy_from_slice(&table[..16]);
+        tables[1].copy_from_slice(&table[table.len() - 16..]);
+        Some(BlockRule { tables })
+    }
+
+    /// Both tables, aligned partition first, in `from_table()`'s layout
+    pub fn table(&self) -> Vec<u8> {
+        self.tables.concat()
+    }
+
+    /// Whether every block has exactly one predecessor, so the run could be
+    /// played backwards
+    pub fn is_reversible(&self) -> bool {
+        self.tables.iter().all(|table| {
+            let mut seen = [false; 16];
+            table.iter().all(|&result| !std::mem::replace(&mut seen[result as usize], true))
+        })
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Margolus {
+    rule: BlockRule,
+    // 0 for blocks aligned to (0, 0), 1 for blocks aligned to (1, 1)
+    phase: u32,
+}
+
+impl Universe {
+    pub(crate) fn margolus_changes(&mut self) -> Vec<usize> {
+        let (table, phase) = match self.margolus.as_mut() {
+            Some(margolus) => {
+                let phase = margolus.phase;
+                margolus.phase = 1 - phase;
+                (margolus.rule.tables[phase as usize], phase)
+            }
+            None => return vec![],
+        };
+
+        let mut changed = vec![];
+        for block_row in (phase as i64..self.height as i64).step_by(2) {
+            for block_column in (phase as i64..self.width as i64).step_by(2) {
+                let positions = [
+                    (block_row, block_column),
+                    (block_row, block_column + 1),
+                    (block_row + 1, block_column),
+                    (block_row + 1, block_column + 1),
+                ];
+
+                // Blocks hanging off a non wrapping edge are left alone
+                let mut indices = [0; 4];
+                let mut complete = true;
+                for (idx, &(row, column)) in indices.iter_mut().zip(&positions) {
+                    match self.boundary_index(row, column) {
+                        Some(found) => *idx = found,
+                        None => complete = false,
+                    }
+                }
+                if !complete {
+                    continue
+                }
+
+                let block = to_bits(indices.map(|idx| self.cells[idx]));
+                let flipped = block ^ table[block as usize];
+                for (i, &idx) in indices.iter().enumerate() {
+                    if flipped >> i & 1 == 1 {
+                        changed.push(idx);
+                    }
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Run tick() on 2x2 blocks with `rule` instead of the usual rule,
+    /// starting from the aligned partition. Needs even dimensions so the
+    /// blocks tile, returns whether it's running
+    pub fn set_block_rule(&mut self, rule: &BlockRule) -> bool {
+        if !self.width.is_multiple_of(2) || !self.height.is_multiple_of(2) {
+            log!(warn, "block rules need even dimensions, not {}x{}", self.width, self.height);
+            self.clear_block_rule();
+            return false;
+        }
+
+        let was_critters = self.critters_mode();
+        self.margolus = Some(Margolus { rule: rule.clone(), phase: 0 });
+        if was_critters != self.critters_mode() {
+            self.timeline_record(TimelineKind::CrittersMode(!was_critters));
+        }
+        true
+    }
+
+    /// Go back to the usual rule
+    pub fn clear_block_rule(&mut self) {
+        if self.critters_mode() {
+            self.timeline_record(TimelineKind::CrittersMode(false));
+        }
+        self.margolus = None;
+        self.active_cells = None;
+    }
+
+    pub fn block_rule(&self) -> Option<BlockRule> {
+        self.margolus.as_ref().map(|margolus| margolus.rule.clone())
+    }
+
+    /// Which block partition the next tick uses, 0 or 1
+    pub fn block_phase(&self) -> u32 {
+        self.margolus.as_ref().map_or(0, |margolus| margolus.phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        assert!(BlockRule::critters().is_reversible());
+        assert!(BlockRule::billiard_ball().is_reversible());
+        assert!(BlockRule::tron().is_reversible());
+        assert!(!BlockRule::from_table(&[0; 16]).unwrap().is_reversible());
+
+        let table = BlockRule::billiard_ball().table();
+        assert_eq!(32, table.len());
+        assert_eq!(Some(BlockRule::billiard_ball()), BlockRule::from_table(&table));
+        assert_eq!(Some(BlockRule::billiard_ball()), BlockRule::from_table(&table[..16]));
+        assert_eq!(None, BlockRule::from_table(&table[..20]));
+        assert_eq!(None, BlockRule::from_table(&[16; 16]));
+    }
+
+    #[test]
+    fn block_rules() {
+        // A ball heads down and right, one cell a tick, until it hits the
+        // wall across the board and comes back
+        let mut cells: Vec<_> = (0..8).map(|column| (6, column)).collect();
+        cells.push((0, 0));
+        let mut universe = Universe::new(8, 8, cells);
+        assert!(universe.set_block_rule(&BlockRule::billiard_ball()));
+        let mut path = vec![];
+        for _ in 0..8 {
+            universe.tick();
+            path.push((0..8).find(|&i| universe.is_alive(i, i)));
+            assert_eq!(9, universe.population());
+        }
+        assert_eq!(vec![1, 2, 3, 4, 5, 5, 4, 3], path.into_iter().flatten().collect::<Vec<_>>());
+        assert_eq!(0, universe.block_phase());
+
+        // Tron fills an empty board on the first partition and empties it
+        // again on the second
+        let mut universe = Universe::new(4, 4, vec![]);
+        universe.set_block_rule(&BlockRule::tron());
+        universe.tick();
+        assert_eq!((16, 1), (universe.population(), universe.block_phase()));
+        universe.tick();
+        assert_eq!(0, universe.population());
+
+        let mut odd = Universe::new(7, 8, vec![]);
+        assert!(!odd.set_block_rule(&BlockRule::tron()));
+        assert_eq!(None, odd.block_rule());
+    }
+}
@@ -0,0 +1,114 @@
+// Memory use, for pages deciding when to shrink history or the board
+//
+// Sizes are what each buffer has allocated rather than what it's using, so
+// a history trimmed down from a larger capacity still counts until it's
+// cleared. The linear memory size is the whole WASM heap, including
+// allocator slack and anything JS has been handed, and is 0 outside WASM
+
+use crate::Universe;
+
+use std::mem::size_of;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Bytes of the cell buffer and the active set, neighbour tables and
+    /// counts and tiles kept alongside it
+    pub cell_bytes: usize,
+    /// Bytes of optional per-cell layers: ownership, lineage, ages, the
+    /// envelope, cell data, the heatmap, trails, walls and change tracking
+    pub layer_bytes: usize,
+    /// Bytes of rewind history, the undo and redo stacks and statistics
+    /// series
+    pub history_bytes: usize,
+    /// Generations held for `step_back`
+    pub history_generations: u32,
+    /// Size of WASM linear memory in 64 KiB pages
+    pub linear_memory_pages: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MemoryStats {
+    /// Everything the universe's own buffers hold
+    pub fn total_bytes(&self) -> usize {
+        self.cell_bytes + self.layer_bytes + self.history_bytes
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn linear_memory_pages() -> u32 {
+    core::arch::wasm32::memory_size(0) as u32
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn linear_memory_pages() -> u32 {
+    0
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn memory_stats(&self) -> MemoryStats {
+        let active = self.active_cells.as_ref().map_or(0, Vec::capacity);
+        let cell_bytes = self.cells.capacity() * size_of::<crate::Cell>()
+            + self.active_marks.capacity() * size_of::<bool>()
+            + self.offsets.heap_bytes()
+            + self.neighbour_counts.heap_bytes()
+            + self.tiles.as_ref().map_or(0, |tiles| tiles.heap_bytes())
+            + active * size_of::<usize>();
+
+        let layer_bytes = self.ownership.as_ref().map_or(0, |ownership| ownership.heap_bytes())
+            + self.lineage.as_ref().map_or(0, |lineage| lineage.heap_bytes())
+            + self.game.as_ref().map_or(0, |game| game.heap_bytes())
+            + self.born.as_ref().map_or(0, |born| born.capacity() * size_of::<u64>())
+            + self.envelope.as_ref().map_or(0, Vec::capacity)
+            + self.cell_data.as_ref().map_or(0, Vec::capacity)
+            + self.heatmap.as_ref().map_or(0, |heatmap| heatmap.heap_bytes())
+            + self.trails.as_ref().map_or(0, |trails| trails.heap_bytes())
+            + self.walls.as_ref().map_or(0, |walls| walls.capacity() * size_of::<bool>())
+            + self.change_log.as_ref().map_or(0, |change_log| change_log.heap_bytes())
+            + self.delta_stream.as_ref().map_or(0, |stream| stream.heap_bytes());
+
+        MemoryStats {
+            cell_bytes,
+            layer_bytes,
+            history_bytes: self.history.heap_bytes()
+                + self.edits.heap_bytes()
+                + self.series.as_ref().map_or(0, |series| series.heap_bytes()),
+            history_generations: self.history_len(),
+            linear_memory_pages: linear_memory_pages(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_stats() {
+        let mut universe = Universe::new(64, 32, vec![(1, 1), (1, 2), (1, 3)]);
+        let stats = universe.memory_stats();
+        assert_eq!(0, stats.layer_bytes);
+        assert_eq!(0, stats.history_bytes);
+        assert!(stats.cell_bytes >= 64 * 32 * 2);
+
+        universe.set_history_capacity(4);
+        universe.enable_change_tracking();
+        for _ in 0..6 {
+            universe.tick();
+        }
+        let stats = universe.memory_stats();
+        assert_eq!(4, stats.history_generations);
+        // Each generation is packed into 256 bytes
+        assert!(stats.history_bytes >= 4 * 256);
+        assert_eq!(64 * 32 * 8, stats.layer_bytes);
+        assert_eq!(stats.cell_bytes + stats.layer_bytes + stats.history_bytes, stats.total_bytes());
+
+        universe.clear_history();
+        universe.disable_change_tracking();
+        assert_eq!(0, universe.memory_stats().history_generations);
+        assert_eq!(0, universe.memory_stats().layer_bytes);
+    }
+}
@@ -0,0 +1,93 @@
+// Wrapped neighbour positions worked out once per board size
+//
+// Counting a cell's neighbours on a torus needs the rows above and below it
+// and the columns either side, wrapping round at the edges. Rather than
+// working those out for every cell on every tick, a universe keeps two
+// small tables built whenever its size is set: for each row where the row
+// above, the row itself and the row below start in the cell buffer, and for
+// each column the columns to its left, itself and to its right. Each
+// neighbour is then an add of two looked up values, and the tables cost a
+// few words per row and column rather than anything per cell
+
+use crate::Cell;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct NeighbourOffsets {
+    rows: Vec<[usize; 3]>,
+    columns: Vec<[usize; 3]>,
+}
+
+// Positions before, at and after each position on a ring of `size`
+fn ring(size: u32) -> impl Iterator<Item = [usize; 3]> {
+    let size = size as usize;
+    (0..size).map(move |position| [(position + size - 1) % size, position, (position + 1) % size])
+}
+
+impl NeighbourOffsets {
+    pub(crate) fn new(width: u32, height: u32) -> NeighbourOffsets {
+        let starts = |rows: [usize; 3]| rows.map(|row| row * width as usize);
+        NeighbourOffsets { rows: ring(height).map(starts).collect(), columns: ring(width).collect() }
+    }
+
+    #[inline]
+    pub(crate) fn count(&self, cells: &[Cell], row: u32, column: u32) -> u8 {
+        let (rows, columns) = (&self.rows[row as usize], &self.columns[column as usize]);
+        let mut alive_count = 0;
+        for (row_offset, &start) in rows.iter().enumerate() {
+            for (column_offset, &column) in columns.iter().enumerate() {
+                if row_offset == 1 && column_offset == 1 {
+                    continue // Don't count our own cell, we only want the neighbours
+                }
+                alive_count += cells[start + column] as u8;
+            }
+        }
+        alive_count
+    }
+
+    // Where the eight neighbours of (row, column) are, with repeats on boards
+    // too small for them all to be different cells
+    #[inline]
+    pub(crate) fn neighbours(&self, row: u32, column: u32) -> [usize; 8] {
+        let (rows, columns) = (&self.rows[row as usize], &self.columns[column as usize]);
+        let mut neighbours = [0; 8];
+        let mut slot = 0;
+        for (row_offset, &start) in rows.iter().enumerate() {
+            for (column_offset, &column) in columns.iter().enumerate() {
+                if row_offset == 1 && column_offset == 1 {
+                    continue
+                }
+                neighbours[slot] = start + column;
+                slot += 1;
+            }
+        }
+        neighbours
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        (self.rows.capacity() + self.columns.capacity()) * std::mem::size_of::<[usize; 3]>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::torus_neighbour_count;
+    use crate::Universe;
+
+    #[test]
+    fn matches_counting_directly() {
+        for (width, height) in [(7, 5), (1, 4), (3, 1), (2, 2), (1, 1)] {
+            let universe = Universe::random_seeded(width, height, 0.5, 3).unwrap();
+            let offsets = NeighbourOffsets::new(width, height);
+            for row in 0..height {
+                for column in 0..width {
+                    assert_eq!(
+                        torus_neighbour_count(&universe.cells, width, height, row, column),
+                        offsets.count(&universe.cells, row, column),
+                        "({}, {}) of {}x{}", row, column, width, height
+                    );
+                }
+            }
+        }
+    }
+}
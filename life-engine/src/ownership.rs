@@ -0,0 +1,358 @@
+// Optional ownership layer for territory scoring
+//
+// Every live cell can be owned by a colony (owner ids 1..=255, 0 meaning
+// unowned). Newborn cells are attributed to whichever owner holds the most
+// of its alive neighbours, and dead cells lose their owner. The area held by
+// each owner is kept up to date as owners change, and recorded after every
+// tick for the last `set_area_history_capacity()` ticks. Edits leave the
+// cells they touch unowned, apart from `set_owner()`, while undo and
+// `step_back()` put back the owners the cells had
+
+use std::collections::VecDeque;
+
+use crate::lineage::dominant;
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone)]
+pub(crate) struct Ownership {
+    owners: Vec<u8>,
+    // areas[owner] = live cells held by that owner, only owned cells are
+    // alive so this is the number of cells with that owner
+    areas: Vec<u32>,
+    // area_history[tick][owner], oldest first
+    area_history: VecDeque<Vec<u32>>,
+    area_capacity: usize,
+}
+
+// Ticks of areas kept until set_area_history_capacity() says otherwise
+const DEFAULT_AREA_CAPACITY: usize = 1024;
+
+impl Ownership {
+    fn new(size: usize) -> Self {
+        Ownership {
+            owners: vec![0; size],
+            areas: vec![],
+            area_history: VecDeque::new(),
+            area_capacity: DEFAULT_AREA_CAPACITY,
+        }
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let areas = self.area_history.iter().map(Vec::capacity).sum::<usize>() + self.areas.capacity();
+        self.owners.capacity()
+            + self.area_history.capacity() * std::mem::size_of::<Vec<u32>>()
+            + areas * std::mem::size_of::<u32>()
+    }
+
+    // Every owner change goes through here to keep the areas in step
+    pub(crate) fn set(&mut self, idx: usize, owner: u8) {
+        let previous = self.owners[idx];
+        if previous == owner {
+            return
+        }
+        // Owner 0 is unowned rather than a colony
+        if previous != 0 {
+            self.areas[previous as usize] -= 1;
+        }
+        if owner != 0 {
+            if owner as usize >= self.areas.len() {
+                self.areas.resize(owner as usize + 1, 0);
+            }
+            self.areas[owner as usize] += 1;
+        }
+        self.owners[idx] = owner;
+    }
+
+    pub(crate) fn owners(&self) -> &[u8] {
+        &self.owners
+    }
+
+    pub(crate) fn set_owners(&mut self, owners: Vec<u8>) {
+        self.owners = vec![0; owners.len()];
+        self.areas.clear();
+        for (idx, owner) in owners.into_iter().enumerate() {
+            self.set(idx, owner);
+        }
+    }
+
+    // Owners follow their cells when the board is moved around, see
+    // Universe::rearrange
+    pub(crate) fn rearranged(&self, source: &[Option<usize>]) -> Vec<u8> {
+        source.iter().map(|from| from.map_or(0, |from| self.owners[from])).collect()
+    }
+
+    fn set_area_capacity(&mut self, capacity: usize) {
+        self.area_capacity = capacity;
+        while self.area_history.len() > capacity {
+            self.area_history.pop_front();
+        }
+    }
+
+    fn record_areas(&mut self) {
+        if self.area_capacity == 0 {
+            return
+        }
+        if self.area_history.len() == self.area_capacity {
+            self.area_history.pop_front();
+        }
+        self.area_history.push_back(self.areas.clone());
+    }
+}
+
+impl Universe {
+    pub(crate) fn owner_of(&self, idx: usize) -> u8 {
+        self.ownership.as_ref().map_or(0, |ownership| ownership.owners[idx])
+    }
+
+    // Called from write_cell() for edits, which leave the cell unowned
+    pub(crate) fn ownership_edit(&mut self, idx: usize) {
+        if let Some(ownership) = self.ownership.as_mut() {
+            ownership.set(idx, 0);
+        }
+    }
+
+    // For undo, giving a cell brought back to life the owner it had
+    pub(crate) fn restore_owner(&mut self, idx: usize, owner: u8) {
+        if let Some(ownership) = self.ownership.as_mut() {
+            if self.cells[idx] == Cell::Alive {
+                ownership.set(idx, owner);
+            }
+        }
+    }
+
+    // Called from tick() with the cells about to flip, before they are
+    // written, so births see the previous generation's owners
+    pub(crate) fn update_owners(&mut self, changed: &[usize]) {
+        let ownership = match &self.ownership {
+            Some(ownership) => ownership,
+            None => return,
+        };
+
+        let mut new_owners = Vec::with_capacity(changed.len());
+        for &idx in changed {
+            let new_owner = match self.cells[idx] {
+                // Cell is about to die
+                Cell::Alive => 0,
+                // Cell is about to be born, unowned neighbours don't compete
+                Cell::Dead => {
+                    let (neighbours, count) = self.neighbour_indices(idx);
+                    dominant(neighbours[..count].iter()
+                        .filter(|&&neighbour| self.cells[neighbour] == Cell::Alive)
+                        .map(|&neighbour| ownership.owners[neighbour] as u32)) as u8
+                }
+            };
+            new_owners.push(new_owner);
+        }
+
+        let ownership = self.ownership.as_mut().unwrap();
+        for (&idx, new_owner) in changed.iter().zip(new_owners) {
+            ownership.set(idx, new_owner);
+        }
+    }
+
+    // Called at the end of tick() once the new generation is in place
+    pub(crate) fn record_owner_areas(&mut self) {
+        if let Some(ownership) = self.ownership.as_mut() {
+            ownership.record_areas();
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn enable_ownership(&mut self) {
+        if self.ownership.is_none() {
+            self.ownership = Some(Ownership::new(self.cells.len()));
+        }
+    }
+
+    pub fn disable_ownership(&mut self) {
+        self.ownership = None;
+    }
+
+    pub fn ownership_enabled(&self) -> bool {
+        self.ownership.is_some()
+    }
+
+    /// Bring a cell to life owned by `owner`, enabling ownership if needed.
+    /// An owner of 0 leaves the cell alive but unowned
+    pub fn set_owner(&mut self, row: u32, column: u32, owner: u8) {
+        self.enable_ownership();
+
+        let idx = self.get_index(row, column);
+        self.write_cell(idx, Cell::Alive);
+        self.ownership.as_mut().unwrap().set(idx, owner);
+        self.match_claim(idx, owner);
+    }
+
+    pub fn owner_at(&self, row: u32, column: u32) -> u8 {
+        match &self.ownership {
+            Some(ownership) => ownership.owners[self.get_index(row, column)],
+            None => 0,
+        }
+    }
+
+    /// Owner id of every cell, in the same layout as the cell buffer
+    pub fn owners(&self) -> Vec<u8> {
+        match &self.ownership {
+            Some(ownership) => ownership.owners.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Number of live cells currently held by `owner`, 0 for owner 0 since
+    /// unowned cells aren't held by anyone
+    pub fn owner_area(&self, owner: u8) -> u32 {
+        match &self.ownership {
+            Some(ownership) if owner != 0 => ownership.areas.get(owner as usize).cloned().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Keep the areas of the last `capacity` ticks, dropping the oldest ones
+    /// beyond it, enabling ownership if needed. Defaults to 1024
+    pub fn set_area_history_capacity(&mut self, capacity: u32) {
+        self.enable_ownership();
+        self.ownership.as_mut().unwrap().set_area_capacity(capacity as usize);
+    }
+
+    pub fn area_history_capacity(&self) -> u32 {
+        self.ownership.as_ref().map_or(0, |ownership| ownership.area_capacity as u32)
+    }
+
+    /// Area held by `owner` after each of the last ticks kept, oldest first
+    pub fn owner_area_history(&self, owner: u8) -> Vec<u32> {
+        match &self.ownership {
+            Some(ownership) => ownership.area_history.iter()
+                .map(|areas| areas.get(owner as usize).cloned().unwrap_or(0))
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn births_take_dominant_owner() {
+        let mut universe = Universe::new(8, 8, vec![]);
+
+        // Blinker made of two owner 1 cells and one owner 2 cell
+        universe.set_owner(3, 2, 1);
+        universe.set_owner(3, 3, 1);
+        universe.set_owner(3, 4, 2);
+        universe.tick();
+
+        // New vertical cells neighbour two owner 1 cells and one owner 2 cell
+        assert_eq!(1, universe.owner_at(2, 3));
+        assert_eq!(1, universe.owner_at(4, 3));
+        assert_eq!(1, universe.owner_at(3, 3));
+        assert_eq!(0, universe.owner_at(3, 2));
+        assert_eq!(0, universe.owner_at(3, 4));
+
+        assert_eq!(3, universe.owner_area(1));
+        assert_eq!(0, universe.owner_area(2));
+        assert_eq!(vec![3], universe.owner_area_history(1));
+        assert_eq!(vec![0], universe.owner_area_history(2));
+    }
+
+    #[test]
+    fn edits_drop_owners() {
+        let mut universe = Universe::new(8, 8, vec![]);
+        universe.set_undo_capacity(4);
+        universe.set_owner(3, 2, 1);
+        universe.set_owner(3, 3, 1);
+        universe.set_owner(3, 4, 2);
+        universe.clear();
+        assert_eq!(vec![0; 64], universe.owners());
+        universe.toggle_cell(0, 0);
+        assert_eq!(0, universe.owner_at(0, 0));
+        universe.toggle_cell(0, 0);
+
+        // Undoing the clear brings the owners back, redoing it drops them
+        assert!(universe.undo_edit());
+        assert!(universe.undo_edit());
+        assert!(universe.undo_edit());
+        assert_eq!((1, 2), (universe.owner_at(3, 3), universe.owner_at(3, 4)));
+        assert!(universe.redo_edit());
+        assert_eq!(0, universe.owner_at(3, 3));
+        assert!(universe.undo_edit());
+        universe.tick();
+        assert_eq!(vec![3], universe.owner_area_history(1));
+
+        universe.clear();
+        universe.tick();
+        assert_eq!(0, universe.owner_at(2, 3));
+        assert_eq!(vec![3, 0], universe.owner_area_history(1));
+
+        // Undoing a set_owner() and redoing it keeps its owner
+        universe.set_owner(5, 5, 2);
+        assert!(universe.undo_edit());
+        assert!(universe.redo_edit());
+        assert_eq!(2, universe.owner_at(5, 5));
+    }
+
+    #[test]
+    fn step_back_restores_owners() {
+        let mut universe = Universe::new(8, 8, vec![]);
+        universe.set_history_capacity(4);
+        universe.set_owner(3, 2, 1);
+        universe.set_owner(3, 3, 1);
+        universe.set_owner(3, 4, 2);
+        universe.tick();
+        universe.tick();
+        assert_ne!(2, universe.owner_at(3, 4));
+        assert!(universe.step_back());
+        assert!(universe.step_back());
+        assert_eq!((1, 1, 2), (universe.owner_at(3, 2), universe.owner_at(3, 3), universe.owner_at(3, 4)));
+        assert_eq!(0, universe.owner_at(2, 3));
+    }
+
+    #[test]
+    fn area_history_is_bounded() {
+        let mut universe = Universe::new(8, 8, vec![]);
+        universe.set_area_history_capacity(2);
+        universe.set_owner(3, 2, 1);
+        universe.set_owner(3, 3, 1);
+        universe.set_owner(3, 4, 2);
+        assert_eq!((2, 1), (universe.owner_area(1), universe.owner_area(2)));
+        universe.set_owner(0, 0, 3);
+        universe.tick();
+        universe.tick();
+        universe.tick();
+        assert_eq!(vec![3, 3], universe.owner_area_history(1));
+        assert_eq!(vec![0, 0], universe.owner_area_history(3));
+        universe.toggle_cell(3, 3);
+        universe.set_owner(0, 0, 3);
+
+        // The running areas match a count of the owned live cells
+        let owners = universe.owners();
+        let cells = universe.cells.clone();
+        for owner in 1..=3 {
+            let counted = owners.iter().zip(&cells)
+                .filter(|&(&o, &cell)| o == owner && cell == Cell::Alive)
+                .count() as u32;
+            assert_eq!(counted, universe.owner_area(owner));
+        }
+
+        universe.set_area_history_capacity(1);
+        assert_eq!(1, universe.owner_area_history(1).len());
+        universe.set_area_history_capacity(0);
+        universe.tick();
+        assert!(universe.owner_area_history(1).is_empty());
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut universe = Universe::new(4, 4, vec![(1, 1)]);
+        universe.tick();
+        assert!(!universe.ownership_enabled());
+        assert_eq!(vec![0; 16], universe.owners());
+        assert!(universe.owner_area_history(0).is_empty());
+    }
+}
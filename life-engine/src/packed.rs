@@ -0,0 +1,59 @@
+// The cells a bit each, for renderers and network layers short on bandwidth
+//
+// `cells_packed()` gives each row as a run of u32 words, least significant
+// bit first, so column c of a row is bit c % 32 of its word c / 32. Every
+// row starts on a fresh word, `packed_row_words()` of them, with the bits
+// past the width clear. That's a 32nd of the bytes of the cell buffer,
+// ready to hand to `texImage2D` as an integer texture or send over a socket:
+//
+//     const words = universe.cells_packed();
+//     const stride = universe.packed_row_words();
+//     const alive = (row, column) => (words[row * stride + (column >> 5)] >> (column & 31)) & 1;
+//
+// The bits are the cell buffer, like the shared state a worker reads, so
+// under a B0 rule they're the complemented cells (see strobing.rs)
+
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const WORD_BITS: usize = 32;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Words each row takes up in `cells_packed()`
+    pub fn packed_row_words(&self) -> u32 {
+        (self.width as usize).div_ceil(WORD_BITS) as u32
+    }
+
+    /// Every row as bit-packed words, least significant bit first
+    pub fn cells_packed(&self) -> Vec<u32> {
+        let mut words = Vec::with_capacity(self.packed_row_words() as usize * self.height as usize);
+        for row in self.cells.chunks(self.width as usize) {
+            words.extend(row.chunks(WORD_BITS).map(|cells| {
+                cells.iter().enumerate().fold(0, |word, (bit, &cell)| word | (cell as u32) << bit)
+            }));
+        }
+        words
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_each_row() {
+        let universe = Universe::new(40, 3, vec![(0, 0), (0, 31), (0, 32), (1, 39), (2, 5)]);
+        assert_eq!(2, universe.packed_row_words());
+        assert_eq!(vec![1 | 1 << 31, 1, 0, 1 << 7, 1 << 5, 0], universe.cells_packed());
+
+        let universe = Universe::random_seeded(33, 7, 0.5, 3).unwrap();
+        let (words, stride) = (universe.cells_packed(), universe.packed_row_words() as usize);
+        for (row, column) in (0..7).flat_map(|row| (0..33).map(move |column| (row, column))) {
+            let bit = words[row as usize * stride + column as usize / 32] >> (column % 32) & 1;
+            assert_eq!(universe.is_alive(row, column), bit == 1);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Multithreaded tick for big universes, behind the "parallel" experimental
+// flag
+//
+// Each thread works out the changes for a run of the candidate cells, which
+// come in row order, so the result is in the same order as the single
+// threaded loop. Only plain Life on a torus is handled since the other
+// boundaries need more of the universe than the cell buffer.
+//
+// With the `parallel` feature the work goes to rayon's thread pool, which is
+// kept between ticks. Without it native builds spawn a thread per core each
+// tick, and WASM builds stay single threaded. `wasm-threads` runs rayon on
+// Web Workers in the browser, which needs a build with the atomics target
+// feature and a page served cross-origin isolated (for SharedArrayBuffer),
+// and the pool started from JS first:
+//
+//     import init, { initThreadPool, Universe, set_experimental } from "./pkg/life_wasm.js";
+//     await init();
+//     await initThreadPool(navigator.hardwareConcurrency);
+//     set_experimental("parallel", true);
+
+use crate::experimental::experimental_enabled;
+use crate::{Boundary, Universe};
+
+#[cfg(feature = "wasm-threads")]
+pub use wasm_bindgen_rayon::init_thread_pool;
+
+// Below this splitting the work costs more than it saves
+const PARALLEL_MIN_CELLS: usize = 1 << 16;
+
+impl Universe {
+    pub(crate) fn use_parallel(&self, candidates: usize) -> bool {
+        (cfg!(feature = "wasm-threads") || !cfg!(target_arch = "wasm32"))
+            && candidates >= PARALLEL_MIN_CELLS
+            && self.boundary == Boundary::Torus
+            && experimental_enabled("parallel")
+    }
+
+    pub(crate) fn parallel_life_changes(&self, candidates: &[usize]) -> Vec<usize> {
+        let (cells, offsets, width, rule) = (&self.cells[..], &self.offsets, self.width, self.rule);
+        let counts = self.neighbour_counts.current();
+        let flips = move |&idx: &usize| {
+            let (row, column) = (idx as u32 / width, idx as u32 % width);
+            let alive_neighbour_count = match counts {
+                Some(counts) => counts[idx],
+                None => offsets.count(cells, row, column),
+            };
+            rule.next(cells[idx], alive_neighbour_count) != cells[idx]
+        };
+        split_changes(candidates, flips)
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn split_changes(candidates: &[usize], flips: impl Fn(&usize) -> bool + Sync) -> Vec<usize> {
+    use rayon::prelude::*;
+
+    // Collecting keeps the candidates' order
+    candidates.par_iter().with_min_len(PARALLEL_MIN_CELLS / 16).filter(|idx| flips(idx)).cloned().collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn split_changes(candidates: &[usize], flips: impl Fn(&usize) -> bool + Copy + Send) -> Vec<usize> {
+    let threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = candidates.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        let workers = candidates.chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().filter(|idx| flips(idx)).cloned().collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+
+        workers.into_iter()
+            .flat_map(|worker| worker.join().expect("tick worker panicked"))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Universe;
+
+    #[test]
+    fn matches_single_threaded() {
+        let mut universe = Universe::random_seeded(96, 96, 0.35, 11).unwrap();
+        let all = (0..universe.cells.len()).collect::<Vec<_>>();
+        for _ in 0..3 {
+            let parallel = universe.parallel_life_changes(&all);
+            universe.active_cells = None;
+            assert_eq!(universe.life_changes(), parallel);
+            universe.tick();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Rolling throughput figures, for an FPS overlay or tuning the speed
+//
+// Every tick is timed with `performance.now()` in the browser (`Instant`
+// natively) and counted into ten 100ms buckets, so `perf_stats()` can give
+// the generations per second and average tick time over the last second
+// without keeping a sample per tick. It also gives the cells changed since
+// it was last called, so calling it once a frame gives each frame's changes:
+//
+//     const stats = universe.perf_stats();
+//     overlay.textContent = `${stats.generations_per_second.toFixed(0)} gen/s`;
+//     // Keep the ticks to about half of a 60Hz frame
+//     if (stats.average_tick_ms > 0) speed = Math.max(1, Math.floor(8 / stats.average_tick_ms));
+//
+// Unlike the phase timings in profile.rs this is always on, it costs two
+// clock reads a tick
+
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const BUCKET_MS: f64 = 100.0;
+const BUCKETS: usize = 10;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PerfStats {
+    /// Ticks per second over the last second
+    pub generations_per_second: f64,
+    /// Average time a tick took over the last second
+    pub average_tick_ms: f64,
+    /// Cells changed by ticks since the last `perf_stats()`
+    pub frame_changes: u32,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Bucket {
+    // Which 100ms since the clock started this is counting
+    slot: i64,
+    ticks: u32,
+    tick_ms: f64,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct PerfCounter {
+    buckets: [Bucket; BUCKETS],
+    // When the first tick ended, so the rate isn't spread over a whole
+    // second straight after starting
+    first_ms: Option<f64>,
+    frame_changes: u32,
+}
+
+fn slot(time_ms: f64) -> i64 {
+    (time_ms / BUCKET_MS).floor() as i64
+}
+
+impl PerfCounter {
+    pub(crate) fn record(&mut self, start_ms: f64, end_ms: f64, changed: usize) {
+        let slot = slot(end_ms);
+        let bucket = &mut self.buckets[slot.rem_euclid(BUCKETS as i64) as usize];
+        if bucket.slot != slot {
+            *bucket = Bucket { slot, ..Bucket::default() };
+        }
+        bucket.ticks += 1;
+        bucket.tick_ms += end_ms - start_ms;
+        self.first_ms.get_or_insert(end_ms);
+        self.frame_changes = self.frame_changes.saturating_add(changed as u32);
+    }
+
+    // Figures for the second up to `now_ms`, starting a new frame
+    pub(crate) fn stats(&mut self, now_ms: f64) -> PerfStats {
+        let current = slot(now_ms);
+        let recent = self.buckets.iter().filter(|bucket| bucket.slot > current - BUCKETS as i64 && bucket.slot <= current);
+        let (ticks, tick_ms) = recent.fold((0, 0.0), |(ticks, tick_ms), bucket| (ticks + bucket.ticks, tick_ms + bucket.tick_ms));
+        // From the start of the oldest bucket counted, or the first tick
+        let oldest_ms = (current - BUCKETS as i64 + 1) as f64 * BUCKET_MS;
+        let span_ms = self.first_ms.map_or(0.0, |first| now_ms - first.max(oldest_ms));
+
+        PerfStats {
+            generations_per_second: if span_ms > 0.0 { ticks as f64 * 1000.0 / span_ms } else { 0.0 },
+            average_tick_ms: if ticks > 0 { tick_ms / ticks as f64 } else { 0.0 },
+            frame_changes: std::mem::take(&mut self.frame_changes),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Rolling generations per second and tick time over the last second,
+    /// and the cells changed since the last call. Call once a frame
+    pub fn perf_stats(&mut self) -> PerfStats {
+        self.perf.stats(crate::utils::now_ms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_over_the_last_second() {
+        let mut counter = PerfCounter::default();
+        assert_eq!(PerfStats::default(), counter.stats(50.0));
+
+        // A 2ms tick every 20ms for two seconds, changing 10 cells each
+        for tick in 0..100 {
+            let start = 1000.0 + tick as f64 * 20.0;
+            counter.record(start, start + 2.0, 10);
+        }
+        let stats = counter.stats(3000.0);
+        assert!((stats.generations_per_second - 50.0).abs() < 3.0, "{}", stats.generations_per_second);
+        assert!((stats.average_tick_ms - 2.0).abs() < 1e-9);
+        assert_eq!(1000, stats.frame_changes);
+        assert_eq!(0, counter.stats(3000.0).frame_changes);
+
+        // Paused for half a second, then stopped for good
+        let rate = counter.stats(3500.0).generations_per_second;
+        assert!(rate > 20.0 && rate < 30.0, "{}", rate);
+        assert_eq!(PerfStats::default(), counter.stats(4100.0));
+    }
+
+    #[test]
+    fn rate_right_after_starting() {
+        let mut counter = PerfCounter::default();
+        for tick in 0..5 {
+            let start = 500.0 + tick as f64 * 10.0;
+            counter.record(start, start + 1.0, 0);
+        }
+        // Five ticks in the 50ms since the first ended, not in a second
+        assert!((counter.stats(551.0).generations_per_second - 100.0).abs() < 1e-9);
+
+        let mut universe = Universe::new(16, 16, vec![(1, 2), (2, 2), (3, 2)]);
+        universe.tick();
+        universe.tick();
+        let stats = universe.perf_stats();
+        assert_eq!(8, stats.frame_changes);
+        assert!(stats.average_tick_ms >= 0.0 && stats.generations_per_second >= 0.0);
+    }
+}
@@ -0,0 +1,92 @@
+// Live cells counted along each row and down each column, and over blocks
+//
+// The two marginals of the board: plotted along its edges they show where
+// the population sits, and fed to an oscillator bank per row they turn the
+// board into sound. Both reach JS as Uint32Arrays, counted fresh on each
+// call in one pass over the cells.
+//
+// `density_map(block_size)` shrinks the board for a minimap instead, one
+// byte per square block of cells from 0 for empty to 255 for full. Blocks
+// are laid out row by row, `ceil(width / block_size)` to a row, and blocks
+// cut short by the right or bottom edge are scaled by the cells they have
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Live cells in each row, top to bottom
+    pub fn row_populations(&self) -> Vec<u32> {
+        self.cells.chunks(self.width as usize)
+            .map(|row| row.iter().filter(|&&cell| cell == Cell::Alive).count() as u32)
+            .collect()
+    }
+
+    /// Live cells in each column, left to right
+    pub fn column_populations(&self) -> Vec<u32> {
+        let mut counts = vec![0; self.width as usize];
+        for row in self.cells.chunks(self.width as usize) {
+            for (count, &cell) in counts.iter_mut().zip(row) {
+                *count += cell as u32;
+            }
+        }
+        counts
+    }
+
+    /// Live cell density of each `block_size` square block, scaled to
+    /// 0..=255, row by row. A block size of 0 counts as 1
+    pub fn density_map(&self, block_size: u32) -> Vec<u8> {
+        let block_size = block_size.max(1) as usize;
+        let (width, height) = (self.width as usize, self.height as usize);
+        let columns = width.div_ceil(block_size);
+
+        let mut counts = vec![0u32; columns * height.div_ceil(block_size)];
+        for (row, cells) in self.cells.chunks(width).enumerate() {
+            let blocks = &mut counts[row / block_size * columns..][..columns];
+            for (block, cells) in blocks.iter_mut().zip(cells.chunks(block_size)) {
+                *block += cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+            }
+        }
+        counts.iter().enumerate()
+            .map(|(block, &count)| {
+                let rows = block_size.min(height - block / columns * block_size);
+                let columns = block_size.min(width - block % columns * block_size);
+                let area = (rows * columns) as u64;
+                ((count as u64 * 255 + area / 2) / area) as u8
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_rows_and_columns() {
+        let mut universe: Universe = "\
+            .#..\n\
+            ..#.\n\
+            ###.\n".parse().unwrap();
+        assert_eq!(vec![1, 1, 3], universe.row_populations());
+        assert_eq!(vec![1, 2, 2, 0], universe.column_populations());
+
+        universe.clear();
+        assert_eq!(vec![0; 3], universe.row_populations());
+        assert_eq!(universe.population(), universe.column_populations().iter().sum::<u32>());
+    }
+
+    #[test]
+    fn density_blocks() {
+        // A full 2x2 block, a quarter full one and the bottom row cut short
+        let universe: Universe = "\
+            ##.#.\n\
+            ##...\n\
+            ....#\n".parse().unwrap();
+        assert_eq!(vec![255, 64, 0, 0, 0, 255], universe.density_map(2));
+        assert_eq!(vec![0, 255, 0, 255], Universe::new(2, 2, vec![(0, 1), (1, 1)]).density_map(0));
+        assert_eq!(vec![102], universe.density_map(10));
+    }
+}
@@ -0,0 +1,253 @@
+// Searching backwards for a generation that ticks into the current one
+//
+// Finding a predecessor is a constraint problem: every cell of the current
+// board says its previous 3x3 neighbourhood must have given it its state.
+// The search assigns the previous generation a cell at a time in reading
+// order, dead first, and backtracks as soon as some cell can no longer be
+// satisfied. With a totalistic rule a cell is checked from the neighbours
+// known so far, as there has to be a count between those alive and those
+// which might still be that gives the right state; rules with Hensel letters
+// can only check a cell once its whole neighbourhood is known.
+//
+// Working in reading order, a cell's constraint is settled shortly after
+// it's passed, so dead ends are found while they're still cheap to undo.
+// Even so the search is exponential in the worst case, so it's bounded by
+// how many cell assignments it may try. Running out of them says nothing
+// either way, while finishing without a predecessor proves the board is a
+// Garden of Eden under its rule and boundary.
+//
+// Only the Life-like rule is searched. A universe running a rule table,
+// block rule or transition function gives up straight away
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub enum PredecessorSearch {
+    /// A universe that becomes this one on its next tick
+    Found(Box<Universe>),
+    /// No predecessor exists
+    GardenOfEden,
+    /// The search ran out of candidates before finishing
+    GaveUp,
+}
+
+struct Search<'a> {
+    universe: &'a Universe,
+    // The previous generation so far, unknown cells held dead
+    cells: Vec<Cell>,
+    known: Vec<bool>,
+    // Per current cell, live and unknown neighbours in the previous
+    // generation
+    alive: Vec<u8>,
+    unknown: Vec<u8>,
+    // Current cells whose constraint each previous cell appears in, and
+    // whether as the centre. A neighbour appears once for each time it's
+    // counted, which on tiny boards can be more than once
+    watchers: Vec<Vec<(usize, bool)>>,
+    // allowed[target][centre][count]: whether a centre cell with `count`
+    // live neighbours becomes target
+    allowed: [[[bool; 9]; 2]; 2],
+    totalistic: bool,
+}
+
+impl<'a> Search<'a> {
+    fn new(universe: &'a Universe) -> Search<'a> {
+        let size = universe.cells.len();
+        let mut watchers = vec![vec![]; size];
+        let mut unknown = Vec::with_capacity(size);
+        for (idx, watcher) in watchers.iter_mut().enumerate() {
+            watcher.push((idx, true));
+        }
+        for idx in 0..size {
+            let (neighbours, count) = universe.neighbour_indices(idx);
+            for &neighbour in &neighbours[..count] {
+                watchers[neighbour].push((idx, false));
+            }
+            unknown.push(count as u8);
+        }
+
+        let mut allowed = [[[false; 9]; 2]; 2];
+        for centre in [Cell::Dead, Cell::Alive] {
+            for count in 0..=8 {
+                let target = universe.rule.next(centre, count);
+                allowed[target as usize][centre as usize][count as usize] = true;
+            }
+        }
+
+        Search {
+            universe,
+            cells: vec![Cell::Dead; size],
+            known: vec![false; size],
+            alive: vec![0; size],
+            unknown,
+            watchers,
+            allowed,
+            totalistic: universe.rule.is_totalistic(),
+        }
+    }
+
+    // Whether the current cell `idx` can still get its state
+    fn satisfiable(&self, idx: usize) -> bool {
+        let target = self.universe.cells[idx];
+        if !self.totalistic {
+            if self.unknown[idx] > 0 || !self.known[idx] {
+                return true;
+            }
+            let width = self.universe.width;
+            let neighbourhood = self.universe.neighbourhood(&self.cells, idx as u32 / width, idx as u32 % width);
+            return self.universe.rule.next_neighbourhood(self.cells[idx], neighbourhood) == target;
+        }
+
+        let centres: &[Cell] = if self.known[idx] { &[self.cells[idx]][..] } else { &[Cell::Dead, Cell::Alive] };
+        let (alive, unknown) = (self.alive[idx] as usize, self.unknown[idx] as usize);
+        centres.iter().any(|&centre| {
+            self.allowed[target as usize][centre as usize][alive..=alive + unknown].contains(&true)
+        })
+    }
+
+    // Fix the previous state of `idx`, returning whether every constraint
+    // it's part of can still be met
+    fn assign(&mut self, idx: usize, cell: Cell) -> bool {
+        self.cells[idx] = cell;
+        self.known[idx] = true;
+        for &(watcher, centre) in &self.watchers[idx] {
+            if !centre {
+                self.unknown[watcher] -= 1;
+                self.alive[watcher] += cell as u8;
+            }
+        }
+        self.watchers[idx].iter().all(|&(watcher, _)| self.satisfiable(watcher))
+    }
+
+    fn unassign(&mut self, idx: usize) {
+        for &(watcher, centre) in &self.watchers[idx] {
+            if !centre {
+                self.unknown[watcher] += 1;
+                self.alive[watcher] -= self.cells[idx] as u8;
+            }
+        }
+        self.cells[idx] = Cell::Dead;
+        self.known[idx] = false;
+    }
+
+    fn run(mut self, max_candidates: u32) -> PredecessorSearch {
+        let size = self.cells.len();
+        if !(0..size).all(|idx| self.satisfiable(idx)) {
+            return PredecessorSearch::GardenOfEden;
+        }
+
+        // Values tried so far for the cell at each depth
+        let mut tried = vec![0u8; size];
+        let mut depth = 0;
+        let mut candidates = 0;
+        while depth < size {
+            if self.known[depth] {
+                self.unassign(depth);
+            }
+            if tried[depth] == 2 {
+                tried[depth] = 0;
+                if depth == 0 {
+                    return PredecessorSearch::GardenOfEden;
+                }
+                depth -= 1;
+                continue;
+            }
+            if candidates == max_candidates {
+                return PredecessorSearch::GaveUp;
+            }
+            candidates += 1;
+
+            let cell = if tried[depth] == 0 { Cell::Dead } else { Cell::Alive };
+            tried[depth] += 1;
+            if self.assign(depth, cell) {
+                depth += 1;
+            }
+        }
+
+        let mut predecessor = Universe::from_cells(self.universe.width, self.universe.height, self.cells);
+        predecessor.rule = self.universe.rule;
+        predecessor.boundary = self.universe.boundary;
+        PredecessorSearch::Found(Box::new(predecessor))
+    }
+}
+
+impl Universe {
+    /// Look for a generation that becomes this one, trying at most
+    /// `max_candidates` cell assignments
+    pub fn search_predecessor(&self, max_candidates: u32) -> PredecessorSearch {
+        if self.rule_table.is_some() || self.margolus.is_some() || self.transition.is_some() {
+            return PredecessorSearch::GaveUp;
+        }
+        Search::new(self).run(max_candidates)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// A universe, with the same rule and boundary, that ticks into this
+    /// one, if `search_predecessor()` finds one within `max_candidates`
+    /// cell assignments
+    pub fn find_predecessor(&self, max_candidates: u32) -> Option<Universe> {
+        match self.search_predecessor(max_candidates) {
+            PredecessorSearch::Found(predecessor) => Some(*predecessor),
+            PredecessorSearch::GardenOfEden | PredecessorSearch::GaveUp => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boundary;
+
+    fn check(universe: &Universe, max_candidates: u32) -> Option<bool> {
+        match universe.search_predecessor(max_candidates) {
+            PredecessorSearch::Found(mut predecessor) => {
+                predecessor.tick();
+                assert_eq!(universe.cells, predecessor.cells);
+                Some(true)
+            }
+            PredecessorSearch::GardenOfEden => Some(false),
+            PredecessorSearch::GaveUp => None,
+        }
+    }
+
+    #[test]
+    fn agrees_with_every_board() {
+        // Every 4x4 torus is ticked once, so the boards that are reached are
+        // exactly those with a predecessor
+        let mut reached = vec![false; 1 << 16];
+        for state in 0..1u32 << 16 {
+            let cells = (0..16).map(|bit| if state >> bit & 1 == 1 { Cell::Alive } else { Cell::Dead }).collect();
+            let mut universe = Universe::from_cells(4, 4, cells);
+            universe.tick();
+            let next = universe.cells.iter().enumerate().fold(0, |next, (bit, &cell)| next | (cell as usize) << bit);
+            reached[next] = true;
+        }
+
+        for state in (0..1 << 16).step_by(97) {
+            let cells = (0..16).map(|bit| if state >> bit & 1 == 1 { Cell::Alive } else { Cell::Dead }).collect();
+            let universe = Universe::from_cells(4, 4, cells);
+            assert_eq!(Some(reached[state]), check(&universe, u32::MAX), "{:016b}", state);
+        }
+    }
+
+    #[test]
+    fn other_rules_and_boundaries() {
+        let mut universe: Universe = "......\n..##..\n.#..#.\n..##..\n......\n......\n".parse().unwrap();
+        assert_eq!(Some(true), check(&universe, 100_000));
+
+        universe.set_boundary(Boundary::Dead);
+        assert_eq!(Some(true), check(&universe, 100_000));
+
+        universe.set_life_rule("B2-a/S12".parse().unwrap());
+        assert_eq!(Some(true), check(&universe, 100_000));
+
+        // Five assignments don't get far into 64 cells
+        let universe = Universe::new(8, 8, vec![(3, 3)]);
+        assert_eq!(None, check(&universe, 5));
+        assert_eq!(Some(true), check(&universe, 100_000));
+    }
+}
@@ -0,0 +1,146 @@
+// How long each phase of tick() took, for tracking down slow ticks
+//
+// Built with the `profile` feature, every tick times its phases and keeps
+// them for `last_tick_profile()`:
+//
+//   - count, working out which cells change, mostly neighbour counting
+//   - transform, probabilities, walls, old age and the owner, lineage and
+//     statistics bookkeeping on the changes
+//   - swap, flipping the changed cells in the buffer
+//   - deltas, everything recorded from the changes afterwards: history,
+//     the heatmap, journals, events and the rest
+//
+// In the browser each phase is also wrapped in `console.time()` and
+// `console.timeEnd()` as "life tick: <phase>", so the phases show up in
+// the console and on the devtools performance timeline. Without the
+// feature the timer compiles away and `last_tick_profile()` is always None
+
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TickProfile {
+    pub count_ms: f64,
+    pub transform_ms: f64,
+    pub swap_ms: f64,
+    pub deltas_ms: f64,
+    /// The whole tick, a little more than the phases added up
+    pub total_ms: f64,
+    /// Cells that changed state
+    pub changed: u32,
+}
+
+#[cfg(feature = "profile")]
+const PHASES: [&str; 4] = ["life tick: count", "life tick: transform", "life tick: swap", "life tick: deltas"];
+
+#[cfg(all(feature = "profile", target_arch = "wasm32", feature = "wasm"))]
+fn console_timer(method: &str, label: &str) {
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::JsCast;
+
+    let console = match Reflect::get(&js_sys::global(), &"console".into()) {
+        Ok(console) => console,
+        Err(_) => return,
+    };
+    let function = Reflect::get(&console, &method.into()).ok().and_then(|f| f.dyn_into::<Function>().ok());
+    if let Some(function) = function {
+        let _ = function.call1(&console, &JsValue::from_str(label));
+    }
+}
+
+#[cfg(all(feature = "profile", not(all(target_arch = "wasm32", feature = "wasm"))))]
+fn console_timer(_method: &str, _label: &str) {}
+
+// Times the phases of one tick in order, a phase ending as the next begins
+#[cfg(feature = "profile")]
+pub(crate) struct Profiler {
+    start: f64,
+    mark: f64,
+    phases: [f64; PHASES.len()],
+    next: usize,
+}
+
+#[cfg(feature = "profile")]
+impl Profiler {
+    pub(crate) fn start() -> Profiler {
+        console_timer("time", PHASES[0]);
+        let start = crate::utils::now_ms();
+        Profiler { start, mark: start, phases: [0.0; PHASES.len()], next: 0 }
+    }
+
+    pub(crate) fn end_phase(&mut self) {
+        let now = crate::utils::now_ms();
+        console_timer("timeEnd", PHASES[self.next]);
+        self.phases[self.next] = now - self.mark;
+        self.mark = now;
+        self.next += 1;
+        if self.next < PHASES.len() {
+            console_timer("time", PHASES[self.next]);
+        }
+    }
+
+    pub(crate) fn finish(self, changed: usize) -> Option<TickProfile> {
+        let [count_ms, transform_ms, swap_ms, deltas_ms] = self.phases;
+        Some(TickProfile {
+            count_ms,
+            transform_ms,
+            swap_ms,
+            deltas_ms,
+            total_ms: crate::utils::now_ms() - self.start,
+            changed: changed as u32,
+        })
+    }
+}
+
+#[cfg(not(feature = "profile"))]
+pub(crate) struct Profiler;
+
+#[cfg(not(feature = "profile"))]
+impl Profiler {
+    #[inline(always)]
+    pub(crate) fn start() -> Profiler {
+        Profiler
+    }
+
+    #[inline(always)]
+    pub(crate) fn end_phase(&mut self) {}
+
+    #[inline(always)]
+    pub(crate) fn finish(self, _changed: usize) -> Option<TickProfile> {
+        None
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Phase timings of the last tick, None before the first tick or when
+    /// built without the `profile` feature
+    pub fn last_tick_profile(&self) -> Option<TickProfile> {
+        self.tick_profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_ticks() {
+        let mut universe = Universe::new(64, 64, vec![(1, 2), (2, 2), (3, 2)]);
+        assert!(universe.last_tick_profile().is_none());
+        universe.tick();
+
+        let profile = universe.last_tick_profile();
+        if cfg!(feature = "profile") {
+            let profile = profile.unwrap();
+            assert_eq!(4, profile.changed);
+            let phases = profile.count_ms + profile.transform_ms + profile.swap_ms + profile.deltas_ms;
+            assert!(phases >= 0.0 && phases <= profile.total_ms);
+        } else {
+            assert!(profile.is_none());
+        }
+    }
+}
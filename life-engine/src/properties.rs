@@ -0,0 +1,116 @@
+// Invariants checked over lots of random universes, in particular
+// rectangular ones of every shape since width and height were once mixed up
+//
+// Cases come from proptest strategies for the size, density and boundary,
+// with the board drawn cell by cell at that density, so a failure is shrunk
+// down to the smallest board (and fewest live cells) that still shows it
+
+use crate::{Boundary, Cell, TickStrategy, Universe};
+
+use proptest::prelude::*;
+
+const BOUNDARIES: [Boundary; 6] = [
+    Boundary::Torus,
+    Boundary::Dead,
+    Boundary::Mirror,
+    Boundary::KleinBottle,
+    Boundary::CrossSurface,
+    Boundary::Sphere,
+];
+
+#[derive(Clone, Debug)]
+struct Case {
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    cells: Vec<Cell>,
+}
+
+impl Case {
+    fn universe(&self) -> Universe {
+        let mut universe = Universe::from_cells(self.width, self.height, self.cells.clone());
+        universe.set_boundary(self.boundary);
+        universe
+    }
+}
+
+// Skewed towards small sizes, where the edge cases are
+fn size() -> impl Strategy<Value = u32> {
+    prop_oneof![1..=4u32, 1..=40u32]
+}
+
+fn case() -> impl Strategy<Value = Case> {
+    (size(), size(), 0.0..1.0f64, proptest::sample::select(BOUNDARIES.to_vec())).prop_flat_map(
+        |(width, height, density, boundary)| {
+            let cell = proptest::bool::weighted(density).prop_map(|alive| if alive { Cell::Alive } else { Cell::Dead });
+            proptest::collection::vec(cell, (width * height) as usize)
+                .prop_map(move |cells| Case { width, height, boundary, cells })
+        },
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn index_round_trips(case in case()) {
+        let universe = case.universe();
+        prop_assert_eq!((case.width * case.height) as usize, universe.cells.len());
+        for row in 0..case.height {
+            for column in 0..case.width {
+                let idx = universe.get_index(row, column);
+                prop_assert!(idx < universe.cells.len());
+                prop_assert_eq!((row, column), (idx as u32 / case.width, idx as u32 % case.width));
+            }
+        }
+    }
+
+    #[test]
+    fn neighbour_counts_are_bounded(case in case()) {
+        let universe = case.universe();
+        for row in 0..case.height {
+            for column in 0..case.width {
+                let count = universe.live_neighbours(row, column);
+                prop_assert!(count <= 8, "({}, {}) has {} neighbours", row, column, count);
+                if case.boundary == Boundary::Dead {
+                    let (neighbours, slots) = universe.neighbour_indices(universe.get_index(row, column));
+                    let alive = neighbours[..slots].iter().filter(|&&n| universe.cells[n] == Cell::Alive).count();
+                    prop_assert_eq!(alive as u8, count);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn population_is_accounted_for(case in case()) {
+        let mut universe = case.universe();
+        for _ in 0..8 {
+            let before = universe.population();
+            universe.tick();
+            let counted = universe.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+            prop_assert_eq!(counted, universe.population());
+            prop_assert_eq!(before + universe.births_last_tick() - universe.deaths_last_tick(), universe.population());
+        }
+    }
+
+    #[test]
+    fn sparse_and_dense_ticks_agree(case in case()) {
+        let mut sparse = case.universe();
+        let mut dense = case.universe();
+        sparse.set_tick_strategy(TickStrategy::Sparse);
+        dense.set_tick_strategy(TickStrategy::Dense);
+        for generation in 1..=8 {
+            sparse.tick();
+            dense.tick();
+            prop_assert_eq!(&dense.cells, &sparse.cells, "at generation {}", generation);
+        }
+    }
+
+    #[test]
+    fn render_has_universe_shape(case in case()) {
+        let rendered = case.universe().render();
+        let lines = rendered.lines().collect::<Vec<_>>();
+        prop_assert_eq!(case.height as usize, lines.len());
+        prop_assert!(lines.iter().all(|line| line.chars().count() == case.width as usize));
+    }
+}
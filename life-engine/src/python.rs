@@ -0,0 +1,253 @@
+// Python bindings, for scripting the engine from notebooks
+//
+// The `life` extension module built from life-python wraps `Universe` in a
+// class of the same name. Cells come out as bytes, one per cell row by row,
+// which numpy reads without copying:
+//
+//     import numpy as np
+//     from life import Universe
+//
+//     universe = Universe(64, 64, density=0.3, seed=1)
+//     universe.tick(100)
+//     grid = np.frombuffer(universe.cells(), np.uint8).reshape(universe.shape)
+//
+// and any contiguous buffer of 0s and 1s (a uint8 array, bytes, bytearray)
+// can be written back with `set_cells()`. Patterns go in and out as the
+// text grids `render()` prints, snapshots, JSON and saved states. The class
+// is tied to the thread which made it, as a universe isn't Send
+
+use crate::{Cell, Rule, Universe};
+
+use pyo3::buffer::PyBuffer;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+fn value_error(err: impl ToString) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+#[pyclass(name = "Universe", module = "life", unsendable)]
+pub struct PyUniverse {
+    universe: Universe,
+}
+
+impl PyUniverse {
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+}
+
+impl From<Universe> for PyUniverse {
+    fn from(universe: Universe) -> PyUniverse {
+        PyUniverse { universe }
+    }
+}
+
+#[pymethods]
+impl PyUniverse {
+    /// A universe with each cell alive with probability `density`, from
+    /// `seed` if one is given. Raises ValueError for an invalid size
+    #[new]
+    #[pyo3(signature = (width, height, density = 0.0, seed = None))]
+    fn new(width: u32, height: u32, density: f32, seed: Option<u64>) -> PyResult<PyUniverse> {
+        let universe = match seed {
+            Some(seed) => Universe::random_seeded(width, height, density, seed),
+            None => Universe::random(width, height, density),
+        };
+        universe.map(PyUniverse::from).map_err(value_error)
+    }
+
+    /// A universe from grid text like `render()` produces, or a plaintext
+    /// pattern
+    #[staticmethod]
+    fn from_text(text: &str) -> PyResult<PyUniverse> {
+        text.parse::<Universe>().map(PyUniverse::from).map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn from_snapshot(data: &[u8]) -> PyResult<PyUniverse> {
+        Universe::decode_snapshot(data).map(PyUniverse::from).map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<PyUniverse> {
+        Universe::from_json_str(json).map(PyUniverse::from).map_err(value_error)
+    }
+
+    /// Restore the full state `save()` returned
+    #[staticmethod]
+    fn load(saved: &str) -> PyResult<PyUniverse> {
+        Universe::load_str(saved).map(PyUniverse::from).map_err(value_error)
+    }
+
+    #[getter]
+    fn width(&self) -> u32 {
+        self.universe.width()
+    }
+
+    #[getter]
+    fn height(&self) -> u32 {
+        self.universe.height()
+    }
+
+    /// (height, width), for reshaping `cells()`
+    #[getter]
+    fn shape(&self) -> (u32, u32) {
+        (self.universe.height(), self.universe.width())
+    }
+
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.universe.generation()
+    }
+
+    #[getter]
+    fn population(&self) -> u32 {
+        self.universe.population()
+    }
+
+    /// The B/S rule, e.g. "B3/S23". Setting it raises ValueError for a rule
+    /// which can't be parsed
+    #[getter]
+    fn rule(&self) -> String {
+        self.universe.rule.to_string()
+    }
+
+    #[setter]
+    fn set_rule(&mut self, rule: &str) -> PyResult<()> {
+        let rule = rule.parse::<Rule>().map_err(value_error)?;
+        self.universe.set_life_rule(rule);
+        Ok(())
+    }
+
+    #[pyo3(signature = (generations = 1))]
+    fn tick(&mut self, generations: u32) {
+        for _ in 0..generations {
+            self.universe.tick();
+        }
+    }
+
+    fn is_alive(&self, row: u32, column: u32) -> PyResult<bool> {
+        self.check_bounds(row, column)?;
+        Ok(self.universe.is_alive(row, column))
+    }
+
+    fn toggle_cell(&mut self, row: u32, column: u32) -> PyResult<()> {
+        self.check_bounds(row, column)?;
+        self.universe.toggle_cell(row, column);
+        Ok(())
+    }
+
+    /// Every cell, one byte each (1 alive, 0 dead), row by row
+    fn cells<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        let cells = self.universe.cells.iter().map(|&cell| cell as u8).collect::<Vec<_>>();
+        PyBytes::new(py, &cells)
+    }
+
+    /// Overwrite every cell from a buffer laid out like `cells()`, where any
+    /// non-zero byte is alive. This is one edit, so it can be undone
+    fn set_cells(&mut self, py: Python, buffer: PyBuffer<u8>) -> PyResult<()> {
+        let cells = buffer.to_vec(py)?;
+        if cells.len() != self.universe.cells.len() {
+            let message = format!("expected {} cells but got {}", self.universe.cells.len(), cells.len());
+            return Err(PyValueError::new_err(message));
+        }
+
+        self.universe.edit_group(|universe| {
+            for (idx, &cell) in cells.iter().enumerate() {
+                universe.write_cell(idx, if cell == 0 { Cell::Dead } else { Cell::Alive });
+            }
+        });
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.universe.clear();
+    }
+
+    fn render(&self) -> String {
+        self.universe.render()
+    }
+
+    fn to_snapshot<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.universe.to_snapshot())
+    }
+
+    fn to_json(&self) -> String {
+        self.universe.to_json()
+    }
+
+    fn to_svg(&self, cell_size: u32, alive_color: &str, dead_color: &str) -> String {
+        self.universe.to_svg(cell_size, alive_color, dead_color)
+    }
+
+    fn save(&self) -> String {
+        self.universe.save()
+    }
+
+    fn __str__(&self) -> String {
+        self.universe.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Universe(width={}, height={}, generation={}, population={})",
+            self.universe.width(), self.universe.height(), self.universe.generation(), self.universe.population()
+        )
+    }
+}
+
+impl PyUniverse {
+    fn check_bounds(&self, row: u32, column: u32) -> PyResult<()> {
+        if row >= self.universe.height() || column >= self.universe.width() {
+            let message = format!("cell ({}, {}) is outside the universe", row, column);
+            return Err(PyValueError::new_err(message));
+        }
+        Ok(())
+    }
+}
+
+/// Add the classes to a Python module, see life-python
+pub fn register_python(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_class::<PyUniverse>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+    use std::ffi::CString;
+
+    #[test]
+    fn scripted_from_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "life").unwrap();
+            register_python(&module).unwrap();
+            let globals = PyDict::new(py);
+            globals.set_item("Universe", module.getattr("Universe").unwrap()).unwrap();
+
+            let run = |code: &str| py.run(&CString::new(code).unwrap(), Some(&globals), None);
+            run("universe = Universe.from_text('.....\\n..#..\\n..#..\\n..#..\\n.....\\n')\n\
+                 universe.tick(3)\n\
+                 cells = universe.cells()\n\
+                 universe.set_cells(bytearray(cells))\n\
+                 universe.rule = 'B36/S23'").unwrap();
+            let universe = globals.get_item("universe").unwrap().unwrap();
+            let universe = universe.downcast::<PyUniverse>().unwrap().borrow();
+            assert_eq!(3, universe.universe().generation());
+            let cells = universe.cells(py);
+            assert_eq!(vec![11, 12, 13], (0..25).filter(|&idx| cells.as_bytes()[idx] == 1).collect::<Vec<_>>());
+            assert_eq!("B36/S23", universe.rule());
+            drop(universe);
+
+            let err = run("universe.rule = 'X'").unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            let err = run("universe.set_cells(b'\\x01')").unwrap_err();
+            assert_eq!("ValueError: expected 25 cells but got 1", err.to_string());
+            let err = run("Universe(0, 4)").unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
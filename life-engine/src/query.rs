@@ -0,0 +1,432 @@
+// A tiny query language for poking at a universe from an analysis console
+//
+//     count alive                          live cells in the whole universe
+//     count dead in (10,10)-(30,40)        dead cells in a region
+//     bbox                                 bounding box of the live cells
+//     bbox in (0,0)-(15,15)
+//     find .#./..#/### in (0,0)-(40,40)    top left corner of every exact
+//                                          match of a pattern
+//
+// Regions are inclusive (row,column)-(row,column) corners and several can be
+// joined with `+`. Patterns are rows of `#`/`o` (alive) and `.`/`b` (dead)
+// separated by `/`. Rust code can skip the text and build a `Query` directly,
+// or use `find_pattern()` with a universe as the pattern, which can also
+// look for it in every orientation
+
+use crate::census::orient;
+use crate::error::LifeError;
+use crate::{Cell, Rect, Region, Universe};
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query {
+    Count { state: Cell, region: Option<Region> },
+    BoundingBox { region: Option<Region> },
+    Find { pattern: Vec<Vec<Cell>>, region: Option<Region> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryResult {
+    Count(u32),
+    BoundingBox(Option<Rect>),
+    Matches(Vec<(u32, u32)>),
+}
+
+impl fmt::Display for QueryResult {
+    // JSON, which is easy to consume from JS
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryResult::Count(count) => write!(f, "{{\"count\":{}}}", count),
+            QueryResult::BoundingBox(None) => write!(f, "{{\"bbox\":null}}"),
+            QueryResult::BoundingBox(Some(rect)) => write!(
+                f, "{{\"bbox\":{{\"top\":{},\"left\":{},\"rows\":{},\"columns\":{}}}}}",
+                rect.top, rect.left, rect.rows, rect.columns
+            ),
+            QueryResult::Matches(matches) => {
+                write!(f, "{{\"matches\":[")?;
+                for (i, (row, column)) in matches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "[{},{}]", row, column)?;
+                }
+                write!(f, "]}}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueryError {
+    /// Byte offset into the query text
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "query error at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, QueryError> {
+        Err(QueryError { position: self.position, message: message.to_string() })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.text[self.position..].chars().next().filter(|c| c.is_whitespace()) {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.position == self.text.len()
+    }
+
+    // Next whitespace separated word and where it started
+    fn word(&mut self) -> (usize, &'a str) {
+        self.skip_whitespace();
+        let start = self.position;
+        let rest = &self.text[start..];
+        let length = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.position += length;
+        (start, &rest[..length])
+    }
+
+    fn expect_char(&mut self, expected: char) -> Result<(), QueryError> {
+        self.skip_whitespace();
+        if self.text[self.position..].starts_with(expected) {
+            self.position += 1;
+            Ok(())
+        } else {
+            self.error(&format!("expected '{}'", expected))
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, QueryError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.position..];
+        let length = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        match rest[..length].parse() {
+            Ok(number) => {
+                self.position += length;
+                Ok(number)
+            }
+            Err(_) => self.error("expected a number"),
+        }
+    }
+
+    fn point(&mut self) -> Result<(u32, u32), QueryError> {
+        self.expect_char('(')?;
+        let row = self.number()?;
+        self.expect_char(',')?;
+        let column = self.number()?;
+        self.expect_char(')')?;
+        Ok((row, column))
+    }
+
+    fn region(&mut self) -> Result<Region, QueryError> {
+        let mut region = Region::new();
+        loop {
+            let start = self.position;
+            let (top, left) = self.point()?;
+            self.expect_char('-')?;
+            let (bottom, right) = self.point()?;
+            if bottom < top || right < left {
+                self.position = start;
+                return self.error("region corners are the wrong way round");
+            }
+            let rows = (bottom - top).checked_add(1);
+            let columns = (right - left).checked_add(1);
+            match rows.zip(columns) {
+                Some((rows, columns)) => region.add_rect(Rect::new(top, left, rows, columns)),
+                None => {
+                    self.position = start;
+                    return self.error("region is too big");
+                }
+            }
+
+            self.skip_whitespace();
+            if !self.text[self.position..].starts_with('+') {
+                return Ok(region);
+            }
+            self.position += 1;
+        }
+    }
+
+    // Optional trailing `in REGION`
+    fn optional_region(&mut self) -> Result<Option<Region>, QueryError> {
+        if self.at_end() {
+            return Ok(None);
+        }
+        let (start, word) = self.word();
+        if word != "in" {
+            self.position = start;
+            return self.error("expected 'in' or the end of the query");
+        }
+        self.region().map(Some)
+    }
+
+    fn pattern(&mut self) -> Result<Vec<Vec<Cell>>, QueryError> {
+        let (start, text) = self.word();
+        let mut pattern = vec![];
+        for row in text.split('/') {
+            let mut cells = vec![];
+            for symbol in row.chars() {
+                cells.push(match symbol {
+                    '#' | 'o' => Cell::Alive,
+                    '.' | 'b' => Cell::Dead,
+                    _ => {
+                        self.position = start;
+                        return self.error(&format!("unexpected '{}' in pattern", symbol));
+                    }
+                });
+            }
+            pattern.push(cells);
+        }
+
+        let width = pattern[0].len();
+        if width == 0 || pattern.iter().any(|row| row.len() != width) {
+            self.position = start;
+            return self.error("pattern rows must be non-empty and the same length");
+        }
+        Ok(pattern)
+    }
+
+    fn query(&mut self) -> Result<Query, QueryError> {
+        let (start, command) = self.word();
+        let query = match command {
+            "count" => {
+                let (start, state) = self.word();
+                let state = match state {
+                    "alive" => Cell::Alive,
+                    "dead" => Cell::Dead,
+                    _ => {
+                        self.position = start;
+                        return self.error("expected 'alive' or 'dead'");
+                    }
+                };
+                Query::Count { state, region: self.optional_region()? }
+            }
+            "bbox" => Query::BoundingBox { region: self.optional_region()? },
+            "find" => {
+                let pattern = self.pattern()?;
+                Query::Find { pattern, region: self.optional_region()? }
+            }
+            _ => {
+                self.position = start;
+                return self.error("expected 'count', 'bbox' or 'find'");
+            }
+        };
+
+        if !self.at_end() {
+            return self.error("unexpected text after the query");
+        }
+        Ok(query)
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, LifeError> {
+        Ok(Parser { text, position: 0 }.query()?)
+    }
+}
+
+impl Universe {
+    fn query_region(&self, region: &Option<Region>) -> Region {
+        match region {
+            Some(region) => self.clip_region(region),
+            None => Region::from_rect(self.bounds()),
+        }
+    }
+
+    pub fn run_query(&self, query: &Query) -> QueryResult {
+        match query {
+            Query::Count { state, region } => {
+                let count = self.query_region(region).cells()
+                    .filter(|&(row, column)| self.cells[self.get_index(row, column)] == *state)
+                    .count();
+                QueryResult::Count(count as u32)
+            }
+            Query::BoundingBox { region } => {
+                let mut live = Region::new();
+                for (row, column) in self.query_region(region).cells() {
+                    if self.cells[self.get_index(row, column)] == Cell::Alive {
+                        live.add_rect(Rect::new(row, column, 1, 1));
+                    }
+                }
+                QueryResult::BoundingBox(live.bounding_box())
+            }
+            Query::Find { pattern, region } => QueryResult::Matches(self.matches(pattern, &self.query_region(region))),
+        }
+    }
+
+    // Top left corner of every exact match of the pattern lying wholly
+    // within the region, in reading order
+    fn matches(&self, pattern: &[Vec<Cell>], region: &Region) -> Vec<(u32, u32)> {
+        let (rows, columns) = (pattern.len() as u32, pattern[0].len() as u32);
+        let mut matches = region.cells()
+            .filter(|&(top, left)| {
+                let placed = Rect::new(top, left, rows, columns);
+                placed.cells().all(|(row, column)| region.contains(row, column))
+                    && placed.cells().all(|(row, column)| {
+                        let expected = pattern[(row - top) as usize][(column - left) as usize];
+                        self.cells[self.get_index(row, column)] == expected
+                    })
+            })
+            .collect::<Vec<_>>();
+        // Region cells come rect by rect
+        matches.sort_unstable();
+        matches
+    }
+
+    /// Top left corner of every place the whole of `pattern`, dead cells
+    /// included, matches the board exactly, in reading order. Matches don't
+    /// wrap round the edges
+    pub fn find_pattern(&self, pattern: &Universe) -> Vec<(u32, u32)> {
+        self.matches(&pattern.grid(), &Region::from_rect(self.bounds()))
+    }
+
+    /// `find_pattern()` trying each distinct rotation and reflection of the
+    /// pattern too, giving the corner of each oriented copy's rectangle
+    pub fn find_pattern_oriented(&self, pattern: &Universe) -> Vec<(u32, u32)> {
+        let grid = pattern.grid();
+        let cells: Vec<(i64, i64)> = (0..pattern.height as i64)
+            .flat_map(|row| (0..pattern.width as i64).map(move |column| (row, column)))
+            .collect();
+
+        let mut orientations = vec![];
+        for orientation in 0..8 {
+            let oriented: Vec<_> = cells.iter().map(|&cell| orient(cell, orientation)).collect();
+            let top = oriented.iter().map(|&(row, _)| row).min().unwrap_or(0);
+            let left = oriented.iter().map(|&(_, column)| column).min().unwrap_or(0);
+            let rows = oriented.iter().map(|&(row, _)| row - top + 1).max().unwrap_or(0);
+            let columns = oriented.iter().map(|&(_, column)| column - left + 1).max().unwrap_or(0);
+            let mut turned = vec![vec![Cell::Dead; columns as usize]; rows as usize];
+            for (&(row, column), &(to_row, to_column)) in cells.iter().zip(&oriented) {
+                turned[(to_row - top) as usize][(to_column - left) as usize] = grid[row as usize][column as usize];
+            }
+            if !orientations.contains(&turned) {
+                orientations.push(turned);
+            }
+        }
+
+        let whole = Region::from_rect(self.bounds());
+        let mut matches: Vec<_> = orientations.iter().flat_map(|turned| self.matches(turned, &whole)).collect();
+        matches.sort_unstable();
+        matches.dedup();
+        matches
+    }
+
+    // The cells as rows
+    fn grid(&self) -> Vec<Vec<Cell>> {
+        self.cells.chunks(self.width as usize).map(|row| row.to_vec()).collect()
+    }
+
+    /// Rust side of `query`
+    pub fn query_str(&self, text: &str) -> Result<QueryResult, LifeError> {
+        Query::parse(text).map(|query| self.run_query(&query))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Run a query such as `count alive in (10,10)-(30,40)`, returning the
+    /// result as JSON
+    #[cfg(feature = "wasm")]
+    pub fn query(&self, text: &str) -> Result<String, JsValue> {
+        self.query_str(text)
+            .map(|result| result.to_string())
+            .map_err(crate::error::js_error)
+    }
+
+    /// Where `pattern` occurs on the board, optionally in any orientation,
+    /// as a flat `[row, column, row, column, ...]` list of top left corners
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = find_pattern)]
+    pub fn find_pattern_js(&self, pattern: &Universe, any_orientation: bool) -> Vec<u32> {
+        let matches = if any_orientation { self.find_pattern_oriented(pattern) } else { self.find_pattern(pattern) };
+        matches.into_iter().flat_map(|(row, column)| [row, column]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider_universe() -> Universe {
+        Universe::new(10, 8, vec![(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)])
+    }
+
+    #[test]
+    fn counts_and_bbox() {
+        let universe = glider_universe();
+        assert_eq!(Ok(QueryResult::Count(5)), universe.query_str("count alive"));
+        assert_eq!(Ok(QueryResult::Count(75)), universe.query_str("count dead"));
+        assert_eq!(Ok(QueryResult::Count(2)), universe.query_str("count alive in (0,0)-(2,9)"));
+        assert_eq!(Ok(QueryResult::Count(3)), universe.query_str("count alive in (1,2)-(1,2) + (3, 0)-(3, 2)"));
+
+        assert_eq!(Ok(QueryResult::BoundingBox(Some(Rect::new(1, 1, 3, 3)))), universe.query_str("bbox"));
+        assert_eq!(Ok(QueryResult::BoundingBox(None)), universe.query_str("bbox in (5,5)-(7,9)"));
+        assert_eq!(r#"{"bbox":{"top":1,"left":1,"rows":3,"columns":3}}"#, universe.query_str("bbox").unwrap().to_string());
+    }
+
+    #[test]
+    fn find_pattern() {
+        let universe = glider_universe();
+        assert_eq!(Ok(QueryResult::Matches(vec![(1, 1)])), universe.query_str("find .#./..#/### in (0,0)-(7,9)"));
+        assert_eq!(Ok(QueryResult::Matches(vec![])), universe.query_str("find .#./..#/### in (2,0)-(7,9)"));
+        assert_eq!(r#"{"matches":[[3,1]]}"#, universe.query_str("find ooo").unwrap().to_string());
+    }
+
+    #[test]
+    fn find_pattern_universe() {
+        let mut universe = glider_universe();
+        universe.toggle_cell(6, 6);
+        universe.toggle_cell(6, 7);
+        universe.toggle_cell(6, 8);
+        let horizontal = Universe::new(3, 1, vec![(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(vec![(3, 1), (6, 6)], universe.find_pattern(&horizontal));
+
+        // A glider flipped the other way only turns up when orientations
+        // count, and a blinker is found whichever way it's given
+        let flipped = Universe::new(3, 3, vec![(0, 1), (1, 0), (2, 0), (2, 1), (2, 2)]);
+        assert!(universe.find_pattern(&flipped).is_empty());
+        assert_eq!(vec![(1, 1)], universe.find_pattern_oriented(&flipped));
+        let vertical = Universe::new(1, 3, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(vec![(3, 1), (6, 6)], universe.find_pattern_oriented(&vertical));
+    }
+
+    #[test]
+    fn errors() {
+        let universe = glider_universe();
+        let position = |text: &str| match universe.query_str(text) {
+            Err(LifeError::ParseError { column, .. }) => column - 1,
+            result => panic!("expected a parse error, got {:?}", result),
+        };
+        assert_eq!(0, position("tally"));
+        assert_eq!(6, position("count living"));
+        assert_eq!(18, position("count alive in (1,x)-(2,2)"));
+        assert_eq!(5, position("find #x#"));
+        assert!(universe.query_str("find ##/#").is_err());
+        assert!(universe.query_str("bbox in (3,3)-(1,1)").is_err());
+        assert!(universe.query_str("bbox please").is_err());
+
+        // Wide spaces and regions past the largest size
+        assert_eq!(Ok(QueryResult::Count(5)), universe.query_str("count\u{a0}alive"));
+        assert_eq!(4, position("\u{3000} dead"));
+        assert_eq!(14, position("count alive in (0,0)-(4294967295,3)"));
+    }
+}
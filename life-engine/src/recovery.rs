@@ -0,0 +1,166 @@
+// Crash recovery, handing the last good state to a callback on panic
+//
+// When a panic happens the universe is likely borrowed mid-update so it
+// can't be read safely. Instead a universe with recovery enabled stores a
+// base64 snapshot after every tick into a per-thread slot, and the panic hook
+// passes the latest one to the registered callback (e.g. to stash it in
+// localStorage) before the WASM instance aborts. Only the most recently
+// registered universe is covered: each registration gets an id, and a
+// universe whose id no longer holds the slot leaves it alone
+
+use crate::Universe;
+
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+type Callback = Box<dyn Fn(&str, u64)>;
+
+struct RecoverySlot {
+    owner: u64,
+    callback: Callback,
+    snapshot: String,
+    generation: u64,
+}
+
+thread_local! {
+    static SLOT: RefCell<Option<RecoverySlot>> = const { RefCell::new(None) };
+    static NEXT_OWNER: Cell<u64> = const { Cell::new(0) };
+}
+
+static HOOK: Once = Once::new();
+
+fn install_panic_hook() {
+    HOOK.call_once(|| {
+        // Keep whatever hook was already there (e.g. console_error_panic_hook)
+        // running after ours
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = SLOT.try_with(|slot| {
+                if let Ok(slot) = slot.try_borrow() {
+                    if let Some(slot) = slot.as_ref() {
+                        (slot.callback)(&slot.snapshot, slot.generation);
+                    }
+                }
+            });
+            previous(info);
+        }));
+    });
+}
+
+impl Universe {
+    /// On panic call `callback` with the base64 snapshot (see `to_base64`)
+    /// and generation of this universe as of its last completed tick
+    pub fn set_recovery_callback(&mut self, callback: impl Fn(&str, u64) + 'static) {
+        install_panic_hook();
+        let owner = NEXT_OWNER.with(|next| next.replace(next.get() + 1));
+        self.recovery = Some(owner);
+
+        let snapshot = self.to_base64();
+        let generation = self.generation;
+        SLOT.with(|slot| {
+            *slot.borrow_mut() = Some(RecoverySlot { owner, callback: Box::new(callback), snapshot, generation });
+        });
+    }
+
+    // Whether this universe's registration still holds the slot
+    fn holds_recovery(&self) -> bool {
+        self.recovery.is_some()
+            && SLOT.with(|slot| slot.borrow().as_ref().map(|slot| slot.owner) == self.recovery)
+    }
+
+    // Called at the end of tick()
+    pub(crate) fn update_recovery(&mut self) {
+        if self.recovery.is_none() {
+            return;
+        }
+        if !self.holds_recovery() {
+            // Another universe has registered since
+            self.recovery = None;
+            return;
+        }
+
+        let snapshot = self.to_base64();
+        SLOT.with(|slot| {
+            if let Some(slot) = slot.borrow_mut().as_mut() {
+                slot.snapshot = snapshot;
+                slot.generation = self.generation;
+            }
+        });
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// JS version of `set_recovery_callback`, `callback` receives
+    /// `(snapshot, generation)` and `Universe.from_base64(snapshot)` restores
+    /// the board
+    #[cfg(feature = "wasm")]
+    pub fn enable_crash_recovery(&mut self, callback: js_sys::Function) {
+        self.set_recovery_callback(move |snapshot, generation| {
+            let _ = callback.call2(&JsValue::NULL, &JsValue::from_str(snapshot), &JsValue::from(generation as f64));
+        });
+    }
+
+    pub fn disable_crash_recovery(&mut self) {
+        if self.holds_recovery() {
+            SLOT.with(|slot| *slot.borrow_mut() = None);
+        }
+        self.recovery = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn recovers_on_panic() {
+        let recovered = Rc::new(RefCell::new(None));
+        let sink = recovered.clone();
+
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        universe.set_recovery_callback(move |snapshot, generation| {
+            *sink.borrow_mut() = Some((snapshot.to_string(), generation));
+        });
+        universe.tick();
+
+        let result = std::panic::catch_unwind(|| panic!("experimental engine bug"));
+        assert!(result.is_err());
+
+        let (snapshot, generation) = recovered.borrow_mut().take().unwrap();
+        assert_eq!(1, generation);
+        assert_eq!(universe.cells, Universe::decode_base64(&snapshot).unwrap().cells);
+
+        universe.disable_crash_recovery();
+        let _ = std::panic::catch_unwind(|| panic!("after disabling"));
+        assert!(recovered.borrow().is_none());
+    }
+
+    #[test]
+    fn latest_registration_keeps_the_slot() {
+        let recovered = Rc::new(RefCell::new(vec![]));
+        let (first_sink, second_sink) = (recovered.clone(), recovered.clone());
+
+        let mut first = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        first.set_recovery_callback(move |_, generation| first_sink.borrow_mut().push(("first", generation)));
+        let mut second = Universe::new(8, 8, vec![]);
+        second.set_recovery_callback(move |_, generation| second_sink.borrow_mut().push(("second", generation)));
+
+        // The first universe ticking on doesn't overwrite the second's
+        // snapshot, and disabling it leaves the second's callback in place
+        for _ in 0..3 {
+            first.tick();
+        }
+        second.tick();
+        first.disable_crash_recovery();
+        let _ = std::panic::catch_unwind(|| panic!("second universe still covered"));
+        assert_eq!(vec![("second", 1)], *recovered.borrow());
+
+        second.disable_crash_recovery();
+        let _ = std::panic::catch_unwind(|| panic!("after disabling both"));
+        assert_eq!(1, recovered.borrow().len());
+    }
+}
@@ -0,0 +1,294 @@
+// Rectangles and regions (unions of rectangles) of cells
+//
+// Anything in the API referring to an area of the universe should take one
+// of these rather than its own (row, column, rows, columns) tuple. Regions
+// are kept as a list of non-overlapping rectangles so the set operations and
+// area stay simple
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub top: u32,
+    pub left: u32,
+    pub rows: u32,
+    pub columns: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Rect {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(top: u32, left: u32, rows: u32, columns: u32) -> Rect {
+        Rect { top, left, rows, columns }
+    }
+
+    // Exclusive end coordinates, widened so huge rects can't overflow
+    fn bottom(&self) -> u64 {
+        self.top as u64 + self.rows as u64
+    }
+
+    fn right(&self) -> u64 {
+        self.left as u64 + self.columns as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0 || self.columns == 0
+    }
+
+    pub fn area(&self) -> u64 {
+        self.rows as u64 * self.columns as u64
+    }
+
+    pub fn contains(&self, row: u32, column: u32) -> bool {
+        row >= self.top && (row as u64) < self.bottom()
+            && column >= self.left && (column as u64) < self.right()
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let top = self.top.max(other.top);
+        let left = self.left.max(other.left);
+        let bottom = self.bottom().min(other.bottom());
+        let right = self.right().min(other.right());
+
+        if bottom <= top as u64 || right <= left as u64 {
+            return None;
+        }
+        Some(Rect::new(top, left, (bottom - top as u64) as u32, (right - left as u64) as u32))
+    }
+}
+
+impl Rect {
+    // Parts of self not covered by other, as up to four disjoint rects
+    fn difference(&self, other: &Rect) -> Vec<Rect> {
+        let overlap = match self.intersection(other) {
+            Some(overlap) => overlap,
+            None => return vec![*self],
+        };
+
+        let mut pieces = vec![
+            // Full width band above and below the overlap
+            Rect::new(self.top, self.left, overlap.top - self.top, self.columns),
+            Rect::new(
+                overlap.bottom() as u32, self.left,
+                (self.bottom() - overlap.bottom()) as u32, self.columns,
+            ),
+            // Either side of the overlap within its rows
+            Rect::new(overlap.top, self.left, overlap.rows, overlap.left - self.left),
+            Rect::new(
+                overlap.top, overlap.right() as u32,
+                overlap.rows, (self.right() - overlap.right()) as u32,
+            ),
+        ];
+        pieces.retain(|piece| !piece.is_empty());
+        pieces
+    }
+
+    /// Every (row, column) inside the rect, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (u32, u32)> {
+        let rect = *self;
+        (rect.top..(rect.bottom() as u32))
+            .flat_map(move |row| (rect.left..(rect.right() as u32)).map(move |column| (row, column)))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Region {
+    rects: Vec<Rect>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Region {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Region {
+        Region { rects: vec![] }
+    }
+
+    pub fn from_rect(rect: Rect) -> Region {
+        let mut region = Region::new();
+        region.add_rect(rect);
+        region
+    }
+
+    pub fn add_rect(&mut self, rect: Rect) {
+        // Only keep the parts not already covered so rects never overlap
+        let mut pieces = vec![rect];
+        for existing in &self.rects {
+            pieces = pieces.iter().flat_map(|piece| piece.difference(existing)).collect();
+        }
+        self.rects.extend(pieces.into_iter().filter(|piece| !piece.is_empty()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn area(&self) -> u64 {
+        self.rects.iter().map(Rect::area).sum()
+    }
+
+    pub fn contains(&self, row: u32, column: u32) -> bool {
+        self.rects.iter().any(|rect| rect.contains(row, column))
+    }
+
+    pub fn union(&self, other: &Region) -> Region {
+        let mut region = self.clone();
+        for &rect in &other.rects {
+            region.add_rect(rect);
+        }
+        region
+    }
+
+    pub fn intersection(&self, other: &Region) -> Region {
+        // Both sides are disjoint so their pairwise overlaps are too
+        let rects = self.rects.iter()
+            .flat_map(|rect| other.rects.iter().filter_map(move |o| rect.intersection(o)))
+            .collect();
+        Region { rects }
+    }
+
+    pub fn difference(&self, other: &Region) -> Region {
+        let mut rects = self.rects.clone();
+        for subtract in &other.rects {
+            rects = rects.iter().flat_map(|rect| rect.difference(subtract)).collect();
+        }
+        Region { rects }
+    }
+
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let first = self.rects.first()?;
+        let (mut top, mut left) = (first.top, first.left);
+        let (mut bottom, mut right) = (first.bottom(), first.right());
+        for rect in &self.rects[1..] {
+            top = top.min(rect.top);
+            left = left.min(rect.left);
+            bottom = bottom.max(rect.bottom());
+            right = right.max(rect.right());
+        }
+        Some(Rect::new(top, left, (bottom - top as u64) as u32, (right - left as u64) as u32))
+    }
+
+    /// The disjoint rects making up the region
+    pub fn rects(&self) -> Vec<Rect> {
+        self.rects.clone()
+    }
+}
+
+impl Region {
+    /// Every (row, column) in the region, each exactly once
+    pub fn cells(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.rects.iter().flat_map(Rect::cells)
+    }
+}
+
+impl From<Rect> for Region {
+    fn from(rect: Rect) -> Region {
+        Region::from_rect(rect)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Rect covering the whole universe
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.height, self.width)
+    }
+
+    /// Smallest rect holding every live cell, or nothing on an empty board.
+    /// Worked out when asked, scanning in from the top and bottom rows
+    pub fn live_bounds(&self) -> Option<Rect> {
+        let width = self.width as usize;
+        let alive = |row: &[Cell]| row.contains(&Cell::Alive);
+        let top = self.cells.chunks(width).position(alive)?;
+        let bottom = self.cells.chunks(width).rposition(alive)?;
+
+        let (mut left, mut right) = (width, 0);
+        for row in self.cells.chunks(width).take(bottom + 1).skip(top) {
+            if let Some(first) = row.iter().position(|&cell| cell == Cell::Alive) {
+                left = left.min(first);
+                right = right.max(row.iter().rposition(|&cell| cell == Cell::Alive).unwrap_or(first));
+            }
+        }
+        Some(Rect::new(top as u32, left as u32, (bottom - top + 1) as u32, (right - left + 1) as u32))
+    }
+
+    /// Number of alive cells inside the region, ignoring any part of it
+    /// which falls outside the universe
+    pub fn population_in(&self, region: &Region) -> u32 {
+        self.clip_region(region).cells()
+            .filter(|&(row, column)| self.cells[self.get_index(row, column)] == Cell::Alive)
+            .count() as u32
+    }
+}
+
+impl Universe {
+    // Restrict a region to the cells that actually exist in this universe
+    pub(crate) fn clip_region(&self, region: &Region) -> Region {
+        region.intersection(&Region::from_rect(self.bounds()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_operations() {
+        let rect = Rect::new(2, 2, 4, 3);
+        assert!(rect.contains(2, 2));
+        assert!(rect.contains(5, 4));
+        assert!(!rect.contains(6, 4));
+        assert!(!rect.contains(5, 5));
+        assert_eq!(12, rect.area());
+        assert_eq!(12, rect.cells().count());
+
+        assert_eq!(Some(Rect::new(4, 3, 2, 2)), rect.intersection(&Rect::new(4, 3, 10, 10)));
+        assert_eq!(None, rect.intersection(&Rect::new(6, 0, 10, 10)));
+    }
+
+    #[test]
+    fn region_set_operations() {
+        let a = Region::from_rect(Rect::new(0, 0, 4, 4));
+        let b = Region::from_rect(Rect::new(2, 2, 4, 4));
+
+        // Overlapping 2x2 square is only counted once
+        let union = a.union(&b);
+        assert_eq!(28, union.area());
+        assert_eq!(28, union.cells().count());
+        assert!(union.contains(5, 5));
+        assert!(!union.contains(5, 0));
+        assert_eq!(Some(Rect::new(0, 0, 6, 6)), union.bounding_box());
+
+        let intersection = a.intersection(&b);
+        assert_eq!(4, intersection.area());
+        assert!(intersection.contains(3, 3));
+        assert!(!intersection.contains(1, 1));
+
+        let difference = a.difference(&b);
+        assert_eq!(12, difference.area());
+        assert!(difference.contains(1, 1));
+        assert!(!difference.contains(3, 3));
+
+        assert!(Region::new().is_empty());
+
+        let universe = Universe::new(4, 3, vec![(0, 0), (1, 3), (2, 3)]);
+        assert_eq!(12, universe.clip_region(&union).area());
+        assert_eq!(3, universe.population_in(&union));
+        assert_eq!(1, universe.population_in(&b));
+        assert_eq!(None, Region::new().bounding_box());
+    }
+
+    #[test]
+    fn live_bounds() {
+        let mut universe = Universe::new(9, 7, vec![(2, 5), (4, 1), (5, 3)]);
+        assert_eq!(Some(Rect::new(2, 1, 4, 5)), universe.live_bounds());
+        universe.clear();
+        assert_eq!(None, universe.live_bounds());
+        universe.toggle_cell(6, 8);
+        assert_eq!(Some(Rect::new(6, 8, 1, 1)), universe.live_bounds());
+    }
+}
@@ -0,0 +1,180 @@
+// Named patterns kept parsed, ready to stamp onto any universe
+//
+// A frontend that fetches a pattern collection registers each pattern once
+// under its name, and from then on places it with `stamp()` without the
+// text being parsed again:
+//
+//     const patterns = new PatternRegistry();
+//     patterns.register("glider", "x = 3, y = 3\nbo$2bo$3o!");
+//     patterns.register("beacon", "!Name: Beacon\nOO\nO\n...O\n..OO");
+//     patterns.stamp(universe, "glider", 10, 10, OverlayMode.Or);
+//
+// Text with an `x = ` header is read as RLE and anything else as plaintext
+// (.cells), where `!` lines are comments and short rows are padded with
+// dead cells. Registering a name again replaces its pattern. Stamping is an
+// ordinary overlay, one undoable edit clipped at the edges of the board
+
+use crate::error::LifeError;
+use crate::{OverlayMode, Universe};
+
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Default)]
+pub struct PatternRegistry {
+    // Ordered so names() comes out sorted
+    patterns: BTreeMap<String, Universe>,
+}
+
+fn is_rle(text: &str) -> bool {
+    let header = text.lines().map(str::trim).find(|line| !line.is_empty() && !line.starts_with('#'));
+    header.and_then(|line| line.strip_prefix('x')).is_some_and(|rest| rest.trim_start().starts_with('='))
+}
+
+// Plaintext rows padded out to the longest, comments dropped
+fn parse_plaintext(text: &str) -> Result<Universe, LifeError> {
+    let rows = text.lines().map(str::trim).filter(|line| !line.starts_with('!')).collect::<Vec<_>>();
+    let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    let mut grid = String::new();
+    for row in rows {
+        // Blank rows inside the pattern are rows of dead cells
+        grid.push_str(row);
+        grid.extend(std::iter::repeat_n('.', width - row.chars().count()));
+        grid.push('\n');
+    }
+    Ok(grid.parse()?)
+}
+
+impl PatternRegistry {
+    pub fn get(&self, name: &str) -> Option<&Universe> {
+        self.patterns.get(name)
+    }
+
+    pub fn insert(&mut self, name: &str, pattern: Universe) {
+        self.patterns.insert(name.to_string(), pattern);
+    }
+
+    /// Rust side of `register`
+    pub fn register_str(&mut self, name: &str, text: &str) -> Result<(), LifeError> {
+        let pattern = if is_rle(text) { Universe::from_rle_str(text)? } else { parse_plaintext(text)? };
+        self.insert(name, pattern);
+        Ok(())
+    }
+
+    /// Rust side of `stamp`
+    pub fn stamp_onto(
+        &self,
+        universe: &mut Universe,
+        name: &str,
+        row: u32,
+        column: u32,
+        mode: OverlayMode,
+    ) -> Result<(), LifeError> {
+        let pattern = self.get(name)
+            .ok_or_else(|| LifeError::InvalidArgument(format!("no pattern called '{}'", name)))?;
+        if row >= universe.height() || column >= universe.width() {
+            return Err(LifeError::OutOfBounds { row, column });
+        }
+        universe.overlay(pattern, row, column, mode);
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PatternRegistry {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> PatternRegistry {
+        PatternRegistry::default()
+    }
+
+    /// Parse RLE or plaintext and keep it as `name`
+    #[cfg(feature = "wasm")]
+    pub fn register(&mut self, name: &str, text: &str) -> Result<(), JsValue> {
+        self.register_str(name, text).map_err(crate::error::js_error)
+    }
+
+    /// Keep a copy of `pattern`'s cells as `name`
+    pub fn register_universe(&mut self, name: &str, pattern: &Universe) {
+        self.insert(name, pattern.duplicate());
+    }
+
+    /// Forget `name`, false if it wasn't registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.patterns.remove(name).is_some()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.patterns.contains_key(name)
+    }
+
+    /// Every registered name, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.patterns.keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> u32 {
+        self.patterns.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// A copy of the pattern, as a universe of its own size
+    pub fn pattern(&self, name: &str) -> Option<Universe> {
+        self.get(name).map(Universe::duplicate)
+    }
+
+    /// Overlay the pattern onto `universe` with its top left corner at
+    /// (row, column), which has to be on the board
+    #[cfg(feature = "wasm")]
+    pub fn stamp(
+        &self,
+        universe: &mut Universe,
+        name: &str,
+        row: u32,
+        column: u32,
+        mode: OverlayMode,
+    ) -> Result<(), JsValue> {
+        self.stamp_onto(universe, name, row, column, mode).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registers_and_stamps() {
+        let mut registry = PatternRegistry::new();
+        registry.register_str("glider", "#N Glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!").unwrap();
+        registry.register_str("beacon", "!Name: Beacon\n!\nOO\nO\n...O\n..OO\n").unwrap();
+        assert_eq!(vec!["beacon", "glider"], registry.names());
+        assert_eq!((4, 4, 6), {
+            let beacon = registry.get("beacon").unwrap();
+            (beacon.width(), beacon.height(), beacon.population())
+        });
+
+        let mut universe = Universe::new(10, 10, vec![]);
+        universe.set_undo_capacity(10);
+        registry.stamp_onto(&mut universe, "glider", 1, 1, OverlayMode::Or).unwrap();
+        registry.stamp_onto(&mut universe, "beacon", 8, 8, OverlayMode::Or).unwrap();
+        assert_eq!(vec![(1, 2), (2, 3), (3, 1), (3, 2), (3, 3), (8, 8), (8, 9), (9, 8)],
+            universe.live_cells().collect::<Vec<_>>());
+        assert!(universe.undo_edit());
+        assert_eq!(5, universe.population());
+
+        assert!(matches!(
+            registry.stamp_onto(&mut universe, "glider", 10, 0, OverlayMode::Or),
+            Err(LifeError::OutOfBounds { row: 10, column: 0 })
+        ));
+        assert!(registry.stamp_onto(&mut universe, "gun", 0, 0, OverlayMode::Or).is_err());
+        assert!(registry.register_str("bad", "x = 3, y = 3\nbo$2bo$4o!").is_err());
+        assert!(!registry.contains("bad"));
+
+        assert!(registry.unregister("glider"));
+        assert_eq!(1, registry.len());
+    }
+}
@@ -0,0 +1,268 @@
+// Text rendering settings for render() and Display
+//
+// The default matches the original output, ◼ for alive and ◻ for dead with
+// every row ending in a newline. Environments which can't show those glyphs
+// can switch to `RenderConfig::ascii()` or pick their own
+//
+// Big boards don't have to be rendered as one string: `render_rows()` gives
+// the rows one at a time, and from JS `row_string(row)` renders a single row
+// and `stream_rows(callback)` hands each to `callback(text, row)` in turn
+
+use crate::{Cell, Rect, Universe};
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderConfig {
+    alive: String,
+    dead: String,
+    row_separator: String,
+    // Whether the last row gets a separator after it too
+    trailing_separator: bool,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            alive: "◼".to_string(),
+            dead: "◻".to_string(),
+            row_separator: "\n".to_string(),
+            trailing_separator: true,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl RenderConfig {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> RenderConfig {
+        RenderConfig::default()
+    }
+
+    /// `#` for alive and `.` for dead
+    pub fn ascii() -> RenderConfig {
+        RenderConfig { alive: "#".to_string(), dead: ".".to_string(), ..RenderConfig::default() }
+    }
+
+    pub fn alive_glyph(&self) -> String {
+        self.alive.clone()
+    }
+
+    pub fn set_alive_glyph(&mut self, glyph: &str) {
+        self.alive = glyph.to_string();
+    }
+
+    pub fn dead_glyph(&self) -> String {
+        self.dead.clone()
+    }
+
+    pub fn set_dead_glyph(&mut self, glyph: &str) {
+        self.dead = glyph.to_string();
+    }
+
+    pub fn row_separator(&self) -> String {
+        self.row_separator.clone()
+    }
+
+    pub fn set_row_separator(&mut self, separator: &str) {
+        self.row_separator = separator.to_string();
+    }
+
+    pub fn trailing_separator(&self) -> bool {
+        self.trailing_separator
+    }
+
+    pub fn set_trailing_separator(&mut self, trailing: bool) {
+        self.trailing_separator = trailing;
+    }
+}
+
+impl RenderConfig {
+    pub(crate) fn write(&self, f: &mut impl fmt::Write, width: u32, cells: &[Cell]) -> fmt::Result {
+        if width == 0 {
+            return Ok(());
+        }
+        let rows = cells.len() / width as usize;
+        for (row, line) in cells.chunks(width as usize).enumerate() {
+            self.write_row(f, line, false)?;
+            if row + 1 < rows || self.trailing_separator {
+                f.write_str(&self.row_separator)?;
+            }
+        }
+        Ok(())
+    }
+
+    // One row's glyphs, without a separator, swapped over for cells stored
+    // complemented
+    fn write_row(&self, f: &mut impl fmt::Write, line: &[Cell], inverted: bool) -> fmt::Result {
+        for &cell in line {
+            f.write_str(if (cell == Cell::Alive) != inverted { &self.alive } else { &self.dead })?;
+        }
+        Ok(())
+    }
+
+    fn row_string(&self, line: &[Cell], inverted: bool) -> String {
+        let mut out = String::with_capacity(line.len() * self.alive.len().max(self.dead.len()));
+        self.write_row(&mut out, line, inverted).expect("writing to a String can't fail");
+        out
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn render_config(&self) -> RenderConfig {
+        self.render_config.clone()
+    }
+
+    /// Settings used by render() and Display from now on
+    pub fn set_render_config(&mut self, config: &RenderConfig) {
+        self.render_config = config.clone();
+    }
+
+    /// Render once with different settings
+    pub fn render_with(&self, config: &RenderConfig) -> String {
+        let mut out = String::new();
+        config.write(&mut out, self.width, &self.true_cells()).expect("writing to a String can't fail");
+        out
+    }
+
+    /// Render just the part of `window` inside the universe, so huge boards
+    /// can be explored without rendering all of it every frame
+    pub fn render_viewport(&self, window: &Rect) -> String {
+        let (columns, mut cells) = self.window_cells(window);
+        cells.iter_mut().for_each(|cell| *cell = self.true_state(*cell));
+        let mut out = String::new();
+        self.render_config.write(&mut out, columns, &cells).expect("writing to a String can't fail");
+        out
+    }
+
+    /// Buffer variant of render_viewport, one byte per cell (0 dead, 1
+    /// alive) row by row. The width is the window's, clipped to the universe
+    pub fn viewport_cells(&self, window: &Rect) -> Vec<u8> {
+        self.window_cells(window).1.into_iter().map(|cell| cell as u8).collect()
+    }
+
+    /// The live cells' bounding box grown by `padding` cells on every side,
+    /// clipped to the universe. Nothing when nothing is alive
+    pub fn cropped_bounds(&self, padding: u32) -> Option<Rect> {
+        let live = self.live_bounds()?;
+        let (top, left) = (live.top.saturating_sub(padding), live.left.saturating_sub(padding));
+        let bottom = (live.top as u64 + live.rows as u64 + padding as u64).min(self.height as u64) as u32;
+        let right = (live.left as u64 + live.columns as u64 + padding as u64).min(self.width as u64) as u32;
+        Some(Rect::new(top, left, bottom - top, right - left))
+    }
+
+    /// Render just `cropped_bounds(padding)`, so a small pattern on a huge
+    /// board prints without screens of empty grid. Empty with nothing alive
+    pub fn render_cropped(&self, padding: u32) -> String {
+        self.cropped_bounds(padding).map_or_else(String::new, |bounds| self.render_viewport(&bounds))
+    }
+
+    /// Buffer variant of render_cropped, one byte per cell row by row, as
+    /// wide as `cropped_bounds(padding)`
+    pub fn cropped_cells(&self, padding: u32) -> Vec<u8> {
+        self.cropped_bounds(padding).map_or_else(Vec::new, |bounds| self.viewport_cells(&bounds))
+    }
+
+    /// The glyphs of one row with the current settings, no separator.
+    /// Empty past the last row
+    pub fn row_string(&self, row: u32) -> String {
+        if row >= self.height {
+            return String::new();
+        }
+        let start = self.get_index(row, 0);
+        self.render_config.row_string(&self.cells[start..start + self.width as usize], self.inverted)
+    }
+
+    /// Call `callback(text, row)` with every row's glyphs from the top, as
+    /// `row_string()` gives them, stopping at the first exception it throws
+    #[cfg(feature = "wasm")]
+    pub fn stream_rows(&self, callback: &js_sys::Function) -> Result<(), JsValue> {
+        for (row, text) in self.render_rows().enumerate() {
+            callback.call2(&JsValue::NULL, &JsValue::from_str(&text), &JsValue::from(row as u32))?;
+        }
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// The rendered rows from the top, one string each without separators,
+    /// for writing out a board without building all of it at once
+    pub fn render_rows(&self) -> impl Iterator<Item = String> + '_ {
+        self.cells.chunks(self.width as usize).map(move |line| self.render_config.row_string(line, self.inverted))
+    }
+
+    // Width and cells of the window clipped to the universe
+    fn window_cells(&self, window: &Rect) -> (u32, Vec<Cell>) {
+        match window.intersection(&self.bounds()) {
+            Some(clipped) => (
+                clipped.columns,
+                clipped.cells().map(|(row, column)| self.cells[self.get_index(row, column)]).collect(),
+            ),
+            None => (0, vec![]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyphs_and_separators() {
+        let mut universe = Universe::new(3, 2, vec![(0, 0), (1, 2)]);
+        assert_eq!("◼◻◻\n◻◻◼\n", universe.render());
+
+        let mut config = RenderConfig::ascii();
+        assert_eq!("#..\n..#\n", universe.render_with(&config));
+
+        config.set_alive_glyph("O");
+        config.set_dead_glyph(" .");
+        config.set_row_separator("|");
+        config.set_trailing_separator(false);
+        universe.set_render_config(&config);
+        assert_eq!("O . .| . .O", universe.render());
+        assert_eq!("O . .| . .O", universe.to_string());
+    }
+
+    #[test]
+    fn rows() {
+        let mut universe = Universe::new(3, 2, vec![(0, 0), (1, 2)]);
+        universe.set_render_config(&RenderConfig::ascii());
+        assert_eq!(vec!["#..", "..#"], universe.render_rows().collect::<Vec<_>>());
+        assert_eq!("..#", universe.row_string(1));
+        assert_eq!("", universe.row_string(2));
+
+        // The rows joined up are what render() gives
+        let mut joined = universe.render_rows().collect::<Vec<_>>().join("\n");
+        joined.push('\n');
+        assert_eq!(universe.render(), joined);
+    }
+
+    #[test]
+    fn viewport() {
+        let universe = Universe::new(4, 3, vec![(0, 0), (1, 2), (2, 3)]);
+        assert_eq!("◻◼\n◻◻\n", universe.render_viewport(&Rect::new(1, 1, 2, 2)));
+        // Clipped at the edges of the universe
+        assert_eq!(vec![1, 0, 0, 1], universe.viewport_cells(&Rect::new(1, 2, 10, 2)));
+        assert_eq!("", universe.render_viewport(&Rect::new(5, 5, 2, 2)));
+    }
+
+    #[test]
+    fn cropped() {
+        let mut universe = Universe::new(40, 30, vec![(10, 20), (11, 21)]);
+        assert_eq!("◼◻\n◻◼\n", universe.render_cropped(0));
+        assert_eq!(Some(Rect::new(9, 19, 4, 4)), universe.cropped_bounds(1));
+        assert_eq!(16, universe.cropped_cells(1).len());
+
+        // Padding stops at the edges
+        universe.toggle_cell(0, 39);
+        assert_eq!(Some(Rect::new(0, 17, 15, 23)), universe.cropped_bounds(3));
+        universe.clear();
+        assert_eq!("", universe.render_cropped(2));
+        assert!(universe.cropped_cells(2).is_empty());
+    }
+}
@@ -0,0 +1,350 @@
+// Replay logs, rebuilding a run exactly from where it started and what was
+// done to it
+//
+// A log is plain text, one entry per line, so it can be pasted into a bug
+// report:
+//
+//     replay 1
+//     seeded 64 64 0.5 1234      or  snapshot <generation> <base64>
+//     rule B3/S23
+//     boundary Torus
+//     tick 120                   runs of ticks are merged
+//     edit 513 1                 cell index and new state (0 dead, 1 alive)
+//     randomize 0.3 99           density and seed
+//     randomize_region 0 0 8 8 0.5 7   area as top, left, rows and columns,
+//                                      then density and seed
+//     clear
+//     history 16                 generations kept for stepping back
+//     generation 118             after stepping back
+//
+// Edits are caught wherever they come from (toggles, bulk setters, pattern
+// pastes, transforms) while randomizing and clearing are kept to one line
+// each. Stepping back is one line too: the replay keeps the same history
+// and steps back itself, so it can't go back past where the log starts.
+// Rule, boundary and history capacity changes are recorded, other settings
+// aren't, so a run using them won't replay exactly
+
+use crate::error::LifeError;
+use crate::{Boundary, Cell, Rule, Universe};
+
+use std::convert::TryFrom;
+use std::fmt::{self, Write};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const VERSION: u32 = 1;
+
+pub(crate) const BOUNDARIES: [Boundary; 7] = [
+    Boundary::Torus,
+    Boundary::Dead,
+    Boundary::Mirror,
+    Boundary::Inject,
+    Boundary::KleinBottle,
+    Boundary::CrossSurface,
+    Boundary::Sphere,
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ReplayError {
+    /// 1 based line of the problem
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "parse error at line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+#[derive(Clone)]
+pub(crate) struct ReplayLog {
+    text: String,
+    // Ticks since the last line, written out as one entry
+    ticks: u64,
+}
+
+impl ReplayLog {
+    fn push(&mut self, entry: fmt::Arguments) {
+        self.flush_ticks();
+        writeln!(self.text, "{}", entry).unwrap();
+    }
+
+    fn flush_ticks(&mut self) {
+        if self.ticks > 0 {
+            writeln!(self.text, "tick {}", self.ticks).unwrap();
+            self.ticks = 0;
+        }
+    }
+}
+
+impl Universe {
+    // Start a log with `start` as its first entry, followed by the settings
+    // which are recorded
+    fn start_log(&mut self, start: fmt::Arguments) {
+        let mut log = ReplayLog { text: format!("replay {}\n", VERSION), ticks: 0 };
+        log.push(start);
+        log.push(format_args!("rule {}", self.rule));
+        log.push(format_args!("boundary {:?}", self.boundary));
+        if self.history_capacity() > 0 {
+            log.push(format_args!("history {}", self.history_capacity()));
+        }
+        self.replay = Some(log);
+    }
+
+    pub(crate) fn replay_tick(&mut self) {
+        if let Some(log) = self.replay.as_mut() {
+            log.ticks += 1;
+        }
+    }
+
+    // Called from write_cell() for edits
+    pub(crate) fn replay_edit(&mut self, idx: usize, cell: Cell) {
+        if let Some(log) = self.replay.as_mut() {
+            log.push(format_args!("edit {} {}", idx, cell as u8));
+        }
+    }
+
+    pub(crate) fn replay_record(&mut self, entry: fmt::Arguments) {
+        if let Some(log) = self.replay.as_mut() {
+            log.push(entry);
+        }
+    }
+
+    // Run `operation` without logging its edits, then log `entry` instead
+    pub(crate) fn replay_as(&mut self, entry: fmt::Arguments, operation: impl FnOnce(&mut Universe)) {
+        let log = self.replay.take();
+        operation(self);
+        self.replay = log;
+        self.replay_record(entry);
+    }
+
+    /// Seeded random universe like `random_seeded`, recording a replay log
+    /// from its seed
+    pub fn random_recorded(width: u32, height: u32, density: f32, seed: u64) -> Result<Universe, LifeError> {
+        let mut universe = Universe::random_seeded(width, height, density, seed)?;
+        universe.start_log(format_args!("seeded {} {} {} {}", width, height, density, seed));
+        Ok(universe)
+    }
+
+    /// Rust side of `replay`
+    pub fn replay_str(log: &str) -> Result<Universe, LifeError> {
+        Universe::replay_until(log, u64::MAX)
+    }
+
+    /// Replay `log` only as far as `generation`, including any edits made
+    /// in that generation
+    pub fn replay_until(log: &str, generation: u64) -> Result<Universe, LifeError> {
+        let mut lines = log.lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let error = |line, message: &str| ReplayError { line, message: message.to_string() };
+
+        match lines.next() {
+            Some((_, line)) if line == format!("replay {}", VERSION) => {}
+            Some((number, _)) => return Err(error(number, "not a replay log, or an unsupported version").into()),
+            None => return Err(error(1, "log is empty").into()),
+        }
+
+        let mut universe: Option<Universe> = None;
+        for (number, line) in lines {
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap();
+            let args = words.collect::<Vec<_>>();
+            let arg = |idx: usize| args.get(idx).copied().ok_or_else(|| error(number, "missing argument"));
+            let number_arg = |idx: usize| arg(idx)?.parse::<u64>().map_err(|_| error(number, "invalid number"));
+            let size_arg = |idx: usize| arg(idx)?.parse::<u32>().map_err(|_| error(number, "invalid size"));
+            let density_arg = |idx: usize| arg(idx)?.parse::<f32>().map_err(|_| error(number, "invalid density"));
+
+            let current = match (keyword, universe.as_mut()) {
+                ("seeded", None) => {
+                    let (width, height) = (size_arg(0)?, size_arg(1)?);
+                    let seeded = Universe::random_seeded(width, height, density_arg(2)?, number_arg(3)?)
+                        .map_err(|err| error(number, &err.to_string()))?;
+                    universe = Some(seeded);
+                    continue;
+                }
+                ("snapshot", None) => {
+                    let mut snapshot = Universe::decode_base64(arg(1)?).map_err(|err| error(number, &err.to_string()))?;
+                    snapshot.generation = number_arg(0)?;
+                    universe = Some(snapshot);
+                    continue;
+                }
+                (_, None) => return Err(error(number, "log has no starting state").into()),
+                ("seeded", Some(_)) | ("snapshot", Some(_)) => {
+                    return Err(error(number, "log has two starting states").into());
+                }
+                (_, Some(current)) => current,
+            };
+
+            match keyword {
+                "generation" => {
+                    // Logs from before stepping back was one line carry
+                    // its edits instead, and no history to step back with
+                    current.step_back();
+                    current.generation = number_arg(0)?;
+                }
+                "history" => current.set_history_capacity(size_arg(0)?),
+                "rule" => current.set_life_rule(arg(0)?.parse::<Rule>().map_err(|err| error(number, &err.to_string()))?),
+                "boundary" => {
+                    let name = arg(0)?;
+                    let boundary = BOUNDARIES.iter()
+                        .find(|boundary| format!("{:?}", boundary) == name)
+                        .ok_or_else(|| error(number, "unknown boundary"))?;
+                    current.set_boundary(*boundary);
+                }
+                "tick" => {
+                    let requested = number_arg(0)?;
+                    let ticks = requested.min(generation.saturating_sub(current.generation));
+                    for _ in 0..ticks {
+                        current.tick();
+                    }
+                    if ticks < requested {
+                        break;
+                    }
+                }
+                "edit" => {
+                    let idx = usize::try_from(number_arg(0)?).ok()
+                        .filter(|&idx| idx < current.cells.len())
+                        .ok_or_else(|| error(number, "cell index out of range"))?;
+                    let cell = match number_arg(1)? {
+                        0 => Cell::Dead,
+                        1 => Cell::Alive,
+                        _ => return Err(error(number, "cell state must be 0 or 1").into()),
+                    };
+                    current.write_cell(idx, cell);
+                }
+                "randomize" => current.randomize(density_arg(0)?, number_arg(1)?),
+                "randomize_region" => {
+                    let (top, left, rows, columns) = (size_arg(0)?, size_arg(1)?, size_arg(2)?, size_arg(3)?);
+                    current.randomize_region(top, left, rows, columns, density_arg(4)?, number_arg(5)?);
+                }
+                "clear" => current.clear(),
+                _ => return Err(error(number, &format!("unknown entry '{}'", keyword)).into()),
+            }
+        }
+
+        universe.ok_or_else(|| error(1, "log has no starting state").into())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start recording a replay log from the current state, replacing any
+    /// log already being recorded
+    pub fn start_replay_log(&mut self) {
+        let (generation, snapshot) = (self.generation, self.to_base64());
+        self.start_log(format_args!("snapshot {} {}", generation, snapshot));
+    }
+
+    pub fn stop_replay_log(&mut self) {
+        self.replay = None;
+    }
+
+    /// The log recorded so far, or an empty string while not recording
+    pub fn replay_log(&self) -> String {
+        match &self.replay {
+            Some(log) if log.ticks > 0 => format!("{}tick {}\n", log.text, log.ticks),
+            Some(log) => log.text.clone(),
+            None => String::new(),
+        }
+    }
+
+    /// Rebuild the universe a replay log ends with. Throws if the log can't
+    /// be read
+    #[cfg(feature = "wasm")]
+    pub fn replay(log: &str) -> Result<Universe, JsValue> {
+        Universe::replay_str(log).map_err(crate::error::js_error)
+    }
+
+    /// Rebuild the universe as it was at `generation` partway through a log
+    #[cfg(feature = "wasm")]
+    pub fn replay_to(log: &str, generation: u64) -> Result<Universe, JsValue> {
+        Universe::replay_until(log, generation).map_err(crate::error::js_error)
+    }
+}
+
+/// Same as `generate_universe_seeded`, recording a replay log from the seed
+#[cfg(feature = "wasm")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn generate_universe_recorded(width: u32, height: u32, density: f32, seed: u64) -> Result<Universe, JsValue> {
+    Universe::random_recorded(width, height, density, seed).map_err(crate::error::js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_rebuilds_run() {
+        let mut universe = Universe::random_recorded(24, 24, 0.3, 8).unwrap();
+        for _ in 0..10 {
+            universe.tick();
+        }
+        universe.toggle_cell(3, 4);
+        universe.set_cells(&[40, 41, 42]);
+        universe.set_life_rule("B36/S23".parse().unwrap());
+        universe.tick();
+        universe.randomize(0.5, 3);
+        universe.randomize_region(4, 20, 8, 8, 0.6, 2);
+        universe.set_boundary(Boundary::KleinBottle);
+        universe.set_history_capacity(1);
+        universe.tick();
+        universe.tick();
+        universe.step_back();
+
+        let log = universe.replay_log();
+        assert!(log.starts_with("replay 1\nseeded 24 24 0.3 8\nrule B3/S23\nboundary Torus\ntick 10\nedit 76 "));
+        assert!(log.contains("randomize 0.5 3\nrandomize_region 4 20 8 8 0.6 2\nboundary KleinBottle\nhistory 1\ntick 2\n"));
+        assert!(log.ends_with("\ngeneration 12\n"));
+        let replayed = Universe::replay_str(&log).unwrap();
+        assert_eq!(universe.cells, replayed.cells);
+        assert_eq!(12, replayed.generation());
+
+        // Stopping partway keeps the edits made at that generation
+        let partway = Universe::replay_until(&log, 10).unwrap();
+        assert_eq!(10, partway.generation());
+        assert!(partway.is_alive(1, 16) && partway.is_alive(1, 17) && partway.is_alive(1, 18));
+    }
+
+    #[test]
+    fn replay_from_snapshot() {
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        universe.tick();
+        universe.start_replay_log();
+        universe.clear();
+        universe.toggle_cell(0, 0);
+        universe.tick();
+        let log = universe.replay_log();
+        assert!(log.starts_with("replay 1\nsnapshot 1 "));
+        assert!(log.ends_with("\nrule B3/S23\nboundary Torus\nclear\nedit 0 1\ntick 1\n"));
+
+        let replayed = Universe::replay_str(&log).unwrap();
+        assert_eq!(universe.cells, replayed.cells);
+        assert_eq!(2, replayed.generation());
+
+        universe.stop_replay_log();
+        assert_eq!("", universe.replay_log());
+    }
+
+    #[test]
+    fn bad_logs() {
+        let error = |log: &str| Universe::replay_str(log).err().unwrap();
+        let line = |log: &str| match error(log) {
+            LifeError::ParseError { line, .. } => line,
+            err => panic!("expected a parse error, got {}", err),
+        };
+        assert_eq!(1, line(""));
+        assert_eq!(1, line("replay 2\n"));
+        assert_eq!(2, line("replay 1\ntick 3\n"));
+        assert_eq!(3, line("replay 1\nseeded 4 4 0.5 1\nedit 16 1\n"));
+        assert_eq!(3, line("replay 1\nseeded 4 4 0.5 1\nboundary Cylinder\n"));
+        // Sizes past u32 used to wrap round to a small board
+        assert_eq!("parse error at line 2: invalid size", error("replay 1\nseeded 4294967300 4 0.5 1\n").to_string());
+        assert_eq!(3, line("replay 1\nseeded 4 4 0.5 1\nedit 18446744073709551615 1\n"));
+        assert_eq!("parse error at line 3: unknown entry 'paste'", error("replay 1\nseeded 4 4 0.5 1\npaste\n").to_string());
+    }
+}
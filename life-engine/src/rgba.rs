@@ -0,0 +1,85 @@
+// Seeding a universe from an image
+//
+// `from_rgba()` takes pixels the way a canvas's `getImageData()` hands them
+// over, four bytes a pixel (red, green, blue, alpha) in rows, and makes each
+// pixel a cell: alive where the pixel is darker than `threshold`, dead
+// where it's lighter. That's the same way round as the PNG snapshots, so
+// dark text or a logo on a light background comes out alive. Brightness is
+// the usual luma weighting of the colours, with see-through pixels laid
+// over white, so the empty parts of a transparent canvas are dead.
+//
+// Scale the image to the universe's size before taking its pixels; a
+// webcam frame drawn onto a 160x120 canvas gives a 160x120 universe
+
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Brightness from 0 to 255 of one RGBA pixel, over white
+fn brightness(pixel: &[u8]) -> u32 {
+    let luma = (299 * pixel[0] as u32 + 587 * pixel[1] as u32 + 114 * pixel[2] as u32) / 1000;
+    let alpha = pixel[3] as u32;
+    (luma * alpha + 255 * (255 - alpha)) / 255
+}
+
+impl Universe {
+    /// Rust side of `from_rgba`
+    pub fn decode_rgba(width: u32, height: u32, data: &[u8], threshold: u8) -> Result<Universe, LifeError> {
+        let size = check_size(width, height)?;
+        if data.len() != size * 4 {
+            return Err(LifeError::InvalidArgument(format!(
+                "a {}x{} image is {} bytes of RGBA but got {}",
+                width,
+                height,
+                size * 4,
+                data.len()
+            )));
+        }
+        let cells = data
+            .chunks_exact(4)
+            .map(|pixel| if brightness(pixel) < threshold as u32 { Cell::Alive } else { Cell::Dead })
+            .collect();
+        Ok(Universe::from_cells(width, height, cells))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// A universe with a cell for each pixel of `data`, RGBA in rows as
+    /// from `getImageData()`, alive where the pixel is darker than
+    /// `threshold`
+    #[cfg(feature = "wasm")]
+    pub fn from_rgba(width: u32, height: u32, data: &[u8], threshold: u8) -> Result<Universe, JsValue> {
+        Universe::decode_rgba(width, height, data, threshold).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_pixels() {
+        // Black, white, mid grey, red, then transparent black and half
+        // transparent black
+        let data = [
+            0, 0, 0, 255, 255, 255, 255, 255, 128, 128, 128, 255, //
+            255, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 128,
+        ];
+        let universe = Universe::decode_rgba(3, 2, &data, 128).unwrap();
+        assert_eq!(vec![(0, 0), (1, 0), (1, 2)], universe.live_cells().collect::<Vec<_>>());
+
+        let universe = Universe::decode_rgba(3, 2, &data, 200).unwrap();
+        assert_eq!(
+            vec![(0, 0), (0, 2), (1, 0), (1, 2)],
+            universe.live_cells().collect::<Vec<_>>()
+        );
+        assert_eq!(0, Universe::decode_rgba(3, 2, &data, 0).unwrap().population());
+
+        assert!(matches!(Universe::decode_rgba(3, 2, &data[..20], 128), Err(LifeError::InvalidArgument(_))));
+        assert!(matches!(Universe::decode_rgba(0, 2, &[], 128), Err(LifeError::InvalidDimensions { .. })));
+    }
+}
@@ -0,0 +1,197 @@
+// Reading and writing RLE, the format pattern collections and other Life
+// programs share
+//
+//     #N Glider
+//     x = 3, y = 3, rule = B3/S23
+//     bo$2bo$3o!
+//
+// Runs of `b` (dead) and `o` (alive) cells, with `$` ending a row. Dead
+// cells at the end of a row and empty rows at the end are left out, as
+// readers fill them in from the header. Lines wrap at 70 characters like
+// most files in the wild. When reading, `#` comment lines are skipped, the
+// rule is optional and anything after the `!` is ignored
+
+use crate::error::LifeError;
+use crate::{Cell, Rect, Rule, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const LINE_LENGTH: usize = 70;
+
+// Collects the items, wrapping lines without splitting any
+struct Writer {
+    text: String,
+    line: usize,
+}
+
+impl Writer {
+    fn push(&mut self, count: u32, tag: char) {
+        let item = if count == 1 { tag.to_string() } else { format!("{}{}", count, tag) };
+        if self.line + item.len() > LINE_LENGTH {
+            self.text.push('\n');
+            self.line = 0;
+        }
+        self.line += item.len();
+        self.text.push_str(&item);
+    }
+}
+
+impl Universe {
+    // The cells in `area`, which has to be on the board, as RLE
+    pub(crate) fn rle(&self, area: Rect) -> String {
+        let mut writer = Writer {
+            text: format!("x = {}, y = {}, rule = {}\n", area.columns, area.rows, self.rule),
+            line: 0,
+        };
+
+        // Row ends owed, held back until there's a live cell after them
+        let mut row_ends = 0;
+        for row in area.top..area.top + area.rows {
+            let mut runs: Vec<(bool, u32)> = vec![];
+            for column in area.left..area.left + area.columns {
+                let alive = self.is_alive(row, column);
+                match runs.last_mut() {
+                    Some((state, count)) if *state == alive => *count += 1,
+                    _ => runs.push((alive, 1)),
+                }
+            }
+            if let Some((false, _)) = runs.last() {
+                runs.pop();
+            }
+
+            if !runs.is_empty() && row_ends > 0 {
+                writer.push(row_ends, '$');
+                row_ends = 0;
+            }
+            for (alive, count) in runs {
+                writer.push(count, if alive { 'o' } else { 'b' });
+            }
+            row_ends += 1;
+        }
+
+        writer.text.push('!');
+        writer.text
+    }
+
+    /// Rust side of `from_rle`
+    pub fn from_rle_str(text: &str) -> Result<Universe, LifeError> {
+        let error = |line: usize, column: usize, message: &str| {
+            LifeError::ParseError { line, column, message: message.to_string() }
+        };
+        let mut lines = text.lines()
+            .enumerate()
+            .map(|(number, line)| (number + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let (header_line, header) = lines.next().ok_or_else(|| error(1, 1, "no header found"))?;
+        let (mut width, mut height, mut rule) = (None, None, Rule::CONWAY);
+        for item in header.split(',') {
+            let (key, value) = item.split_once('=')
+                .ok_or_else(|| error(header_line, 1, "header items look like `x = 3`"))?;
+            let (key, value) = (key.trim(), value.trim());
+            match key {
+                "x" | "y" => {
+                    let size = value.parse::<u32>().map_err(|_| error(header_line, 1, "sizes are whole numbers"))?;
+                    if key == "x" { width = Some(size) } else { height = Some(size) }
+                }
+                "rule" => rule = value.parse()?,
+                _ => {}
+            }
+        }
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => return Err(error(header_line, 1, "the header needs both x and y")),
+        };
+        crate::size::check_size(width, height)?;
+
+        let mut cells = vec![Cell::Dead; width as usize * height as usize];
+        let (mut row, mut column, mut count) = (0u32, 0u32, None::<u32>);
+        'body: for (number, line) in lines {
+            for (position, glyph) in line.char_indices() {
+                if let Some(digit) = glyph.to_digit(10) {
+                    count = Some(count.unwrap_or(0).checked_mul(10).and_then(|run| run.checked_add(digit))
+                        .ok_or_else(|| error(number, position + 1, "run too long"))?);
+                    continue;
+                }
+                let run = count.take().unwrap_or(1);
+                match glyph {
+                    'b' | '.' => column = column.saturating_add(run),
+                    'o' | 'A'..='Z' => {
+                        if row >= height || run > width.saturating_sub(column) {
+                            return Err(error(number, position + 1, "cells past the size in the header"));
+                        }
+                        let start = crate::size::index(width, row, column);
+                        cells[start..start + run as usize].fill(Cell::Alive);
+                        column += run;
+                    }
+                    '$' => {
+                        row = row.saturating_add(run);
+                        column = 0;
+                    }
+                    '!' => break 'body,
+                    _ if glyph.is_whitespace() => {}
+                    _ => return Err(error(number, position + 1, &format!("unexpected '{}'", glyph))),
+                }
+            }
+        }
+
+        let mut universe = Universe::from_cells(width, height, cells);
+        universe.set_life_rule(rule);
+        Ok(universe)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// A universe the size of the RLE's header with its cells and rule
+    #[cfg(feature = "wasm")]
+    pub fn from_rle(text: &str) -> Result<Universe, JsValue> {
+        Universe::from_rle_str(text).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_rle() {
+        let glider = Universe::new(5, 5, vec![(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+        assert_eq!("x = 3, y = 3, rule = B3/S23\nbo$2bo$3o!", glider.rle(Rect::new(1, 1, 3, 3)));
+
+        // Empty rows in the middle merge, and at the end go
+        let universe = Universe::new(4, 6, vec![(0, 0), (3, 3)]);
+        assert_eq!("x = 4, y = 6, rule = B3/S23\no3$3bo!", universe.rle(universe.bounds()));
+        assert_eq!("x = 2, y = 2, rule = B3/S23\n!", universe.rle(Rect::new(1, 1, 2, 2)));
+
+        // Long lines wrap between items
+        let stripes = Universe::new(100, 1, (0..50).map(|column| (0, column * 2)).collect());
+        let rle = stripes.rle(stripes.bounds());
+        assert!(rle.lines().all(|line| line.len() <= 70));
+        assert_eq!(100, rle.lines().skip(1).collect::<String>().matches(['o', 'b']).count() + 1);
+    }
+
+    #[test]
+    fn reads_rle() {
+        let glider = Universe::new(5, 5, vec![(1, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+        let read = Universe::from_rle_str(&glider.rle(Rect::new(1, 1, 3, 3))).unwrap();
+        assert_eq!(vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)], read.live_cells().collect::<Vec<_>>());
+
+        // Comments, wrapped lines, long runs and the rule
+        let text = "#N Strip\n#C Comment\nx = 12, y = 3, rule = B36/S23\n11o$\n$b\nobo!\ntrailing text";
+        let read = Universe::from_rle_str(text).unwrap();
+        assert_eq!((12, 3, 13), (read.width(), read.height(), read.population()));
+        assert_eq!("B36/S23", read.rule());
+
+        let error = |text: &str| match Universe::from_rle_str(text) {
+            Err(LifeError::ParseError { line, column, .. }) => (line, column),
+            _ => panic!("{} should fail to parse", text),
+        };
+        assert_eq!((1, 1), error("bo$2bo$3o!"));
+        assert_eq!((2, 5), error("x = 2, y = 2\nbo$3o!"));
+        assert_eq!((3, 4), error("x = 2, y = 3\nbo$\n$o$o!"));
+        assert_eq!((2, 2), error("x = 2, y = 2\nbx!"));
+        assert!(Universe::from_rle_str("x = 0, y = 2\n!").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+// Random numbers for random fills, edge injection and stochastic rules
+//
+// With the default `os-rng` feature these come from rand's StdRng, and fills
+// without a seed draw from the OS (the browser's crypto API in WASM, through
+// getrandom). The `small-rng` feature swaps in the PCG32 below instead,
+// seeded from the clock when no seed is given. That's a few lines rather
+// than ChaCha plus getrandom, for a noticeably smaller WASM binary when
+// nothing needs cryptographic quality randomness. Build without default
+// features to drop getrandom altogether
+//
+// The same seed gives different cells under the two, so saved seeds and
+// replay logs only reproduce on builds with the same choice. The default
+// names ChaCha12, StdRng's generator, directly: the numbers are the same,
+// but its position can be saved and restored
+
+use rand::{Error, Rng, RngCore, SeedableRng};
+
+#[cfg(all(feature = "os-rng", not(feature = "small-rng")))]
+pub(crate) type SeededRng = rand_chacha::ChaCha12Rng;
+#[cfg(any(feature = "small-rng", not(feature = "os-rng")))]
+pub(crate) type SeededRng = Pcg32;
+
+pub(crate) fn seeded(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
+
+/// Everything needed to carry on from where `rng` is: the key, stream and
+/// position
+#[cfg(all(feature = "os-rng", not(feature = "small-rng")))]
+pub(crate) fn rng_state(rng: &SeededRng) -> Vec<u8> {
+    let mut state = rng.get_seed().to_vec();
+    state.extend_from_slice(&rng.get_stream().to_le_bytes());
+    state.extend_from_slice(&rng.get_word_pos().to_le_bytes());
+    state
+}
+
+/// A generator from `rng_state()`, or None if `state` came from a build
+/// using the other generator
+#[cfg(all(feature = "os-rng", not(feature = "small-rng")))]
+pub(crate) fn rng_from_state(state: &[u8]) -> Option<SeededRng> {
+    if state.len() != 56 {
+        return None;
+    }
+    let mut seed = [0; 32];
+    seed.copy_from_slice(&state[..32]);
+    let mut stream = [0; 8];
+    stream.copy_from_slice(&state[32..40]);
+    let mut word_pos = [0; 16];
+    word_pos.copy_from_slice(&state[40..]);
+
+    let mut rng = SeededRng::from_seed(seed);
+    rng.set_stream(u64::from_le_bytes(stream));
+    rng.set_word_pos(u128::from_le_bytes(word_pos));
+    Some(rng)
+}
+
+/// Everything needed to carry on from where `rng` is
+#[cfg(any(feature = "small-rng", not(feature = "os-rng")))]
+pub(crate) fn rng_state(rng: &SeededRng) -> Vec<u8> {
+    let mut state = rng.state.to_le_bytes().to_vec();
+    state.extend_from_slice(&rng.increment.to_le_bytes());
+    state
+}
+
+/// A generator from `rng_state()`, or None if `state` came from a build
+/// using the other generator
+#[cfg(any(feature = "small-rng", not(feature = "os-rng")))]
+pub(crate) fn rng_from_state(state: &[u8]) -> Option<SeededRng> {
+    if state.len() != 16 {
+        return None;
+    }
+    let mut half = [0; 8];
+    half.copy_from_slice(&state[..8]);
+    let rng_state = u64::from_le_bytes(half);
+    half.copy_from_slice(&state[8..]);
+    Some(Pcg32 { state: rng_state, increment: u64::from_le_bytes(half) })
+}
+
+/// Generator for fills where the caller doesn't care about reproducing them
+#[cfg(all(feature = "os-rng", not(feature = "small-rng")))]
+pub(crate) fn unseeded() -> impl Rng {
+    rand::thread_rng()
+}
+
+/// Generator for fills where the caller doesn't care about reproducing them
+#[cfg(any(feature = "small-rng", not(feature = "os-rng")))]
+pub(crate) fn unseeded() -> impl Rng {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Two fills in the same clock tick still differ
+    static CALLS: AtomicU64 = AtomicU64::new(0);
+    let calls = CALLS.fetch_add(1, Ordering::Relaxed);
+    seeded(clock_seed() ^ calls.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+#[cfg(all(any(feature = "small-rng", not(feature = "os-rng")), target_arch = "wasm32", feature = "wasm"))]
+fn clock_seed() -> u64 {
+    // Microseconds since the epoch, plus the sub-millisecond part of the
+    // page's own clock
+    (js_sys::Date::now() * 1000.0) as u64 ^ (crate::utils::now_ms().fract() * 1e9) as u64
+}
+
+#[cfg(all(any(feature = "small-rng", not(feature = "os-rng")), not(all(target_arch = "wasm32", feature = "wasm"))))]
+fn clock_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_nanos() as u64).unwrap_or(0)
+}
+
+// PCG32 (XSH RR), see https://www.pcg-random.org
+#[cfg_attr(all(feature = "os-rng", not(feature = "small-rng")), allow(dead_code))]
+#[derive(Clone, Debug)]
+pub(crate) struct Pcg32 {
+    state: u64,
+    // Must be odd
+    increment: u64,
+}
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+impl RngCore for Pcg32 {
+    fn next_u32(&mut self) -> u32 {
+        let state = self.state;
+        self.state = state.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        xorshifted.rotate_right((state >> 59) as u32)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let low = self.next_u32() as u64;
+        (self.next_u32() as u64) << 32 | low
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            chunk.copy_from_slice(&self.next_u32().to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    // State then stream
+    type Seed = [u8; 16];
+
+    fn from_seed(seed: [u8; 16]) -> Pcg32 {
+        let mut half = [0; 8];
+        half.copy_from_slice(&seed[..8]);
+        let state = u64::from_le_bytes(half);
+        half.copy_from_slice(&seed[8..]);
+
+        // The reference initialisation, stepping either side of adding the
+        // state so similar seeds don't start out alike
+        let mut rng = Pcg32 { state: 0, increment: u64::from_le_bytes(half) << 1 | 1 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(state);
+        rng.next_u32();
+        rng
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pcg32_reference_output() {
+        // pcg32-demo from the reference implementation, seeded with
+        // state 42 and stream 54
+        let mut seed = [0; 16];
+        seed[..8].copy_from_slice(&42u64.to_le_bytes());
+        seed[8..].copy_from_slice(&54u64.to_le_bytes());
+        let mut rng = Pcg32::from_seed(seed);
+        let output = (0..6).map(|_| rng.next_u32()).collect::<Vec<_>>();
+        assert_eq!(vec![0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e], output);
+
+        // Whichever generator is in use, seeds are reproducible
+        let numbers = |seed| {
+            let mut rng = seeded(seed);
+            (0..64).map(|_| rng.gen::<u32>()).collect::<Vec<_>>()
+        };
+        assert_eq!(numbers(7), numbers(7));
+        assert_ne!(seeded(7).gen::<u64>(), seeded(8).gen::<u64>());
+    }
+
+    #[test]
+    fn state_round_trip() {
+        #[cfg(all(feature = "os-rng", not(feature = "small-rng")))]
+        {
+            // Still StdRng's numbers, so seeds give the cells they used to
+            let mut std_rng = rand::rngs::StdRng::seed_from_u64(5);
+            let mut rng = seeded(5);
+            assert!((0..40).all(|_| std_rng.gen::<u64>() == rng.gen::<u64>()));
+        }
+
+        // Part way through a block, and with a u32 left over
+        let mut rng = seeded(3);
+        for _ in 0..21 {
+            rng.next_u32();
+        }
+        let mut restored = rng_from_state(&rng_state(&rng)).unwrap();
+        assert!((0..100).all(|_| rng.next_u32() == restored.next_u32()));
+        assert!(rng_from_state(&[0; 3]).is_none());
+    }
+}
@@ -0,0 +1,545 @@
+// Life-like rules in B/S notation, e.g. B3/S23 for Conway's Life or B36/S23
+// for HighLife
+//
+// A rule is the set of neighbour counts which bring a dead cell to life and
+// the set which keep a live cell alive, stored as bit masks. The older S/B
+// form ("23/3") is accepted too. Rules are part of the core engine so this
+// only needs `core` and `alloc` without the `std` feature
+//
+// Counts can be narrowed with Hensel letters to isotropic non-totalistic
+// rules, which look at where the neighbours are as well as how many there
+// are. "2a" is two neighbours side by side around a corner, "2-a" any other
+// pair, and so on, with letters standing for an arrangement along with its
+// rotations and reflections:
+//
+//     B2-a/S12       Snowflakes
+//     B3/S2-i34q     tlife
+//
+// Those rules keep the set of neighbourhoods (the 8 neighbours as bits
+// clockwise from north) giving birth and survival, so ticking them needs
+// `next_neighbourhood()` rather than `next()`
+//
+// Rules with B0 bring empty space to life, see strobing.rs for how a
+// universe runs them
+
+use crate::Cell;
+#[cfg(feature = "std")]
+use crate::{TimelineKind, Universe};
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    // Bit n set when n alive neighbours give birth/survival
+    birth: u16,
+    survival: u16,
+    // Birth and survival neighbourhoods of non-totalistic rules, None when
+    // only the counts matter. The masks above then hold the counts where
+    // every arrangement gives birth/survival
+    hensel: Option<[Neighbourhoods; 2]>,
+}
+
+// Set of neighbourhoods, bit n for the neighbours making n as bits clockwise
+// from north
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+struct Neighbourhoods([u64; 4]);
+
+impl Neighbourhoods {
+    fn contains(&self, neighbourhood: u8) -> bool {
+        self.0[neighbourhood as usize >> 6] & 1 << (neighbourhood & 63) != 0
+    }
+
+    fn insert(&mut self, neighbourhood: u8) {
+        self.0[neighbourhood as usize >> 6] |= 1 << (neighbourhood & 63);
+    }
+
+    fn remove(&mut self, neighbourhood: u8) {
+        self.0[neighbourhood as usize >> 6] &= !(1 << (neighbourhood & 63));
+    }
+
+    fn from_counts(mask: u16) -> Neighbourhoods {
+        let mut set = Neighbourhoods::default();
+        for neighbourhood in 0..=255u8 {
+            if mask & 1 << neighbourhood.count_ones() != 0 {
+                set.insert(neighbourhood);
+            }
+        }
+        set
+    }
+
+    // Counts where every arrangement is in the set
+    fn whole_counts(&self) -> u16 {
+        let mut mask = 0x1ff;
+        for neighbourhood in (0..=255u8).filter(|&neighbourhood| !self.contains(neighbourhood)) {
+            mask &= !(1 << neighbourhood.count_ones());
+        }
+        mask
+    }
+}
+
+// Neighbour offsets (row, column) clockwise from north, the bit order of a
+// neighbourhood
+pub(crate) const NEIGHBOURS: [(i64, i64); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+
+// Hensel letters for 1 to 4 neighbours, in their usual order, with one
+// arrangement each picks out. The rest are its rotations and reflections.
+// 5 to 7 neighbours use the same letters for the complements of 3 to 1, and
+// 0 and 8 have just the one arrangement
+const HENSEL: [&[(char, u8)]; 5] = [
+    &[],
+    &[('c', 0b0000_1000), ('e', 0b0001_0000)],
+    &[('c', 0b0010_1000), ('e', 0b0001_0100), ('k', 0b0010_0100), ('a', 0b0001_1000), ('i', 0b0100_0100), ('n', 0b0010_0010)],
+    &[
+        ('c', 0b0010_1010), ('e', 0b0101_0100), ('k', 0b0101_0010), ('a', 0b0001_1100), ('i', 0b0011_1000),
+        ('n', 0b0010_1100), ('y', 0b0100_1010), ('q', 0b0011_0010), ('j', 0b0011_0100), ('r', 0b0100_1100),
+    ],
+    &[
+        ('c', 0b1010_1010), ('e', 0b0101_0101), ('k', 0b0101_1010), ('a', 0b0011_1100), ('i', 0b0110_1100),
+        ('n', 0b0011_1010), ('y', 0b0110_1010), ('q', 0b0111_0010), ('j', 0b0101_0110), ('r', 0b0101_1100),
+        ('t', 0b0100_1110), ('w', 0b0011_0110), ('z', 0b0110_0110),
+    ],
+];
+
+fn letters(count: u8) -> &'static [(char, u8)] {
+    HENSEL[count.min(8 - count) as usize]
+}
+
+// Every neighbourhood a letter stands for with `count` neighbours
+fn arrangements(count: u8, arrangement: u8) -> [u8; 8] {
+    let mut symmetric = [0; 8];
+    for (idx, rotation) in (0..8).step_by(2).enumerate() {
+        let rotated = arrangement.rotate_right(rotation);
+        symmetric[2 * idx] = rotated;
+        // Mirrored left to right, keeping north and south in place
+        symmetric[2 * idx + 1] = rotated.reverse_bits().rotate_left(1);
+    }
+    if count > 4 {
+        symmetric.iter_mut().for_each(|neighbourhood| *neighbourhood = !*neighbourhood);
+    }
+    symmetric
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleError {
+    pub message: String,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid rule: {}", self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RuleError {}
+
+// Neighbour counts, each taken whole or narrowed by Hensel letters ("2ak")
+// or with them left out ("2-ak")
+fn conditions(text: &str) -> Result<Neighbourhoods, RuleError> {
+    let error = |message: String| Err(RuleError { message });
+    let mut set = Neighbourhoods::default();
+    let mut chars = text.chars().peekable();
+    while let Some(digit) = chars.next() {
+        let count = match digit.to_digit(10) {
+            Some(count) if count <= 8 => count as u8,
+            _ => return error(format!("'{}' isn't a neighbour count from 0 to 8", digit)),
+        };
+        let letters = letters(count);
+        let excluding = chars.next_if_eq(&'-').is_some();
+
+        // Bit n for the nth letter named
+        let mut named = 0u16;
+        while let Some(letter) = chars.next_if(char::is_ascii_alphabetic) {
+            match letters.iter().position(|&(name, _)| name == letter) {
+                Some(idx) => named |= 1 << idx,
+                None => return error(format!("'{}' isn't a Hensel letter for {} neighbours", letter, count)),
+            }
+        }
+
+        if named == 0 {
+            if excluding {
+                return error(format!("expected letters after '{}-'", count));
+            }
+            for neighbourhood in (0..=255u8).filter(|neighbourhood| neighbourhood.count_ones() == count as u32) {
+                set.insert(neighbourhood);
+            }
+        }
+        for (idx, &(_, arrangement)) in letters.iter().enumerate() {
+            if named != 0 && (named & 1 << idx != 0) != excluding {
+                arrangements(count, arrangement).iter().for_each(|&neighbourhood| set.insert(neighbourhood));
+            }
+        }
+    }
+    Ok(set)
+}
+
+fn write_counts(f: &mut fmt::Formatter, mask: u16) -> fmt::Result {
+    for count in 0..=8 {
+        if mask & (1 << count) != 0 {
+            write!(f, "{}", count)?;
+        }
+    }
+    Ok(())
+}
+
+// Counts with letters for those only partly in the set, using whichever of
+// the letters in or out is shorter
+fn write_conditions(f: &mut fmt::Formatter, set: &Neighbourhoods) -> fmt::Result {
+    for count in 0..=8u8 {
+        let letters = letters(count);
+        if letters.is_empty() {
+            if set.contains(if count == 0 { 0 } else { 0xff }) {
+                write!(f, "{}", count)?;
+            }
+            continue;
+        }
+
+        let taken = letters.iter().filter(|&&(_, arrangement)| set.contains(arrangements(count, arrangement)[0])).count();
+        if taken == 0 {
+            continue;
+        }
+        write!(f, "{}", count)?;
+        if taken < letters.len() {
+            let excluding = taken * 2 > letters.len();
+            if excluding {
+                write!(f, "-")?;
+            }
+            for &(letter, arrangement) in letters {
+                if set.contains(arrangements(count, arrangement)[0]) != excluding {
+                    write!(f, "{}", letter)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule { birth: 1 << 3, survival: 1 << 2 | 1 << 3, hensel: None };
+
+    /// `birth` and `survival` list the neighbour counts, e.g. `&[3]` and
+    /// `&[2, 3]` for Conway's Life. Counts above 8 are ignored
+    pub fn new(birth: &[u8], survival: &[u8]) -> Rule {
+        let mask = |counts: &[u8]| counts.iter().filter(|&&n| n <= 8).fold(0, |mask, &n| mask | 1 << n);
+        Rule { birth: mask(birth), survival: mask(survival), hensel: None }
+    }
+
+    fn from_neighbourhoods(birth: Neighbourhoods, survival: Neighbourhoods) -> Rule {
+        let (birth_counts, survival_counts) = (birth.whole_counts(), survival.whole_counts());
+        let totalistic = birth == Neighbourhoods::from_counts(birth_counts)
+            && survival == Neighbourhoods::from_counts(survival_counts);
+        Rule { birth: birth_counts, survival: survival_counts, hensel: if totalistic { None } else { Some([birth, survival]) } }
+    }
+
+    /// Whether only the neighbour count matters, false for rules using
+    /// Hensel letters
+    pub fn is_totalistic(&self) -> bool {
+        self.hensel.is_none()
+    }
+
+    /// Next state from the neighbour count. Under a non-totalistic rule only
+    /// counts where every arrangement gives birth or survival do, see
+    /// `next_neighbourhood()`
+    pub fn next(&self, current_cell: Cell, alive_neighbour_count: u8) -> Cell {
+        let mask = match current_cell {
+            Cell::Dead => self.birth,
+            Cell::Alive => self.survival,
+        };
+        if mask & (1 << alive_neighbour_count) != 0 { Cell::Alive } else { Cell::Dead }
+    }
+
+    /// Next state from the neighbours as bits clockwise from north (bit 0
+    /// north, bit 1 north east and so on), right for every rule
+    pub fn next_neighbourhood(&self, current_cell: Cell, neighbourhood: u8) -> Cell {
+        let set = match (&self.hensel, current_cell) {
+            (None, _) => return self.next(current_cell, neighbourhood.count_ones() as u8),
+            (Some([birth, _]), Cell::Dead) => birth,
+            (Some([_, survival]), Cell::Alive) => survival,
+        };
+        if set.contains(neighbourhood) { Cell::Alive } else { Cell::Dead }
+    }
+
+    /// Whether a dead cell with no live neighbours comes to life, so empty
+    /// space flashes on (and off again, unless there's S8 too)
+    pub fn has_b0(&self) -> bool {
+        self.birth & 1 != 0
+    }
+
+    // The rule as it acts on cells stored complemented, their live cells
+    // standing for dead ones, when `inverted` before a step and
+    // `inverted_next` after it. Complemented cells see their neighbourhood
+    // complemented too, and only ever use the other half of the rule
+    #[cfg(feature = "std")]
+    pub(crate) fn phase_rule(&self, inverted: bool, inverted_next: bool) -> Rule {
+        let [birth, survival] = self.hensel
+            .unwrap_or([Neighbourhoods::from_counts(self.birth), Neighbourhoods::from_counts(self.survival)]);
+        let (from_dead, from_alive) = if inverted { (survival, birth) } else { (birth, survival) };
+        let (mut births, mut survivals) = (Neighbourhoods::default(), Neighbourhoods::default());
+        for neighbourhood in 0..=255u8 {
+            let actual = if inverted { !neighbourhood } else { neighbourhood };
+            if from_dead.contains(actual) != inverted_next {
+                births.insert(neighbourhood);
+            }
+            if from_alive.contains(actual) != inverted_next {
+                survivals.insert(neighbourhood);
+            }
+        }
+        Rule::from_neighbourhoods(births, survivals)
+    }
+
+    /// Whether every arrangement of `count` neighbours gives birth, or
+    /// survival when `birth` is false
+    pub fn contains_count(&self, birth: bool, count: u8) -> bool {
+        let whole = if birth { self.birth } else { self.survival };
+        count <= 8 && whole & 1 << count != 0
+    }
+
+    /// The same rule with `count` neighbours giving birth (or survival when
+    /// `birth` is false), or no longer giving it if every arrangement of
+    /// that count already did. Counts above 8 leave the rule as it is
+    pub fn toggled(&self, birth: bool, count: u8) -> Rule {
+        if count > 8 {
+            return *self;
+        }
+        let [mut births, mut survivals] = self.hensel
+            .unwrap_or([Neighbourhoods::from_counts(self.birth), Neighbourhoods::from_counts(self.survival)]);
+        let removing = self.contains_count(birth, count);
+        let set = if birth { &mut births } else { &mut survivals };
+        for neighbourhood in (0..=255u8).filter(|neighbourhood| neighbourhood.count_ones() == count as u32) {
+            if removing {
+                set.remove(neighbourhood);
+            } else {
+                set.insert(neighbourhood);
+            }
+        }
+        Rule::from_neighbourhoods(births, survivals)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = RuleError;
+
+    fn from_str(text: &str) -> Result<Rule, RuleError> {
+        let text = text.trim();
+        let (first, second) = text.split_once('/')
+            .ok_or_else(|| RuleError { message: format!("expected B.../S... but found '{}'", text) })?;
+
+        let (birth, survival) = match (first.chars().next(), second.chars().next()) {
+            (Some('B' | 'b'), Some('S' | 's')) => (&first[1..], &second[1..]),
+            (Some('S' | 's'), Some('B' | 'b')) => (&second[1..], &first[1..]),
+            // Bare digits are survival/birth
+            _ => (second, first),
+        };
+        Ok(Rule::from_neighbourhoods(conditions(birth)?, conditions(survival)?))
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.hensel {
+            None => {
+                write!(f, "B")?;
+                write_counts(f, self.birth)?;
+                write!(f, "/S")?;
+                write_counts(f, self.survival)
+            }
+            Some([birth, survival]) => {
+                write!(f, "B")?;
+                write_conditions(f, birth)?;
+                write!(f, "/S")?;
+                write_conditions(f, survival)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Universe {
+    pub fn life_rule(&self) -> Rule {
+        self.rule
+    }
+
+    // Whether ticking needs each cell's neighbourhood rather than its count,
+    // which only a non-totalistic rule does. A transition function takes
+    // over from the rule, so then it doesn't
+    pub(crate) fn uses_neighbourhoods(&self) -> bool {
+        !self.rule.is_totalistic() && self.transition.is_none()
+    }
+
+    // Cells among `candidates` whose state differs next generation under a
+    // non-totalistic rule
+    pub(crate) fn neighbourhood_changes(&self, candidates: &[usize]) -> Vec<usize> {
+        candidates.iter()
+            .cloned()
+            .filter(|&idx| {
+                let neighbourhood = self.neighbourhood(&self.cells, idx as u32 / self.width, idx as u32 % self.width);
+                self.rule.next_neighbourhood(self.cells[idx], neighbourhood) != self.cells[idx]
+            })
+            .collect()
+    }
+
+    pub fn set_life_rule(&mut self, rule: Rule) {
+        if rule != self.rule {
+            self.rule = rule;
+            self.active_cells = None;
+            self.stability.reset();
+            self.timeline_record(TimelineKind::RuleChange(rule));
+            self.replay_record(format_args!("rule {}", rule));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// The rule in B/S notation
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Switch rule, e.g. "B36/S23"
+    #[cfg(feature = "wasm")]
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        let rule = rule.parse::<Rule>().map_err(crate::error::js_error)?;
+        self.set_life_rule(rule);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(Ok(Rule::CONWAY), "B3/S23".parse());
+        assert_eq!(Ok(Rule::CONWAY), "s32/b3".parse());
+        assert_eq!(Ok(Rule::CONWAY), "23/3".parse());
+        assert_eq!(Ok(Rule::new(&[3, 6], &[2, 3])), "B36/S23".parse());
+        assert_eq!("B36/S23", Rule::new(&[6, 3], &[3, 2]).to_string());
+        assert_eq!("B/S012345678", "B/S876543210".parse::<Rule>().unwrap().to_string());
+
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("B3S23".parse::<Rule>().is_err());
+        assert!("Bx/S23".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn hensel_parse_and_display() {
+        assert_eq!("B2-a/S12", "B2-a/S12".parse::<Rule>().unwrap().to_string());
+        assert_eq!("B3/S2-i34q", "b3/s2cekan34q".parse::<Rule>().unwrap().to_string());
+        assert_eq!("B2n3/S23", "B32n/S23".parse::<Rule>().unwrap().to_string());
+        assert_eq!("B2-n/S", "B2cekai/S".parse::<Rule>().unwrap().to_string());
+        // Naming every letter is the same as the plain count
+        assert_eq!(Ok(Rule::CONWAY), "B3cekainyqjr/S23".parse());
+        assert!(Rule::CONWAY.is_totalistic() && !"B2a/S".parse::<Rule>().unwrap().is_totalistic());
+
+        assert!("B2x/S23".parse::<Rule>().is_err());
+        assert!("B1k/S23".parse::<Rule>().is_err());
+        assert!("B0c/S23".parse::<Rule>().is_err());
+        assert_eq!("invalid rule: expected letters after '2-'", "B2-/S".parse::<Rule>().unwrap_err().to_string());
+    }
+
+    #[test]
+    fn hensel_letters_cover_every_arrangement() {
+        for count in 1..=7u8 {
+            // Letters never share an arrangement, and between them name
+            // every arrangement of the count
+            let mut seen = Neighbourhoods::default();
+            let mut total = 0;
+            for &(_, arrangement) in letters(count) {
+                let mut distinct = arrangements(count, arrangement);
+                distinct.sort_unstable();
+                for (idx, &neighbourhood) in distinct.iter().enumerate() {
+                    assert_eq!(count as u32, neighbourhood.count_ones());
+                    if idx == 0 || distinct[idx - 1] != neighbourhood {
+                        assert!(!seen.contains(neighbourhood));
+                        seen.insert(neighbourhood);
+                        total += 1;
+                    }
+                }
+            }
+            assert_eq!((0..=255u8).filter(|neighbourhood| neighbourhood.count_ones() == count as u32).count(), total);
+        }
+    }
+
+    #[test]
+    fn next_neighbourhood() {
+        let rule: Rule = "B2a/S1c".parse().unwrap();
+        // North and north east are side by side, north and south aren't
+        assert_eq!(Cell::Alive, rule.next_neighbourhood(Cell::Dead, 0b0000_0011));
+        assert_eq!(Cell::Dead, rule.next_neighbourhood(Cell::Dead, 0b0001_0001));
+        assert_eq!(Cell::Alive, rule.next_neighbourhood(Cell::Alive, 0b1000_0000));
+        assert_eq!(Cell::Dead, rule.next_neighbourhood(Cell::Alive, 0b0100_0000));
+        // Totalistic rules only count
+        assert_eq!(Cell::Alive, Rule::CONWAY.next_neighbourhood(Cell::Dead, 0b1001_0001));
+    }
+
+    #[test]
+    fn toggled() {
+        assert_eq!("B36/S23", Rule::CONWAY.toggled(true, 6).to_string());
+        assert_eq!("B3/S3", Rule::CONWAY.toggled(false, 2).to_string());
+        assert_eq!(Rule::CONWAY, Rule::CONWAY.toggled(true, 9));
+        // A count with only some arrangements is filled in
+        assert_eq!("B23/S", "B2a3/S".parse::<Rule>().unwrap().toggled(true, 2).to_string());
+    }
+
+    #[test]
+    fn highlife_birth() {
+        // Under HighLife a dead cell with 6 neighbours is born
+        let rule: Rule = "B36/S23".parse().unwrap();
+        assert_eq!(Cell::Alive, rule.next(Cell::Dead, 6));
+        assert_eq!(Cell::Dead, Rule::CONWAY.next(Cell::Dead, 6));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn highlife_replicator_step() {
+        let rule: Rule = "B36/S23".parse().unwrap();
+        let mut universe = Universe::new(6, 6, vec![(1, 1), (1, 2), (1, 3), (3, 1), (3, 2), (3, 3)]);
+        universe.set_life_rule(rule);
+        assert_eq!("B36/S23", universe.rule());
+        let mut conway = Universe::new(6, 6, vec![(1, 1), (1, 2), (1, 3), (3, 1), (3, 2), (3, 3)]);
+        universe.tick();
+        conway.tick();
+        // (2, 2) had six neighbours
+        assert_eq!(Cell::Alive, universe.cells[universe.get_index(2, 2)]);
+        assert_eq!(Cell::Dead, conway.cells[conway.get_index(2, 2)]);
+
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hensel_tick() {
+        // Under B2-a two cells a gap apart fill the gap and the cells either
+        // side of it, all of which see a pair of neighbours not side by side
+        let mut universe = Universe::new(7, 5, vec![(2, 2), (2, 4)]);
+        universe.set_boundary(crate::Boundary::Dead);
+        universe.set_life_rule("B2-a/S".parse().unwrap());
+        universe.tick();
+        assert_eq!(3, universe.population());
+        assert!(universe.is_alive(1, 3) && universe.is_alive(2, 3) && universe.is_alive(3, 3));
+        assert_eq!("B2-a/S", universe.rule());
+
+        let mut engine = crate::Engine::new(7, 5).unwrap();
+        engine.set_rule("B2-a/S".parse().unwrap());
+        engine.set(2, 2, Cell::Alive);
+        engine.set(2, 4, Cell::Alive);
+        engine.tick();
+        assert_eq!(3, engine.population());
+        assert_eq!(Cell::Alive, engine.get(2, 3));
+    }
+}
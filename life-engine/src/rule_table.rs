@@ -0,0 +1,414 @@
+// Golly `.rule` files, driving tick() from an arbitrary transition table
+//
+// Only the @TABLE section is used (@RULE supplies the name, anything else
+// like @COLORS is skipped) and only 2 state tables fit our cells. Each
+// transition lists the cell, its neighbours clockwise from north and the
+// new state:
+//
+//     @RULE Life
+//     @TABLE
+//     n_states:2
+//     neighborhood:Moore
+//     symmetries:permute
+//     var a={0,1}
+//     0,1,1,1,0,0,0,0,0,1
+//
+// Variables are bound, so a name used twice in a transition takes the same
+// value both times. Transitions are tried in order with the first match
+// winning, and a cell matching none keeps its state. The table is expanded
+// up front into a lookup indexed by the whole neighbourhood, so ticking
+// costs the same whatever the table looks like
+
+use crate::{Cell, Universe};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Neighbour offsets clockwise from north
+const MOORE: [(i64, i64); 8] = [(-1, 0), (-1, 1), (0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1)];
+const VON_NEUMANN: [(i64, i64); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Symmetry {
+    None,
+    Rotate4,
+    Rotate8,
+    ReflectHorizontal,
+    Rotate4Reflect,
+    Rotate8Reflect,
+    Permute,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleTable {
+    name: String,
+    neighbours: &'static [(i64, i64)],
+    // New state for each neighbourhood, indexed by the neighbours as bits
+    // clockwise from north with the cell itself above them. None where no
+    // transition matched
+    lookup: Vec<Option<Cell>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RuleTableError {
+    /// 1 based line of the problem, 0 if it's about the file as a whole
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for RuleTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rule table error at line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for RuleTableError {}
+
+// Every arrangement of the neighbour bits `mask` is equivalent to under the
+// symmetry, including itself
+fn symmetric_masks(mask: usize, count: usize, symmetry: Symmetry) -> Vec<usize> {
+    if symmetry == Symmetry::Permute {
+        return (0..1 << count).filter(|other: &usize| other.count_ones() == mask.count_ones()).collect();
+    }
+
+    let rotation_step = match (symmetry, count) {
+        (Symmetry::None | Symmetry::ReflectHorizontal, _) => count,
+        (Symmetry::Rotate4 | Symmetry::Rotate4Reflect, 8) => 2,
+        _ => 1,
+    };
+    let reflect = matches!(symmetry, Symmetry::ReflectHorizontal | Symmetry::Rotate4Reflect | Symmetry::Rotate8Reflect);
+
+    let bit = |mask: usize, position: usize| (mask >> position) & 1;
+    let mut masks = vec![];
+    for rotation in (0..count).step_by(rotation_step) {
+        let rotated = (0..count).fold(0, |out, i| out | bit(mask, (i + rotation) % count) << i);
+        masks.push(rotated);
+        if reflect {
+            // Mirror left to right, keeping north and south in place
+            masks.push((0..count).fold(0, |out, i| out | bit(rotated, (count - i) % count) << i));
+        }
+    }
+    masks
+}
+
+impl RuleTable {
+    /// Name from the @RULE line
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Cell state after a tick given the cell and its neighbours in order
+    fn next(&self, cell: Cell, neighbours: impl Iterator<Item = Cell>) -> Cell {
+        let count = self.neighbours.len();
+        let idx = neighbours.enumerate().fold((cell as usize) << count, |idx, (i, neighbour)| idx | (neighbour as usize) << i);
+        self.lookup[idx].unwrap_or(cell)
+    }
+}
+
+impl FromStr for RuleTable {
+    type Err = RuleTableError;
+
+    fn from_str(text: &str) -> Result<RuleTable, RuleTableError> {
+        let error = |line: usize, message: String| RuleTableError { line, message };
+
+        let mut name = String::new();
+        let mut in_table = false;
+        let mut seen_table = false;
+        let mut neighbours: &'static [(i64, i64)] = &MOORE;
+        let mut symmetry = Symmetry::None;
+        let mut variables: HashMap<String, Vec<u8>> = HashMap::new();
+        let mut lookup = vec![];
+
+        for (number, line) in text.lines().enumerate() {
+            let number = number + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue
+            }
+
+            if let Some(section) = line.strip_prefix('@') {
+                let mut words = section.split_whitespace();
+                let section = words.next().unwrap_or("");
+                if section == "RULE" {
+                    name = words.next().unwrap_or("").to_string();
+                }
+                in_table = section == "TABLE";
+                seen_table |= in_table;
+                continue
+            }
+            if !in_table {
+                continue
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => {
+                        if value != "2" {
+                            return Err(error(number, format!("only 2 state tables are supported, not {}", value)));
+                        }
+                    }
+                    "neighborhood" => {
+                        neighbours = match value {
+                            "Moore" => &MOORE,
+                            "vonNeumann" => &VON_NEUMANN,
+                            _ => return Err(error(number, format!("unsupported neighborhood '{}'", value))),
+                        };
+                    }
+                    "symmetries" => {
+                        symmetry = match value {
+                            "none" => Symmetry::None,
+                            "rotate4" => Symmetry::Rotate4,
+                            "rotate8" if neighbours.len() == 8 => Symmetry::Rotate8,
+                            "reflect_horizontal" => Symmetry::ReflectHorizontal,
+                            "rotate4reflect" => Symmetry::Rotate4Reflect,
+                            "rotate8reflect" if neighbours.len() == 8 => Symmetry::Rotate8Reflect,
+                            "permute" => Symmetry::Permute,
+                            _ => return Err(error(number, format!("unsupported symmetries '{}'", value))),
+                        };
+                    }
+                    key => return Err(error(number, format!("unknown setting '{}'", key))),
+                }
+                continue
+            }
+
+            if lookup.is_empty() {
+                lookup = vec![None; 1 << (neighbours.len() + 1)];
+            }
+
+            let parse_state = |token: &str, variables: &HashMap<String, Vec<u8>>| -> Result<Vec<u8>, RuleTableError> {
+                if let Some(values) = variables.get(token) {
+                    return Ok(values.clone());
+                }
+                match token.parse::<u8>() {
+                    Ok(state) if state < 2 => Ok(vec![state]),
+                    _ => Err(error(number, format!("'{}' isn't a state or variable", token))),
+                }
+            };
+
+            if let Some(definition) = line.strip_prefix("var ") {
+                let (variable, values) = definition.split_once('=')
+                    .ok_or_else(|| error(number, "expected var name={...}".to_string()))?;
+                let values = values.trim().strip_prefix('{').and_then(|values| values.strip_suffix('}'))
+                    .ok_or_else(|| error(number, "variable values must be in {}".to_string()))?;
+                let mut states = vec![];
+                for token in values.split(',').map(str::trim) {
+                    states.extend(parse_state(token, &variables)?);
+                }
+                variables.insert(variable.trim().to_string(), states);
+                continue
+            }
+
+            // Either separated by commas or spaces, or compact with a
+            // single character per state
+            let mut tokens = line.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|token| !token.is_empty())
+                .collect::<Vec<_>>();
+            if tokens.len() == 1 {
+                tokens = line.split("").filter(|token| !token.is_empty()).collect();
+            }
+            if tokens.len() != neighbours.len() + 2 {
+                return Err(error(number, format!("expected {} states but found {}", neighbours.len() + 2, tokens.len())));
+            }
+
+            // Every combination of the variables, each name bound to one value
+            let mut names = vec![];
+            for &token in &tokens {
+                if variables.contains_key(token) && !names.contains(&token) {
+                    names.push(token);
+                }
+            }
+            let mut states = vec![];
+            for &token in &tokens {
+                states.push(parse_state(token, &variables)?);
+            }
+            if let Some(&output) = tokens.last() {
+                if variables.contains_key(output) && tokens[..tokens.len() - 1].iter().all(|&token| token != output) {
+                    return Err(error(number, format!("output variable '{}' isn't bound by an input", output)));
+                }
+            }
+
+            let combinations = names.iter().map(|&name| variables[name].len()).product::<usize>();
+            for combination in 0..combinations {
+                let mut remaining = combination;
+                let mut bound = HashMap::new();
+                for &name in &names {
+                    let values = &variables[name];
+                    bound.insert(name, values[remaining % values.len()]);
+                    remaining /= values.len();
+                }
+
+                let value = |i: usize| bound.get(tokens[i]).cloned().unwrap_or(states[i][0]) as usize;
+                let count = neighbours.len();
+                let mask = (0..count).fold(0, |mask, i| mask | value(i + 1) << i);
+                let output = if value(count + 1) == 1 { Cell::Alive } else { Cell::Dead };
+                for symmetric in symmetric_masks(mask, count, symmetry) {
+                    let entry = &mut lookup[value(0) << count | symmetric];
+                    if entry.is_none() {
+                        *entry = Some(output);
+                    }
+                }
+            }
+        }
+
+        if !seen_table {
+            return Err(error(0, "no @TABLE section".to_string()));
+        }
+        if lookup.is_empty() {
+            lookup = vec![None; 1 << (neighbours.len() + 1)];
+        }
+        Ok(RuleTable { name, neighbours, lookup })
+    }
+}
+
+impl Universe {
+    pub fn rule_table(&self) -> Option<&RuleTable> {
+        self.rule_table.as_ref()
+    }
+
+    /// Drive tick() from `table` instead of the B/S rule, or go back to the
+    /// B/S rule with None
+    pub fn set_rule_table(&mut self, table: Option<RuleTable>) {
+        self.rule_table = table;
+        self.active_cells = None;
+        self.stability.reset();
+    }
+
+    // Cells whose state differs under the rule table next generation
+    pub(crate) fn table_changes(&mut self) -> Vec<usize> {
+        let candidates = self.candidate_cells();
+        let table = match &self.rule_table {
+            Some(table) => table,
+            None => return vec![],
+        };
+
+        let mut changed = vec![];
+        for idx in candidates {
+            let row = (idx as u32 / self.width) as i64;
+            let column = (idx as u32 % self.width) as i64;
+            let neighbours = table.neighbours.iter()
+                .map(|&(row_delta, column_delta)| self.boundary_cell(row + row_delta, column + column_delta));
+            if table.next(self.cells[idx], neighbours) != self.cells[idx] {
+                changed.push(idx);
+            }
+        }
+        changed
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Run on the transition table from the text of a Golly `.rule` file
+    /// rather than the B/S rule, until `clear_rule_table()`. Throws if the
+    /// table can't be read or needs more than 2 states
+    #[cfg(feature = "wasm")]
+    pub fn load_rule_table(&mut self, text: &str) -> Result<(), JsValue> {
+        let table = text.parse::<RuleTable>().map_err(crate::error::js_error)?;
+        self.set_rule_table(Some(table));
+        Ok(())
+    }
+
+    pub fn clear_rule_table(&mut self) {
+        self.set_rule_table(None);
+    }
+
+    /// Name of the loaded rule table, if any
+    pub fn rule_table_name(&self) -> Option<String> {
+        self.rule_table.as_ref().map(|table| table.name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boundary;
+
+    const LIFE: &str = "
+        @RULE Life
+        # Conway's Life written out as a table
+        @TABLE
+        n_states:2
+        neighborhood:Moore
+        symmetries:permute
+        var a={0,1}
+        var b={a}
+        var c={a}
+        var d={a}
+        var e={a}
+        var f={a}
+        var g={a}
+        var h={a}
+        var i={a}
+        0,1,1,1,0,0,0,0,0,1
+        1,1,1,0,0,0,0,0,0,1
+        1,1,1,1,0,0,0,0,0,1
+        1,b,c,d,e,f,g,h,i,0
+
+        @COLORS
+        1 255 255 255
+    ";
+
+    #[test]
+    fn life_table_matches_life() {
+        let table: RuleTable = LIFE.parse().unwrap();
+        assert_eq!("Life", table.name());
+
+        let mut universe = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        let mut expected = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        universe.set_rule_table(Some(table));
+        assert_eq!(Some("Life".to_string()), universe.rule_table_name());
+        for _ in 0..20 {
+            universe.tick();
+            expected.tick();
+            assert_eq!(expected.cells, universe.cells);
+        }
+
+        universe.clear_rule_table();
+        assert!(universe.rule_table().is_none());
+    }
+
+    #[test]
+    fn non_totalistic_table() {
+        // A dead cell with only its north neighbour alive comes alive, so a
+        // cell grows a column downwards. Under rotate4 it grows in every
+        // direction instead
+        let grow = |symmetries: &str, transition: &str| {
+            let text = format!("@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:{}\n{}\n", symmetries, transition);
+            let mut universe = Universe::new(7, 7, vec![(0, 3)]);
+            universe.set_boundary(Boundary::Dead);
+            universe.set_rule_table(Some(text.parse().unwrap()));
+            for _ in 0..3 {
+                universe.tick();
+            }
+            universe
+        };
+
+        let column = grow("none", "0,1,0,0,0,1");
+        assert_eq!(4, column.population());
+        assert!((0..4).all(|row| column.is_alive(row, 3)));
+
+        let compact = grow("none", "010001");
+        assert_eq!(column.cells, compact.cells);
+
+        let spread = grow("rotate4", "0 1 0 0 0 1");
+        assert!(spread.is_alive(0, 0) && spread.is_alive(0, 6) && spread.is_alive(3, 3));
+        // Two live neighbours is no match, so the cell stays dead
+        assert!(!spread.is_alive(1, 2));
+    }
+
+    #[test]
+    fn table_errors() {
+        let error = |text: &str| text.parse::<RuleTable>().err().unwrap();
+        assert_eq!(0, error("@RULE Nothing\n").line);
+        assert_eq!(3, error("@TABLE\nneighborhood:Moore\nn_states:3\n").line);
+        assert_eq!(2, error("@TABLE\nneighborhood:hexagonal\n").line);
+        assert_eq!(3, error("@TABLE\nneighborhood:vonNeumann\nsymmetries:rotate8\n").line);
+        assert_eq!(2, error("@TABLE\n0,1,1\n").line);
+        assert_eq!(2, error("@TABLE\n0,1,1,1,0,0,0,0,x,1\n").line);
+        assert_eq!(3, error("@TABLE\nvar a={0,1}\n0,1,1,1,0,0,0,0,0,b\n").line);
+        assert_eq!(3, error("@TABLE\nvar a={0,1}\n0,1,1,1,0,0,0,0,1,a\n").line);
+    }
+}
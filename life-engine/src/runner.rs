@@ -0,0 +1,167 @@
+// Drives a universe from the animation loop
+//
+// Without a target rate the runner ticks once per frame, the original
+// behaviour, which makes the simulation speed depend on the display's
+// refresh rate. With a target rate it uses a fixed timestep accumulator so a
+// 144Hz monitor runs generations at the same speed as a 60Hz one
+//
+// `tick_for_budget()` is the other way round, running as fast as the board
+// allows within a slice of each frame, for when the frontend wants speed
+// rather than a steady rate
+
+use crate::utils::now_ms;
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Cap on generations per advance so a long stall (e.g. a backgrounded tab)
+// doesn't try to catch up with thousands of ticks in one frame
+const DEFAULT_MAX_STEPS: u32 = 8;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Runner {
+    // Generations per second, None meaning one generation per frame
+    target_rate: Option<f64>,
+    accumulator_ms: f64,
+    max_steps: u32,
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Runner::new()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Runner {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> Runner {
+        Runner { target_rate: None, accumulator_ms: 0.0, max_steps: DEFAULT_MAX_STEPS }
+    }
+
+    /// Run at a fixed number of generations per second regardless of frame
+    /// rate. Zero, negative or non-finite rates go back to once per frame
+    pub fn set_target_rate(&mut self, generations_per_second: f64) {
+        self.target_rate = if generations_per_second.is_finite() && generations_per_second > 0.0 {
+            Some(generations_per_second)
+        } else {
+            None
+        };
+        self.accumulator_ms = 0.0;
+    }
+
+    pub fn target_rate(&self) -> Option<f64> {
+        self.target_rate
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps.max(1);
+    }
+
+    /// Call once per frame with the milliseconds since the last frame,
+    /// returns how many generations were run
+    pub fn advance(&mut self, universe: &mut Universe, elapsed_ms: f64) -> u32 {
+        let rate = match self.target_rate {
+            Some(rate) => rate,
+            None => {
+                universe.tick();
+                return 1;
+            }
+        };
+
+        let step_ms = 1000.0 / rate;
+        self.accumulator_ms += elapsed_ms.max(0.0);
+
+        let mut steps = 0;
+        while self.accumulator_ms >= step_ms && steps < self.max_steps {
+            universe.tick();
+            self.accumulator_ms -= step_ms;
+            steps += 1;
+        }
+
+        // Drop whatever we couldn't catch up on rather than carrying it over
+        if steps == self.max_steps {
+            self.accumulator_ms = self.accumulator_ms.min(step_ms);
+        }
+
+        steps
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Tick for up to `max_millis` milliseconds and return how many
+    /// generations ran. Stops early rather than overrun, judging by the
+    /// average tick so far, but always runs one tick for a positive budget
+    pub fn tick_for_budget(&mut self, max_millis: f64) -> u32 {
+        if max_millis.is_nan() || max_millis <= 0.0 {
+            return 0;
+        }
+
+        let start = now_ms();
+        let mut ticks = 0;
+        loop {
+            self.tick();
+            ticks += 1;
+
+            let elapsed = now_ms() - start;
+            if elapsed + elapsed / ticks as f64 > max_millis {
+                return ticks;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_rate_independent() {
+        let mut universe = Universe::new(8, 8, vec![]);
+
+        // One second at 60Hz and at 144Hz both give 30 generations
+        for &frame_rate in &[60.0, 144.0] {
+            let mut runner = Runner::new();
+            runner.set_target_rate(30.0);
+
+            let mut generations = 0;
+            for _ in 0..(frame_rate as u32) {
+                generations += runner.advance(&mut universe, 1000.0 / frame_rate);
+            }
+            assert!((29..=30).contains(&generations), "{} at {}Hz", generations, frame_rate);
+        }
+    }
+
+    #[test]
+    fn per_frame_and_catch_up() {
+        let mut universe = Universe::new(8, 8, vec![]);
+
+        let mut runner = Runner::new();
+        assert_eq!(1, runner.advance(&mut universe, 1000.0));
+
+        runner.set_target_rate(10.0);
+        assert_eq!(0, runner.advance(&mut universe, 50.0));
+        assert_eq!(1, runner.advance(&mut universe, 50.0));
+
+        // A long stall is capped rather than replayed
+        assert_eq!(DEFAULT_MAX_STEPS, runner.advance(&mut universe, 60_000.0));
+        assert_eq!(1, runner.advance(&mut universe, 0.0));
+        assert_eq!(0, runner.advance(&mut universe, 0.0));
+    }
+
+    #[test]
+    fn time_budget() {
+        let mut universe = Universe::new(16, 16, vec![(3, 2), (3, 3), (3, 4)]);
+        assert_eq!(0, universe.tick_for_budget(0.0));
+        assert_eq!(0, universe.tick_for_budget(f64::NAN));
+        assert_eq!(0, universe.generation());
+
+        let start = now_ms();
+        let ticks = universe.tick_for_budget(20.0);
+        assert!(ticks > 1);
+        assert_eq!(ticks as u64, universe.generation());
+        assert!(now_ms() - start < 200.0);
+    }
+}
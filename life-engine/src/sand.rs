@@ -0,0 +1,240 @@
+// A falling sand toy, as a second automaton alongside Life
+//
+// Each cell holds a material rather than alive or dead. Sand falls straight
+// down, or slides diagonally off a pile, sinking through water as it goes.
+// Water falls the same way and otherwise spreads sideways to find its
+// level. Walls never move, and the edges of the board act as walls.
+//
+// Cells are updated in place from the bottom row up, so a column of sand
+// falls together rather than a row at a time, and each grain moves at most
+// once a tick. Rows are swept in a random direction and diagonal or sideways
+// moves pick a random side first, so piles don't lean. The randomness comes
+// from the seed, making runs reproducible. Like `Universe` the cells are one
+// byte each (the `Material` values) for JS to read through `cells_ptr()`
+
+use crate::error::LifeError;
+use crate::rng::{seeded, SeededRng};
+use crate::size::{check_size, index};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Material {
+    Empty = 0,
+    Sand = 1,
+    Water = 2,
+    Wall = 3,
+}
+
+impl Material {
+    // Whether a grain of this could move into a cell holding `other`
+    fn displaces(self, other: Material) -> bool {
+        match self {
+            Material::Sand => other == Material::Empty || other == Material::Water,
+            Material::Water => other == Material::Empty,
+            Material::Empty | Material::Wall => false,
+        }
+    }
+
+    fn glyph(self) -> char {
+        match self {
+            Material::Empty => ' ',
+            Material::Sand => ':',
+            Material::Water => '~',
+            Material::Wall => '#',
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct FallingSand {
+    width: u32,
+    height: u32,
+    cells: Vec<Material>,
+    // Scratch marks for cells already moved into this tick
+    moved: Vec<bool>,
+    rng: Box<SeededRng>,
+    generation: u64,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl FallingSand {
+    /// An empty box, with `seed` choosing which way grains tumble. Throws
+    /// for a zero or too large size
+    #[cfg(feature = "wasm")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32, seed: u64) -> Result<FallingSand, JsValue> {
+        FallingSand::empty(width, height, seed).map_err(crate::error::js_error)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn get(&self, row: u32, column: u32) -> Material {
+        self.cells[self.get_index(row, column)]
+    }
+
+    pub fn set(&mut self, row: u32, column: u32, material: Material) {
+        let idx = self.get_index(row, column);
+        self.cells[idx] = material;
+    }
+
+    /// Cells holding `material`
+    pub fn count(&self, material: Material) -> u32 {
+        self.cells.iter().filter(|&&cell| cell == material).count() as u32
+    }
+
+    /// Empty every cell, walls included
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = Material::Empty);
+    }
+
+    /// Pointer to the width * height material bytes in WASM memory, only
+    /// valid until the next call that changes the box
+    pub fn cells_ptr(&self) -> *const Material {
+        self.cells.as_ptr()
+    }
+
+    /// Copy of the cells, one `Material` byte each
+    pub fn cells(&self) -> Vec<u8> {
+        self.cells.iter().map(|&cell| cell as u8).collect()
+    }
+
+    pub fn tick(&mut self) {
+        self.moved.iter_mut().for_each(|moved| *moved = false);
+        for row in (0..self.height).rev() {
+            let columns: Box<dyn Iterator<Item = u32>> = if self.rng.gen() {
+                Box::new(0..self.width)
+            } else {
+                Box::new((0..self.width).rev())
+            };
+            for column in columns {
+                self.update(row, column);
+            }
+        }
+        self.generation += 1;
+    }
+
+    /// The box as text, one row per line: space for empty, `:` sand, `~`
+    /// water and `#` wall
+    pub fn render(&self) -> String {
+        let mut out = String::with_capacity(self.cells.len() + self.height as usize);
+        for row in self.cells.chunks(self.width as usize) {
+            out.extend(row.iter().map(|cell| cell.glyph()));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl FallingSand {
+    /// Rust side of `new`
+    pub fn empty(width: u32, height: u32, seed: u64) -> Result<FallingSand, LifeError> {
+        let count = check_size(width, height)?;
+        Ok(FallingSand {
+            width,
+            height,
+            cells: vec![Material::Empty; count],
+            moved: vec![false; count],
+            rng: Box::new(seeded(seed)),
+            generation: 0,
+        })
+    }
+
+    fn get_index(&self, row: u32, column: u32) -> usize {
+        index(self.width, row, column)
+    }
+
+    // Move the grain at (row, column), if it's free to go anywhere
+    fn update(&mut self, row: u32, column: u32) {
+        let idx = self.get_index(row, column);
+        let material = self.cells[idx];
+        if self.moved[idx] || material == Material::Empty || material == Material::Wall {
+            return;
+        }
+
+        let side: i64 = if self.rng.gen() { 1 } else { -1 };
+        let mut targets = vec![(1, 0), (1, side), (1, -side)];
+        if material == Material::Water {
+            targets.extend([(0, side), (0, -side)]);
+        }
+        for (row_delta, column_delta) in targets {
+            let (to_row, to_column) = (row as i64 + row_delta, column as i64 + column_delta);
+            if to_row >= self.height as i64 || to_column < 0 || to_column >= self.width as i64 {
+                continue
+            }
+            let to = self.get_index(to_row as u32, to_column as u32);
+            if material.displaces(self.cells[to]) {
+                self.cells.swap(idx, to);
+                self.moved[to] = true;
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sand_piles_up() {
+        let mut sand = FallingSand::empty(7, 6, 3).unwrap();
+        for row in 0..3 {
+            sand.set(row, 3, Material::Sand);
+        }
+        for _ in 0..10 {
+            sand.tick();
+        }
+        assert_eq!(3, sand.count(Material::Sand));
+        assert_eq!(Material::Sand, sand.get(5, 3));
+        // Nothing is left hanging in the air
+        for row in 0..5 {
+            for column in 0..7 {
+                if sand.get(row, column) == Material::Sand {
+                    assert_ne!(Material::Empty, sand.get(row + 1, column));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sand_sinks_through_water() {
+        let mut sand = FallingSand::empty(3, 4, 1).unwrap();
+        for column in 0..3 {
+            sand.set(3, column, Material::Water);
+        }
+        sand.set(2, 0, Material::Wall);
+        sand.set(2, 2, Material::Wall);
+        sand.set(0, 1, Material::Sand);
+        for _ in 0..4 {
+            sand.tick();
+        }
+        assert_eq!("   \n   \n#~#\n~:~\n", sand.render());
+        assert_eq!(4, sand.generation());
+
+        // Water spreads out flat
+        let mut sand = FallingSand::empty(6, 3, 2).unwrap();
+        for row in 0..3 {
+            sand.set(row, 0, Material::Water);
+        }
+        for _ in 0..20 {
+            sand.tick();
+        }
+        assert_eq!(3, sand.count(Material::Water));
+        assert_eq!(3, (0..6).filter(|&column| sand.get(2, column) == Material::Water).count());
+    }
+}
@@ -0,0 +1,163 @@
+// Asynchronous update schedules
+//
+// Classic Life updates every cell at once from the previous generation. The
+// asynchronous modes break that:
+//
+//     RandomOrder   every cell is updated once per tick, one at a time in a
+//                   fresh random order, each seeing the cells updated before it
+//     RandomSubset  each cell is updated with a fixed probability per tick,
+//                   the chosen ones synchronously from the previous generation
+//
+// Both are seeded so runs can be reproduced
+
+use crate::rng::{seeded, SeededRng};
+use crate::{Cell, Universe};
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateMode {
+    Synchronous,
+    RandomOrder,
+    RandomSubset,
+}
+
+#[derive(Clone)]
+pub(crate) struct Schedule {
+    pub(crate) mode: UpdateMode,
+    pub(crate) probability: f64,
+    pub(crate) rng: Box<SeededRng>,
+}
+
+impl Universe {
+    pub(crate) fn asynchronous(&self) -> bool {
+        self.schedule.is_some()
+    }
+
+    pub(crate) fn asynchronous_changes(&mut self) -> Vec<usize> {
+        let mut schedule = match self.schedule.take() {
+            Some(schedule) => schedule,
+            None => return vec![],
+        };
+        let changed = match schedule.mode {
+            UpdateMode::RandomOrder => {
+                // Still needed to keep injected edges moving, even though
+                // every cell is looked at below
+                self.candidate_cells();
+
+                let mut order = (0..self.cells.len()).collect::<Vec<_>>();
+                order.shuffle(&mut *schedule.rng);
+
+                // Update a working copy in place, each cell flips at most once
+                let mut cells = self.cells.clone();
+                let mut changed = vec![];
+                for idx in order {
+                    let new_cell = if self.uses_neighbourhoods() {
+                        let neighbourhood = self.neighbourhood(&cells, idx as u32 / self.width, idx as u32 % self.width);
+                        self.rule.next_neighbourhood(cells[idx], neighbourhood)
+                    } else {
+                        let (neighbours, count) = self.neighbour_indices(idx);
+                        let alive = neighbours[..count].iter().filter(|&&n| cells[n] == Cell::Alive).count();
+                        self.cell_transform(cells[idx], alive as u8)
+                    };
+                    if new_cell != cells[idx] {
+                        cells[idx] = new_cell;
+                        changed.push(idx);
+                    }
+                }
+                changed
+            }
+            UpdateMode::RandomSubset => {
+                // Unchosen cells may still be due to change next tick, so the
+                // active set can't be trusted and everything is evaluated
+                self.active_cells = None;
+                let mut changed = self.life_changes();
+                let probability = schedule.probability;
+                changed.retain(|_| schedule.rng.gen_bool(probability));
+                changed
+            }
+            UpdateMode::Synchronous => self.life_changes(),
+        };
+
+        self.schedule = Some(schedule);
+        changed
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn update_mode(&self) -> UpdateMode {
+        self.schedule.as_ref().map_or(UpdateMode::Synchronous, |schedule| schedule.mode)
+    }
+
+    /// Switch update schedule, `seed` drives the random order or subset
+    pub fn set_update_mode(&mut self, mode: UpdateMode, seed: u64) {
+        let probability = self.update_probability();
+        self.schedule = match mode {
+            UpdateMode::Synchronous => None,
+            _ => Some(Schedule { mode, probability, rng: Box::new(seeded(seed)) }),
+        };
+        self.active_cells = None;
+    }
+
+    /// Chance of each cell being updated per tick in RandomSubset mode
+    pub fn update_probability(&self) -> f64 {
+        self.schedule.as_ref().map_or(0.5, |schedule| schedule.probability)
+    }
+
+    pub fn set_update_probability(&mut self, probability: f64) {
+        if let Some(schedule) = self.schedule.as_mut() {
+            schedule.probability = probability.clamp(0.0, 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_order_is_reproducible() {
+        let mut a = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        let mut b = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        let mut synchronous = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        a.set_update_mode(UpdateMode::RandomOrder, 9);
+        b.set_update_mode(UpdateMode::RandomOrder, 9);
+        assert_eq!(UpdateMode::RandomOrder, a.update_mode());
+
+        for _ in 0..10 {
+            a.tick();
+            b.tick();
+            synchronous.tick();
+        }
+        assert_eq!(a.cells, b.cells);
+        assert_ne!(a.cells, synchronous.cells);
+    }
+
+    #[test]
+    fn random_subset_extremes() {
+        // Probability 1 is the synchronous rule, 0 freezes everything
+        let mut subset = Universe::random_seeded(24, 24, 0.4, 5).unwrap();
+        let mut synchronous = Universe::random_seeded(24, 24, 0.4, 5).unwrap();
+        subset.set_update_mode(UpdateMode::RandomSubset, 1);
+        subset.set_update_probability(1.0);
+        for _ in 0..5 {
+            subset.tick();
+            synchronous.tick();
+        }
+        assert_eq!(synchronous.cells, subset.cells);
+
+        subset.set_update_probability(0.0);
+        subset.tick();
+        assert_eq!(synchronous.cells, subset.cells);
+
+        subset.set_update_mode(UpdateMode::Synchronous, 0);
+        subset.tick();
+        synchronous.tick();
+        assert_eq!(synchronous.cells, subset.cells);
+    }
+}
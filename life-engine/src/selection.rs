@@ -0,0 +1,158 @@
+// A rectangular selection for the editor
+//
+// `select()` marks out an area and the operations below act on every cell
+// in it at once, each as a single edit for undo. The selection is clipped to
+// the board, and again whenever it's used in case the board has since been
+// rotated or resized. With nothing selected the operations do nothing
+
+use crate::{random_cells, unseeded, Cell, Rect, Universe};
+
+use std::collections::BTreeMap;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // The selection as it lies on the board now
+    fn selected(&self) -> Option<Rect> {
+        self.selection?.intersection(&self.bounds())
+    }
+
+    // Write the cells in `rect` from a row by row block the same size
+    fn write_block(&mut self, rect: Rect, block: Vec<Cell>) {
+        self.edit_group(|universe| {
+            for ((row, column), cell) in rect.cells().zip(block) {
+                let idx = universe.get_index(row, column);
+                universe.write_cell(idx, cell);
+            }
+        });
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Select `rows` x `cols` cells from (top, left), replacing any earlier
+    /// selection. Whatever falls outside the board is dropped
+    pub fn select(&mut self, top: u32, left: u32, rows: u32, cols: u32) {
+        self.selection = Rect::new(top, left, rows, cols).intersection(&self.bounds());
+    }
+
+    pub fn deselect(&mut self) {
+        self.selection = None;
+    }
+
+    pub fn selection(&self) -> Option<Rect> {
+        self.selected()
+    }
+
+    /// The selected cells as an RLE pattern, for copying somewhere else.
+    /// Nothing if there's no selection
+    pub fn selection_to_rle(&self) -> Option<String> {
+        self.selected().map(|rect| self.rle(rect))
+    }
+
+    pub fn clear_selection(&mut self) {
+        if let Some(rect) = self.selected() {
+            self.write_block(rect, vec![Cell::Dead; rect.area() as usize]);
+        }
+    }
+
+    pub fn fill_selection(&mut self) {
+        if let Some(rect) = self.selected() {
+            self.write_block(rect, vec![Cell::Alive; rect.area() as usize]);
+        }
+    }
+
+    /// Refill the selection, each cell alive with probability `density`
+    pub fn randomize_selection(&mut self, density: f32) {
+        if let Some(rect) = self.selected() {
+            let block = random_cells(rect.columns, rect.rows, density, &mut unseeded());
+            self.write_block(rect, block);
+        }
+    }
+
+    /// Turn the selected cells a quarter turn clockwise about the selection's
+    /// top left corner. A selection that isn't square swaps its width and
+    /// height, clearing the cells it leaves and clipping any that would land
+    /// off the board, and the selection moves with them
+    pub fn rotate_selection(&mut self) {
+        let rect = match self.selected() {
+            Some(rect) => rect,
+            None => return,
+        };
+        let turned = Rect::new(rect.top, rect.left, rect.columns, rect.rows);
+
+        // Final state of every cell touched, so none is written twice
+        let mut writes = BTreeMap::new();
+        for (row, column) in rect.cells() {
+            writes.insert(self.get_index(row, column), Cell::Dead);
+        }
+        for (row, column) in turned.cells().filter(|&(row, column)| row < self.height && column < self.width) {
+            // New (r, c) comes from old (rows - 1 - c, r), relative to the corner
+            let (r, c) = (row - turned.top, column - turned.left);
+            let from = self.get_index(rect.top + rect.rows - 1 - c, rect.left + r);
+            writes.insert(self.get_index(row, column), self.cells[from]);
+        }
+
+        self.edit_group(|universe| {
+            for (idx, cell) in writes {
+                universe.write_cell(idx, cell);
+            }
+        });
+        self.selection = turned.intersection(&self.bounds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_operations() {
+        let mut universe = Universe::new(8, 6, vec![]);
+        universe.set_undo_capacity(4);
+        universe.fill_selection();
+        assert_eq!(0, universe.population());
+
+        universe.select(4, 6, 10, 10);
+        assert_eq!(Some(Rect::new(4, 6, 2, 2)), universe.selection());
+        universe.fill_selection();
+        assert_eq!(4, universe.population());
+
+        universe.select(0, 0, 3, 8);
+        universe.randomize_selection(1.0);
+        assert_eq!(28, universe.population());
+        universe.clear_selection();
+        assert_eq!(4, universe.population());
+
+        assert!(universe.undo_edit());
+        assert_eq!(28, universe.population());
+        universe.select(2, 5, 3, 3);
+        assert_eq!(Some("x = 3, y = 3, rule = B3/S23\n3o2$b2o!".to_string()), universe.selection_to_rle());
+
+        universe.deselect();
+        universe.clear_selection();
+        assert_eq!(None, universe.selection());
+        assert_eq!(None, universe.selection_to_rle());
+        assert_eq!(28, universe.population());
+    }
+
+    #[test]
+    fn rotate_selection() {
+        // An L in a 3 tall, 2 wide selection
+        let mut universe = Universe::new(5, 5, vec![(1, 1), (2, 1), (3, 1), (3, 2)]);
+        universe.select(1, 1, 3, 2);
+        universe.rotate_selection();
+        assert_eq!(Some(Rect::new(1, 1, 2, 3)), universe.selection());
+        let expected = Universe::new(5, 5, vec![(1, 1), (1, 2), (1, 3), (2, 1)]);
+        assert_eq!(expected.render(), universe.render());
+
+        // Four turns of a square selection put it back as it was
+        let mut universe = Universe::new(4, 4, vec![(0, 2), (1, 2), (1, 3)]);
+        universe.select(0, 2, 2, 2);
+        for _ in 0..4 {
+            universe.rotate_selection();
+        }
+        assert_eq!(3, universe.population());
+        assert!(universe.is_alive(0, 2) && universe.is_alive(1, 2) && universe.is_alive(1, 3));
+    }
+}
@@ -0,0 +1,144 @@
+// Serde support for persisting and restoring a Universe
+//
+// Only the simulation state is stored, caches like the active cell set are
+// rebuilt on load. Injection sources aren't persisted either since they hold
+// a live RNG, so a loaded universe with an Inject boundary behaves like Dead
+// until a new injection is configured
+
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::{Boundary, Cell, Rule, Universe};
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+struct UniverseState {
+    width: u32,
+    height: u32,
+    boundary: Boundary,
+    #[serde(default)]
+    generation: u64,
+    // One byte per cell, 0 dead and 1 alive
+    cells: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    owners: Option<Vec<u8>>,
+    // B/S notation, left out for Conway's Life
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rule: Option<String>,
+}
+
+impl Serialize for Universe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        UniverseState {
+            width: self.width,
+            height: self.height,
+            boundary: self.boundary,
+            generation: self.generation,
+            cells: self.true_cells().into_iter().map(|cell| cell as u8).collect(),
+            owners: if self.ownership_enabled() { Some(self.owners()) } else { None },
+            rule: if self.rule != Rule::CONWAY { Some(self.rule.to_string()) } else { None },
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Universe {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = UniverseState::deserialize(deserializer)?;
+
+        let size = check_size(state.width, state.height).map_err(D::Error::custom)?;
+        if state.cells.len() != size {
+            return Err(D::Error::custom(format!(
+                "expected {} cells for a {}x{} universe but found {}",
+                size, state.width, state.height, state.cells.len()
+            )));
+        }
+
+        let mut cells = Vec::with_capacity(size);
+        for &value in &state.cells {
+            cells.push(match value {
+                0 => Cell::Dead,
+                1 => Cell::Alive,
+                _ => return Err(D::Error::custom(format!("invalid cell value {}", value))),
+            });
+        }
+
+        let mut universe = Universe::from_cells(state.width, state.height, cells);
+        universe.boundary = state.boundary;
+        universe.generation = state.generation;
+        if let Some(rule) = state.rule {
+            universe.rule = rule.parse().map_err(D::Error::custom)?;
+        }
+
+        if let Some(owners) = state.owners {
+            if owners.len() != size {
+                return Err(D::Error::custom("owner buffer doesn't match universe dimensions"));
+            }
+            universe.enable_ownership();
+            for (idx, owner) in owners.into_iter().enumerate() {
+                universe.ownership.as_mut().unwrap().set(idx, owner);
+            }
+        }
+
+        Ok(universe)
+    }
+}
+
+impl Universe {
+    /// Rust side of `load`, reporting what was wrong with the saved state
+    pub fn load_str(saved: &str) -> Result<Universe, LifeError> {
+        Ok(serde_json::from_str(saved)?)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Full simulation state as a string which `load` can restore
+    pub fn save(&self) -> String {
+        serde_json::to_string(self).expect("universe state is always serializable")
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn load(saved: &str) -> Result<Universe, JsValue> {
+        Universe::load_str(saved).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let mut universe = Universe::new(6, 4, vec![(1, 2), (1, 3), (1, 4)]);
+        universe.set_boundary(Boundary::Mirror);
+        universe.set_owner(3, 0, 7);
+        universe.set_life_rule("B36/S23".parse().unwrap());
+        universe.tick();
+
+        let mut loaded = Universe::load_str(&universe.save()).unwrap();
+        assert_eq!(6, loaded.width);
+        assert_eq!(4, loaded.height);
+        assert_eq!(Boundary::Mirror, loaded.boundary);
+        assert_eq!(1, loaded.generation);
+        assert_eq!(universe.cells, loaded.cells);
+        assert_eq!(universe.owners(), loaded.owners());
+        assert_eq!(universe.life_rule(), loaded.life_rule());
+
+        universe.tick();
+        loaded.tick();
+        assert_eq!(universe.cells, loaded.cells);
+    }
+
+    #[test]
+    fn load_rejects_bad_state() {
+        assert!(Universe::load_str("not json").is_err());
+        assert!(Universe::load_str(r#"{"width":2,"height":2,"boundary":"Torus","cells":[0,1,0]}"#).is_err());
+        assert!(Universe::load_str(r#"{"width":0,"height":2,"boundary":"Torus","cells":[]}"#).is_err());
+        assert!(Universe::load_str(r#"{"width":1,"height":2,"boundary":"Torus","cells":[0,2]}"#).is_err());
+        assert!(Universe::load_str(r#"{"width":1,"height":2,"boundary":"Torus","cells":[0,1],"rule":"B9"}"#).is_err());
+        assert!(Universe::load_str(r#"{"width":1,"height":2,"boundary":"Torus","cells":[0,1]}"#).is_ok());
+    }
+}
@@ -0,0 +1,170 @@
+// Rolling time series of the per-tick statistics, for charts
+//
+// After every tick the generation, population, births and deaths are added
+// to ring buffers holding the last `capacity` ticks, so a chart can redraw
+// from them instead of collecting samples in JS. Each sample is written
+// twice, `capacity` apart, which keeps the window oldest to newest in one
+// contiguous run that JS can view in place:
+//
+//     const len = universe.series_len();
+//     const population = new Float64Array(memory.buffer, universe.population_series_ptr(), len);
+//
+// Values are f64s since that's what charting libraries want. Edits between
+// ticks show up in the next tick's population, and stepping back doesn't
+// remove samples
+
+use crate::Universe;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Copy)]
+enum Measure {
+    Generation,
+    Population,
+    Births,
+    Deaths,
+}
+
+#[derive(Clone)]
+pub(crate) struct Series {
+    capacity: usize,
+    // Where the oldest sample is, and how many there are
+    start: usize,
+    len: usize,
+    // 2 * capacity samples of each measure, in Measure order
+    buffers: [Vec<f64>; 4],
+}
+
+impl Series {
+    fn new(capacity: usize) -> Series {
+        let buffer = vec![0.0; 2 * capacity];
+        Series { capacity, start: 0, len: 0, buffers: [buffer.clone(), buffer.clone(), buffer.clone(), buffer] }
+    }
+
+    fn push(&mut self, samples: [f64; 4]) {
+        let slot = (self.start + self.len) % self.capacity;
+        for (buffer, sample) in self.buffers.iter_mut().zip(samples) {
+            buffer[slot] = sample;
+            buffer[slot + self.capacity] = sample;
+        }
+        if self.len < self.capacity {
+            self.len += 1;
+        } else {
+            self.start = (self.start + 1) % self.capacity;
+        }
+    }
+
+    fn window(&self, measure: Measure) -> &[f64] {
+        &self.buffers[measure as usize][self.start..self.start + self.len]
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.buffers.iter().map(|buffer| buffer.capacity() * std::mem::size_of::<f64>()).sum()
+    }
+}
+
+impl Universe {
+    // Called at the end of tick()
+    pub(crate) fn record_series(&mut self) {
+        let stats = self.statistics();
+        if let Some(series) = self.series.as_mut() {
+            series.push([stats.generation as f64, stats.population as f64, stats.births as f64, stats.deaths as f64]);
+        }
+    }
+
+    fn series_window(&self, measure: Measure) -> &[f64] {
+        self.series.as_ref().map_or(&[], |series| series.window(measure))
+    }
+
+    fn series_ptr(&self, measure: Measure) -> *const f64 {
+        self.series.as_ref().map_or(std::ptr::null(), |series| series.window(measure).as_ptr())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Start keeping the statistics of the last `capacity` ticks, dropping
+    /// any already kept. A capacity of 0 turns them off
+    pub fn enable_statistics_series(&mut self, capacity: u32) {
+        self.series = if capacity == 0 { None } else { Some(Series::new(capacity as usize)) };
+    }
+
+    pub fn disable_statistics_series(&mut self) {
+        self.series = None;
+    }
+
+    /// Ticks held in each series, 0 while they're off
+    pub fn series_len(&self) -> u32 {
+        self.series.as_ref().map_or(0, |series| series.len as u32)
+    }
+
+    /// Generation after each tick, oldest first, the x axis for the others
+    pub fn generation_series(&self) -> Vec<f64> {
+        self.series_window(Measure::Generation).to_vec()
+    }
+
+    pub fn population_series(&self) -> Vec<f64> {
+        self.series_window(Measure::Population).to_vec()
+    }
+
+    pub fn births_series(&self) -> Vec<f64> {
+        self.series_window(Measure::Births).to_vec()
+    }
+
+    pub fn deaths_series(&self) -> Vec<f64> {
+        self.series_window(Measure::Deaths).to_vec()
+    }
+
+    /// Pointers to `series_len()` f64s of each series in WASM memory, oldest
+    /// first, or null while they're off. Only valid until the next tick
+    pub fn generation_series_ptr(&self) -> *const f64 {
+        self.series_ptr(Measure::Generation)
+    }
+
+    pub fn population_series_ptr(&self) -> *const f64 {
+        self.series_ptr(Measure::Population)
+    }
+
+    pub fn births_series_ptr(&self) -> *const f64 {
+        self.series_ptr(Measure::Births)
+    }
+
+    pub fn deaths_series_ptr(&self) -> *const f64 {
+        self.series_ptr(Measure::Deaths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_window() {
+        let mut universe = Universe::new(8, 8, vec![(2, 1), (2, 2), (2, 3)]);
+        assert_eq!(0, universe.series_len());
+        assert!(universe.population_series_ptr().is_null());
+
+        universe.enable_statistics_series(3);
+        for _ in 0..5 {
+            universe.tick();
+        }
+        assert_eq!(3, universe.series_len());
+        assert_eq!(vec![3.0, 4.0, 5.0], universe.generation_series());
+        assert_eq!(vec![3.0, 3.0, 3.0], universe.population_series());
+        assert_eq!(vec![2.0, 2.0, 2.0], universe.births_series());
+
+        // The pointer views the same window in order, even once it's wrapped
+        let view = unsafe { std::slice::from_raw_parts(universe.generation_series_ptr(), 3) };
+        assert_eq!(&universe.generation_series()[..], view);
+
+        universe.toggle_cell(6, 6);
+        universe.tick();
+        assert_eq!(vec![4.0, 5.0, 6.0], universe.generation_series());
+        assert_eq!(vec![3.0, 3.0, 3.0], universe.population_series());
+        assert_eq!(3.0, universe.deaths_series()[2]);
+
+        universe.enable_statistics_series(0);
+        assert!(universe.deaths_series().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+// Publishing the board for a rendering worker
+//
+// With shared state on, the universe keeps a small header in wasm memory
+// describing where its cells are and which generation they hold, and a
+// renderer can read the cells where they lie instead of being sent a copy
+// every frame. Built with shared memory (`-C target-feature=+atomics` and
+// a rebuilt std, so `memory.buffer` is a SharedArrayBuffer), that renderer
+// can be a Web Worker handed the module's memory once at startup.
+//
+// The header is eight u32 words at `shared_state_ptr()`:
+//
+//     0  sequence, odd while the cells are being written
+//     1  byte offset of the cells, one byte each in reading order
+//     2  width
+//     3  height
+//     4  generation, low 32 bits
+//     5  generation, high 32 bits
+//     6  population
+//     7  layout version (1)
+//
+// The sequence works as a seqlock. A reader loads it, reads the cells and
+// loads it again, keeping the frame if both loads were the same even
+// number:
+//
+//     const header = new Int32Array(memory.buffer, ptr, 8);
+//     const before = Atomics.load(header, 0);
+//     if (before % 2 == 0) {
+//         const cells = new Uint8Array(memory.buffer, header[1], header[2] * header[3]);
+//         draw(cells);
+//         if (Atomics.load(header, 0) != before) { /* torn, draw the next one */ }
+//     }
+//
+// The cells can move when the board is rotated to a new shape, so read
+// the offset and size from the header each frame rather than keeping them
+
+use crate::Universe;
+
+use std::sync::atomic::{fence, AtomicU32, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const SEQUENCE: usize = 0;
+const CELLS: usize = 1;
+const WIDTH: usize = 2;
+const HEIGHT: usize = 3;
+const GENERATION_LOW: usize = 4;
+const GENERATION_HIGH: usize = 5;
+const POPULATION: usize = 6;
+const VERSION: usize = 7;
+
+const LAYOUT_VERSION: u32 = 1;
+
+pub(crate) struct SharedState {
+    // Boxed so its address doesn't move with the universe
+    header: Box<[AtomicU32; 8]>,
+}
+
+impl SharedState {
+    fn new() -> SharedState {
+        let header = Box::new([0; 8].map(AtomicU32::new));
+        header[VERSION].store(LAYOUT_VERSION, Ordering::Relaxed);
+        SharedState { header }
+    }
+
+    fn begin(&self) {
+        let sequence = self.header[SEQUENCE].load(Ordering::Relaxed);
+        if sequence & 1 == 0 {
+            self.header[SEQUENCE].store(sequence.wrapping_add(1), Ordering::Relaxed);
+            fence(Ordering::Release);
+        }
+    }
+
+    fn publish(&self, universe: &Universe) {
+        let store = |word: usize, value: u32| self.header[word].store(value, Ordering::Relaxed);
+        store(CELLS, universe.cells.as_ptr() as usize as u32);
+        store(WIDTH, universe.width);
+        store(HEIGHT, universe.height);
+        store(GENERATION_LOW, universe.generation as u32);
+        store(GENERATION_HIGH, (universe.generation >> 32) as u32);
+        store(POPULATION, universe.stats.population(&universe.cells));
+        // Straight to the next even number, whether or not begin() was
+        // called
+        let sequence = self.header[SEQUENCE].load(Ordering::Relaxed);
+        self.header[SEQUENCE].store((sequence | 1).wrapping_add(1), Ordering::Release);
+    }
+}
+
+impl Universe {
+    // Called before the cells are written in place
+    pub(crate) fn shared_begin(&self) {
+        if let Some(shared) = &self.shared {
+            shared.begin();
+        }
+    }
+
+    // Called once they're settled again
+    pub(crate) fn shared_publish(&self) {
+        if let Some(shared) = &self.shared {
+            shared.publish(self);
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn enable_shared_state(&mut self) {
+        if self.shared.is_none() {
+            self.shared = Some(SharedState::new());
+            self.shared_publish();
+        }
+    }
+
+    pub fn disable_shared_state(&mut self) {
+        self.shared = None;
+    }
+
+    /// Where the header described in shared.rs is, or null while shared
+    /// state is off
+    pub fn shared_state_ptr(&self) -> *const u32 {
+        self.shared.as_ref().map_or(std::ptr::null(), |shared| shared.header.as_ptr() as *const u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(universe: &Universe) -> &[u32] {
+        unsafe { std::slice::from_raw_parts(universe.shared_state_ptr(), 8) }
+    }
+
+    #[test]
+    fn publishes_each_change() {
+        let mut universe = Universe::new(6, 4, vec![(1, 1), (1, 2), (1, 3)]);
+        assert!(universe.shared_state_ptr().is_null());
+        universe.enable_shared_state();
+        let cells = universe.cells.as_ptr() as usize as u32;
+        assert_eq!(&[2, cells, 6, 4, 0, 0, 3, LAYOUT_VERSION], header(&universe));
+
+        universe.tick();
+        assert_eq!(&[4, cells, 6, 4, 1, 0, 3, LAYOUT_VERSION], header(&universe));
+        universe.set_cells(&[0]);
+        assert_eq!((6, 4), (header(&universe)[SEQUENCE], header(&universe)[POPULATION]));
+
+        // Rotating a non-square board gives it a new buffer
+        universe.rotate_cw();
+        let header = header(&universe);
+        assert_eq!(universe.cells.as_ptr() as usize as u32, header[CELLS]);
+        assert_eq!((4, 6), (header[WIDTH], header[HEIGHT]));
+        assert_eq!(0, header[SEQUENCE] % 2);
+    }
+}
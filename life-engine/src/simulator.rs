@@ -0,0 +1,228 @@
+// A universe driven from requestAnimationFrame, behind the `web` feature
+//
+// The animation loop every frontend otherwise writes itself: `play()`
+// starts ticking `speed()` generations each frame until `pause()`, and
+// `on_frame()` gets a call after each frame's ticks to redraw. Speeds below
+// 1 tick less often than every frame, 0.25 being once every fourth frame
+//
+//     const simulator = new Simulator(generate_universe(64, 64, 0.3));
+//     simulator.on_frame(() => simulator.draw_to_canvas(ctx, 4));
+//     simulator.set_speed(2);
+//     simulator.play();
+//
+// Frames are asked for from the window, or from the global scope in a
+// dedicated worker so it runs there with an OffscreenCanvas too. Freeing the
+// simulator cancels any pending frame, but do it from outside `on_frame()`,
+// pausing from inside instead
+
+use crate::Universe;
+
+use js_sys::Function;
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, DedicatedWorkerGlobalScope, OffscreenCanvasRenderingContext2d, Window};
+
+struct State {
+    universe: Universe,
+    playing: bool,
+    ticks_per_frame: f64,
+    // Fractions of a tick owed from slow speeds
+    owed: f64,
+    on_frame: Option<Function>,
+    // Id of the frame asked for, if one is pending
+    request: Option<i32>,
+    // The frame callback, which hands itself to the next frame. Owned by the
+    // Simulator, so this doesn't keep it alive
+    frame: Weak<Closure<dyn FnMut(f64)>>,
+}
+
+#[wasm_bindgen]
+pub struct Simulator {
+    state: Rc<RefCell<State>>,
+    frame: Rc<Closure<dyn FnMut(f64)>>,
+}
+
+// Where requestAnimationFrame lives
+enum Scope {
+    Window(Window),
+    Worker(DedicatedWorkerGlobalScope),
+}
+
+fn scope() -> Result<Scope, JsValue> {
+    let global = js_sys::global();
+    match global.dyn_into::<Window>() {
+        Ok(window) => Ok(Scope::Window(window)),
+        Err(global) => global.dyn_into::<DedicatedWorkerGlobalScope>()
+            .map(Scope::Worker)
+            .map_err(|_| JsValue::from_str("requestAnimationFrame isn't available here")),
+    }
+}
+
+// Ask for `frame` to run on the next animation frame, unless one is pending
+fn request_frame(state: &mut State, frame: &Closure<dyn FnMut(f64)>) -> Result<(), JsValue> {
+    if state.request.is_none() {
+        let callback = frame.as_ref().unchecked_ref::<Function>();
+        state.request = Some(match scope()? {
+            Scope::Window(window) => window.request_animation_frame(callback)?,
+            Scope::Worker(worker) => worker.request_animation_frame(callback)?,
+        });
+    }
+    Ok(())
+}
+
+impl State {
+    // Run this frame's share of ticks, returning how many
+    fn advance(&mut self) -> u32 {
+        self.owed += self.ticks_per_frame;
+        let ticks = self.owed.floor();
+        self.owed -= ticks;
+        for _ in 0..ticks as u32 {
+            self.universe.tick();
+        }
+        ticks as u32
+    }
+}
+
+#[wasm_bindgen]
+impl Simulator {
+    /// Take over `universe`, paused at one tick per frame
+    #[wasm_bindgen(constructor)]
+    pub fn new(universe: Universe) -> Simulator {
+        let state = Rc::new(RefCell::new(State {
+            universe,
+            playing: false,
+            ticks_per_frame: 1.0,
+            owed: 0.0,
+            on_frame: None,
+            request: None,
+            frame: Weak::new(),
+        }));
+
+        let weak_state = Rc::downgrade(&state);
+        let frame = Rc::new(Closure::wrap(Box::new(move |_time: f64| {
+            let state = match weak_state.upgrade() {
+                Some(state) => state,
+                None => return,
+            };
+            let (ticks, callback) = {
+                let mut state = state.borrow_mut();
+                state.request = None;
+                if !state.playing {
+                    return;
+                }
+                (state.advance(), state.on_frame.clone())
+            };
+
+            // Outside the borrow, as the callback will want to draw
+            if let Some(callback) = callback {
+                let _ = callback.call1(&JsValue::NULL, &JsValue::from(ticks));
+            }
+
+            let mut state = state.borrow_mut();
+            if let (true, Some(frame)) = (state.playing, state.frame.upgrade()) {
+                let _ = request_frame(&mut state, &frame);
+            }
+        }) as Box<dyn FnMut(f64)>));
+        state.borrow_mut().frame = Rc::downgrade(&frame);
+
+        Simulator { state, frame }
+    }
+
+    /// Start ticking every animation frame. Throws if there's no
+    /// requestAnimationFrame, e.g. under Node
+    pub fn play(&mut self) -> Result<(), JsValue> {
+        let mut state = self.state.borrow_mut();
+        state.playing = true;
+        request_frame(&mut state, &self.frame)
+    }
+
+    pub fn pause(&mut self) {
+        self.state.borrow_mut().playing = false;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.state.borrow().playing
+    }
+
+    /// Generations per frame, where fractions tick less often than every
+    /// frame. Negative and non-finite speeds count as 0, which plays
+    /// without ticking
+    pub fn set_speed(&mut self, ticks_per_frame: f64) {
+        let mut state = self.state.borrow_mut();
+        state.ticks_per_frame = if ticks_per_frame.is_finite() { ticks_per_frame.max(0.0) } else { 0.0 };
+        state.owed = 0.0;
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.state.borrow().ticks_per_frame
+    }
+
+    /// One generation now, playing or not
+    pub fn step(&mut self) {
+        self.state.borrow_mut().universe.tick();
+    }
+
+    /// `callback` receives the generations run after every frame while
+    /// playing, 0 on frames a slow speed skips. Exceptions it throws are
+    /// swallowed
+    pub fn on_frame(&mut self, callback: Function) {
+        self.state.borrow_mut().on_frame = Some(callback);
+    }
+
+    pub fn clear_on_frame(&mut self) {
+        self.state.borrow_mut().on_frame = None;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.state.borrow().universe.generation()
+    }
+
+    pub fn population(&self) -> u32 {
+        self.state.borrow().universe.population()
+    }
+
+    pub fn render(&self) -> String {
+        self.state.borrow().universe.render()
+    }
+
+    pub fn toggle_cell(&mut self, row: u32, column: u32) {
+        self.state.borrow_mut().universe.toggle_cell(row, column);
+    }
+
+    /// See `Universe.draw_to_canvas`
+    pub fn draw_to_canvas(&self, ctx: &CanvasRenderingContext2d, cell_size: u32) {
+        self.state.borrow().universe.draw_to_canvas(ctx, cell_size);
+    }
+
+    /// See `Universe.draw_to_offscreen_canvas`
+    pub fn draw_to_offscreen_canvas(&self, ctx: &OffscreenCanvasRenderingContext2d, cell_size: u32) {
+        self.state.borrow().universe.draw_to_offscreen_canvas(ctx, cell_size);
+    }
+
+    /// A copy of the universe as it stands, for anything the simulator
+    /// doesn't pass through
+    pub fn universe(&self) -> Universe {
+        self.state.borrow().universe.duplicate()
+    }
+
+    /// Carry on with `universe` instead, keeping the speed and whether it's
+    /// playing
+    pub fn set_universe(&mut self, universe: Universe) {
+        self.state.borrow_mut().universe = universe;
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        if let Some(id) = self.state.borrow_mut().request.take() {
+            let _ = match scope() {
+                Ok(Scope::Window(window)) => window.cancel_animation_frame(id),
+                Ok(Scope::Worker(worker)) => worker.cancel_animation_frame(id),
+                Err(_) => Ok(()),
+            };
+        }
+    }
+}
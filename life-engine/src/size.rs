@@ -0,0 +1,70 @@
+// Checks on universe dimensions shared by the public constructors
+//
+// Besides having at least one cell a universe can't have more than u32::MAX
+// of them, so flat indices, populations and counts all fit the u32s handed
+// to JS. Either side can be anything up to that, a 1 x u32::MAX strip as
+// much as a 65535 x 65537 board, so indices are always worked out in usize
+// with `index()` rather than in u32, where the intermediate products and
+// wrapping sums can overflow
+
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SizeError {
+    Empty { width: u32, height: u32 },
+    TooLarge { width: u32, height: u32 },
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SizeError::Empty { width, height } => {
+                write!(f, "a {}x{} universe has no cells, width and height must be non-zero", width, height)
+            }
+            SizeError::TooLarge { width, height } => write!(f, "a {}x{} universe is too large", width, height),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SizeError {}
+
+/// Number of cells in a width x height universe, if it can exist
+pub(crate) fn check_size(width: u32, height: u32) -> Result<usize, SizeError> {
+    if width == 0 || height == 0 {
+        return Err(SizeError::Empty { width, height });
+    }
+    match width.checked_mul(height) {
+        Some(count) => Ok(count as usize),
+        None => Err(SizeError::TooLarge { width, height }),
+    }
+}
+
+/// Flat index of (row, column) on a board `width` cells wide
+#[inline]
+pub(crate) fn index(width: u32, row: u32, column: u32) -> usize {
+    row as usize * width as usize + column as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_dimensions() {
+        assert_eq!(Ok(12), check_size(4, 3));
+        assert_eq!(Err(SizeError::Empty { width: 0, height: 3 }), check_size(0, 3));
+        assert_eq!(Err(SizeError::TooLarge { width: 1 << 16, height: 1 << 16 }), check_size(1 << 16, 1 << 16));
+        assert_eq!(Ok(u32::MAX as usize), check_size(u32::MAX, 1));
+        assert_eq!(Ok(u32::MAX as usize), check_size(65535, 65537));
+        assert_eq!(Err(SizeError::TooLarge { width: 65536, height: 65537 }), check_size(65536, 65537));
+        assert_eq!(Err(SizeError::TooLarge { width: 2, height: 1 << 31 }), check_size(2, 1 << 31));
+    }
+
+    #[test]
+    fn indices_near_the_limit() {
+        assert_eq!(u32::MAX as usize - 1, index(65535, 65536, 65534));
+        assert_eq!(u32::MAX as usize - 1, index(1, u32::MAX - 1, 0));
+        assert_eq!(u32::MAX as usize - 1, index(u32::MAX, 0, u32::MAX - 1));
+    }
+}
@@ -0,0 +1,166 @@
+// Compact binary snapshots of the board
+//
+// Layout, all integers little endian:
+//
+//     bytes 0..2   magic b"GL"
+//     byte  2      format version (1)
+//     bytes 3..7   width  (u32)
+//     bytes 7..11  height (u32)
+//     bytes 11..   cells, one bit each in row-major order, least significant
+//                  bit first, with the last byte zero padded
+//
+// so a 256x256 universe is 8KB raw or about 11KB as base64
+
+use crate::base64;
+use crate::error::LifeError;
+use crate::size::check_size;
+use crate::{Cell, Universe};
+
+use std::fmt;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8; 2] = b"GL";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 11;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    InvalidBase64,
+    BadHeader,
+    UnsupportedVersion(u8),
+    InvalidDimensions { width: u32, height: u32 },
+    Truncated { expected: usize, found: usize },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidBase64 => write!(f, "snapshot isn't valid base64"),
+            SnapshotError::BadHeader => write!(f, "data isn't a universe snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::InvalidDimensions { width, height } => {
+                write!(f, "invalid universe dimensions {}x{}", width, height)
+            }
+            SnapshotError::Truncated { expected, found } => {
+                write!(f, "snapshot should be {} bytes but is {}", expected, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+// Bit-pack cells, least significant bit first
+pub(crate) fn pack_cells(cells: &[Cell]) -> Vec<u8> {
+    let mut packed = vec![0u8; cells.len().div_ceil(8)];
+    for (idx, &cell) in cells.iter().enumerate() {
+        packed[idx / 8] |= (cell as u8) << (idx % 8);
+    }
+    packed
+}
+
+pub(crate) fn unpack_cells(packed: &[u8], count: usize) -> Vec<Cell> {
+    (0..count)
+        .map(|idx| if packed[idx / 8] >> (idx % 8) & 1 == 1 { Cell::Alive } else { Cell::Dead })
+        .collect()
+}
+
+impl Universe {
+    /// Rust side of `from_snapshot`, reporting what was wrong with the data
+    pub fn decode_snapshot(data: &[u8]) -> Result<Universe, LifeError> {
+        if data.len() < HEADER_LEN || &data[0..2] != MAGIC {
+            return Err(SnapshotError::BadHeader.into());
+        }
+        if data[2] != VERSION {
+            return Err(SnapshotError::UnsupportedVersion(data[2]).into());
+        }
+
+        let width = u32::from_le_bytes([data[3], data[4], data[5], data[6]]);
+        let height = u32::from_le_bytes([data[7], data[8], data[9], data[10]]);
+        let count = check_size(width, height).map_err(|_| SnapshotError::InvalidDimensions { width, height })?;
+
+        let expected = HEADER_LEN + count.div_ceil(8);
+        if data.len() != expected {
+            return Err(SnapshotError::Truncated { expected, found: data.len() }.into());
+        }
+
+        Ok(Universe::from_cells(width, height, unpack_cells(&data[HEADER_LEN..], count)))
+    }
+
+    /// Rust side of `from_base64`
+    pub fn decode_base64(encoded: &str) -> Result<Universe, LifeError> {
+        let data = base64::decode(encoded).ok_or(SnapshotError::InvalidBase64)?;
+        Universe::decode_snapshot(&data)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN + self.cells.len().div_ceil(8));
+        data.extend_from_slice(MAGIC);
+        data.push(VERSION);
+        data.extend_from_slice(&self.width.to_le_bytes());
+        data.extend_from_slice(&self.height.to_le_bytes());
+        data.extend(pack_cells(&self.true_cells()));
+        data
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn from_snapshot(data: &[u8]) -> Result<Universe, JsValue> {
+        Universe::decode_snapshot(data).map_err(crate::error::js_error)
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.to_snapshot())
+    }
+
+    #[cfg(feature = "wasm")]
+    pub fn from_base64(encoded: &str) -> Result<Universe, JsValue> {
+        Universe::decode_base64(encoded).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let universe = Universe::new(37, 3, vec![(0, 0), (1, 36), (2, 17)]);
+        let snapshot = universe.to_snapshot();
+        assert_eq!(HEADER_LEN + (37 * 3usize).div_ceil(8), snapshot.len());
+
+        let restored = Universe::decode_snapshot(&snapshot).unwrap();
+        assert_eq!((37, 3), (restored.width, restored.height));
+        assert_eq!(universe.cells, restored.cells);
+
+        let universe = Universe::random_seeded(40, 40, 0.3, 5).unwrap();
+
+        let restored = Universe::decode_base64(&universe.to_base64()).unwrap();
+        assert_eq!(universe.cells, restored.cells);
+
+        // 256x256 fits in a few KB
+        assert!(Universe::random_seeded(256, 256, 0.5, 1).unwrap().to_base64().len() < 11 * 1024);
+    }
+
+    #[test]
+    fn snapshot_errors() {
+        let snapshot = Universe::new(4, 4, vec![(1, 1)]).to_snapshot();
+
+        assert_eq!(Some(SnapshotError::BadHeader.into()), Universe::decode_snapshot(b"nope").err());
+        assert_eq!(
+            Some(SnapshotError::Truncated { expected: 13, found: 12 }.into()),
+            Universe::decode_snapshot(&snapshot[..12]).err()
+        );
+
+        let mut bad_version = snapshot.clone();
+        bad_version[2] = 9;
+        assert_eq!(Some(SnapshotError::UnsupportedVersion(9).into()), Universe::decode_snapshot(&bad_version).err());
+
+        assert_eq!(Some(SnapshotError::InvalidBase64.into()), Universe::decode_base64("!!!!").err());
+    }
+}
@@ -0,0 +1,234 @@
+// Headless runs of many random soups for statistical exploration
+//
+// Soup i of a search started with seed s is the 50% random fill that
+// `generate_universe_seeded(width, height, 0.5, s + i)` gives, so anything
+// interesting can be looked at again. Each soup runs until it dies, settles
+// into a still life or oscillator, or reaches the generation limit. The
+// summary includes a census of the objects left on every soup by apgcode so
+// they can be compared against Catagolue. `to_json()` gives:
+//
+//     {"soups": 100, "extinct": 12, "still_life": 40, "oscillating": 45,
+//      "running": 3, "mean_population": 31.5, "periods": {"2": 44, "15": 1},
+//      "census": {"xp2_7": 130, "xs4_33": 212, "other": 9},
+//      "results": [{"seed": 7, "population": 28, "status": "oscillating",
+//                   "period": 2, "stabilized_at": 811}, ...]}
+//
+// while from JS `soup_search()` returns the same as a typed `SoupReport`
+// object, with each result's status a `Status`
+
+use crate::apgcode::add_apgcodes;
+use crate::error::LifeError;
+use crate::{Rule, Status, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const SOUP_DENSITY: f32 = 0.5;
+
+// Seeds and generations reach JS as BigInts, like the seed passed in
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[cfg_attr(feature = "wasm", tsify(large_number_types_as_bigints))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoupResult {
+    pub seed: u64,
+    pub final_population: u32,
+    /// Status at the end, Running if the generation limit was reached first
+    pub status: Status,
+    /// Generation the final state or cycle was first seen, if it settled
+    pub stabilized_at: Option<u64>,
+    /// Period of the final cycle, 1 for still lifes and 0 if it didn't settle
+    pub period: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SoupSearch {
+    pub results: Vec<SoupResult>,
+    /// Objects left at the end of every soup by apgcode, "other" for those
+    /// which aren't periodic
+    pub census: BTreeMap<String, u32>,
+}
+
+impl SoupSearch {
+    pub fn run(count: u32, width: u32, height: u32, max_generations: u64, seed: u64, rule: Rule) -> Result<SoupSearch, LifeError> {
+        let mut results = Vec::with_capacity(count as usize);
+        let mut census = BTreeMap::new();
+        for i in 0..count as u64 {
+            let soup_seed = seed.wrapping_add(i);
+            let mut universe = Universe::random_seeded(width, height, SOUP_DENSITY, soup_seed)?;
+            universe.rule = rule;
+            universe.run_until_stable(max_generations);
+
+            add_apgcodes(&mut census, &universe);
+
+            let settled = universe.status() != Status::Running;
+            results.push(SoupResult {
+                seed: soup_seed,
+                final_population: universe.population(),
+                status: universe.status(),
+                stabilized_at: if settled { Some(universe.status_generation()) } else { None },
+                period: if settled && universe.status() != Status::Extinct { universe.status_period() } else { 0 },
+            });
+        }
+        Ok(SoupSearch { results, census })
+    }
+
+    pub fn count(&self, status: Status) -> usize {
+        self.results.iter().filter(|result| result.status == status).count()
+    }
+
+    pub fn mean_population(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.results.iter().map(|result| result.final_population as f64).sum::<f64>() / self.results.len() as f64
+    }
+
+    /// Number of oscillating soups by period
+    pub fn periods(&self) -> BTreeMap<u32, usize> {
+        let mut periods = BTreeMap::new();
+        for result in self.results.iter().filter(|result| result.status == Status::Oscillating) {
+            *periods.entry(result.period).or_insert(0) += 1;
+        }
+        periods
+    }
+
+    /// Summary counts followed by every soup's result, see `soup_search`
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        write!(
+            out, "{{\"soups\":{},\"extinct\":{},\"still_life\":{},\"oscillating\":{},\"running\":{},\"mean_population\":{},",
+            self.results.len(), self.count(Status::Extinct), self.count(Status::StillLife),
+            self.count(Status::Oscillating), self.count(Status::Running), self.mean_population(),
+        ).unwrap();
+
+        out.push_str("\"periods\":{");
+        for (i, (period, count)) in self.periods().iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "\"{}\":{}", period, count).unwrap();
+        }
+        out.push_str("},\"census\":{");
+        for (i, (code, count)) in self.census.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "\"{}\":{}", code, count).unwrap();
+        }
+        out.push_str("},\"results\":[");
+        for (i, result) in self.results.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let status = match result.status {
+                Status::Running => "running",
+                Status::Extinct => "extinct",
+                Status::StillLife => "still_life",
+                Status::Oscillating => "oscillating",
+            };
+            write!(
+                out, "{{\"seed\":{},\"population\":{},\"status\":\"{}\",\"period\":{},\"stabilized_at\":",
+                result.seed, result.final_population, status, result.period,
+            ).unwrap();
+            match result.stabilized_at {
+                Some(generation) => write!(out, "{}}}", generation).unwrap(),
+                None => out.push_str("null}"),
+            }
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+/// What `soup_search()` gives JS, the summary `to_json()` writes out
+#[cfg(feature = "wasm")]
+#[derive(Serialize, Tsify)]
+#[tsify(hashmap_as_object, large_number_types_as_bigints)]
+pub struct SoupReport {
+    pub soups: u32,
+    pub extinct: u32,
+    pub still_life: u32,
+    pub oscillating: u32,
+    pub running: u32,
+    pub mean_population: f64,
+    /// Number of oscillating soups by period
+    pub periods: BTreeMap<u32, u32>,
+    pub census: BTreeMap<String, u32>,
+    pub results: Vec<SoupResult>,
+}
+
+#[cfg(feature = "wasm")]
+impl SoupSearch {
+    fn report(&self) -> SoupReport {
+        SoupReport {
+            soups: self.results.len() as u32,
+            extinct: self.count(Status::Extinct) as u32,
+            still_life: self.count(Status::StillLife) as u32,
+            oscillating: self.count(Status::Oscillating) as u32,
+            running: self.count(Status::Running) as u32,
+            mean_population: self.mean_population(),
+            periods: self.periods().into_iter().map(|(period, count)| (period, count as u32)).collect(),
+            census: self.census.clone(),
+            results: self.results.clone(),
+        }
+    }
+}
+
+/// Run `count` random soups under Conway's Life for up to `max_generations`
+/// each, returning a summary and every soup's result
+#[cfg(feature = "wasm")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn soup_search(count: u32, width: u32, height: u32, max_generations: u64, seed: u64) -> Result<Ts<SoupReport>, JsValue> {
+    SoupSearch::run(count, width, height, max_generations, seed, Rule::CONWAY)
+        .map(|search| crate::utils::to_js(&search.report()))
+        .map_err(crate::error::js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soups_are_reproducible() {
+        let search = SoupSearch::run(12, 16, 16, 2000, 40, Rule::CONWAY).unwrap();
+        assert_eq!(12, search.results.len());
+        assert_eq!(search, SoupSearch::run(12, 16, 16, 2000, 40, Rule::CONWAY).unwrap());
+
+        let statuses = [Status::Running, Status::Extinct, Status::StillLife, Status::Oscillating];
+        assert_eq!(12, statuses.iter().map(|&status| search.count(status)).sum::<usize>());
+        assert_eq!(search.count(Status::Oscillating), search.periods().values().sum::<usize>());
+
+        // Any soup can be replayed on its own
+        let result = search.results[5];
+        let mut universe = Universe::random_seeded(16, 16, SOUP_DENSITY, result.seed).unwrap();
+        while universe.status() == Status::Running && universe.generation() < 2000 {
+            universe.tick();
+        }
+        assert_eq!(result.final_population, universe.population());
+        assert_eq!(result.status, universe.status());
+
+        // Objects left on every soup are named by apgcode
+        let objects = search.census.values().sum::<u32>();
+        assert!(objects > 0 && search.census.keys().all(|code| code.starts_with('x') || code == "other"));
+
+        let json = search.to_json();
+        assert!(json.starts_with("{\"soups\":12,"));
+        assert_eq!(12, json.matches("\"seed\":").count());
+    }
+
+    #[test]
+    fn generation_limit() {
+        let search = SoupSearch::run(3, 32, 32, 5, 1, Rule::CONWAY).unwrap();
+        assert!(search.results.iter().all(|result| result.status == Status::Running && result.stabilized_at.is_none()));
+        assert!(search.to_json().contains("\"running\":3,"));
+        assert!(search.to_json().contains("\"census\":{"));
+        assert!(SoupSearch::run(1, 0, 4, 5, 1, Rule::CONWAY).is_err());
+    }
+}
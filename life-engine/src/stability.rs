@@ -0,0 +1,261 @@
+// Detection of universes which have died out, frozen or started cycling
+//
+// A hash of every recent generation is kept, when a new generation hashes
+// the same as one in the window the universe has entered a cycle with the
+// distance between them as its period. The hash is an XOR of a key for each
+// live cell, so a tick only updates it for the cells it changed rather than
+// going over the whole board
+//
+// `run_until_stable()` ticks until that happens, the basis of soup searches
+// and of skipping straight to the end
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_WINDOW: usize = 64;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Running,
+    Extinct,
+    StillLife,
+    Oscillating,
+}
+
+// As the number JS sees for the enum, so reports can be compared against
+// `Status.Oscillating` and so on
+#[cfg(feature = "serde")]
+impl Serialize for Status {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*self as u32)
+    }
+}
+
+/// How a `run_until_stable()` call ended
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StabilizationResult {
+    /// Running if the generation cap was reached first
+    pub status: Status,
+    /// Generations ticked by the call
+    pub generations: u64,
+    /// Period of the final cycle, 1 for still lifes and extinction, 0 if
+    /// still running
+    pub period: u32,
+}
+
+#[derive(Clone)]
+pub(crate) struct StabilityTracker {
+    window: usize,
+    // (generation, hash) of recent generations, oldest first
+    recent: VecDeque<(u64, u64)>,
+    status: Status,
+    period: u32,
+    // First generation of the repeating state
+    since: u64,
+    // Of the cells as of the last tick, None to work it out from scratch
+    hash: Option<u64>,
+}
+
+impl Default for StabilityTracker {
+    fn default() -> Self {
+        StabilityTracker {
+            window: DEFAULT_WINDOW,
+            recent: VecDeque::new(),
+            status: Status::Running,
+            period: 0,
+            since: 0,
+            hash: None,
+        }
+    }
+}
+
+impl StabilityTracker {
+    pub(crate) fn reset(&mut self) {
+        self.recent.clear();
+        self.status = Status::Running;
+        self.period = 0;
+        self.since = 0;
+        self.hash = None;
+    }
+
+    // For when the cells are rewritten some other way than by ticks or
+    // edits, without starting detection over
+    pub(crate) fn rehash(&mut self) {
+        self.hash = None;
+    }
+}
+
+// SplitMix64 finaliser, spreading cell indices over all 64 bits
+fn cell_key(idx: usize) -> u64 {
+    let mut key = (idx as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    key ^ (key >> 31)
+}
+
+impl Universe {
+    // Called at the end of tick() once the new generation is in place, with
+    // the cells it flipped
+    pub(crate) fn update_stability(&mut self, changed: &[usize]) {
+        let hash = match self.stability.hash {
+            Some(hash) => changed.iter().fold(hash, |hash, &idx| hash ^ cell_key(idx)),
+            None => self.cells.iter()
+                .enumerate()
+                .filter(|&(_, &cell)| cell == Cell::Alive)
+                .fold(0, |hash, (idx, _)| hash ^ cell_key(idx)),
+        };
+        self.stability.hash = Some(hash);
+        let extinct = self.population() == 0;
+        let generation = self.generation;
+
+        let tracker = &mut self.stability;
+        if tracker.status == Status::Running {
+            if extinct {
+                tracker.status = Status::Extinct;
+                tracker.period = 1;
+                tracker.since = generation;
+            } else if let Some(&(seen_at, _)) = tracker.recent.iter().rev().find(|&&(_, h)| h == hash) {
+                tracker.period = (generation - seen_at) as u32;
+                tracker.status = if tracker.period == 1 { Status::StillLife } else { Status::Oscillating };
+                tracker.since = seen_at;
+            }
+        }
+
+        if tracker.recent.len() == tracker.window {
+            tracker.recent.pop_front();
+        }
+        tracker.recent.push_back((generation, hash));
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn status(&self) -> Status {
+        self.stability.status
+    }
+
+    /// Period of the cycle once the universe has stabilized, 0 while running
+    pub fn status_period(&self) -> u32 {
+        self.stability.period
+    }
+
+    /// Generation the universe first reached its final state or cycle
+    pub fn status_generation(&self) -> u64 {
+        self.stability.since
+    }
+
+    /// How many recent generations to compare against, bounding the longest
+    /// detectable period
+    pub fn set_cycle_detection_window(&mut self, window: u32) {
+        self.stability.window = (window as usize).max(1);
+        while self.stability.recent.len() > self.stability.window {
+            self.stability.recent.pop_front();
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = run_until_stable)]
+    pub fn run_until_stable_js(&mut self, max_generations: u64) -> Ts<StabilizationResult> {
+        crate::utils::to_js(&self.run_until_stable(max_generations))
+    }
+}
+
+impl Universe {
+    /// Tick until the universe dies out, freezes or starts cycling, or
+    /// `max_generations` have run. Returns straight away if it already has
+    pub fn run_until_stable(&mut self, max_generations: u64) -> StabilizationResult {
+        let mut generations = 0;
+        while self.stability.status == Status::Running && generations < max_generations {
+            self.tick();
+            generations += 1;
+        }
+        StabilizationResult { status: self.stability.status, generations, period: self.stability.period }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extinction() {
+        let mut universe = Universe::new(8, 8, vec![(1, 1)]);
+        assert_eq!(Status::Running, universe.status());
+        universe.tick();
+        assert_eq!(Status::Extinct, universe.status());
+        assert_eq!(1, universe.status_generation());
+    }
+
+    #[test]
+    fn still_life_and_oscillator() {
+        let mut block = Universe::new(8, 8, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        block.tick();
+        assert_eq!(Status::Running, block.status());
+        block.tick();
+        assert_eq!(Status::StillLife, block.status());
+        assert_eq!(1, block.status_period());
+
+        let mut blinker = Universe::new(8, 8, vec![(1, 1), (1, 2), (1, 3)]);
+        for _ in 0..3 {
+            blinker.tick();
+        }
+        assert_eq!(Status::Oscillating, blinker.status());
+        assert_eq!(2, blinker.status_period());
+        assert_eq!(1, blinker.status_generation());
+    }
+
+    #[test]
+    fn edits_reset_detection() {
+        let mut block = Universe::new(8, 8, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        block.tick();
+        block.tick();
+        assert_eq!(Status::StillLife, block.status());
+
+        block.set_owner(5, 5, 0);
+        assert_eq!(Status::Running, block.status());
+    }
+
+    #[test]
+    fn glider_keeps_running() {
+        let mut glider = Universe::new(16, 16, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        glider.set_cycle_detection_window(8);
+        for _ in 0..40 {
+            glider.tick();
+        }
+        assert_eq!(Status::Running, glider.status());
+    }
+
+    #[test]
+    fn hash_follows_the_ticks() {
+        let mut universe = Universe::random_seeded(32, 32, 0.4, 6).unwrap();
+        for _ in 0..20 {
+            universe.tick();
+            let mut scratch = Universe::from_cells(32, 32, universe.cells.clone());
+            scratch.update_stability(&[]);
+            assert_eq!(scratch.stability.hash, universe.stability.hash);
+        }
+    }
+
+    #[test]
+    fn run_until_stable() {
+        let mut blinker = Universe::new(8, 8, vec![(1, 1), (1, 2), (1, 3)]);
+        let result = blinker.run_until_stable(100);
+        assert_eq!(StabilizationResult { status: Status::Oscillating, generations: 3, period: 2 }, result);
+        assert_eq!(0, blinker.run_until_stable(100).generations);
+
+        let mut glider = Universe::new(16, 16, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        glider.set_cycle_detection_window(8);
+        let result = glider.run_until_stable(40);
+        assert_eq!(StabilizationResult { status: Status::Running, generations: 40, period: 0 }, result);
+    }
+}
@@ -0,0 +1,145 @@
+// Per-tick statistics, kept up to date incrementally so reading them is free
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Generation, population and the last tick's births and deaths, see
+/// `statistics()`
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Statistics {
+    pub generation: u64,
+    pub population: u32,
+    /// Cells born and cells which died in the most recent tick
+    pub births: u32,
+    pub deaths: u32,
+}
+
+#[derive(Clone)]
+pub(crate) struct TickStats {
+    population: u32,
+    births: u32,
+    deaths: u32,
+    // Set when cells may have been written behind our back through
+    // `IndexMut`, so the population has to be counted again
+    recount: bool,
+}
+
+impl TickStats {
+    pub(crate) fn new(cells: &[Cell]) -> Self {
+        let population = cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+        TickStats { population, births: 0, deaths: 0, recount: false }
+    }
+
+    pub(crate) fn population(&self, cells: &[Cell]) -> u32 {
+        if self.recount {
+            return cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+        }
+        self.population
+    }
+
+    // Called when a cell is handed out for writing directly
+    pub(crate) fn invalidate(&mut self) {
+        self.recount = true;
+    }
+
+    // Called from tick() with the cells about to flip, before they're written
+    pub(crate) fn record_tick(&mut self, cells: &[Cell], changed: &[usize]) {
+        if self.recount {
+            self.population = self.population(cells);
+            self.recount = false;
+        }
+        self.deaths = changed.iter().filter(|&&idx| cells[idx] == Cell::Alive).count() as u32;
+        self.births = changed.len() as u32 - self.deaths;
+        self.population = self.population + self.births - self.deaths;
+    }
+
+    // Called when an edit changes a cell to `cell`
+    pub(crate) fn record_edit(&mut self, cell: Cell) {
+        if self.recount {
+            return;
+        }
+        match cell {
+            Cell::Alive => self.population += 1,
+            Cell::Dead => self.population -= 1,
+        }
+    }
+}
+
+impl Universe {
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            generation: self.generation,
+            population: self.stats.population(&self.cells),
+            births: self.stats.births,
+            deaths: self.stats.deaths,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Number of alive cells
+    pub fn population(&self) -> u32 {
+        self.stats.population(&self.cells)
+    }
+
+    /// Cells born in the most recent tick
+    pub fn births_last_tick(&self) -> u32 {
+        self.stats.births
+    }
+
+    /// Cells which died in the most recent tick
+    pub fn deaths_last_tick(&self) -> u32 {
+        self.stats.deaths
+    }
+
+    /// Generation, population, births and deaths as one object, for a
+    /// stats panel reading them every frame
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(js_name = statistics)]
+    pub fn statistics_js(&self) -> Ts<Statistics> {
+        crate::utils::to_js(&self.statistics())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blinker_stats() {
+        let mut universe = Universe::new(5, 5, vec![(2, 1), (2, 2), (2, 3)]);
+        assert_eq!(3, universe.population());
+        assert_eq!(0, universe.births_last_tick());
+
+        universe.tick();
+        assert_eq!(3, universe.population());
+        assert_eq!(2, universe.births_last_tick());
+        assert_eq!(2, universe.deaths_last_tick());
+        assert_eq!(Statistics { generation: 1, population: 3, births: 2, deaths: 2 }, universe.statistics());
+
+        universe.set_owner(0, 0, 1);
+        assert_eq!(4, universe.population());
+    }
+
+    #[test]
+    fn population_matches_cells() {
+        let mut universe = Universe::random_seeded(40, 40, 0.3, 8).unwrap();
+        universe.set_history_capacity(4);
+        for _ in 0..30 {
+            universe.tick();
+        }
+        universe.step_back();
+
+        let counted = universe.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32;
+        assert_eq!(counted, universe.population());
+    }
+}
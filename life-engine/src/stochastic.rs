@@ -0,0 +1,130 @@
+// Probabilistic rules, for noisy Life variants
+//
+// A dead cell the rule would bring to life is only born with probability
+// `birth`, and a live cell the rule would keep alive only survives with
+// probability `survival`, otherwise it dies. Cells the rule kills always
+// die. Both probabilities at 1 is the ordinary deterministic rule. Draws
+// come from a seeded generator so runs can be reproduced. Critters is left
+// alone since it has to conserve particles
+//
+// Blocked births can happen on a later tick and any live cell can die, so
+// while this is on every cell is evaluated each tick
+
+use crate::rng::{seeded, SeededRng};
+use crate::{Cell, Universe};
+
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone)]
+pub(crate) struct Stochastic {
+    pub(crate) birth: f64,
+    pub(crate) survival: f64,
+    pub(crate) rng: Box<SeededRng>,
+}
+
+impl Universe {
+    // Called from tick() with the cells the rule changes, before they're
+    // written, giving the cells which actually change
+    pub(crate) fn apply_probabilities(&mut self, mut changed: Vec<usize>) -> Vec<usize> {
+        let stochastic = match self.stochastic.as_mut() {
+            Some(stochastic) if self.margolus.is_none() => stochastic,
+            _ => return changed,
+        };
+
+        let cells = &self.cells;
+        if stochastic.birth < 1.0 {
+            let (birth, rng) = (stochastic.birth, &mut stochastic.rng);
+            changed.retain(|&idx| cells[idx] == Cell::Alive || rng.gen_bool(birth));
+        }
+        if stochastic.survival < 1.0 {
+            let mut dying = vec![false; cells.len()];
+            for &idx in &changed {
+                dying[idx] = true;
+            }
+            for idx in 0..cells.len() {
+                if cells[idx] == Cell::Alive && !dying[idx] && !stochastic.rng.gen_bool(stochastic.survival) {
+                    changed.push(idx);
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Make the rule probabilistic, `seed` driving the random draws.
+    /// Probabilities are clamped to 0 to 1, and both at 1 switches it off
+    pub fn set_rule_probabilities(&mut self, birth: f64, survival: f64, seed: u64) {
+        let (birth, survival) = (birth.clamp(0.0, 1.0), survival.clamp(0.0, 1.0));
+        self.stochastic = if birth < 1.0 || survival < 1.0 {
+            Some(Stochastic { birth, survival, rng: Box::new(seeded(seed)) })
+        } else {
+            None
+        };
+        self.active_cells = None;
+        self.stability.reset();
+    }
+
+    /// Chance of a birth the rule calls for happening, 1 unless
+    /// probabilities are set
+    pub fn birth_probability(&self) -> f64 {
+        self.stochastic.as_ref().map_or(1.0, |stochastic| stochastic.birth)
+    }
+
+    /// Chance of a live cell the rule keeps alive surviving
+    pub fn survival_probability(&self) -> f64 {
+        self.stochastic.as_ref().map_or(1.0, |stochastic| stochastic.survival)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_noise_is_reproducible() {
+        let mut a = Universe::random_seeded(24, 24, 0.4, 2).unwrap();
+        let mut b = Universe::random_seeded(24, 24, 0.4, 2).unwrap();
+        let mut deterministic = Universe::random_seeded(24, 24, 0.4, 2).unwrap();
+        a.set_rule_probabilities(0.9, 0.95, 7);
+        b.set_rule_probabilities(0.9, 0.95, 7);
+        assert_eq!(0.9, a.birth_probability());
+        for _ in 0..10 {
+            a.tick();
+            b.tick();
+            deterministic.tick();
+        }
+        assert_eq!(a.cells, b.cells);
+        assert_ne!(a.cells, deterministic.cells);
+
+        // Certainty is the ordinary rule
+        a.set_rule_probabilities(1.0, 3.0, 0);
+        assert_eq!(1.0, a.survival_probability());
+        let mut expected = Universe::from_cells(24, 24, a.cells.clone());
+        a.tick();
+        expected.tick();
+        assert_eq!(expected.cells, a.cells);
+    }
+
+    #[test]
+    fn probability_extremes() {
+        // Nothing is ever born
+        let mut universe = Universe::random_seeded(24, 24, 0.4, 3).unwrap();
+        universe.set_rule_probabilities(0.0, 1.0, 1);
+        for _ in 0..5 {
+            let before = universe.population();
+            universe.tick();
+            assert!(universe.births_last_tick() == 0 && universe.population() <= before);
+        }
+
+        // Nothing survives, a blinker only leaves its two births
+        let mut blinker = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        blinker.set_rule_probabilities(1.0, 0.0, 1);
+        blinker.tick();
+        assert_eq!(2, blinker.population());
+        assert!(blinker.is_alive(2, 3) && blinker.is_alive(4, 3) && !blinker.is_alive(3, 3));
+    }
+}
@@ -0,0 +1,191 @@
+// Rules with B0, where empty space comes to life
+//
+// Under a rule with B0 every dead cell with no live neighbours is born, so
+// the background fills with life in one tick, and without S8 dies again the
+// next. Ticked as it is, the board strobes between two inverted pictures of
+// the same pattern and everything bounded by dead cells, like the Dead
+// boundary, goes wrong at the edges. So like Golly the universe keeps the
+// cells complemented whenever the background is alive: the live cells in
+// the buffer are the dead ones, the background there is always dead and a
+// pattern moves through it like one under any other rule. Each tick runs
+// the rule rewritten for the phases before and after it (`phase_rule()`),
+// alternating between two rules for B0 without S8, and the complemented
+// rule from then on with S8. After a switch to a rule without B0 the cells
+// come back out of complement as soon as the background dies.
+//
+// The cell buffer, population, live cell lists, edits and analysis all see
+// the complemented cells, which are what's worth drawing. Text rendering,
+// the saved formats (`to_snapshot()`, `snapshot()`, `save()`) and deltas
+// are the true states, so `render()` shows what the rule really did this
+// generation and a board loaded back carries on as if never saved;
+// `inverted_phase()` says which the buffer is. Asynchronous updates,
+// probabilistic rules, transition functions, rule tables and block rules
+// run the rule as given, on the true states
+
+use crate::{Cell, Rule, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Whether this tick goes through the phase rules at all
+    fn emulates_b0(&self) -> bool {
+        self.transition.is_none()
+            && self.stochastic.is_none()
+            && self.margolus.is_none()
+            && self.rule_table.is_none()
+            && !self.asynchronous()
+    }
+
+    // Called at the start of tick() to swap in the rule for this step. The
+    // rule set by the user comes back from `end_phase_step()`
+    pub(crate) fn begin_phase_step(&mut self) -> Option<Rule> {
+        if !self.emulates_b0() {
+            if self.inverted {
+                self.uncomplement();
+            }
+            return None;
+        }
+        let inverted_next = if self.inverted { self.rule.contains_count(false, 8) } else { self.rule.has_b0() };
+        let step_rule = (self.inverted || inverted_next).then(|| self.rule.phase_rule(self.inverted, inverted_next));
+        // A cell left alone by the last step can still change under a
+        // different rule, so a new one looks at every cell
+        if step_rule != self.step_rule {
+            self.active_cells = None;
+        }
+        self.step_rule = step_rule;
+        self.inverted = inverted_next;
+        step_rule.map(|step_rule| std::mem::replace(&mut self.rule, step_rule))
+    }
+
+    // Put the true states back in the buffer for ticking some other way
+    fn uncomplement(&mut self) {
+        self.shared_begin();
+        self.cells = self.true_cells();
+        self.inverted = false;
+        self.step_rule = None;
+        self.active_cells = None;
+        self.stats.invalidate();
+        self.stability.rehash();
+        self.neighbour_counts.invalidate();
+        if let Some(tiles) = self.tiles.as_mut() {
+            tiles.invalidate();
+        }
+        self.shared_publish();
+    }
+
+    pub(crate) fn end_phase_step(&mut self, rule: Option<Rule>) {
+        if let Some(rule) = rule {
+            self.rule = rule;
+        }
+    }
+
+    // The true state of a cell from the buffer
+    #[inline]
+    pub(crate) fn true_state(&self, cell: Cell) -> Cell {
+        match (self.inverted, cell) {
+            (false, cell) => cell,
+            (true, Cell::Alive) => Cell::Dead,
+            (true, Cell::Dead) => Cell::Alive,
+        }
+    }
+
+    pub(crate) fn true_cells(&self) -> Vec<Cell> {
+        self.cells.iter().map(|&cell| self.true_state(cell)).collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Whether the cell buffer holds the complement of the true states
+    /// this generation, as it does while a B0 rule has the background alive
+    pub fn inverted_phase(&self) -> bool {
+        self.inverted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rule::NEIGHBOURS;
+    use crate::{Boundary, RenderConfig};
+
+    // A generation of `cells` on a torus, ticked as the rule says
+    fn literal_tick(rule: Rule, width: usize, cells: &[Cell]) -> Vec<Cell> {
+        let height = cells.len() / width;
+        (0..cells.len())
+            .map(|idx| {
+                let (row, column) = ((idx / width) as i64, (idx % width) as i64);
+                let neighbourhood = NEIGHBOURS.iter().enumerate().fold(0u8, |neighbourhood, (bit, &(dr, dc))| {
+                    let row = (row + dr).rem_euclid(height as i64) as usize;
+                    let column = (column + dc).rem_euclid(width as i64) as usize;
+                    neighbourhood | (cells[row * width + column] as u8) << bit
+                });
+                rule.next_neighbourhood(cells[idx], neighbourhood)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn matches_the_rule_on_a_torus() {
+        for rule in ["B0/S8", "B0/S", "B0123478/S01234678", "B013/S2", "B0/S2-a3", "B3/S23"] {
+            let rule = rule.parse::<Rule>().unwrap();
+            let mut universe = Universe::random_seeded(12, 10, 0.3, 4).unwrap();
+            universe.set_life_rule(rule);
+            let mut expected = universe.cells.clone();
+            for generation in 1..=6 {
+                universe.tick();
+                expected = literal_tick(rule, 12, &expected);
+                assert_eq!(expected, universe.true_cells(), "{} generation {}", rule, generation);
+            }
+            // The set rule is what's reported, whatever ran the steps
+            assert_eq!(rule, universe.life_rule());
+        }
+    }
+
+    #[test]
+    fn keeps_the_background_dead() {
+        // B0/S8 fills the background for good and B0 without S8 flashes it,
+        // both leaving the buffer looking like the pattern in empty space
+        let mut universe = Universe::new(8, 8, vec![(3, 3), (3, 4)]);
+        universe.set_boundary(Boundary::Dead);
+        universe.set_life_rule("B0/S8".parse().unwrap());
+        universe.tick();
+        assert!(universe.inverted_phase());
+        assert!(universe.population() < 32);
+
+        universe.set_render_config(&RenderConfig::ascii());
+        let true_alive = universe.render().matches('#').count() as u32;
+        assert_eq!(64 - universe.population(), true_alive);
+
+        universe.set_life_rule("B0/S".parse().unwrap());
+        universe.tick();
+        assert!(!universe.inverted_phase());
+        universe.tick();
+        assert!(universe.inverted_phase());
+
+        // Back to a rule without B0 the cells come out of complement
+        universe.set_life_rule(Rule::CONWAY);
+        universe.tick();
+        assert!(!universe.inverted_phase());
+    }
+
+    #[test]
+    fn steps_back_and_saves_the_phase() {
+        let mut universe = Universe::random_seeded(10, 10, 0.3, 8).unwrap();
+        universe.set_life_rule("B0/S".parse().unwrap());
+        universe.set_history_capacity(4);
+        universe.tick();
+        let first = universe.render();
+        universe.tick();
+        assert!(universe.step_back());
+        assert_eq!(first, universe.render());
+
+        // A restored copy starts from the true states and carries on the same
+        let mut restored = Universe::decode_transfer(&universe.snapshot()).unwrap();
+        assert_eq!(first, restored.render());
+        universe.tick();
+        restored.tick();
+        assert_eq!(universe.render(), restored.render());
+    }
+}
@@ -0,0 +1,76 @@
+// SVG export, for crisp snapshots that scale in slides and blog posts
+//
+// The drawing is laid out in cell units through the viewBox, with the pixel
+// size given by the width and height. Live cells are drawn as one path, a
+// run of neighbouring live cells in a row being a single rectangle, which
+// keeps files small for busy boards
+
+use crate::{Cell, Universe};
+
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// Colours end up in attributes, so keep them from breaking out
+fn escape_attribute(text: &str) -> String {
+    text.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// The current generation as an SVG document, each cell `cell_size`
+    /// pixels square. Colours are anything CSS accepts, e.g. "#000" or
+    /// "rebeccapurple"
+    pub fn to_svg(&self, cell_size: u32, alive_color: &str, dead_color: &str) -> String {
+        let (width, height) = (self.width, self.height);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">",
+            width as u64 * cell_size as u64,
+            height as u64 * cell_size as u64,
+            width,
+            height,
+        ).unwrap();
+        writeln!(svg, "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>", width, height, escape_attribute(dead_color)).unwrap();
+
+        let mut path = String::new();
+        for row in 0..height {
+            let mut column = 0;
+            while column < width {
+                if self.cells[self.get_index(row, column)] == Cell::Dead {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < width && self.cells[self.get_index(row, column)] == Cell::Alive {
+                    column += 1;
+                }
+                write!(path, "M{} {}h{}v1h-{}z", start, row, column - start, column - start).unwrap();
+            }
+        }
+        if !path.is_empty() {
+            writeln!(svg, "<path fill=\"{}\" d=\"{}\"/>", escape_attribute(alive_color), path).unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_export() {
+        let universe: Universe = "##.#\n....\n".parse().unwrap();
+        let svg = universe.to_svg(10, "black", "\"white\"");
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\" viewBox=\"0 0 4 2\""));
+        assert!(svg.contains("<rect width=\"4\" height=\"2\" fill=\"&quot;white&quot;\"/>"));
+        assert!(svg.contains("<path fill=\"black\" d=\"M0 0h2v1h-2zM3 0h1v1h-1z\"/>"));
+        assert!(svg.ends_with("</svg>\n"));
+
+        let empty: Universe = "..\n".parse().unwrap();
+        assert!(!empty.to_svg(1, "black", "white").contains("<path"));
+    }
+}
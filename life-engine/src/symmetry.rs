@@ -0,0 +1,250 @@
+// Keeping the board symmetric as it evolves, for kaleidoscope patterns
+//
+// With a symmetry set, the cells fall into orbits: the cells the symmetry's
+// mirrors and turns carry onto each other. The first cell of each orbit in
+// reading order stands for the rest, and together they make a fundamental
+// domain, a half, quarter or eighth of the board. A tick only evaluates the
+// domain cells that could change, then gives every other cell in each of
+// their orbits the same new state, so the board comes out symmetric however
+// it started and the work is cut by the size of the group.
+//
+// Edits aren't mirrored as they're made, but a cell edited outside the
+// domain is put back to match its orbit's domain cell on the next tick, so
+// paint inside the domain (the top left) to see it copied round the board.
+// Turning a symmetry on copies the domain over the rest of the board at
+// once, as one undoable edit. Block rules, rule tables and random update
+// orders tick as usual and don't keep it, and a probabilistic rule picks
+// each cell on its own so only roughly keeps it.
+//
+// Turns by a quarter and the diagonal mirrors only fit square boards
+
+use crate::error::LifeError;
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    // Mirrored across the vertical centre line
+    LeftRight,
+    // Mirrored across the horizontal centre line
+    TopBottom,
+    // Both mirrors at once, and so a half turn too
+    Both,
+    // A half turn about the centre
+    HalfTurn,
+    // Quarter turns about the centre, square boards only
+    QuarterTurn,
+    // Mirrored across the top left to bottom right diagonal, square boards only
+    Diagonal,
+    // Every mirror and turn of the square, square boards only
+    Kaleidoscope,
+}
+
+impl Symmetry {
+    pub(crate) fn needs_square(self) -> bool {
+        matches!(self, Symmetry::QuarterTurn | Symmetry::Diagonal | Symmetry::Kaleidoscope)
+    }
+
+    // Where each of the group's mirrors and turns takes (row, column), the
+    // first being the cell itself. May repeat cells on the centre lines
+    fn images(self, row: u32, column: u32, width: u32, height: u32) -> ([(u32, u32); 8], usize) {
+        let (last_row, last_column) = (height - 1, width - 1);
+        let flipped_row = last_row - row;
+        let flipped_column = last_column - column;
+        let mut images = [(row, column); 8];
+        let others: &[(u32, u32)] = match self {
+            Symmetry::None => &[],
+            Symmetry::LeftRight => &[(row, flipped_column)],
+            Symmetry::TopBottom => &[(flipped_row, column)],
+            Symmetry::Both => &[(row, flipped_column), (flipped_row, column), (flipped_row, flipped_column)],
+            Symmetry::HalfTurn => &[(flipped_row, flipped_column)],
+            Symmetry::QuarterTurn => &[(column, flipped_row), (flipped_row, flipped_column), (flipped_column, row)],
+            Symmetry::Diagonal => &[(column, row)],
+            Symmetry::Kaleidoscope => &[
+                (column, flipped_row),
+                (flipped_row, flipped_column),
+                (flipped_column, row),
+                (row, flipped_column),
+                (flipped_row, column),
+                (column, row),
+                (flipped_column, flipped_row),
+            ],
+        };
+        images[1..=others.len()].copy_from_slice(others);
+        (images, others.len() + 1)
+    }
+}
+
+impl Universe {
+    // Indices of every cell in the orbit of idx, idx first
+    fn orbit(&self, idx: usize) -> ([usize; 8], usize) {
+        let (images, count) = self.symmetry.images(idx as u32 / self.width, idx as u32 % self.width, self.width, self.height);
+        let mut orbit = [idx; 8];
+        for (slot, &(row, column)) in orbit.iter_mut().zip(&images[..count]) {
+            *slot = self.get_index(row, column);
+        }
+        (orbit, count)
+    }
+
+    // The domain cell standing for idx's orbit
+    fn representative(&self, idx: usize) -> usize {
+        let (orbit, count) = self.orbit(idx);
+        orbit[..count].iter().cloned().min().unwrap_or(idx)
+    }
+
+    pub(crate) fn keeps_symmetry(&self) -> bool {
+        self.symmetry != Symmetry::None
+    }
+
+    // Called from life_changes in place of evaluating the candidates one by
+    // one. The counts must already be refreshed
+    pub(crate) fn symmetric_changes(&mut self, candidates: &[usize]) -> Vec<usize> {
+        let mut domain = candidates.iter().map(|&idx| self.representative(idx)).collect::<Vec<_>>();
+        domain.sort_unstable();
+        domain.dedup();
+
+        let flipping = if self.uses_neighbourhoods() {
+            self.neighbourhood_changes(&domain)
+        } else {
+            domain.iter()
+                .cloned()
+                .filter(|&idx| {
+                    let count = self.alive_neighbour_count(idx as u32 / self.width, idx as u32 % self.width);
+                    self.cell_transform(self.cells[idx], count) != self.cells[idx]
+                })
+                .collect()
+        };
+
+        let mut changed = vec![];
+        let mut flipping = flipping.into_iter().peekable();
+        for idx in domain {
+            let new_cell = match (self.cells[idx], flipping.next_if_eq(&idx).is_some()) {
+                (cell, false) => cell,
+                (Cell::Dead, true) => Cell::Alive,
+                (Cell::Alive, true) => Cell::Dead,
+            };
+            // Orbits never overlap, so only repeats within one need skipping
+            let (orbit, count) = self.orbit(idx);
+            for (position, &image) in orbit[..count].iter().enumerate() {
+                if self.cells[image] != new_cell && !orbit[..position].contains(&image) {
+                    changed.push(image);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Rust side of `set_symmetry`
+    pub fn try_set_symmetry(&mut self, symmetry: Symmetry) -> Result<(), LifeError> {
+        if symmetry.needs_square() && self.width != self.height {
+            return Err(LifeError::InvalidArgument(format!(
+                "{:?} symmetry needs a square board, not {}x{}",
+                symmetry, self.width, self.height
+            )));
+        }
+        self.symmetry = symmetry;
+        self.active_cells = None;
+        self.edit_group(|universe| {
+            for idx in 0..universe.cells.len() {
+                let representative = universe.representative(idx);
+                if representative != idx {
+                    universe.write_cell(idx, universe.cells[representative]);
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    /// Keep the board `symmetry` symmetric from now on, copying the top left
+    /// of the board over the rest straight away. Throws for a quarter turn,
+    /// diagonal or kaleidoscope on a board that isn't square
+    #[cfg(feature = "wasm")]
+    pub fn set_symmetry(&mut self, symmetry: Symmetry) -> Result<(), JsValue> {
+        self.try_set_symmetry(symmetry).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMMETRIES: [Symmetry; 7] = [
+        Symmetry::LeftRight,
+        Symmetry::TopBottom,
+        Symmetry::Both,
+        Symmetry::HalfTurn,
+        Symmetry::QuarterTurn,
+        Symmetry::Diagonal,
+        Symmetry::Kaleidoscope,
+    ];
+
+    fn is_symmetric(universe: &Universe) -> bool {
+        (0..universe.cells.len()).all(|idx| {
+            let (orbit, count) = universe.orbit(idx);
+            orbit[..count].iter().all(|&image| universe.cells[image] == universe.cells[idx])
+        })
+    }
+
+    #[test]
+    fn evolves_like_a_symmetric_board() {
+        for &symmetry in &SYMMETRIES {
+            for size in [16, 17] {
+                let mut universe = Universe::random_seeded(size, size, 0.4, 8).unwrap();
+                universe.try_set_symmetry(symmetry).unwrap();
+                assert!(is_symmetric(&universe), "{:?} {}", symmetry, size);
+
+                // A symmetric board on a torus stays symmetric by itself, so
+                // evaluating only the domain must give the same boards
+                let mut plain = Universe::from_cells(size, size, universe.cells.clone());
+                for generation in 0..30 {
+                    universe.tick();
+                    plain.tick();
+                    assert!(universe.cells == plain.cells, "{:?} {} generation {}", symmetry, size, generation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn puts_edits_outside_the_domain_back() {
+        let mut universe = Universe::new(10, 10, vec![]);
+        universe.try_set_symmetry(Symmetry::LeftRight).unwrap();
+        universe.set_cells(&[12, 23, 31, 32, 33]);
+        universe.toggle_cell(8, 8);
+        universe.tick();
+        assert!(is_symmetric(&universe));
+        assert!(!universe.is_alive(8, 8));
+        assert_eq!(10, universe.population());
+        assert!(universe.is_alive(2, 6) && universe.is_alive(3, 7));
+    }
+
+    #[test]
+    fn copies_the_domain_when_turned_on() {
+        let mut universe = Universe::new(6, 4, vec![(0, 0), (1, 4)]);
+        universe.set_undo_capacity(4);
+        universe.try_set_symmetry(Symmetry::Both).unwrap();
+        assert_eq!(4, universe.population());
+        assert!(universe.is_alive(3, 5) && !universe.is_alive(1, 4));
+        universe.undo_edit();
+        assert_eq!(Cell::Alive, universe[(1, 4)]);
+        assert_eq!(2, universe.population());
+
+        assert!(universe.try_set_symmetry(Symmetry::QuarterTurn).is_err());
+        assert_eq!(Symmetry::Both, universe.symmetry());
+        universe.try_set_symmetry(Symmetry::None).unwrap();
+        universe.set_cells(&[0, 2]);
+        universe.tick();
+        assert_eq!(0, universe.population());
+    }
+}
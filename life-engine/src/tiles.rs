@@ -0,0 +1,309 @@
+// Square tiles of cells, for skipping the quiet parts of big boards
+//
+// With tiles on, the board is also divided into `tile_size` square tiles,
+// numbered row by row with the right and bottom ones cut short by the
+// edges. Each tile keeps its live cell count and whether any of its cells
+// changed in the last tick or has been edited since. The cell buffer keeps
+// its row by row layout, which the shared state and everything else read,
+// so the tiles are a few words of bookkeeping per tile rather than a copy.
+//
+// The Tiles tick strategy works through the board a tile at a time, each
+// tile's rows one after another, so the three rows a cell reads stay in
+// cache for the whole tile. Only tiles holding or next to a change from the
+// last tick are evaluated, and when that isn't known, as after the rule
+// changes, only tiles with live cells and their neighbours, so an empty
+// stretch of a big board costs nothing. Choosing the strategy turns tiles
+// on at 64x64 if they aren't already.
+//
+// Drawing skips them too: the canvas drawing leaves out empty tiles, and
+// `changed_tiles()` lists the tiles a frontend needs to repaint
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const DEFAULT_TILE_SIZE: u32 = 64;
+
+#[derive(Clone)]
+pub(crate) struct Tiles {
+    size: u32,
+    // Tiles across the board
+    columns: u32,
+    populations: Vec<u32>,
+    changed: Vec<bool>,
+    // Set when cells were written behind the tiles' back, so the counts
+    // have to be taken again
+    recount: bool,
+}
+
+impl Tiles {
+    fn new(size: u32, width: u32, height: u32, cells: &[Cell]) -> Tiles {
+        let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+        let tile_count = columns as usize * rows as usize;
+        let mut tiles = Tiles {
+            size,
+            columns,
+            populations: vec![0; tile_count],
+            // Nothing has been drawn yet, so every tile needs painting
+            changed: vec![true; tile_count],
+            recount: false,
+        };
+        tiles.count(width, cells);
+        tiles
+    }
+
+    // The same size of tiles over a board of a new shape
+    pub(crate) fn resized(&self, width: u32, height: u32, cells: &[Cell]) -> Tiles {
+        Tiles::new(self.size, width, height, cells)
+    }
+
+    #[inline]
+    fn tile_of(&self, width: u32, idx: usize) -> usize {
+        let (row, column) = (idx / width as usize, idx % width as usize);
+        row / self.size as usize * self.columns as usize + column / self.size as usize
+    }
+
+    fn count(&mut self, width: u32, cells: &[Cell]) {
+        self.populations.fill(0);
+        for idx in (0..cells.len()).filter(|&idx| cells[idx] == Cell::Alive) {
+            let tile = self.tile_of(width, idx);
+            self.populations[tile] += 1;
+        }
+        self.recount = false;
+    }
+
+    // Called when cells are written directly, or all replaced at once
+    pub(crate) fn invalidate(&mut self) {
+        self.changed.fill(true);
+        self.recount = true;
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.populations.capacity() * std::mem::size_of::<u32>() + self.changed.capacity()
+    }
+}
+
+impl Universe {
+    // Called from tick() with the cells that just flipped
+    pub(crate) fn update_tiles(&mut self, changed: &[usize]) {
+        if let Some(tiles) = self.tiles.as_mut() {
+            tiles.changed.fill(false);
+            if tiles.recount {
+                tiles.count(self.width, &self.cells);
+            }
+        }
+        self.mark_tiles(changed);
+    }
+
+    // Called for edits, and from tick() after clearing the last tick's flags
+    pub(crate) fn mark_tiles(&mut self, changed: &[usize]) {
+        if let Some(tiles) = self.tiles.as_mut() {
+            for &idx in changed {
+                let tile = tiles.tile_of(self.width, idx);
+                tiles.changed[tile] = true;
+                if !tiles.recount {
+                    match self.cells[idx] {
+                        Cell::Alive => tiles.populations[tile] += 1,
+                        Cell::Dead => tiles.populations[tile] -= 1,
+                    }
+                }
+            }
+        }
+    }
+
+    // Every cell in a tile which could change this tick, a tile at a time.
+    // `changed` is last tick's changes, or None when they aren't known
+    pub(crate) fn tile_candidates(&mut self, changed: Option<Vec<usize>>, injected: Vec<usize>) -> Vec<usize> {
+        let (width, height) = (self.width, self.height);
+        match self.tiles.as_mut() {
+            Some(tiles) if tiles.recount => tiles.count(width, &self.cells),
+            Some(_) => {}
+            None => self.tiles = Some(Tiles::new(DEFAULT_TILE_SIZE, width, height, &self.cells)),
+        }
+        let tiles = self.tiles.as_ref().unwrap();
+        let size = tiles.size;
+
+        let mut needed = vec![false; tiles.populations.len()];
+        match changed {
+            Some(changed) => {
+                for idx in changed.into_iter().chain(injected) {
+                    self.reach_tiles(tiles, &mut needed, idx);
+                }
+            }
+            // Rules that can bring empty space to life, and anything that
+            // doesn't go by the rule, could change any tile
+            None if self.rule.has_b0() || self.transition.is_some() || self.stochastic.is_some() => {
+                needed.fill(true);
+            }
+            None => {
+                for tile in (0..needed.len()).filter(|&tile| tiles.populations[tile] > 0) {
+                    needed[tile] = true;
+                    // Only cells on a tile's edge have neighbours in others
+                    for idx in self.tile_range(size, tile) {
+                        let (row, column) = (idx as u32 / width, idx as u32 % width);
+                        let on_edge = row % size == 0 || row % size == size - 1 || row == height - 1
+                            || column % size == 0 || column % size == size - 1 || column == width - 1;
+                        if on_edge && self.cells[idx] == Cell::Alive {
+                            self.reach_tiles(tiles, &mut needed, idx);
+                        }
+                    }
+                }
+                for idx in injected {
+                    self.reach_tiles(tiles, &mut needed, idx);
+                }
+            }
+        }
+
+        (0..needed.len())
+            .filter(|&tile| needed[tile])
+            .flat_map(|tile| self.tile_range(size, tile))
+            .collect()
+    }
+
+    // Mark the tile holding idx and the tiles of its neighbours
+    fn reach_tiles(&self, tiles: &Tiles, needed: &mut [bool], idx: usize) {
+        needed[tiles.tile_of(self.width, idx)] = true;
+        let (neighbours, count) = self.neighbour_indices(idx);
+        for &neighbour in &neighbours[..count] {
+            needed[tiles.tile_of(self.width, neighbour)] = true;
+        }
+    }
+
+    // Cell indices of a tile, row by row
+    fn tile_range(&self, size: u32, tile: usize) -> impl Iterator<Item = usize> {
+        let columns = self.width.div_ceil(size) as usize;
+        let (size, width, height) = (size as usize, self.width as usize, self.height as usize);
+        let (top, left) = (tile / columns * size, tile % columns * size);
+        let (bottom, right) = ((top + size).min(height), (left + size).min(width));
+        (top..bottom).flat_map(move |row| row * width + left..row * width + right)
+    }
+
+    // The rect covered by a tile
+    #[cfg(feature = "web")]
+    pub(crate) fn tile_rect(&self, tile: u32) -> Option<crate::Rect> {
+        let tiles = self.tiles.as_ref()?;
+        let (top, left) = (tile / tiles.columns * tiles.size, tile % tiles.columns * tiles.size);
+        let rows = tiles.size.min(self.height - top);
+        Some(crate::Rect::new(top, left, rows, tiles.size.min(self.width - left)))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Keep a live cell count and a changed flag for each `tile_size`
+    /// square tile of the board. A size of 0 turns tiles off
+    pub fn enable_tiles(&mut self, tile_size: u32) {
+        self.tiles = (tile_size > 0).then(|| Tiles::new(tile_size, self.width, self.height, &self.cells));
+    }
+
+    /// Stop keeping tiles, unless the Tiles tick strategy is in use, which
+    /// turns them on again at the next tick
+    pub fn disable_tiles(&mut self) {
+        self.tiles = None;
+    }
+
+    /// Width and height of a tile, 0 while tiles are off
+    pub fn tile_size(&self) -> u32 {
+        self.tiles.as_ref().map_or(0, |tiles| tiles.size)
+    }
+
+    /// Tiles across the board, 0 while tiles are off
+    pub fn tile_columns(&self) -> u32 {
+        self.tiles.as_ref().map_or(0, |tiles| tiles.columns)
+    }
+
+    /// Tiles down the board, 0 while tiles are off
+    pub fn tile_rows(&self) -> u32 {
+        self.tiles.as_ref().map_or(0, |tiles| self.height.div_ceil(tiles.size))
+    }
+
+    /// Live cells in each tile, row by row
+    pub fn tile_populations(&self) -> Vec<u32> {
+        match &self.tiles {
+            Some(tiles) if tiles.recount => {
+                let mut tiles = tiles.clone();
+                tiles.count(self.width, &self.cells);
+                tiles.populations
+            }
+            Some(tiles) => tiles.populations.clone(),
+            None => vec![],
+        }
+    }
+
+    /// Tiles with a cell that changed in the last tick or has been edited
+    /// since, in order. Every tile straight after tiles are turned on
+    pub fn changed_tiles(&self) -> Vec<u32> {
+        let changed = self.tiles.as_ref().map_or(&[][..], |tiles| &tiles.changed);
+        (0..changed.len() as u32).filter(|&tile| changed[tile as usize]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Boundary, TickStrategy};
+
+    #[test]
+    fn counts_and_flags_tiles() {
+        let mut universe = Universe::new(10, 7, vec![(3, 1), (3, 2), (3, 3), (5, 8)]);
+        universe.enable_tiles(4);
+        assert_eq!((4, 3, 2), (universe.tile_size(), universe.tile_columns(), universe.tile_rows()));
+        assert_eq!(vec![3, 0, 0, 0, 0, 1], universe.tile_populations());
+        assert_eq!(6, universe.changed_tiles().len());
+
+        // The blinker turns upright, reaching into the tile below
+        universe.tick();
+        assert_eq!(vec![2, 0, 0, 1, 0, 0], universe.tile_populations());
+        assert_eq!(vec![0, 3, 5], universe.changed_tiles());
+        universe.toggle_cell(6, 4);
+        assert_eq!(vec![0, 3, 4, 5], universe.changed_tiles());
+        universe.tick();
+        assert_eq!(vec![0, 3, 4], universe.changed_tiles());
+
+        // Written directly, the counts are taken again
+        universe[(0, 9)] = Cell::Alive;
+        assert_eq!(6, universe.changed_tiles().len());
+        assert_eq!(vec![3, 0, 1, 0, 0, 0], universe.tile_populations());
+
+        universe.rotate_cw();
+        assert_eq!((2, 3), (universe.tile_columns(), universe.tile_rows()));
+        assert_eq!(universe.population(), universe.tile_populations().iter().sum::<u32>());
+    }
+
+    #[test]
+    fn tiles_strategy_agrees() {
+        for &boundary in &[Boundary::Torus, Boundary::Dead, Boundary::KleinBottle, Boundary::Sphere] {
+            let mut sparse = Universe::random_seeded(40, 40, 0.3, 5).unwrap();
+            let mut tiled = Universe::random_seeded(40, 40, 0.3, 5).unwrap();
+            sparse.set_boundary(boundary);
+            tiled.set_boundary(boundary);
+            tiled.set_tick_strategy(TickStrategy::Tiles);
+            tiled.enable_tiles(7);
+            for generation in 0..30 {
+                // A new rule makes the next tick start from the live tiles
+                if generation == 10 {
+                    sparse.set_life_rule("B36/S23".parse().unwrap());
+                    tiled.set_life_rule("B36/S23".parse().unwrap());
+                }
+                sparse.tick();
+                tiled.tick();
+                assert_eq!(sparse.cells, tiled.cells, "{:?} generation {}", boundary, generation);
+            }
+        }
+    }
+
+    #[test]
+    fn skips_empty_tiles() {
+        // A glider alone in one corner of a large board, when the last
+        // tick's changes aren't known
+        let mut universe = Universe::new(256, 256, vec![(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        universe.set_tick_strategy(TickStrategy::Tiles);
+        universe.tick();
+        assert_eq!(64, universe.tile_size());
+        let candidates = universe.tile_candidates(None, vec![]);
+        // Its own tile, and the one round the torus that it's touching on
+        // the left edge
+        assert_eq!(2 * 64 * 64, candidates.len());
+    }
+}
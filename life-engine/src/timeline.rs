@@ -0,0 +1,181 @@
+// A timeline of notable events over a run, for annotated scrubber bars
+//
+// Recording is off until `enable_timeline()`. Edits made in the same
+// generation are merged into one event so painting a stroke doesn't flood the
+// timeline. Anything the engine doesn't detect itself, like a spotted
+// pattern, can be added with `annotate_timeline()`
+
+use crate::{Boundary, Rule, Status, Universe};
+
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimelineKind {
+    Extinction,
+    Stabilized { period: u32 },
+    Edit { cells: u32 },
+    BoundaryChange(Boundary),
+    CrittersMode(bool),
+    RuleChange(Rule),
+    Note(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimelineEvent {
+    pub generation: u64,
+    pub kind: TimelineKind,
+}
+
+fn write_json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Universe {
+    pub fn timeline(&self) -> &[TimelineEvent] {
+        self.timeline.as_deref().unwrap_or(&[])
+    }
+
+    pub(crate) fn timeline_record(&mut self, kind: TimelineKind) {
+        let generation = self.generation;
+        if let Some(timeline) = self.timeline.as_mut() {
+            if let (TimelineKind::Edit { cells }, Some(last)) = (&kind, timeline.last_mut()) {
+                if let TimelineKind::Edit { cells: merged } = &mut last.kind {
+                    if last.generation == generation {
+                        *merged += cells;
+                        return;
+                    }
+                }
+            }
+            timeline.push(TimelineEvent { generation, kind });
+        }
+    }
+
+    // Called at the end of tick() with the status from before it
+    pub(crate) fn timeline_status(&mut self, previous: Status) {
+        let status = self.status();
+        if self.timeline.is_none() || status == previous {
+            return;
+        }
+        match status {
+            Status::Extinct => self.timeline_record(TimelineKind::Extinction),
+            Status::StillLife | Status::Oscillating => {
+                let period = self.status_period();
+                self.timeline_record(TimelineKind::Stabilized { period });
+            }
+            Status::Running => {}
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    pub fn enable_timeline(&mut self) {
+        if self.timeline.is_none() {
+            self.timeline = Some(vec![]);
+        }
+    }
+
+    pub fn disable_timeline(&mut self) {
+        self.timeline = None;
+    }
+
+    pub fn clear_timeline(&mut self) {
+        if let Some(timeline) = self.timeline.as_mut() {
+            timeline.clear();
+        }
+    }
+
+    /// Add a labelled event at the current generation
+    pub fn annotate_timeline(&mut self, label: &str) {
+        self.timeline_record(TimelineKind::Note(label.to_string()));
+    }
+
+    /// The timeline as a JSON array of `{"generation": n, "event": kind, ...}`
+    /// objects, where kind is one of extinction, stabilized (with period),
+    /// edit (with cells), boundary (with mode), critters (with enabled),
+    /// rule (with rule) or note (with label)
+    pub fn timeline_json(&self) -> String {
+        let mut out = String::from("[");
+        for (i, event) in self.timeline().iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write!(out, "{{\"generation\":{},", event.generation).unwrap();
+            match &event.kind {
+                TimelineKind::Extinction => out.push_str("\"event\":\"extinction\""),
+                TimelineKind::Stabilized { period } => {
+                    write!(out, "\"event\":\"stabilized\",\"period\":{}", period).unwrap()
+                }
+                TimelineKind::Edit { cells } => write!(out, "\"event\":\"edit\",\"cells\":{}", cells).unwrap(),
+                TimelineKind::BoundaryChange(boundary) => {
+                    write!(out, "\"event\":\"boundary\",\"mode\":\"{:?}\"", boundary).unwrap()
+                }
+                TimelineKind::CrittersMode(enabled) => {
+                    write!(out, "\"event\":\"critters\",\"enabled\":{}", enabled).unwrap()
+                }
+                TimelineKind::RuleChange(rule) => write!(out, "\"event\":\"rule\",\"rule\":\"{}\"", rule).unwrap(),
+                TimelineKind::Note(label) => {
+                    out.push_str("\"event\":\"note\",\"label\":");
+                    write_json_string(&mut out, label);
+                }
+            }
+            out.push('}');
+        }
+        out.push(']');
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_events() {
+        let mut universe = Universe::new(8, 8, vec![(1, 1), (1, 2), (1, 3)]);
+        universe.set_owner(6, 6, 0);
+        assert!(universe.timeline().is_empty());
+
+        universe.enable_timeline();
+        universe.tick();
+        universe.tick();
+        universe.tick();
+        universe.set_owner(5, 5, 0);
+        universe.set_owner(5, 6, 0);
+        universe.set_boundary(Boundary::Dead);
+        universe.annotate_timeline("say \"hi\"");
+
+        assert_eq!(
+            vec![
+                TimelineEvent { generation: 3, kind: TimelineKind::Stabilized { period: 2 } },
+                TimelineEvent { generation: 3, kind: TimelineKind::Edit { cells: 2 } },
+                TimelineEvent { generation: 3, kind: TimelineKind::BoundaryChange(Boundary::Dead) },
+                TimelineEvent { generation: 3, kind: TimelineKind::Note("say \"hi\"".to_string()) },
+            ],
+            universe.timeline()
+        );
+        assert!(universe.timeline_json().starts_with(r#"[{"generation":3,"event":"stabilized","period":2},"#));
+        assert!(universe.timeline_json().ends_with(r#"{"generation":3,"event":"note","label":"say \"hi\""}]"#));
+
+        universe.clear_timeline();
+        for _ in 0..3 {
+            universe.tick();
+        }
+        // The edits reset stabilization, so it's detected again
+        assert_eq!(
+            vec![TimelineEvent { generation: 6, kind: TimelineKind::Stabilized { period: 2 } }],
+            universe.timeline()
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// Fading trails behind moving patterns
+//
+// With trails on, every cell has an intensity from 0 to 255. Live cells are
+// always at 255, and once a cell dies its intensity drops by the decay rate
+// each tick until it reaches 0, so drawing the intensities instead of the
+// cells gives spaceships a comet tail and dying patterns a smooth fade out.
+// Only the fading cells are visited each tick, so a mostly settled board
+// costs next to nothing however big it is.
+//
+// Edits count like ticks: a cell switched on jumps to 255 and one switched
+// off starts fading from 255 at the next tick. The trails move with the
+// board when it's rotated, flipped or shifted. JS can read the intensities
+// in place through `trails_ptr()`
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const FULL: u8 = u8::MAX;
+
+#[derive(Clone)]
+pub(crate) struct Trails {
+    decay: u8,
+    levels: Vec<u8>,
+    // Dead cells with some intensity left, possibly listed twice and with
+    // cells born since, which the next tick sorts out
+    fading: Vec<u32>,
+}
+
+impl Trails {
+    fn new(decay: u8, cells: &[Cell]) -> Trails {
+        let levels = cells.iter().map(|&cell| if cell == Cell::Alive { FULL } else { 0 }).collect();
+        Trails { decay, levels, fading: vec![] }
+    }
+
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.levels.capacity() + self.fading.capacity() * std::mem::size_of::<u32>()
+    }
+
+    // The levels of a rearranged board, with the cells fading on it
+    pub(crate) fn set_levels(&mut self, levels: Vec<u8>, cells: &[Cell]) {
+        self.fading = (0..levels.len())
+            .filter(|&idx| cells[idx] == Cell::Dead && levels[idx] > 0)
+            .map(|idx| idx as u32)
+            .collect();
+        self.levels = levels;
+    }
+
+    pub(crate) fn levels(&self) -> &[u8] {
+        &self.levels
+    }
+}
+
+impl Universe {
+    // Called from tick() with the cells that just flipped
+    pub(crate) fn fade_trails(&mut self, changed: &[usize]) {
+        self.mark_trails(changed);
+        let trails = match self.trails.as_mut() {
+            Some(trails) => trails,
+            None => return,
+        };
+        trails.fading.sort_unstable();
+        trails.fading.dedup();
+        let (cells, levels, decay) = (&self.cells, &mut trails.levels, trails.decay);
+        trails.fading.retain(|&idx| {
+            let idx = idx as usize;
+            if cells[idx] == Cell::Alive {
+                return false;
+            }
+            levels[idx] = levels[idx].saturating_sub(decay);
+            levels[idx] > 0
+        });
+    }
+
+    // Called for edits, and from tick() before the fading
+    pub(crate) fn mark_trails(&mut self, changed: &[usize]) {
+        if let Some(trails) = self.trails.as_mut() {
+            for &idx in changed {
+                trails.levels[idx] = FULL;
+                if self.cells[idx] == Cell::Dead {
+                    trails.fading.push(idx as u32);
+                }
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Keep an intensity per cell which is 255 while it's alive and drops
+    /// by `decay` every tick once it's dead, starting from just the cells
+    /// alive now. A decay of 0 turns trails off
+    pub fn enable_trails(&mut self, decay: u8) {
+        self.trails = (decay > 0).then(|| Trails::new(decay, &self.cells));
+    }
+
+    pub fn disable_trails(&mut self) {
+        self.trails = None;
+    }
+
+    /// Intensity lost per tick, 0 while trails are off
+    pub fn trail_decay(&self) -> u8 {
+        self.trails.as_ref().map_or(0, |trails| trails.decay)
+    }
+
+    /// Change how fast trails fade, keeping the intensities so far
+    pub fn set_trail_decay(&mut self, decay: u8) {
+        match self.trails.as_mut() {
+            Some(trails) if decay > 0 => trails.decay = decay,
+            _ => self.enable_trails(decay),
+        }
+    }
+
+    /// The cell's intensity, 0 while trails are off
+    pub fn trail_at(&self, row: u32, column: u32) -> u8 {
+        self.trails.as_ref().map_or(0, |trails| trails.levels[self.get_index(row, column)])
+    }
+
+    /// Every cell's intensity, in the same layout as the cell buffer
+    pub fn trails(&self) -> Vec<u8> {
+        match &self.trails {
+            Some(trails) => trails.levels.clone(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Pointer to the intensities in WASM memory, one byte per cell, or null
+    /// while trails are off. Only valid until the next call that changes
+    /// the universe
+    pub fn trails_ptr(&self) -> *const u8 {
+        match &self.trails {
+            Some(trails) => trails.levels.as_ptr(),
+            None => std::ptr::null(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fades_behind_a_glider() {
+        let mut universe: Universe = "\
+            .#......\n\
+            ..#.....\n\
+            ###.....\n\
+            ........\n\
+            ........\n\
+            ........\n".parse().unwrap();
+        assert!(universe.trails_ptr().is_null());
+        universe.enable_trails(100);
+        assert_eq!(255, universe.trail_at(0, 1));
+
+        // (0, 1) dies on the first tick and fades over the next two
+        universe.tick();
+        assert_eq!(155, universe.trail_at(0, 1));
+        universe.tick();
+        assert_eq!(55, universe.trail_at(0, 1));
+        universe.tick();
+        assert_eq!(0, universe.trail_at(0, 1));
+        assert!(universe.live_cells().all(|(row, column)| universe.trail_at(row, column) == 255));
+
+        // Edits light cells up at once and start them fading from full
+        universe.toggle_cell(5, 7);
+        universe.toggle_cell(5, 7);
+        assert_eq!(255, universe.trail_at(5, 7));
+        universe.tick();
+        assert_eq!(155, universe.trail_at(5, 7));
+
+        universe.set_trail_decay(200);
+        universe.tick();
+        assert_eq!(0, universe.trail_at(5, 7));
+        assert_eq!(5, universe.trails().iter().filter(|&&level| level == 255).count());
+
+        universe.disable_trails();
+        assert_eq!(0, universe.trail_decay());
+    }
+
+    #[test]
+    fn moves_with_the_board() {
+        let mut universe = Universe::new(6, 6, vec![(2, 2)]);
+        universe.enable_trails(50);
+        universe.tick();
+        assert_eq!(205, universe.trail_at(2, 2));
+        universe.flip_horizontal();
+        assert_eq!((0, 205), (universe.trail_at(2, 2), universe.trail_at(2, 3)));
+        universe.tick();
+        assert_eq!(155, universe.trail_at(2, 3));
+    }
+}
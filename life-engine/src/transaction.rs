@@ -0,0 +1,167 @@
+// Many edits in one call, applied all together or not at all
+//
+// An editor making a change out of several pieces (a pasted pattern, a few
+// toggles, a cleared area) can send them over as one list instead of a call
+// each. Every edit is checked before any is made, so a bad one leaves the
+// board untouched, and the whole list is a single undo action. From JS each
+// edit is a plain object tagged by `op`:
+//
+//     universe.apply_edits([
+//         { op: "clear_rect", top: 0, left: 0, rows: 8, columns: 8 },
+//         { op: "stamp", row: 2, column: 2, pattern: ".#.\n..#\n###" },
+//         { op: "set", row: 0, column: 7, alive: true },
+//         { op: "toggle", row: 5, column: 5 },
+//     ]);
+//
+// Each edit's anchor, its cell or top left corner, has to be on the board.
+// Stamped patterns and cleared rectangles hanging off the edges are clipped,
+// the same as `overlay()`
+
+use crate::error::LifeError;
+use crate::{Cell, OverlayMode, Rect, Universe};
+
+#[cfg(feature = "serde")]
+use serde::Deserialize;
+#[cfg(feature = "wasm")]
+use tsify::{Ts, Tsify};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "serde", derive(Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", rename_all = "snake_case"))]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    Set { row: u32, column: u32, alive: bool },
+    Toggle { row: u32, column: u32 },
+    /// Live cells of `pattern`, in the text form `parse()` takes, added
+    /// with their top left corner at (row, column)
+    Stamp { row: u32, column: u32, pattern: String },
+    ClearRect { top: u32, left: u32, rows: u32, columns: u32 },
+}
+
+// An edit once checked, patterns parsed
+enum Checked {
+    Set(usize, Cell),
+    Toggle(usize),
+    Stamp(u32, u32, Box<Universe>),
+    Clear(Rect),
+}
+
+impl Universe {
+    fn check_edit(&self, edit: &Edit) -> Result<Checked, LifeError> {
+        let (row, column) = match *edit {
+            Edit::Set { row, column, .. } | Edit::Toggle { row, column } | Edit::Stamp { row, column, .. } => {
+                (row, column)
+            }
+            Edit::ClearRect { top, left, .. } => (top, left),
+        };
+        if row >= self.height || column >= self.width {
+            return Err(LifeError::OutOfBounds { row, column });
+        }
+
+        Ok(match edit {
+            Edit::Set { alive, .. } => {
+                Checked::Set(self.get_index(row, column), if *alive { Cell::Alive } else { Cell::Dead })
+            }
+            Edit::Toggle { .. } => Checked::Toggle(self.get_index(row, column)),
+            Edit::Stamp { pattern, .. } => Checked::Stamp(row, column, Box::new(pattern.parse()?)),
+            Edit::ClearRect { rows, columns, .. } => {
+                let rect = Rect::new(row, column, *rows, *columns);
+                Checked::Clear(rect.intersection(&self.bounds()).unwrap_or(rect))
+            }
+        })
+    }
+
+    /// Rust side of `apply_edits`
+    pub fn apply_edit_list(&mut self, edits: &[Edit]) -> Result<(), LifeError> {
+        let checked = edits.iter().map(|edit| self.check_edit(edit)).collect::<Result<Vec<_>, _>>()?;
+        self.edit_group(|universe| {
+            for edit in checked {
+                match edit {
+                    Checked::Set(idx, cell) => universe.write_cell(idx, cell),
+                    Checked::Toggle(idx) => {
+                        let toggled = match universe.cells[idx] {
+                            Cell::Dead => Cell::Alive,
+                            Cell::Alive => Cell::Dead,
+                        };
+                        universe.write_cell(idx, toggled);
+                    }
+                    Checked::Stamp(row, column, pattern) => universe.overlay(&pattern, row, column, OverlayMode::Or),
+                    Checked::Clear(rect) => {
+                        for (row, column) in rect.cells() {
+                            let idx = universe.get_index(row, column);
+                            universe.write_cell(idx, Cell::Dead);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Apply a list of edits as one undoable action, making none of them if
+    /// any is malformed or off the board
+    #[cfg(feature = "wasm")]
+    pub fn apply_edits(&mut self, edits: Vec<Ts<Edit>>) -> Result<(), JsValue> {
+        let edits = edits
+            .iter()
+            .map(|edit| edit.to_rust().map_err(|err| LifeError::InvalidArgument(err.to_string())))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(crate::error::js_error)?;
+        self.apply_edit_list(&edits).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_as_one_action() {
+        let mut universe = Universe::new(8, 6, vec![(0, 0), (1, 1), (4, 6)]);
+        universe.set_undo_capacity(10);
+        let before = universe.cells.clone();
+
+        universe
+            .apply_edit_list(&[
+                Edit::ClearRect { top: 0, left: 0, rows: 2, columns: 2 },
+                Edit::Stamp { row: 3, column: 6, pattern: "#.#\n.##".to_string() },
+                Edit::Set { row: 0, column: 7, alive: true },
+                Edit::Toggle { row: 4, column: 6 },
+            ])
+            .unwrap();
+        // The stamp's last column is off the edge
+        assert_eq!(vec![(0, 7), (3, 6), (4, 7)], universe.live_cells().collect::<Vec<_>>());
+
+        assert!(universe.undo_edit());
+        assert_eq!(before, universe.cells);
+        assert!(!universe.can_undo_edit());
+    }
+
+    #[test]
+    fn all_or_nothing() {
+        let mut universe = Universe::new(8, 6, vec![(2, 2)]);
+        universe.set_undo_capacity(10);
+        let before = universe.cells.clone();
+
+        let bad_edits = [
+            Edit::Set { row: 6, column: 0, alive: true },
+            Edit::ClearRect { top: 0, left: 8, rows: 1, columns: 1 },
+            Edit::Stamp { row: 0, column: 0, pattern: "#?".to_string() },
+        ];
+        for bad in bad_edits {
+            let edits = [Edit::Toggle { row: 2, column: 2 }, bad];
+            assert!(universe.apply_edit_list(&edits).is_err());
+            assert_eq!(before, universe.cells);
+        }
+        assert!(matches!(
+            universe.apply_edit_list(&[Edit::Toggle { row: 0, column: 9 }]),
+            Err(LifeError::OutOfBounds { row: 0, column: 9 })
+        ));
+        assert!(!universe.can_undo_edit());
+    }
+}
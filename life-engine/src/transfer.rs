@@ -0,0 +1,471 @@
+// Moving a running simulation between the main thread and a Web Worker
+//
+// `snapshot()` packs everything the next tick depends on into one byte
+// buffer: the cells, rule, boundary and generation count, the update
+// schedule, walls, ages, symmetry and glider spawner, plus the exact
+// position of the random generators behind a probabilistic rule, random
+// edge injection, random update order or glider spawns. A worker given the buffer (transferred rather than copied,
+// `postMessage(bytes, [bytes.buffer])`) can `Universe.restore()` it and tick
+// on exactly as the original would have, and send it back the same way.
+//
+// Layout, all integers little endian:
+//
+//     bytes 0..2   magic b"GT"
+//     byte  2      format version (2)
+//     8 bytes      generation (u64)
+//     byte         boundary (u8, as the Boundary enum)
+//     2 + n bytes  rule in B/S notation, its length first (u16)
+//     4 + n bytes  the cells as a `to_snapshot()`, its length first (u32)
+//     byte         1 if a probabilistic rule follows, else 0:
+//                    8 + 8 bytes  birth and survival probabilities (f64)
+//                    generator state
+//     byte         0 for no injection, 1 random, 2 pattern, then:
+//                    byte         edge (u8, as the Edge enum)
+//                    4 + n bytes  the ghost line, its length first, packed
+//                    random:  4 bytes probability (f32), generator state
+//                    pattern: 4 bytes next frame, then 4 + n bytes frames,
+//                             their count of cells first, packed
+//     byte         update mode (u8, as the UpdateMode enum), unless
+//                  synchronous followed by:
+//                    8 bytes      update probability (f64)
+//                    generator state
+//     byte         1 if walls follow, else 0:
+//                    4 + n bytes  the wall mask, packed like cells
+//     4 bytes      maximum age (u32, 0 for none)
+//     byte         1 if ages follow, else 0:
+//                    a LEB128 varint per cell, generations since it was born
+//     byte         symmetry (u8, as the Symmetry enum)
+//     byte         1 if a glider spawner follows, else 0:
+//                    8 bytes rate (f64), byte heading (as the GliderHeading
+//                    enum), 4 bytes margin, generator state
+//
+// A generator state is its length as a byte followed by that many bytes,
+// and only restores on a build using the same generator (see rng.rs).
+// Version 1 snapshots, which stop after the injection, still restore.
+//
+// Only what decides how the board ticks goes over. Editing layers like
+// ownership, history and undo, and rule tables, block rules and transition
+// functions stay behind; `save()` and the other formats cover those
+
+use crate::boundary::{Injection, InjectionSource};
+use crate::delta::{read_varint, write_varint};
+use crate::error::LifeError;
+use crate::gliders::Gliders;
+use crate::replay::BOUNDARIES;
+use crate::rng::{rng_from_state, rng_state};
+use crate::schedule::Schedule;
+use crate::snapshot::{pack_cells, unpack_cells};
+use crate::stochastic::Stochastic;
+use crate::{Cell, Edge, GliderHeading, Symmetry, Universe, UpdateMode};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const MAGIC: &[u8; 2] = b"GT";
+const VERSION: u8 = 2;
+// Stopped after the injection
+const FIRST_VERSION: u8 = 1;
+
+const EDGES: [Edge; 4] = [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right];
+const UPDATE_MODES: [UpdateMode; 3] = [UpdateMode::Synchronous, UpdateMode::RandomOrder, UpdateMode::RandomSubset];
+const SYMMETRIES: [Symmetry; 8] = [
+    Symmetry::None,
+    Symmetry::LeftRight,
+    Symmetry::TopBottom,
+    Symmetry::Both,
+    Symmetry::HalfTurn,
+    Symmetry::QuarterTurn,
+    Symmetry::Diagonal,
+    Symmetry::Kaleidoscope,
+];
+const HEADINGS: [GliderHeading; 5] = [
+    GliderHeading::Any,
+    GliderHeading::NorthEast,
+    GliderHeading::NorthWest,
+    GliderHeading::SouthEast,
+    GliderHeading::SouthWest,
+];
+
+const NO_INJECTION: u8 = 0;
+const RANDOM_INJECTION: u8 = 1;
+const PATTERN_INJECTION: u8 = 2;
+
+fn write_packed(out: &mut Vec<u8>, cells: &[Cell]) {
+    out.extend_from_slice(&(cells.len() as u32).to_le_bytes());
+    out.extend(pack_cells(cells));
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LifeError> {
+        let end = self.position.checked_add(len).filter(|&end| end <= self.data.len());
+        let end = end.ok_or_else(|| LifeError::InvalidData("snapshot is cut short".to_string()))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], LifeError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, LifeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, LifeError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64, LifeError> {
+        self.array().map(f64::from_le_bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, LifeError> {
+        read_varint(self.data, &mut self.position).ok_or_else(|| invalid("snapshot is cut short"))
+    }
+
+    // A byte that's 1 if something follows, or 0
+    fn flag(&mut self, what: &str) -> Result<bool, LifeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(LifeError::InvalidData(format!("bad {} flag", what))),
+        }
+    }
+
+    fn packed(&mut self) -> Result<Vec<Cell>, LifeError> {
+        let count = self.u32()? as usize;
+        Ok(unpack_cells(self.take(count.div_ceil(8))?, count))
+    }
+
+    fn rng(&mut self) -> Result<Box<crate::rng::SeededRng>, LifeError> {
+        let len = self.u8()? as usize;
+        let rng = rng_from_state(self.take(len)?).ok_or_else(|| {
+            LifeError::InvalidData("random generator state is from a build with a different generator".to_string())
+        })?;
+        Ok(Box::new(rng))
+    }
+}
+
+fn invalid(message: &str) -> LifeError {
+    LifeError::InvalidData(message.to_string())
+}
+
+impl Universe {
+    /// Rust side of `restore`
+    pub fn decode_transfer(data: &[u8]) -> Result<Universe, LifeError> {
+        let mut reader = Reader { data, position: 0 };
+        if reader.take(2).ok() != Some(&MAGIC[..]) {
+            return Err(invalid("data isn't a universe snapshot"));
+        }
+        let version = match reader.u8()? {
+            version @ (FIRST_VERSION | VERSION) => version,
+            version => return Err(LifeError::UnsupportedVersion(version)),
+        };
+
+        let generation = u64::from_le_bytes(reader.array()?);
+        let boundary = *BOUNDARIES.get(reader.u8()? as usize).ok_or_else(|| invalid("unknown boundary"))?;
+        let rule_len = u16::from_le_bytes(reader.array()?) as usize;
+        let rule = std::str::from_utf8(reader.take(rule_len)?).map_err(|_| invalid("rule isn't text"))?;
+        let rule = rule.parse()?;
+        let cells_len = reader.u32()? as usize;
+        let mut universe = Universe::decode_snapshot(reader.take(cells_len)?)?;
+        universe.generation = generation;
+        universe.boundary = boundary;
+        universe.rule = rule;
+
+        if reader.flag("probabilistic rule")? {
+            let birth = reader.f64()?;
+            let survival = reader.f64()?;
+            let rng = reader.rng()?;
+            universe.stochastic = Some(Stochastic { birth, survival, rng });
+        }
+
+        let kind = reader.u8()?;
+        if kind != NO_INJECTION {
+            let edge = *EDGES.get(reader.u8()? as usize).ok_or_else(|| invalid("unknown injection edge"))?;
+            let ghost = reader.packed()?;
+            if ghost.len() != universe.edge_length(edge) {
+                return Err(invalid("injection doesn't fit the edge"));
+            }
+            let source = match kind {
+                RANDOM_INJECTION => {
+                    let probability = f32::from_le_bytes(reader.array()?);
+                    InjectionSource::Random { probability, rng: reader.rng()? }
+                }
+                PATTERN_INJECTION => {
+                    let next_frame = reader.u32()? as usize;
+                    let frames = reader.packed()?;
+                    if frames.is_empty() || frames.len() % ghost.len() != 0 || next_frame >= frames.len() / ghost.len() {
+                        return Err(invalid("injection pattern doesn't fit the edge"));
+                    }
+                    InjectionSource::Pattern { frames, next_frame }
+                }
+                _ => return Err(invalid("unknown injection")),
+            };
+            universe.injection = Some(Injection { edge, source, ghost });
+        }
+        if version != FIRST_VERSION {
+            universe.read_layers(&mut reader)?;
+        }
+
+        if reader.position != data.len() {
+            return Err(invalid("unexpected data after the snapshot"));
+        }
+        Ok(universe)
+    }
+
+    // Everything after the injection, new in version 2
+    fn read_layers(&mut self, reader: &mut Reader) -> Result<(), LifeError> {
+        let mode = *UPDATE_MODES.get(reader.u8()? as usize).ok_or_else(|| invalid("unknown update mode"))?;
+        if mode != UpdateMode::Synchronous {
+            let probability = reader.f64()?;
+            self.schedule = Some(Schedule { mode, probability, rng: reader.rng()? });
+        }
+
+        if reader.flag("walls")? {
+            let walls = reader.packed()?;
+            if walls.len() != self.cells.len() {
+                return Err(invalid("walls don't fit the board"));
+            }
+            self.walls = Some(walls.into_iter().map(|wall| wall == Cell::Alive).collect());
+        }
+
+        let max_age = reader.u32()?;
+        if reader.flag("ages")? {
+            let mut born = Vec::with_capacity(self.cells.len());
+            for _ in 0..self.cells.len() {
+                let age = reader.varint()?;
+                born.push(self.generation.checked_sub(age).ok_or_else(|| invalid("cell born before generation 0"))?);
+            }
+            self.born = Some(born);
+        }
+        self.max_age = match (max_age, &self.born) {
+            (0, _) => None,
+            (_, Some(_)) => Some(max_age),
+            (_, None) => return Err(invalid("maximum age without ages")),
+        };
+
+        self.symmetry = *SYMMETRIES.get(reader.u8()? as usize).ok_or_else(|| invalid("unknown symmetry"))?;
+        if self.symmetry.needs_square() && self.width != self.height {
+            return Err(invalid("symmetry doesn't fit the board"));
+        }
+
+        if reader.flag("glider spawner")? {
+            let rate = reader.f64()?;
+            let heading = *HEADINGS.get(reader.u8()? as usize).ok_or_else(|| invalid("unknown glider heading"))?;
+            let margin = reader.u32()?;
+            self.gliders = Some(Gliders { rate, heading, margin, rng: reader.rng()? });
+        }
+        Ok(())
+    }
+}
+
+fn write_rng(data: &mut Vec<u8>, rng: &crate::rng::SeededRng) {
+    let state = rng_state(rng);
+    data.push(state.len() as u8);
+    data.extend(state);
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Everything needed to carry on ticking elsewhere, for handing the
+    /// simulation to or from a Web Worker. Unlike `to_snapshot()`, which
+    /// is just the cells, this keeps the rule, generation count and random
+    /// generators
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        data.push(VERSION);
+        data.extend_from_slice(&self.generation.to_le_bytes());
+        data.push(self.boundary as u8);
+        let rule = self.rule.to_string();
+        data.extend_from_slice(&(rule.len() as u16).to_le_bytes());
+        data.extend_from_slice(rule.as_bytes());
+        let cells = self.to_snapshot();
+        data.extend_from_slice(&(cells.len() as u32).to_le_bytes());
+        data.extend(cells);
+
+        match &self.stochastic {
+            Some(stochastic) => {
+                data.push(1);
+                data.extend_from_slice(&stochastic.birth.to_le_bytes());
+                data.extend_from_slice(&stochastic.survival.to_le_bytes());
+                write_rng(&mut data, &stochastic.rng);
+            }
+            None => data.push(0),
+        }
+
+        match &self.injection {
+            Some(injection) => {
+                let kind = match injection.source {
+                    InjectionSource::Random { .. } => RANDOM_INJECTION,
+                    InjectionSource::Pattern { .. } => PATTERN_INJECTION,
+                };
+                data.push(kind);
+                data.push(injection.edge as u8);
+                write_packed(&mut data, &injection.ghost);
+                match &injection.source {
+                    InjectionSource::Random { probability, rng } => {
+                        data.extend_from_slice(&probability.to_le_bytes());
+                        write_rng(&mut data, rng);
+                    }
+                    InjectionSource::Pattern { frames, next_frame } => {
+                        data.extend_from_slice(&(*next_frame as u32).to_le_bytes());
+                        write_packed(&mut data, frames);
+                    }
+                }
+            }
+            None => data.push(NO_INJECTION),
+        }
+
+        match &self.schedule {
+            Some(schedule) => {
+                data.push(schedule.mode as u8);
+                data.extend_from_slice(&schedule.probability.to_le_bytes());
+                write_rng(&mut data, &schedule.rng);
+            }
+            None => data.push(UpdateMode::Synchronous as u8),
+        }
+        match &self.walls {
+            Some(walls) => {
+                data.push(1);
+                let walls = walls.iter().map(|&wall| if wall { Cell::Alive } else { Cell::Dead }).collect::<Vec<_>>();
+                write_packed(&mut data, &walls);
+            }
+            None => data.push(0),
+        }
+        data.extend_from_slice(&self.max_age().to_le_bytes());
+        match &self.born {
+            Some(born) => {
+                data.push(1);
+                for &born in born {
+                    write_varint(&mut data, self.generation.saturating_sub(born));
+                }
+            }
+            None => data.push(0),
+        }
+        data.push(self.symmetry as u8);
+        match &self.gliders {
+            Some(gliders) => {
+                data.push(1);
+                data.extend_from_slice(&gliders.rate.to_le_bytes());
+                data.push(gliders.heading as u8);
+                data.extend_from_slice(&gliders.margin.to_le_bytes());
+                write_rng(&mut data, &gliders.rng);
+            }
+            None => data.push(0),
+        }
+        data
+    }
+
+    /// A universe from `snapshot()`, carrying on where that one left off
+    #[cfg(feature = "wasm")]
+    pub fn restore(data: &[u8]) -> Result<Universe, JsValue> {
+        Universe::decode_transfer(data).map_err(crate::error::js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Boundary;
+
+    #[test]
+    fn carries_on_identically() {
+        let mut universe = Universe::random_seeded(20, 12, 0.35, 4).unwrap();
+        universe.set_life_rule("B36/S23".parse().unwrap());
+        universe.set_rule_probabilities(0.8, 0.95, 11);
+        universe.set_injection_random(Edge::Left, 0.3, 5);
+        for _ in 0..7 {
+            universe.tick();
+        }
+
+        let mut restored = Universe::decode_transfer(&universe.snapshot()).unwrap();
+        assert_eq!((7, Boundary::Inject), (restored.generation(), restored.boundary()));
+        assert_eq!(universe.life_rule(), restored.life_rule());
+        for _ in 0..10 {
+            universe.tick();
+            restored.tick();
+            assert_eq!(universe.cells, restored.cells);
+        }
+
+        let mut universe = Universe::new(6, 6, vec![(2, 2), (2, 3), (3, 2)]);
+        universe.set_injection_pattern(Edge::Top, vec![1, 0, 0, 1, 0, 0, 0, 1, 1, 0, 1, 0]).unwrap();
+        universe.tick();
+        let mut restored = Universe::decode_transfer(&universe.snapshot()).unwrap();
+        for _ in 0..3 {
+            universe.tick();
+            restored.tick();
+            assert_eq!(universe.cells, restored.cells);
+        }
+    }
+
+    #[test]
+    fn carries_the_other_layers() {
+        let mut universe = Universe::random_seeded(16, 16, 0.4, 9).unwrap();
+        universe.set_update_mode(UpdateMode::RandomSubset, 3);
+        universe.set_update_probability(0.7);
+        universe.set_wall(5, 5, true);
+        universe.set_max_age(6);
+        universe.try_set_symmetry(Symmetry::QuarterTurn).unwrap();
+        universe.spawn_gliders(0.4, 8);
+        universe.set_glider_heading(GliderHeading::NorthWest);
+        universe.set_glider_margin(2);
+        for _ in 0..5 {
+            universe.tick();
+        }
+
+        let mut restored = Universe::decode_transfer(&universe.snapshot()).unwrap();
+        assert_eq!(UpdateMode::RandomSubset, restored.update_mode());
+        assert_eq!((6, Symmetry::QuarterTurn), (restored.max_age(), restored.symmetry()));
+        assert_eq!((0.4, GliderHeading::NorthWest, 2), (restored.glider_rate(), restored.glider_heading(), restored.glider_margin()));
+        assert_eq!(universe.ages(), restored.ages());
+        for _ in 0..20 {
+            universe.tick();
+            restored.tick();
+            assert_eq!(universe.cells, restored.cells);
+            assert_eq!(universe.ages(), restored.ages());
+        }
+        assert!(!restored.is_alive(5, 5));
+    }
+
+    #[test]
+    fn restores_the_first_version() {
+        let mut universe = Universe::new(6, 6, vec![(2, 2), (2, 3), (2, 4)]);
+        universe.set_rule_probabilities(0.5, 1.0, 1);
+        let mut data = universe.snapshot();
+        // Synchronous, no walls, no maximum age or ages, no symmetry or gliders
+        let layers = data.split_off(data.len() - 9);
+        assert_eq!(vec![0; 9], layers);
+        data[2] = FIRST_VERSION;
+        let restored = Universe::decode_transfer(&data).unwrap();
+        assert_eq!(universe.cells, restored.cells);
+        assert_eq!(UpdateMode::Synchronous, restored.update_mode());
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        let mut universe = Universe::new(5, 5, vec![(1, 1)]);
+        universe.set_rule_probabilities(0.5, 1.0, 1);
+        let data = universe.snapshot();
+        assert!(Universe::decode_transfer(&data).is_ok());
+
+        for len in [0, 3, 12, data.len() - 1] {
+            assert!(matches!(Universe::decode_transfer(&data[..len]), Err(LifeError::InvalidData(_))), "{}", len);
+        }
+        let mut newer = data.clone();
+        newer[2] = 3;
+        assert_eq!(Some(LifeError::UnsupportedVersion(3)), Universe::decode_transfer(&newer).err());
+        let mut longer = data;
+        longer.push(0);
+        assert!(Universe::decode_transfer(&longer).is_err());
+        assert!(Universe::decode_transfer(&universe.to_snapshot()).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+// Rotating, flipping and shifting the whole board
+//
+// Owners, match territory, lineage, walls, cell ages, the envelope, cell
+// data and trails move with their cells. When flipping, or rotating a square universe,
+// the cells are rewritten as ordinary edits. Rotating a non-square universe
+// swaps its width and height, which nothing indexed by the old layout
+// survives: history, change tracking and delta streams start over, an
+// injection is dropped unless its edge keeps its length (leaving Inject
+// behaving like Dead), and a journal sees no frames for it since they can
+// only describe edits. The heatmap starts over after any of them
+
+use crate::{Boundary, Cell, TimelineKind, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    // Lay the cells out again as a width x height board, where source[idx]
+    // is where the new cell idx comes from, or None for a dead cell
+    pub(crate) fn rearrange(&mut self, width: u32, height: u32, source: &[Option<usize>]) {
+        debug_assert_eq!(width as usize * height as usize, source.len());
+        let cells = source.iter()
+            .map(|from| from.map_or(Cell::Dead, |from| self.cells[from]))
+            .collect::<Vec<_>>();
+        let born = self.born.as_ref()
+            .map(|born| source.iter().map(|from| from.map_or(0, |from| born[from])).collect::<Vec<_>>());
+        let lineage_ids = self.lineage.as_ref().map(|lineage| lineage.rearranged(source));
+        let envelope = self.envelope.as_ref()
+            .map(|envelope| source.iter().map(|from| from.map_or(0, |from| envelope[from])).collect::<Vec<_>>());
+        let cell_data = self.cell_data.as_ref()
+            .map(|data| source.iter().map(|from| from.map_or(0, |from| data[from])).collect::<Vec<_>>());
+        let trails = self.trails.as_ref()
+            .map(|trails| source.iter().map(|from| from.map_or(0, |from| trails.levels()[from])).collect::<Vec<_>>());
+        // Lifted while the cells are written so they can move onto old walls
+        let walls = self.walls.take()
+            .map(|walls| source.iter().map(|from| from.is_some_and(|from| walls[from])).collect::<Vec<_>>());
+        let owners = self.ownership.as_ref().map(|ownership| ownership.rearranged(source));
+        if let Some(game) = self.game.as_mut() {
+            game.rearrange(source);
+        }
+
+        if (width, height) == (self.width, self.height) {
+            self.edit_group(|universe| {
+                for (idx, cell) in cells.into_iter().enumerate() {
+                    universe.write_cell(idx, cell);
+                }
+            });
+        } else {
+            // Only ever a rotation, so the population is unchanged
+            self.shared_begin();
+            self.width = width;
+            self.height = height;
+            self.offsets = crate::offsets::NeighbourOffsets::new(width, height);
+            self.neighbour_counts = crate::neighbour_counts::NeighbourCounts::stale(cells.len());
+            self.cells = cells;
+            self.tiles = self.tiles.as_ref().map(|tiles| tiles.resized(width, height, &self.cells));
+            self.active_cells = None;
+            self.clear_history();
+            self.forget_edits();
+            if self.change_log.is_some() {
+                self.disable_change_tracking();
+                self.enable_change_tracking();
+            }
+            self.stop_delta_stream();
+            let edge_resized = self.injection.as_ref()
+                .is_some_and(|injection| injection.ghost.len() != self.edge_length(injection.edge));
+            if edge_resized {
+                self.injection = None;
+            }
+            self.timeline_record(TimelineKind::Edit { cells: self.cells.len() as u32 });
+            self.stability.reset();
+            self.shared_publish();
+        }
+        if born.is_some() {
+            self.born = born;
+        }
+        if envelope.is_some() {
+            self.envelope = envelope;
+        }
+        if cell_data.is_some() {
+            self.cell_data = cell_data;
+        }
+        if let (Some(trails), Some(levels)) = (self.trails.as_mut(), trails) {
+            trails.set_levels(levels, &self.cells);
+        }
+        self.restart_heatmap();
+        if let (Some(lineage), Some(ids)) = (self.lineage.as_mut(), lineage_ids) {
+            lineage.set_ids(ids);
+        }
+        if let (Some(ownership), Some(owners)) = (self.ownership.as_mut(), owners) {
+            ownership.set_owners(owners);
+        }
+        self.walls = walls;
+    }
+
+    // Sources for a new width x height board whose cell (row, column) comes
+    // from old(row, column) on the current one
+    fn sources(&self, width: u32, height: u32, old: impl Fn(u32, u32) -> (u32, u32)) -> Vec<Option<usize>> {
+        (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let (from_row, from_column) = old(row, column);
+                Some(self.get_index(from_row, from_column))
+            })
+            .collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Turn the board a quarter turn clockwise, swapping width and height
+    pub fn rotate_cw(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let source = self.sources(height, width, |row, column| (height - 1 - column, row));
+        self.rearrange(height, width, &source);
+    }
+
+    pub fn rotate_ccw(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let source = self.sources(height, width, |row, column| (column, width - 1 - row));
+        self.rearrange(height, width, &source);
+    }
+
+    /// Mirror left to right
+    pub fn flip_horizontal(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let source = self.sources(width, height, |row, column| (row, width - 1 - column));
+        self.rearrange(width, height, &source);
+    }
+
+    /// Mirror top to bottom
+    pub fn flip_vertical(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let source = self.sources(width, height, |row, column| (height - 1 - row, column));
+        self.rearrange(width, height, &source);
+    }
+
+    /// Move everything `dx` columns right and `dy` rows down (negative for
+    /// left and up). A torus wraps cells round to the other side, any other
+    /// boundary loses the ones pushed off the edge
+    pub fn shift(&mut self, dx: i32, dy: i32) {
+        let (width, height) = (self.width as i64, self.height as i64);
+        let wrap = self.boundary == Boundary::Torus;
+        let source = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let (from_row, from_column) = (row - dy as i64, column - dx as i64);
+                if wrap {
+                    Some((from_row.rem_euclid(height) * width + from_column.rem_euclid(width)) as usize)
+                } else if (0..height).contains(&from_row) && (0..width).contains(&from_column) {
+                    Some((from_row * width + from_column) as usize)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        self.rearrange(self.width, self.height, &source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RenderConfig;
+
+    fn ascii(universe: &Universe) -> String {
+        universe.render_with(&RenderConfig::ascii())
+    }
+
+    #[test]
+    fn rotations_and_flips() {
+        let original = "#..\n.##\n";
+        let mut universe: Universe = original.parse().unwrap();
+
+        universe.rotate_cw();
+        assert_eq!((2, 3), (universe.width(), universe.height()));
+        assert_eq!(".#\n#.\n#.\n", ascii(&universe));
+        universe.rotate_ccw();
+        assert_eq!(original, ascii(&universe));
+
+        universe.flip_horizontal();
+        assert_eq!("..#\n##.\n", ascii(&universe));
+        universe.flip_vertical();
+        assert_eq!("##.\n..#\n", ascii(&universe));
+
+        // Four turns is back to the start
+        let mut universe: Universe = original.parse().unwrap();
+        for _ in 0..4 {
+            universe.rotate_cw();
+        }
+        assert_eq!(original, ascii(&universe));
+    }
+
+    #[test]
+    fn shifting() {
+        let mut universe: Universe = "#...\n.#..\n....\n".parse().unwrap();
+        universe.shift(1, 1);
+        assert_eq!("....\n.#..\n..#.\n", ascii(&universe));
+        universe.shift(-2, 0);
+        assert_eq!("....\n...#\n#...\n", ascii(&universe));
+        universe.shift(4, -3);
+        assert_eq!("....\n...#\n#...\n", ascii(&universe));
+
+        universe.set_boundary(Boundary::Dead);
+        universe.shift(1, 0);
+        assert_eq!("....\n....\n.#..\n", ascii(&universe));
+        universe.shift(0, i32::MIN);
+        assert_eq!(0, universe.population());
+    }
+
+    #[test]
+    fn rotated_universe_keeps_running() {
+        let mut universe = Universe::random_seeded(13, 7, 0.4, 4).unwrap();
+        universe.enable_age_tracking();
+        universe.set_history_capacity(4);
+        universe.tick();
+        universe.set_owner(0, 0, 3);
+        universe.set_cell_data(0, 0, 5);
+        let age = universe.age_at(0, 0);
+
+        let mut rotated = Universe::from_cells(13, 7, universe.cells.clone());
+        universe.rotate_cw();
+        rotated.rotate_cw();
+        assert_eq!(0, universe.history_len());
+        // The top left corner is now the top right
+        assert_eq!(3, universe.owner_at(0, 6));
+        assert_eq!(age, universe.age_at(0, 6));
+        assert_eq!(5, universe.cell_data_at(0, 6));
+        for _ in 0..10 {
+            universe.tick();
+            rotated.tick();
+        }
+        assert_eq!(rotated.cells, universe.cells);
+    }
+
+    #[test]
+    fn rotating_drops_a_resized_injection() {
+        use crate::Edge;
+
+        let mut universe = Universe::new(6, 4, vec![(1, 1), (1, 2), (1, 3)]);
+        universe.set_injection_pattern(Edge::Left, vec![1, 1, 1, 1]).unwrap();
+        universe.rotate_cw();
+        universe.tick();
+        assert_eq!(Boundary::Inject, universe.boundary());
+        assert!(universe.injection.is_none());
+
+        // Square boards keep theirs
+        let mut universe = Universe::new(4, 4, vec![]);
+        universe.set_injection_pattern(Edge::Top, vec![0, 1, 1, 1]).unwrap();
+        universe.rotate_cw();
+        universe.tick();
+        assert!(universe.injection.is_some());
+    }
+}
@@ -0,0 +1,96 @@
+// Transition functions supplied at runtime, for prototyping rules
+//
+// While one is set it replaces the B/S rule wherever a cell's next state is
+// worked out from its state and live neighbour count, which includes the
+// asynchronous and SIMD updates. Anything can come out of a function so
+// the active set optimisation can't be trusted to find every change, B0
+// style births included, and every cell is evaluated each tick. Worker
+// threads aren't used either as a JS function can't be shared with them
+//
+// Critters and rule tables don't go through a neighbour count and carry on
+// regardless
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) type Transition = Box<dyn Fn(Cell, u8) -> Cell>;
+
+impl Universe {
+    /// Work out each cell's next state with `transition`, given its current
+    /// state and live neighbour count, rather than the rule
+    pub fn set_transition(&mut self, transition: impl Fn(Cell, u8) -> Cell + 'static) {
+        self.transition = Some(Box::new(transition));
+        self.active_cells = None;
+        self.stability.reset();
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Call `transition(state, neighbours)` for every cell each tick, state
+    /// being 0 for dead or 1 for alive, and make the cell alive if it
+    /// returns something truthy. A cell is left as it is if the function
+    /// throws
+    #[cfg(feature = "wasm")]
+    pub fn set_transition_function(&mut self, transition: js_sys::Function) {
+        self.set_transition(move |cell, neighbours| {
+            match transition.call2(&JsValue::NULL, &JsValue::from(cell as u8), &JsValue::from(neighbours)) {
+                Ok(next) if next.is_truthy() => Cell::Alive,
+                Ok(_) => Cell::Dead,
+                Err(_) => cell,
+            }
+        });
+    }
+
+    /// Go back to the B/S rule
+    pub fn clear_transition_function(&mut self) {
+        if self.transition.take().is_some() {
+            self.active_cells = None;
+            self.stability.reset();
+        }
+    }
+
+    pub fn has_transition_function(&self) -> bool {
+        self.transition.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_replaces_rule() {
+        // Seeds, B2/S: every live cell dies and dead cells with exactly two
+        // live neighbours are born
+        let mut universe = Universe::new(8, 8, vec![(3, 3), (3, 4)]);
+        universe.set_transition(|_, neighbours| if neighbours == 2 { Cell::Alive } else { Cell::Dead });
+        assert!(universe.has_transition_function());
+        universe.tick();
+        assert_eq!(4, universe.population());
+        assert!(universe.is_alive(2, 3) && universe.is_alive(4, 4) && !universe.is_alive(3, 3));
+
+        universe.clear_transition_function();
+        let mut expected = Universe::from_cells(8, 8, universe.cells.clone());
+        universe.tick();
+        expected.tick();
+        assert_eq!(expected.cells, universe.cells);
+    }
+
+    #[test]
+    fn transition_births_from_nothing() {
+        // Every dead cell is born, which only a full pass can find
+        let mut universe = Universe::new(4, 4, vec![]);
+        universe.tick();
+        universe.set_transition(|cell, _| match cell {
+            Cell::Dead => Cell::Alive,
+            Cell::Alive => Cell::Dead,
+        });
+        universe.tick();
+        assert_eq!(16, universe.population());
+        universe.tick();
+        assert_eq!(0, universe.population());
+    }
+}
@@ -0,0 +1,238 @@
+// Undo and redo for edits, separate from the generation history
+//
+// Every edit flips a cell, so an action is just the indices it flipped and
+// undoing or redoing it flips them back. Each index keeps the owner of the
+// cell from whichever side of the flip it was alive on (see ownership.rs),
+// so cells come back with their owners. A toggle is one action, and bulk
+// edits like `set_cells()`, `overlay()`, `clear()` or `clear_region()` are
+// one action each. A stroke painted with several calls can be made one
+// action with `begin_edit_group()` and `end_edit_group()`
+//
+// Undo is meant for editing while paused: ticking, stepping back or
+// resizing the board forgets every action. It's off (capacity 0) until a
+// capacity is set
+
+use crate::{Cell, Region, Universe};
+
+use std::collections::VecDeque;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Default)]
+pub(crate) struct EditHistory {
+    capacity: usize,
+    // Oldest first, each cell flipped and an owner, see flip_cells()
+    undo: VecDeque<Vec<(u32, u8)>>,
+    redo: Vec<Vec<(u32, u8)>>,
+    // Open groups, and the cells flipped since the outermost one began
+    depth: u32,
+    group: Vec<(u32, u8)>,
+}
+
+impl EditHistory {
+    fn push(&mut self, action: Vec<(u32, u8)>) {
+        if self.undo.len() == self.capacity {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(action);
+        self.redo.clear();
+    }
+
+    // Bytes held by both stacks and the open group
+    pub(crate) fn heap_bytes(&self) -> usize {
+        let action = std::mem::size_of::<Vec<(u32, u8)>>();
+        let cells = self.undo.iter().chain(&self.redo).map(Vec::capacity).sum::<usize>() + self.group.capacity();
+        (self.undo.capacity() + self.redo.capacity()) * action + cells * std::mem::size_of::<(u32, u8)>()
+    }
+}
+
+impl Universe {
+    // Called from write_cell() for edits, with the cell's owner before it
+    pub(crate) fn undo_record(&mut self, idx: usize, owner: u8) {
+        let edits = &mut self.edits;
+        if edits.capacity == 0 {
+            return;
+        }
+        if edits.depth > 0 {
+            edits.group.push((idx as u32, owner));
+        } else {
+            edits.push(vec![(idx as u32, owner)]);
+        }
+    }
+
+    // Run `operation` as a single action
+    pub(crate) fn edit_group(&mut self, operation: impl FnOnce(&mut Universe)) {
+        self.begin_edit_group();
+        operation(self);
+        self.end_edit_group();
+    }
+
+    // Drop every action, for when the board has moved on from them
+    pub(crate) fn forget_edits(&mut self) {
+        let edits = &mut self.edits;
+        edits.undo.clear();
+        edits.redo.clear();
+        edits.group.clear();
+    }
+
+    // Flip the cells back, without recording the flips as new edits. The
+    // owners kept with them go to the cells coming to life, and the owners
+    // of the ones dying are kept in their place for flipping them back
+    fn flip_cells(&mut self, action: &mut [(u32, u8)]) {
+        let capacity = std::mem::replace(&mut self.edits.capacity, 0);
+        for (idx, owner) in action {
+            let idx = *idx as usize;
+            let flipped = match self.cells[idx] {
+                Cell::Dead => Cell::Alive,
+                Cell::Alive => Cell::Dead,
+            };
+            let previous = self.owner_of(idx);
+            self.write_cell(idx, flipped);
+            self.restore_owner(idx, *owner);
+            *owner = previous;
+        }
+        self.edits.capacity = capacity;
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Most edit actions to keep for undo, the oldest being dropped first.
+    /// 0 turns undo off and forgets everything
+    pub fn set_undo_capacity(&mut self, actions: u32) {
+        let edits = &mut self.edits;
+        edits.capacity = actions as usize;
+        if edits.capacity == 0 {
+            self.forget_edits();
+        }
+        while self.edits.undo.len() > self.edits.capacity {
+            self.edits.undo.pop_front();
+        }
+    }
+
+    pub fn undo_capacity(&self) -> u32 {
+        self.edits.capacity as u32
+    }
+
+    /// Undo the latest edit action, returning false if there's none
+    pub fn undo_edit(&mut self) -> bool {
+        match self.edits.undo.pop_back() {
+            Some(mut action) => {
+                self.flip_cells(&mut action);
+                self.edits.redo.push(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the latest undone action, returning false if there's none. Any
+    /// new edit clears what can be redone
+    pub fn redo_edit(&mut self) -> bool {
+        match self.edits.redo.pop() {
+            Some(mut action) => {
+                self.flip_cells(&mut action);
+                self.edits.undo.push_back(action);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo_edit(&self) -> bool {
+        !self.edits.undo.is_empty()
+    }
+
+    pub fn can_redo_edit(&self) -> bool {
+        !self.edits.redo.is_empty()
+    }
+
+    /// Gather the edits until the matching `end_edit_group()` into one
+    /// action. Groups can nest, only the outermost one counts
+    pub fn begin_edit_group(&mut self) {
+        self.edits.depth += 1;
+    }
+
+    pub fn end_edit_group(&mut self) {
+        let edits = &mut self.edits;
+        if edits.depth == 0 {
+            return;
+        }
+        edits.depth -= 1;
+        if edits.depth == 0 && !edits.group.is_empty() {
+            let group = std::mem::take(&mut edits.group);
+            edits.push(group);
+        }
+    }
+
+    /// Kill every cell in the region, as one action
+    pub fn clear_region(&mut self, region: &Region) {
+        let cells = self.clip_region(region).cells().collect::<Vec<_>>();
+        self.edit_group(|universe| {
+            for (row, column) in cells {
+                let idx = universe.get_index(row, column);
+                universe.write_cell(idx, Cell::Dead);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OverlayMode, Rect};
+
+    #[test]
+    fn undo_and_redo_edits() {
+        let mut universe = Universe::new(8, 8, vec![(1, 1), (1, 2), (2, 1), (2, 2)]);
+        universe.toggle_cell(0, 0);
+        assert!(!universe.undo_edit());
+        universe.set_undo_capacity(2);
+
+        universe.toggle_cell(5, 5);
+        universe.clear_region(&Region::from_rect(Rect::new(1, 1, 2, 1)));
+        assert_eq!(4, universe.population());
+        let stamp = Universe::new(2, 1, vec![(0, 0), (0, 1)]);
+        universe.overlay(&stamp, 6, 0, OverlayMode::Or);
+        assert_eq!(6, universe.population());
+
+        // Only the last two actions are kept
+        assert!(universe.undo_edit());
+        assert_eq!(4, universe.population());
+        assert!(universe.undo_edit());
+        assert!(universe.is_alive(1, 1) && universe.is_alive(2, 1));
+        assert!(!universe.undo_edit());
+        assert!(universe.is_alive(5, 5));
+
+        assert!(universe.redo_edit());
+        assert!(!universe.is_alive(1, 1));
+        universe.toggle_cell(7, 7);
+        assert!(!universe.can_redo_edit());
+        assert!(universe.can_undo_edit());
+
+        // Ticking moves on from the edits
+        universe.tick();
+        assert!(!universe.can_undo_edit());
+    }
+
+    #[test]
+    fn edit_groups() {
+        let mut universe = Universe::new(8, 8, vec![]);
+        universe.set_undo_capacity(8);
+        universe.begin_edit_group();
+        universe.toggle_cell(0, 0);
+        universe.begin_edit_group();
+        universe.set_cells(&[1, 2]);
+        universe.end_edit_group();
+        universe.toggle_cell(0, 3);
+        universe.end_edit_group();
+        universe.end_edit_group();
+        assert_eq!(4, universe.population());
+
+        assert!(universe.undo_edit());
+        assert_eq!(0, universe.population());
+        assert!(!universe.can_undo_edit());
+        assert!(universe.redo_edit());
+        assert_eq!(4, universe.population());
+    }
+}
@@ -0,0 +1,278 @@
+// Life in three dimensions, on a width x height x depth grid wrapping round
+// in every direction
+//
+// Each cell has 26 neighbours. Rules use Bays' notation of four numbers:
+// the fewest and most live neighbours a live cell survives with, then the
+// fewest and most a dead cell is born with. So "5766" survives on 5 to 7
+// and is born on 6, and "4555" (the default) survives on 4 or 5 and is born
+// on 5. Counts above 9 need commas, e.g. "4,10,5,5".
+//
+// Cells are stored one byte each, layer by layer and row by row within a
+// layer, at index `(layer * height + row) * width + column`. JS can view
+// them without copying through `cells_ptr()`, e.g. to upload as a 3D
+// texture for volume rendering
+
+use crate::error::LifeError;
+use crate::rng::seeded;
+use crate::size::check_size;
+use crate::{Cell, RuleError};
+
+use rand::Rng;
+use std::fmt;
+use std::str::FromStr;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule3D {
+    // Inclusive ranges of live neighbour counts
+    survival: (u8, u8),
+    birth: (u8, u8),
+}
+
+impl Default for Rule3D {
+    fn default() -> Self {
+        Rule3D::BAYS_4555
+    }
+}
+
+impl Rule3D {
+    pub const BAYS_4555: Rule3D = Rule3D { survival: (4, 5), birth: (5, 5) };
+    pub const BAYS_5766: Rule3D = Rule3D { survival: (5, 7), birth: (6, 6) };
+
+    pub fn next(&self, current_cell: Cell, alive_neighbour_count: u8) -> Cell {
+        let (low, high) = match current_cell {
+            Cell::Dead => self.birth,
+            Cell::Alive => self.survival,
+        };
+        if (low..=high).contains(&alive_neighbour_count) { Cell::Alive } else { Cell::Dead }
+    }
+}
+
+impl FromStr for Rule3D {
+    type Err = RuleError;
+
+    fn from_str(text: &str) -> Result<Rule3D, RuleError> {
+        let text = text.trim();
+        let numbers = if text.contains(',') {
+            text.split(',').map(|number| number.trim().parse::<u8>().ok()).collect::<Vec<_>>()
+        } else {
+            text.chars().map(|digit| digit.to_digit(10).map(|digit| digit as u8)).collect()
+        };
+
+        match numbers[..] {
+            [Some(survive_low), Some(survive_high), Some(birth_low), Some(birth_high)]
+                if survive_high <= 26 && birth_high <= 26 =>
+            {
+                Ok(Rule3D { survival: (survive_low, survive_high), birth: (birth_low, birth_high) })
+            }
+            _ => Err(RuleError { message: format!("expected four neighbour counts from 0 to 26 but found '{}'", text) }),
+        }
+    }
+}
+
+impl fmt::Display for Rule3D {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let numbers = [self.survival.0, self.survival.1, self.birth.0, self.birth.1];
+        let separator = if numbers.iter().any(|&number| number > 9) { "," } else { "" };
+        let numbers = numbers.iter().map(|number| number.to_string()).collect::<Vec<_>>();
+        write!(f, "{}", numbers.join(separator))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Universe3D {
+    width: u32,
+    height: u32,
+    depth: u32,
+    cells: Vec<Cell>,
+    generation: u64,
+    rule: Rule3D,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe3D {
+    /// An empty universe. Throws for a zero or too large size
+    #[cfg(feature = "wasm")]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32, depth: u32) -> Result<Universe3D, JsValue> {
+        Universe3D::empty(width, height, depth).map_err(crate::error::js_error)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn population(&self) -> u32 {
+        self.cells.iter().filter(|&&cell| cell == Cell::Alive).count() as u32
+    }
+
+    pub fn is_alive(&self, layer: u32, row: u32, column: u32) -> bool {
+        self.cells[self.get_index(layer, row, column)] == Cell::Alive
+    }
+
+    pub fn set_alive(&mut self, layer: u32, row: u32, column: u32, alive: bool) {
+        let idx = self.get_index(layer, row, column);
+        self.cells[idx] = if alive { Cell::Alive } else { Cell::Dead };
+    }
+
+    /// Refill in place, each cell alive with probability `density`
+    pub fn randomize(&mut self, density: f32, seed: u64) {
+        let density = density.clamp(0.0, 1.0);
+        let mut rng = seeded(seed);
+        for cell in self.cells.iter_mut() {
+            *cell = if rng.gen::<f32>() < density { Cell::Alive } else { Cell::Dead };
+        }
+    }
+
+    /// The rule in Bays' notation
+    pub fn rule(&self) -> String {
+        self.rule.to_string()
+    }
+
+    /// Switch rule, e.g. "5766"
+    #[cfg(feature = "wasm")]
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), JsValue> {
+        self.rule = rule.parse::<Rule3D>().map_err(crate::error::js_error)?;
+        Ok(())
+    }
+
+    /// Pointer to the width * height * depth cell bytes in WASM memory,
+    /// only valid until the next call that changes the universe
+    pub fn cells_ptr(&self) -> *const Cell {
+        self.cells.as_ptr()
+    }
+
+    /// Copy of the cells, one byte each (0 dead, 1 alive)
+    pub fn cells(&self) -> Vec<u8> {
+        self.cells.iter().map(|&cell| cell as u8).collect()
+    }
+
+    pub fn tick(&mut self) {
+        // Spread each live cell into its neighbours' counts rather than
+        // counting 26 neighbours for every cell, as most cells are dead
+        let mut counts = vec![0u8; self.cells.len()];
+        for idx in 0..self.cells.len() {
+            if self.cells[idx] == Cell::Dead {
+                continue
+            }
+            let (layer, row, column) = self.position(idx);
+            for layer_delta in [self.depth - 1, 0, 1].iter().cloned() {
+                for row_delta in [self.height - 1, 0, 1].iter().cloned() {
+                    for column_delta in [self.width - 1, 0, 1].iter().cloned() {
+                        if layer_delta == 0 && row_delta == 0 && column_delta == 0 {
+                            continue
+                        }
+                        let neighbour = self.get_index(
+                            (layer + layer_delta) % self.depth,
+                            (row + row_delta) % self.height,
+                            (column + column_delta) % self.width,
+                        );
+                        counts[neighbour] += 1;
+                    }
+                }
+            }
+        }
+
+        for (cell, count) in self.cells.iter_mut().zip(counts) {
+            *cell = self.rule.next(*cell, count);
+        }
+        self.generation += 1;
+    }
+}
+
+impl Universe3D {
+    /// Rust side of `new`
+    pub fn empty(width: u32, height: u32, depth: u32) -> Result<Universe3D, LifeError> {
+        let area = check_size(width, height)?;
+        let count = check_size(area as u32, depth)?;
+        Ok(Universe3D {
+            width,
+            height,
+            depth,
+            cells: vec![Cell::Dead; count],
+            generation: 0,
+            rule: Rule3D::default(),
+        })
+    }
+
+    fn get_index(&self, layer: u32, row: u32, column: u32) -> usize {
+        (layer as usize * self.height as usize + row as usize) * self.width as usize + column as usize
+    }
+
+    fn position(&self, idx: usize) -> (u32, u32, u32) {
+        let area = self.width as usize * self.height as usize;
+        let width = self.width as usize;
+        ((idx / area) as u32, (idx % area / width) as u32, (idx % width) as u32)
+    }
+
+    pub fn life_rule(&self) -> Rule3D {
+        self.rule
+    }
+
+    pub fn set_life_rule(&mut self, rule: Rule3D) {
+        self.rule = rule;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(Ok(Rule3D::BAYS_5766), "5766".parse());
+        assert_eq!(Ok(Rule3D::BAYS_4555), " 4, 5, 5, 5 ".parse());
+        assert_eq!("4,10,5,5", "4,10,5,5".parse::<Rule3D>().unwrap().to_string());
+        assert_eq!("4555", Rule3D::default().to_string());
+
+        assert!("576".parse::<Rule3D>().is_err());
+        assert!("5x66".parse::<Rule3D>().is_err());
+        assert!("4,27,5,5".parse::<Rule3D>().is_err());
+    }
+
+    #[test]
+    fn neighbours_wrap_in_every_direction() {
+        // Born with exactly one neighbour, so a lone cell fills its 3x3x3
+        // neighbourhood, wrapping round from the corner
+        let mut universe = Universe3D::empty(5, 4, 6).unwrap();
+        universe.set_life_rule("0,26,1,1".parse().unwrap());
+        universe.set_alive(0, 0, 0, true);
+        universe.tick();
+        assert_eq!(27, universe.population());
+        assert!(universe.is_alive(5, 3, 4) && universe.is_alive(1, 1, 1) && !universe.is_alive(2, 0, 0));
+        assert_eq!(1, universe.generation());
+
+        let cells = universe.cells();
+        assert_eq!(5 * 4 * 6, cells.len());
+        assert_eq!(1, cells[universe.get_index(5, 3, 4)]);
+    }
+
+    #[test]
+    fn cube_dies_under_4555() {
+        // Every cube cell has 7 neighbours and no outside cell has 5
+        let mut universe = Universe3D::empty(6, 6, 6).unwrap();
+        for &(layer, row, column) in &[(2, 2, 2), (2, 2, 3), (2, 3, 2), (2, 3, 3), (3, 2, 2), (3, 2, 3), (3, 3, 2), (3, 3, 3)] {
+            universe.set_alive(layer, row, column, true);
+        }
+        universe.tick();
+        assert_eq!(0, universe.population());
+
+        universe.randomize(0.3, 4);
+        let population = universe.population();
+        assert!(population > 30 && population < 100);
+        assert!(Universe3D::empty(4, 0, 4).is_err());
+    }
+}
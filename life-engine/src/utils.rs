@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub fn set_panic_hook() {
+    // When the `console_error_panic_hook` feature is enabled, we can call the
+    // `set_panic_hook` function at least once during initialization, and then
+    // we will get better error messages if our code ever panics.
+    //
+    // For more details see
+    // https://github.com/rustwasm/console_error_panic_hook#readme
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+}
+
+// A report as a plain JS object, typed by its Tsify derive. Reports are
+// built from numbers, strings and maps so this can't fail
+#[cfg(feature = "wasm")]
+pub fn to_js<T: tsify::Tsify + serde::Serialize>(report: &T) -> tsify::Ts<T> {
+    report.into_ts().expect("reports are always representable in JS")
+}
+
+// Milliseconds from an arbitrary starting point, for timing. Uses
+// `performance.now()` in the browser since `Instant` isn't available there
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub fn now_ms() -> f64 {
+    use js_sys::{Date, Function, Reflect};
+    use wasm_bindgen::JsCast;
+
+    // Fall back on the lower resolution Date.now() for odd environments
+    // without a performance object
+    let performance_now = || {
+        let performance = Reflect::get(&js_sys::global(), &"performance".into()).ok()?;
+        let now = Reflect::get(&performance, &"now".into()).ok()?.dyn_into::<Function>().ok()?;
+        now.call0(&performance).ok()?.as_f64()
+    };
+    performance_now().unwrap_or_else(Date::now)
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+// Logging to the browser console (stderr natively), only compiled in with the
+// `debug` feature. Use through the macros:
+//
+//     log!("tick took {}ms", elapsed);
+//     log!(warn, "critters mode needs even dimensions");
+//
+// with levels error, warn, info (the default) and debug
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+static LOG_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// Only log messages at `level` or more severe, Info by default
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+#[cfg_attr(not(feature = "debug"), allow(dead_code))]
+pub(crate) fn log_enabled(level: LogLevel) -> bool {
+    level as u8 <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+#[cfg_attr(not(feature = "debug"), allow(dead_code))]
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub(crate) fn write_log(level: LogLevel, message: &str) {
+    use js_sys::{Function, Reflect};
+    use wasm_bindgen::JsCast;
+
+    let method = match level {
+        LogLevel::Error => "error",
+        LogLevel::Warn => "warn",
+        LogLevel::Info => "log",
+        LogLevel::Debug => "debug",
+    };
+    let console = match Reflect::get(&js_sys::global(), &"console".into()) {
+        Ok(console) => console,
+        Err(_) => return,
+    };
+    let function = Reflect::get(&console, &method.into()).ok().and_then(|f| f.dyn_into::<Function>().ok());
+    if let Some(function) = function {
+        let _ = function.call1(&console, &JsValue::from_str(message));
+    }
+}
+
+#[cfg_attr(not(feature = "debug"), allow(dead_code))]
+#[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+pub(crate) fn write_log(level: LogLevel, message: &str) {
+    eprintln!("[{:?}] {}", level, message);
+}
+
+macro_rules! log {
+    (error, $($arg:tt)+) => { log!(@ $crate::utils::LogLevel::Error, $($arg)+) };
+    (warn, $($arg:tt)+) => { log!(@ $crate::utils::LogLevel::Warn, $($arg)+) };
+    (info, $($arg:tt)+) => { log!(@ $crate::utils::LogLevel::Info, $($arg)+) };
+    (debug, $($arg:tt)+) => { log!(@ $crate::utils::LogLevel::Debug, $($arg)+) };
+    (@ $level:expr, $($arg:tt)+) => {{
+        #[cfg(feature = "debug")]
+        {
+            if $crate::utils::log_enabled($level) {
+                $crate::utils::write_log($level, &format!($($arg)+));
+            }
+        }
+        // Still type check the arguments when logging is compiled out
+        #[cfg(not(feature = "debug"))]
+        {
+            let _ = || format!($($arg)+);
+        }
+    }};
+    ($($arg:tt)+) => { log!(info, $($arg)+) };
+}
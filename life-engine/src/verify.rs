@@ -0,0 +1,105 @@
+// Running one soup through every engine to catch them disagreeing
+//
+// Each engine is its own optimisation of the same rule, and a bug in one
+// shows up as its board drifting away from the rest. `verify_backends(seed,
+// generations)` fills a 64x64 board at 35% from `seed` and runs it under
+// Conway's Life through the plain byte a cell `Engine`, which is taken as
+// right, and through the bit-packed, sparse and HashLife engines and a
+// `Universe` under each tick strategy, comparing every board with the plain
+// one after every generation. The first difference is an `InvalidState`
+// error naming the engine, the generation and the first cell it got wrong,
+// which from JS is thrown, so a test page or the console can check the
+// WASM build itself:
+//
+//     verify_backends(123n, 500);
+
+use crate::error::LifeError;
+use crate::rng::seeded;
+use crate::{random_cells, Backend, Cell, LifeEngine, TickStrategy, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const SIZE: u32 = 64;
+const DENSITY: f32 = 0.35;
+
+const BACKENDS: [Backend; 3] = [Backend::BitPacked, Backend::Sparse, Backend::HashLife];
+const STRATEGIES: [TickStrategy; 4] = [TickStrategy::Sparse, TickStrategy::Dense, TickStrategy::Rows, TickStrategy::Tiles];
+
+// An error for the first cell of `cells` that isn't as `expected`, if any
+fn compare(name: &str, generation: u32, expected: &[Cell], cells: &[Cell]) -> Result<(), LifeError> {
+    match expected.iter().zip(cells).position(|(expected, cell)| expected != cell) {
+        None => Ok(()),
+        Some(idx) => Err(LifeError::InvalidState(format!(
+            "{} differs from the dense engine at generation {}, first at ({}, {})",
+            name,
+            generation,
+            idx as u32 / SIZE,
+            idx as u32 % SIZE
+        ))),
+    }
+}
+
+/// Rust side of `verify_backends`
+pub fn check_backends(seed: u64, generations: u32) -> Result<(), LifeError> {
+    let cells = random_cells(SIZE, SIZE, DENSITY, &mut seeded(seed));
+    let mut reference = Backend::Dense.create(SIZE, SIZE, &cells)?;
+    let mut engines = BACKENDS.iter()
+        .map(|&backend| Ok((format!("{:?}", backend), backend.create(SIZE, SIZE, &cells)?)))
+        .collect::<Result<Vec<(String, Box<dyn LifeEngine>)>, LifeError>>()?;
+    let mut universes = STRATEGIES.iter()
+        .map(|&strategy| {
+            let mut universe = Universe::from_cells(SIZE, SIZE, cells.clone());
+            universe.set_tick_strategy(strategy);
+            (format!("Universe ({:?})", strategy), universe)
+        })
+        .collect::<Vec<_>>();
+
+    for generation in 1..=generations {
+        reference.tick();
+        let expected = reference.to_cells();
+        for (name, engine) in engines.iter_mut() {
+            engine.tick();
+            compare(name, generation, &expected, &engine.to_cells())?;
+        }
+        for (name, universe) in universes.iter_mut() {
+            universe.tick();
+            compare(name, generation, &expected, &universe.true_cells())?;
+        }
+    }
+    Ok(())
+}
+
+/// Tick a 64x64 soup from `seed` for `generations` through every engine and
+/// tick strategy, throwing at the first generation where one disagrees
+/// with the plain byte a cell engine
+#[cfg(feature = "wasm")]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn verify_backends(seed: u64, generations: u32) -> Result<(), JsValue> {
+    check_backends(seed, generations).map_err(crate::error::js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_engine_agrees() {
+        for seed in 0..3 {
+            check_backends(seed, 60).unwrap();
+        }
+    }
+
+    #[test]
+    fn reports_the_first_difference() {
+        let expected = vec![Cell::Dead; 200];
+        let mut cells = expected.clone();
+        cells[130] = Cell::Alive;
+        cells[150] = Cell::Alive;
+        assert_eq!(
+            Err(LifeError::InvalidState("Sparse differs from the dense engine at generation 4, first at (2, 2)".into())),
+            compare("Sparse", 4, &expected, &cells)
+        );
+        assert_eq!(Ok(()), compare("Sparse", 4, &expected, &expected));
+    }
+}
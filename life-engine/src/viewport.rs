@@ -0,0 +1,308 @@
+// Windows onto a universe rendered as RGBA pixel buffers, and the whole
+// universe as one with `render_rgba()`
+//
+// Several viewports can be registered on one universe, each with its own
+// position, zoom and palette, and rendered together in a single call, e.g.
+// for a zoomed in view next to an overview. Colours are 0xRRGGBBAA
+//
+// A viewport also works as the camera for a frontend: pan and zoom it, map
+// clicks back to cells with `screen_to_cell`, and pick what happens at the
+// universe's edges so every frontend behaves the same
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What a viewport shows past the edges of the universe
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewportEdges {
+    /// Free to move anywhere, outside is drawn as dead cells
+    Open,
+    /// Kept within the universe where it fits (it's centred where it doesn't)
+    Clamp,
+    /// The universe repeats in every direction, like its torus topology
+    Wrap,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Viewport {
+    /// Cell shown at the top left pixel, may be outside the universe
+    pub top: i32,
+    pub left: i32,
+    /// Output size in pixels
+    pub width: u32,
+    pub height: u32,
+    /// Pixels per cell along each side
+    pub zoom: u32,
+    pub alive_colour: u32,
+    pub dead_colour: u32,
+    pub edges: ViewportEdges,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Viewport {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> Viewport {
+        Viewport {
+            top: 0,
+            left: 0,
+            width,
+            height,
+            zoom: 1,
+            alive_colour: 0x000000FF,
+            dead_colour: 0xFFFFFFFF,
+            edges: ViewportEdges::Open,
+        }
+    }
+
+    /// Move the view by a number of cells
+    pub fn pan(&mut self, rows: i32, columns: i32) {
+        self.top = self.top.saturating_add(rows);
+        self.left = self.left.saturating_add(columns);
+    }
+
+    /// Move the view by a number of pixels, whole cells at a time
+    pub fn pan_pixels(&mut self, dx: i32, dy: i32) {
+        let zoom = self.zoom.max(1) as i32;
+        self.pan(dy / zoom, dx / zoom);
+    }
+
+    pub fn set_zoom(&mut self, zoom: u32) {
+        self.zoom = zoom.max(1);
+    }
+
+    /// Change zoom keeping the cell under pixel (x, y) where it is, for
+    /// zooming towards the mouse pointer
+    pub fn zoom_at(&mut self, zoom: u32, x: u32, y: u32) {
+        let (old, new) = (self.zoom.max(1) as i64, zoom.max(1) as i64);
+        let row = self.top as i64 + y as i64 / old;
+        let column = self.left as i64 + x as i64 / old;
+        self.zoom = new as u32;
+        self.top = (row - y as i64 / new).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+        self.left = (column - x as i64 / new).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+    }
+
+    /// Number of (possibly partial) cells across and down the view
+    pub fn visible_columns(&self) -> u32 {
+        self.width.div_ceil(self.zoom.max(1))
+    }
+
+    pub fn visible_rows(&self) -> u32 {
+        self.height.div_ceil(self.zoom.max(1))
+    }
+
+    /// Pull the offset back inside `universe` according to the edge mode;
+    /// Wrap brings it into the universe's first copy, Open does nothing
+    pub fn constrain(&mut self, universe: &Universe) {
+        let keep = |offset: i32, visible: u32, size: u32| -> i32 {
+            let (visible, size) = (visible as i64, size as i64);
+            if visible >= size {
+                ((size - visible) / 2) as i32
+            } else {
+                (offset as i64).clamp(0, size - visible) as i32
+            }
+        };
+        match self.edges {
+            ViewportEdges::Open => {}
+            ViewportEdges::Clamp => {
+                self.top = keep(self.top, self.visible_rows(), universe.height);
+                self.left = keep(self.left, self.visible_columns(), universe.width);
+            }
+            ViewportEdges::Wrap => {
+                self.top = (self.top as i64).rem_euclid(universe.height as i64) as i32;
+                self.left = (self.left as i64).rem_euclid(universe.width as i64) as i32;
+            }
+        }
+    }
+
+    /// `[row, column]` of the cell under pixel (x, y), or nothing when it's
+    /// outside the universe
+    pub fn screen_to_cell(&self, universe: &Universe, x: u32, y: u32) -> Option<Vec<u32>> {
+        self.cell_at(universe, x, y).map(|(row, column)| vec![row, column])
+    }
+
+    pub fn set_palette(&mut self, alive_colour: u32, dead_colour: u32) {
+        self.alive_colour = alive_colour;
+        self.dead_colour = dead_colour;
+    }
+}
+
+impl Viewport {
+    // Map a cell offset from the view's origin onto the universe
+    fn map(&self, universe: &Universe, row: i64, column: i64) -> Option<(u32, u32)> {
+        let (height, width) = (universe.height as i64, universe.width as i64);
+        let (row, column) = (self.top as i64 + row, self.left as i64 + column);
+        if self.edges == ViewportEdges::Wrap {
+            return Some((row.rem_euclid(height) as u32, column.rem_euclid(width) as u32));
+        }
+        if (0..height).contains(&row) && (0..width).contains(&column) {
+            Some((row as u32, column as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Rust side of `screen_to_cell`
+    pub fn cell_at(&self, universe: &Universe, x: u32, y: u32) -> Option<(u32, u32)> {
+        let zoom = self.zoom.max(1) as i64;
+        self.map(universe, y as i64 / zoom, x as i64 / zoom)
+    }
+
+    fn render(&self, universe: &Universe) -> Vec<u8> {
+        let alive = self.alive_colour.to_be_bytes();
+        let dead = self.dead_colour.to_be_bytes();
+        let zoom = self.zoom.max(1) as i64;
+
+        let mut pixels = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                let alive_here = match self.map(universe, y / zoom, x / zoom) {
+                    Some((row, column)) => universe.cells[universe.get_index(row, column)] == Cell::Alive,
+                    None => false,
+                };
+                pixels.extend_from_slice(if alive_here { &alive } else { &dead });
+            }
+        }
+        pixels
+    }
+}
+
+impl Universe {
+    /// One RGBA buffer per registered viewport, in id order (removed ids
+    /// are skipped)
+    pub fn render_viewport_buffers(&self) -> Vec<Vec<u8>> {
+        self.viewports.iter().flatten().map(|viewport| viewport.render(self)).collect()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Register a viewport, returning its id
+    pub fn add_viewport(&mut self, viewport: &Viewport) -> u32 {
+        self.viewports.push(Some(viewport.clone()));
+        (self.viewports.len() - 1) as u32
+    }
+
+    pub fn viewport(&self, id: u32) -> Option<Viewport> {
+        self.viewports.get(id as usize).cloned().flatten()
+    }
+
+    /// Replace a registered viewport, e.g. after panning a copy from
+    /// `viewport()`. Returns false for an unknown id
+    pub fn set_viewport(&mut self, id: u32, viewport: &Viewport) -> bool {
+        match self.viewports.get_mut(id as usize) {
+            Some(Some(existing)) => {
+                *existing = viewport.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn remove_viewport(&mut self, id: u32) {
+        if let Some(slot) = self.viewports.get_mut(id as usize) {
+            *slot = None;
+        }
+    }
+
+    /// Whole universe at one pixel per cell, width * height * 4 bytes ready
+    /// for `putImageData`
+    pub fn render_rgba(&self, alive_colour: u32, dead_colour: u32) -> Vec<u8> {
+        let mut viewport = Viewport::new(self.width, self.height);
+        viewport.set_palette(alive_colour, dead_colour);
+        viewport.render(self)
+    }
+
+    /// Every registered viewport rendered in one go, as an array of
+    /// `Uint8Array`s ready for `ImageData`
+    #[cfg(feature = "wasm")]
+    pub fn render_viewports(&self) -> js_sys::Array {
+        self.render_viewport_buffers().iter()
+            .map(|buffer| js_sys::Uint8Array::from(&buffer[..]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn independent_viewports() {
+        let mut universe = Universe::new(4, 4, vec![(1, 1)]);
+
+        let mut zoomed = Viewport::new(4, 4);
+        zoomed.set_zoom(2);
+        zoomed.set_palette(0xFF0000FF, 0x00000000);
+        let overview = Viewport::new(4, 4);
+
+        let zoomed_id = universe.add_viewport(&zoomed);
+        let overview_id = universe.add_viewport(&overview);
+
+        let buffers = universe.render_viewport_buffers();
+        assert_eq!(2, buffers.len());
+        assert!(buffers.iter().all(|buffer| buffer.len() == 64));
+
+        // Cell (1, 1) covers pixels (2..4, 2..4) at zoom 2
+        let pixel = |buffer: &Vec<u8>, x: usize, y: usize| buffer[(y * 4 + x) * 4..][..4].to_vec();
+        assert_eq!(vec![0xFF, 0, 0, 0xFF], pixel(&buffers[0], 3, 3));
+        assert_eq!(vec![0, 0, 0, 0], pixel(&buffers[0], 1, 1));
+        assert_eq!(vec![0, 0, 0, 0xFF], pixel(&buffers[1], 1, 1));
+        assert_eq!(vec![0xFF; 4], pixel(&buffers[1], 2, 2));
+
+        // Panning off the edge shows dead cells
+        let mut moved = universe.viewport(overview_id).unwrap();
+        moved.pan(-1, -1);
+        assert!(universe.set_viewport(overview_id, &moved));
+        assert_eq!(vec![0, 0, 0, 0xFF], pixel(&universe.render_viewport_buffers()[1], 2, 2));
+
+        universe.remove_viewport(zoomed_id);
+        assert_eq!(1, universe.render_viewport_buffers().len());
+        assert!(!universe.set_viewport(zoomed_id, &zoomed));
+    }
+
+    #[test]
+    fn camera() {
+        let universe = Universe::new(10, 10, vec![(2, 3)]);
+        let mut camera = Viewport::new(8, 8);
+        camera.set_zoom(2);
+        assert_eq!(Some((2, 3)), camera.cell_at(&universe, 7, 5));
+        assert_eq!(4, camera.visible_columns());
+
+        // The cell under the pointer stays put
+        camera.zoom_at(4, 7, 5);
+        assert_eq!(Some((2, 3)), camera.cell_at(&universe, 7, 5));
+        assert_eq!((1, 2), (camera.top, camera.left));
+
+        camera.pan_pixels(-12, 0);
+        assert_eq!(-1, camera.left);
+        assert_eq!(None, camera.cell_at(&universe, 0, 0));
+        camera.edges = ViewportEdges::Clamp;
+        camera.constrain(&universe);
+        assert_eq!(0, camera.left);
+
+        camera.pan(20, 20);
+        camera.constrain(&universe);
+        assert_eq!((8, 8), (camera.top, camera.left));
+
+        camera.edges = ViewportEdges::Wrap;
+        camera.pan(5, 0);
+        camera.constrain(&universe);
+        assert_eq!(3, camera.top);
+        camera.pan(-4, 0);
+        assert_eq!(Some((9, 8)), camera.cell_at(&universe, 0, 0));
+    }
+
+    #[test]
+    fn rgba_export() {
+        let universe = Universe::new(3, 2, vec![(0, 1), (1, 2)]);
+        let pixels = universe.render_rgba(0x11223344, 0xAABBCCDD);
+        assert_eq!(3 * 2 * 4, pixels.len());
+        assert_eq!([0xAA, 0xBB, 0xCC, 0xDD], pixels[0..4]);
+        assert_eq!([0x11, 0x22, 0x33, 0x44], pixels[4..8]);
+        assert_eq!([0x11, 0x22, 0x33, 0x44], pixels[20..24]);
+    }
+}
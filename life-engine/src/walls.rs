@@ -0,0 +1,104 @@
+// Wall cells, for drawing barriers and containers
+//
+// A wall is a cell fixed dead: it counts as dead for its neighbours, the
+// rule never brings it to life and edits leave it alone. Walls are kept as
+// a mask beside the cells rather than as another cell state so neighbour
+// counting doesn't change. They move with their cells when the board is
+// rotated, flipped or shifted
+
+use crate::{Cell, Universe};
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+impl Universe {
+    pub(crate) fn is_wall_index(&self, idx: usize) -> bool {
+        self.walls.as_ref().is_some_and(|walls| walls[idx])
+    }
+
+    // Called from tick() with the cells about to flip, wall cells being
+    // dead the only changes to drop are births
+    pub(crate) fn remove_walls(&self, mut changed: Vec<usize>) -> Vec<usize> {
+        if let Some(walls) = &self.walls {
+            changed.retain(|&idx| !walls[idx]);
+        }
+        changed
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Universe {
+    /// Turn a cell into a wall, killing it, or back into an ordinary dead
+    /// cell
+    pub fn set_wall(&mut self, row: u32, column: u32, wall: bool) {
+        let idx = self.get_index(row, column);
+        if wall {
+            self.write_cell(idx, Cell::Dead);
+            let size = self.cells.len();
+            self.walls.get_or_insert_with(|| vec![false; size])[idx] = true;
+        } else if let Some(walls) = self.walls.as_mut() {
+            walls[idx] = false;
+            // Neighbours may have been held back by the wall
+            self.active_cells = None;
+        }
+    }
+
+    pub fn is_wall(&self, row: u32, column: u32) -> bool {
+        self.is_wall_index(self.get_index(row, column))
+    }
+
+    /// One byte per cell in the same layout as the cell buffer, 1 for walls
+    pub fn walls(&self) -> Vec<u8> {
+        match &self.walls {
+            Some(walls) => walls.iter().map(|&wall| wall as u8).collect(),
+            None => vec![0; self.cells.len()],
+        }
+    }
+
+    /// Turn every wall back into an ordinary dead cell
+    pub fn clear_walls(&mut self) {
+        if self.walls.take().is_some() {
+            self.active_cells = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_stay_dead() {
+        // A blinker next to a wall can't grow into it
+        let mut universe = Universe::new(8, 8, vec![(3, 2), (3, 3), (3, 4)]);
+        universe.set_wall(2, 3, true);
+        assert!(universe.is_wall(2, 3));
+        universe.tick();
+        assert!(!universe.is_alive(2, 3));
+        assert!(universe.is_alive(4, 3) && universe.is_alive(3, 3));
+
+        // Edits don't get through either, and walls count as dead
+        universe.toggle_cell(2, 3);
+        assert!(!universe.is_alive(2, 3));
+        assert_eq!(1, universe.live_neighbours(2, 2));
+
+        // Once the wall is gone the cell is ordinary again
+        universe.set_wall(2, 3, false);
+        universe.toggle_cell(2, 3);
+        assert!(universe.is_alive(2, 3));
+    }
+
+    #[test]
+    fn walls_kill_and_move() {
+        let mut universe = Universe::new(4, 4, vec![(0, 0)]);
+        universe.set_wall(0, 0, true);
+        assert_eq!(0, universe.population());
+        assert_eq!(1, universe.walls()[0]);
+
+        universe.flip_horizontal();
+        assert!(universe.is_wall(0, 3) && !universe.is_wall(0, 0));
+
+        universe.clear_walls();
+        assert_eq!(vec![0; 16], universe.walls());
+    }
+}
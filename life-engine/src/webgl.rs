@@ -0,0 +1,179 @@
+// WebGL renderer, behind the `web` feature
+//
+// The cell buffer is uploaded as a one byte per cell texture and a single
+// quad covering the canvas samples it, so the whole grid is one draw call
+// however big the universe is. `new()` takes a WebGL 1 context and
+// `with_webgl2()` a WebGL 2 one, both driven through web-sys by way of `gl!`
+
+use crate::Universe;
+
+use js_sys::Float32Array;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    WebGl2RenderingContext, WebGlProgram, WebGlRenderingContext as Gl, WebGlShader, WebGlTexture, WebGlUniformLocation,
+};
+
+const VERTEX_SOURCE: &str = "
+attribute vec2 position;
+varying vec2 uv;
+void main() {
+    // Row 0 of the texture is the top of the universe
+    uv = vec2(position.x + 1.0, 1.0 - position.y) * 0.5;
+    gl_Position = vec4(position, 0.0, 1.0);
+}
+";
+
+const FRAGMENT_SOURCE: &str = "
+precision mediump float;
+uniform sampler2D cells;
+uniform vec4 alive_colour;
+uniform vec4 dead_colour;
+varying vec2 uv;
+void main() {
+    // Cells are uploaded as 0 or 1, not 0 or 255
+    gl_FragColor = texture2D(cells, uv).r > 0.0 ? alive_colour : dead_colour;
+}
+";
+
+// Both versions of the context have the WebGL 1 methods used here, under the
+// same names
+enum Context {
+    WebGl(Gl),
+    WebGl2(WebGl2RenderingContext),
+}
+
+macro_rules! gl {
+    ($context:expr, $method:ident($($arg:expr),*)) => {
+        match $context {
+            Context::WebGl(gl) => gl.$method($($arg),*),
+            Context::WebGl2(gl) => gl.$method($($arg),*),
+        }
+    };
+}
+
+fn compile_shader(gl: &Context, kind: u32, source: &str) -> Result<WebGlShader, JsValue> {
+    let shader = gl!(gl, create_shader(kind)).ok_or("couldn't create a shader")?;
+    gl!(gl, shader_source(&shader, source));
+    gl!(gl, compile_shader(&shader));
+    if !gl!(gl, get_shader_parameter(&shader, Gl::COMPILE_STATUS)).is_truthy() {
+        let log = gl!(gl, get_shader_info_log(&shader)).unwrap_or_default();
+        return Err(JsValue::from_str(&format!("shader compilation failed: {}", log)));
+    }
+    Ok(shader)
+}
+
+fn link_program(gl: &Context) -> Result<WebGlProgram, JsValue> {
+    let program = gl!(gl, create_program()).ok_or("couldn't create a program")?;
+    for &(kind, source) in [(Gl::VERTEX_SHADER, VERTEX_SOURCE), (Gl::FRAGMENT_SHADER, FRAGMENT_SOURCE)].iter() {
+        let shader = compile_shader(gl, kind, source)?;
+        gl!(gl, attach_shader(&program, &shader));
+    }
+    gl!(gl, link_program(&program));
+    if !gl!(gl, get_program_parameter(&program, Gl::LINK_STATUS)).is_truthy() {
+        let log = gl!(gl, get_program_info_log(&program)).unwrap_or_default();
+        return Err(JsValue::from_str(&format!("shader linking failed: {}", log)));
+    }
+    Ok(program)
+}
+
+// RGBA 0xRRGGBBAA as the four 0..1 floats uniform4f wants
+fn colour_channels(colour: u32) -> [f32; 4] {
+    colour.to_be_bytes().map(|channel| channel as f32 / 255.0)
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct GlRenderer {
+    gl: Context,
+    texture: WebGlTexture,
+    alive_location: Option<WebGlUniformLocation>,
+    dead_location: Option<WebGlUniformLocation>,
+    alive_colour: u32,
+    dead_colour: u32,
+}
+
+impl GlRenderer {
+    fn set_up(gl: Context) -> Result<GlRenderer, JsValue> {
+        let program = link_program(&gl)?;
+        gl!(&gl, use_program(Some(&program)));
+
+        // Two triangles covering the whole of clip space
+        let quad = Float32Array::from(&[-1.0f32, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0][..]);
+        let buffer = gl!(&gl, create_buffer()).ok_or("couldn't create a buffer")?;
+        gl!(&gl, bind_buffer(Gl::ARRAY_BUFFER, Some(&buffer)));
+        gl!(&gl, buffer_data_with_array_buffer_view(Gl::ARRAY_BUFFER, &quad, Gl::STATIC_DRAW));
+        let position = gl!(&gl, get_attrib_location(&program, "position")) as u32;
+        gl!(&gl, enable_vertex_attrib_array(position));
+        gl!(&gl, vertex_attrib_pointer_with_i32(position, 2, Gl::FLOAT, false, 0, 0));
+
+        let texture = gl!(&gl, create_texture()).ok_or("couldn't create a texture")?;
+        gl!(&gl, bind_texture(Gl::TEXTURE_2D, Some(&texture)));
+        for &(parameter, value) in [
+            (Gl::TEXTURE_MIN_FILTER, Gl::NEAREST),
+            (Gl::TEXTURE_MAG_FILTER, Gl::NEAREST),
+            (Gl::TEXTURE_WRAP_S, Gl::CLAMP_TO_EDGE),
+            (Gl::TEXTURE_WRAP_T, Gl::CLAMP_TO_EDGE),
+        ].iter() {
+            gl!(&gl, tex_parameteri(Gl::TEXTURE_2D, parameter, value as i32));
+        }
+        // Rows are one byte per cell with no padding
+        gl!(&gl, pixel_storei(Gl::UNPACK_ALIGNMENT, 1));
+
+        let alive_location = gl!(&gl, get_uniform_location(&program, "alive_colour"));
+        let dead_location = gl!(&gl, get_uniform_location(&program, "dead_colour"));
+
+        Ok(GlRenderer {
+            gl,
+            texture,
+            alive_location,
+            dead_location,
+            alive_colour: 0x000000FF,
+            dead_colour: 0xFFFFFFFF,
+        })
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl GlRenderer {
+    /// Set up the shaders, quad and texture on a `webgl` context
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(gl: Gl) -> Result<GlRenderer, JsValue> {
+        GlRenderer::set_up(Context::WebGl(gl))
+    }
+
+    /// `new()` for a `webgl2` context
+    pub fn with_webgl2(gl: WebGl2RenderingContext) -> Result<GlRenderer, JsValue> {
+        GlRenderer::set_up(Context::WebGl2(gl))
+    }
+
+    /// Colours as 0xRRGGBBAA
+    pub fn set_palette(&mut self, alive_colour: u32, dead_colour: u32) {
+        self.alive_colour = alive_colour;
+        self.dead_colour = dead_colour;
+    }
+
+    /// Upload the cells and draw them stretched over the whole drawing
+    /// buffer, size the canvas to a multiple of the universe for crisp cells
+    pub fn draw(&self, universe: &Universe) -> Result<(), JsValue> {
+        let gl = &self.gl;
+
+        let (width, height) = (gl!(gl, drawing_buffer_width()), gl!(gl, drawing_buffer_height()));
+        gl!(gl, viewport(0, 0, width, height));
+
+        let [red, green, blue, alpha] = colour_channels(self.alive_colour);
+        gl!(gl, uniform4f(self.alive_location.as_ref(), red, green, blue, alpha));
+        let [red, green, blue, alpha] = colour_channels(self.dead_colour);
+        gl!(gl, uniform4f(self.dead_location.as_ref(), red, green, blue, alpha));
+
+        let cells = universe.cells.iter().map(|&cell| cell as u8).collect::<Vec<u8>>();
+        gl!(gl, bind_texture(Gl::TEXTURE_2D, Some(&self.texture)));
+        gl!(gl, tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            Gl::TEXTURE_2D, 0, Gl::LUMINANCE as i32,
+            universe.width as i32, universe.height as i32, 0,
+            Gl::LUMINANCE, Gl::UNSIGNED_BYTE, Some(&cells)
+        ))?;
+
+        gl!(gl, draw_arrays(Gl::TRIANGLE_STRIP, 0, 4));
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+// Regenerates include/life.h from the engine's ffi module
+
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo:rerun-if-changed=../life-engine/src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", crate_dir)).unwrap();
+    // cbindgen only takes functions from the crate it's pointed at
+    cbindgen::generate_with_config(format!("{}/../life-engine", crate_dir), config)
+        .expect("couldn't generate include/life.h")
+        .write_to_file(format!("{}/include/life.h", crate_dir));
+}
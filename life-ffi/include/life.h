@@ -0,0 +1,124 @@
+/* Generated by cbindgen from life-engine/src/ffi.rs, don't edit */
+
+#ifndef LIFE_H
+#define LIFE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct LifeUniverse LifeUniverse;
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Random universe, each cell alive with probability `density`, the same
+// `seed` always giving the same cells. NULL for a width or height of 0 or
+// too many cells
+struct LifeUniverse *life_universe_new(uint32_t width,
+                                       uint32_t height,
+                                       float density,
+                                       uint64_t seed);
+
+// Universe from grid text like `life_universe_render` writes, or a
+// plaintext pattern. NULL if the text can't be read
+//
+// # Safety
+//
+// `text` must be NULL or a NUL terminated string
+struct LifeUniverse *life_universe_from_text(const char *text);
+
+// Free a universe. Does nothing for NULL
+//
+// # Safety
+//
+// `universe` must be NULL or a handle which hasn't been destroyed yet
+void life_universe_destroy(struct LifeUniverse *universe);
+
+// Run `generations` ticks
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle
+void life_universe_tick(struct LifeUniverse *universe, uint32_t generations);
+
+// # Safety
+//
+// `universe` must be NULL or a live handle
+uint32_t life_universe_width(const struct LifeUniverse *universe);
+
+// # Safety
+//
+// `universe` must be NULL or a live handle
+uint32_t life_universe_height(const struct LifeUniverse *universe);
+
+// # Safety
+//
+// `universe` must be NULL or a live handle
+uint64_t life_universe_generation(const struct LifeUniverse *universe);
+
+// # Safety
+//
+// `universe` must be NULL or a live handle
+uint32_t life_universe_population(const struct LifeUniverse *universe);
+
+// The cells, width * height bytes row by row, 1 alive and 0 dead. NULL for
+// a NULL handle
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle. The pointer is only valid until
+// the universe is next changed or destroyed
+const uint8_t *life_universe_cells(const struct LifeUniverse *universe);
+
+// Copy up to `len` cells into `out`, laid out like `life_universe_cells`,
+// returning how many were copied
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle, and `out` must have room for
+// `len` bytes
+size_t life_universe_copy_cells(const struct LifeUniverse *universe, uint8_t *out, size_t len);
+
+// Whether the cell is alive, false outside the universe
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle
+bool life_universe_is_alive(const struct LifeUniverse *universe, uint32_t row, uint32_t column);
+
+// Flip a cell, doing nothing outside the universe
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle
+void life_universe_toggle_cell(struct LifeUniverse *universe, uint32_t row, uint32_t column);
+
+// Switch to a B/S rule like "B36/S23", returning false and keeping the old
+// rule if it can't be parsed
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle, and `rule` NULL or a NUL
+// terminated string
+bool life_universe_set_rule(struct LifeUniverse *universe, const char *rule);
+
+// Write the grid `render()` prints into `out` as a NUL terminated string,
+// returning the length it needs without the terminator. Nothing is
+// written unless `len` is more than that, so call with a NULL `out` first
+// to size the buffer
+//
+// # Safety
+//
+// `universe` must be NULL or a live handle, and `out` NULL or room for
+// `len` bytes
+size_t life_universe_render(const struct LifeUniverse *universe, char *out, size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LIFE_H */
@@ -0,0 +1,10 @@
+//!
+//! C bindings for the life-engine crate, built into `liblife_ffi` with
+//! `cargo build -p life-ffi --release`
+//!
+//! The functions live alongside the engine behind its `ffi` feature and
+//! are linked in from there. The header they're declared in,
+//! include/life.h, is regenerated by build.rs
+//!
+
+pub use life_engine::Universe;
@@ -0,0 +1,14 @@
+//!
+//! Python bindings for the life-engine crate, built into a `life` module
+//! with `maturin develop -m life-python/Cargo.toml`
+//!
+//! The classes live alongside the engine behind its `python` feature and
+//! are added to the module here
+//!
+
+use pyo3::prelude::*;
+
+#[pymodule]
+fn life(module: &Bound<PyModule>) -> PyResult<()> {
+    life_engine::register_python(module)
+}
@@ -0,0 +1,27 @@
+//!
+//! WASM bindings for running the life-engine crate in the browser, built
+//! with `wasm-pack build life-wasm`
+//!
+//! The JS-facing API lives alongside the engine behind its `wasm` feature
+//! and is re-exported from here, along with anything only the browser needs
+//!
+
+pub use life_engine::*;
+
+use wasm_bindgen::prelude::*;
+
+// When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
+// allocator.
+#[cfg(feature = "wee_alloc")]
+#[global_allocator]
+static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+
+#[wasm_bindgen]
+extern {
+    fn alert(s: &str);
+}
+
+#[wasm_bindgen]
+pub fn greet(name: &str) {
+    alert(&format!("Hello, {}!", name));
+}
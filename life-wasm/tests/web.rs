@@ -0,0 +1,177 @@
+//! Test suite for the Web and headless browsers.
+
+#![cfg(target_arch = "wasm32")]
+
+extern crate wasm_bindgen_test;
+use wasm_bindgen_test::*;
+
+use js_sys::{Array, Float64Array, Function, Reflect, Uint32Array, Uint8Array, WebAssembly};
+use life_wasm::{
+    generate_universe_seeded, Universe, Universe3D, UniverseBuilder,
+};
+use wasm_bindgen::{JsCast, JsValue};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+// The WASM memory buffer, as JS sees it through the pointer methods
+fn memory_buffer() -> JsValue {
+    wasm_bindgen::memory().dyn_into::<WebAssembly::Memory>().unwrap().buffer()
+}
+
+#[wasm_bindgen_test]
+fn pass() {
+    assert_eq!(1 + 1, 2);
+}
+
+#[wasm_bindgen_test]
+fn construction() {
+    let universe = generate_universe_seeded(16, 8, 0.5, 3).unwrap();
+    assert_eq!((16, 8), (universe.width(), universe.height()));
+    let same = generate_universe_seeded(16, 8, 0.5, 3).unwrap();
+    assert_eq!(universe.render(), same.render());
+
+    // Bad sizes come back to JS as exceptions rather than panics
+    let err = generate_universe_seeded(0, 8, 0.5, 3).err().unwrap();
+    assert!(err.is_instance_of::<js_sys::Error>());
+    assert_eq!(Some("InvalidDimensions".to_string()), Reflect::get(&err, &"kind".into()).unwrap().as_string());
+    assert_eq!(Some(0.0), Reflect::get(&err, &"width".into()).unwrap().as_f64());
+    assert!(Universe3D::new(0, 4, 4).is_err());
+}
+
+#[wasm_bindgen_test]
+fn tick() {
+    let mut universe = UniverseBuilder::new().width(5).height(5).density(0.0).pattern_at("###", 2, 1).build_js().unwrap();
+    assert_eq!(3, universe.population());
+    universe.tick();
+    assert_eq!(1, universe.generation());
+    assert!(universe.is_alive(1, 2) && universe.is_alive(3, 2) && !universe.is_alive(2, 1));
+
+    // Flat indices straight from a typed array
+    universe.clear_cells(&[7, 12, 17]);
+    universe.set_cells(&Uint32Array::from(&[0u32, 1, 2][..]).to_vec());
+    assert_eq!(3, universe.population());
+}
+
+#[wasm_bindgen_test]
+fn buffer_pointers() {
+    let mut universe = UniverseBuilder::new().width(4).height(4).density(0.0).pattern("#..#").build_js().unwrap();
+    assert!(universe.lineage_ptr().is_null());
+    universe.enable_lineage();
+    let ids = Uint32Array::new_with_byte_offset_and_length(&memory_buffer(), universe.lineage_ptr() as u32, 16);
+    assert_eq!(universe.lineage(), ids.to_vec());
+    assert_eq!(vec![1, 0, 0, 4], ids.to_vec()[..4].to_vec());
+
+    let mut cube = Universe3D::new(4, 4, 4).unwrap();
+    cube.set_alive(1, 2, 3, true);
+    let cells = Uint8Array::new_with_byte_offset_and_length(&memory_buffer(), cube.cells_ptr() as u32, 64);
+    assert_eq!(cube.cells(), cells.to_vec());
+    assert_eq!(1, cells.get_index(16 + 2 * 4 + 3));
+
+    let mut universe = generate_universe_seeded(16, 16, 0.5, 2).unwrap();
+    universe.enable_statistics_series(4);
+    for _ in 0..6 {
+        universe.tick();
+    }
+    let population = Float64Array::new_with_byte_offset_and_length(&memory_buffer(), universe.population_series_ptr() as u32, universe.series_len());
+    assert_eq!(universe.population_series(), population.to_vec());
+    assert_eq!(universe.population() as f64, population.get_index(3));
+}
+
+#[wasm_bindgen_test]
+fn pattern_import() {
+    let universe = Universe::from_ascii(".#.\n..#\n###\n").unwrap();
+    assert_eq!(5, universe.population());
+    let err = Universe::from_ascii(".#.\n.x.\n").err().unwrap();
+    assert_eq!(Some("ParseError".to_string()), Reflect::get(&err, &"kind".into()).unwrap().as_string());
+    assert_eq!(Some(2.0), Reflect::get(&err, &"line".into()).unwrap().as_f64());
+
+    let built = UniverseBuilder::new().width(8).height(8).density(0.0).pattern_at("bo/2o", 1, 1).build_js();
+    assert!(built.is_err());
+    let built = UniverseBuilder::new().width(8).height(8).density(0.0).pattern_at("bob/oob", 1, 1).build_js().unwrap();
+    assert_eq!(3, built.population());
+
+    let copy = Universe::from_base64(&universe.to_base64()).unwrap();
+    assert_eq!(universe.render(), copy.render());
+
+    let mut table = Universe::from_ascii("#..\n...\n").unwrap();
+    assert!(table.load_rule_table("@TABLE\nn_states:3\n").is_err());
+}
+
+#[wasm_bindgen_test]
+fn js_callbacks() {
+    // Seeds, B2/S, written in JS
+    let mut universe = UniverseBuilder::new().width(8).height(8).density(0.0).pattern_at("##", 3, 3).build_js().unwrap();
+    universe.set_transition_function(Function::new_with_args("state, neighbours", "return neighbours === 2"));
+    universe.tick();
+    assert_eq!(4, universe.population());
+
+    // Journal frames arrive as Uint8Arrays
+    let frames = Array::new();
+    let sink = Function::new_with_args("frame", "this.push(frame)").bind0(&frames);
+    universe.set_journal_sink(sink);
+    universe.toggle_cell(0, 0);
+    assert_eq!(1, frames.length());
+    let frame = frames.get(0).dyn_into::<Uint8Array>().unwrap();
+    assert_eq!(2, frame.get_index(0));
+}
+
+#[wasm_bindgen_test]
+fn typed_reports() {
+    use js_sys::Reflect;
+
+    let field = |object: &JsValue, name: &str| Reflect::get(object, &name.into()).unwrap();
+    let mut universe = Universe::from_ascii(".....\n..#..\n..#..\n..#..\n.....\n").unwrap();
+    let stats: JsValue = universe.statistics_js().into();
+    assert_eq!(Some(3.0), field(&stats, "population").as_f64());
+
+    // Census counts come back as a plain object keyed by name
+    let census: JsValue = universe.census_js().into();
+    assert_eq!(Some(1.0), field(&census, "blinker").as_f64());
+
+    let result: JsValue = universe.run_until_stable_js(10).into();
+    assert_eq!(Some(2.0), field(&result, "period").as_f64());
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen_test]
+fn draw_to_canvas() {
+    use js_sys::{Function, Object, Reflect};
+    use life_wasm::generate_universe_seeded;
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::CanvasRenderingContext2d;
+
+    // Stand-in context counting the live cell rects drawn
+    let ctx = Object::new();
+    let noop = Function::new_no_args("");
+    Reflect::set(&ctx, &"rects".into(), &JsValue::from(0)).unwrap();
+    Reflect::set(&ctx, &"fillRect".into(), &noop).unwrap();
+    Reflect::set(&ctx, &"beginPath".into(), &noop).unwrap();
+    Reflect::set(&ctx, &"fill".into(), &noop).unwrap();
+    Reflect::set(&ctx, &"rect".into(), &Function::new_no_args("this.rects += 1")).unwrap();
+
+    let universe = generate_universe_seeded(16, 16, 0.5, 7).unwrap();
+    universe.draw_to_canvas(ctx.unchecked_ref::<CanvasRenderingContext2d>(), 4);
+    let rects = Reflect::get(&ctx, &"rects".into()).unwrap().as_f64().unwrap();
+    assert_eq!(universe.population() as f64, rects);
+}
+
+#[cfg(feature = "web")]
+#[wasm_bindgen_test]
+fn simulator_controls() {
+    use life_wasm::{generate_universe_seeded, Simulator};
+
+    let mut simulator = Simulator::new(generate_universe_seeded(16, 16, 0.3, 4).unwrap());
+    assert!(!simulator.is_playing());
+    simulator.step();
+    simulator.step();
+    assert_eq!(2, simulator.generation());
+
+    simulator.set_speed(-1.0);
+    assert_eq!(0.0, simulator.speed());
+    simulator.set_speed(0.5);
+    assert_eq!(0.5, simulator.speed());
+
+    simulator.set_universe(generate_universe_seeded(8, 8, 0.3, 4).unwrap());
+    assert_eq!(0, simulator.generation());
+    assert_eq!(simulator.render(), simulator.universe().render());
+}
@@ -1,13 +1,43 @@
-import { generate_universe, Universe } from "rust-wasm-tutorial-conways-game-of-life";
+import { ElementaryCA, generate_universe, Runner, Universe } from "life-wasm";
 
 const pre = document.getElementById("game-of-life-canvas");
-const universe = generate_universe(48, 48);
 
-const renderLoop = () => {
+// A shared link carries its board in the fragment, see `to_share_string()`
+const restore = (fragment) => {
+	try {
+		return Universe.from_share_string(fragment);
+	} catch (err) {
+		console.warn("couldn't restore the shared board:", err.message);
+		return generate_universe(48, 48, 0.125);
+	}
+};
+const universe = location.hash.length > 1 ? restore(location.hash.slice(1)) : generate_universe(48, 48, 0.125);
+
+// Clicking the board puts it in the address bar, ready to copy and share
+pre.addEventListener("click", () => {
+	history.replaceState(null, "", "#" + universe.to_share_string());
+});
+
+// Fixed generations per second no matter the display's refresh rate
+const runner = new Runner();
+runner.set_target_rate(30);
+
+let lastFrame = performance.now();
+
+const renderLoop = (now) => {
 	pre.textContent = universe.render();
-	universe.tick();
+	runner.advance(universe, now - lastFrame);
+	lastFrame = now;
 
 	requestAnimationFrame(renderLoop);
 };
 
 requestAnimationFrame(renderLoop);
+
+// Rule 30 grown from a single cell, each row one generation further on
+const triangle = new ElementaryCA(63, 30);
+triangle.set_alive(31, true);
+for (let row = 1; row < 32; row++) {
+	triangle.step();
+}
+document.getElementById("elementary-ca").textContent = triangle.render();